@@ -0,0 +1,135 @@
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use portal_spec_test_utils_rs::accumulator::EpochAccumulator;
+use portal_spec_test_utils_rs::beacon::{BeaconPayload, BeaconValue};
+use portal_spec_test_utils_rs::bls::{verify_sync_aggregate, SyncCommitteeKeys};
+use portal_spec_test_utils_rs::content::ContentValue;
+use portal_spec_test_utils_rs::content_key::ContentKey;
+use portal_spec_test_utils_rs::network::Network;
+use portal_spec_test_utils_rs::validation::Validator;
+use portal_spec_test_utils_rs::vector::{discover, network_dir, VectorFile, VectorKind};
+use std::fs;
+use std::hint::black_box;
+
+/// Every key and value of the valid vectors, offer values included.
+fn mainnet_content() -> Vec<(VectorKind, Vec<u8>, Vec<u8>)> {
+    let mut content = Vec::new();
+    for file in discover(&network_dir("mainnet")).unwrap() {
+        let Some(kind) = file.kind.filter(|kind| *kind != VectorKind::Invalid) else {
+            continue;
+        };
+        for entry in file.content_entries().unwrap() {
+            for value in [entry.content_value, entry.offer_value]
+                .into_iter()
+                .flatten()
+            {
+                content.push((kind, entry.content_key.clone(), value));
+            }
+        }
+    }
+    content
+}
+
+fn beacon_payload(relative: &str) -> BeaconPayload {
+    let root = network_dir("mainnet");
+    let file = VectorFile::load(&root, &root.join(relative)).unwrap();
+    let entry = file.content_entries().unwrap().remove(0);
+    let key = ContentKey::decode(&entry.content_key).unwrap();
+    match ContentValue::decode(&Network::mainnet(), &key, &entry.content_value.unwrap()) {
+        Ok(ContentValue::Beacon(BeaconValue::Single(forked))) => forked.payload,
+        other => panic!("expected a single beacon value, got {:?}", other),
+    }
+}
+
+fn decode(c: &mut Criterion) {
+    let network = Network::mainnet();
+    let content = mainnet_content();
+    let bytes: usize = content
+        .iter()
+        .map(|(_, key, value)| key.len() + value.len())
+        .sum();
+    let mut group = c.benchmark_group("decode");
+    group.throughput(Throughput::Bytes(bytes as u64));
+    group.bench_function("mainnet", |b| {
+        b.iter(|| {
+            for (_, key, value) in &content {
+                let key = ContentKey::decode(key).unwrap();
+                black_box(ContentValue::decode(&network, &key, value).unwrap());
+            }
+        })
+    });
+    group.finish();
+}
+
+fn accumulator(c: &mut Criterion) {
+    let path = network_dir("mainnet").join("history/accumulator/epoch-accumulator-00122.ssz");
+    let data = fs::read(path).unwrap();
+    let accumulator = EpochAccumulator::decode(&data).unwrap();
+    let mut group = c.benchmark_group("accumulator");
+    group.throughput(Throughput::Bytes(data.len() as u64));
+    group.bench_function("hash_tree_root", |b| {
+        b.iter(|| black_box(&accumulator).root())
+    });
+    group.finish();
+}
+
+fn state_proofs(c: &mut Criterion) {
+    let network = Network::mainnet();
+    let validator = Validator::from_vectors(&network, &network_dir("mainnet")).unwrap();
+    let state: Vec<_> = mainnet_content()
+        .into_iter()
+        .filter(|(kind, _, _)| *kind == VectorKind::StateValidation)
+        .collect();
+    let mut group = c.benchmark_group("state");
+    group.throughput(Throughput::Elements(state.len() as u64));
+    group.bench_function("verify_proofs", |b| {
+        b.iter(|| {
+            for (_, key, value) in &state {
+                black_box(validator.validate(key, value).unwrap());
+            }
+        })
+    });
+    group.finish();
+}
+
+fn bls(c: &mut Criterion) {
+    let network = Network::mainnet();
+    let BeaconPayload::Bootstrap(bootstrap) =
+        beacon_payload("beacon_chain/light_client/bootstrap.yaml")
+    else {
+        panic!("expected a bootstrap");
+    };
+    let BeaconPayload::FinalityUpdate(update) =
+        beacon_payload("beacon_chain/light_client/finality_update.yaml")
+    else {
+        panic!("expected a finality update");
+    };
+    let committee = &bootstrap.current_sync_committee;
+    let keys = SyncCommitteeKeys::new(committee).unwrap();
+    let header = &update.attested_header.beacon;
+    let aggregate = &update.sync_aggregate;
+
+    let mut group = c.benchmark_group("bls");
+    group.throughput(Throughput::Elements(1));
+    group.bench_function("verify_update", |b| {
+        b.iter(|| {
+            keys.verify(&network, header, aggregate, update.signature_slot)
+                .unwrap()
+        })
+    });
+    group.bench_function("verify_update_with_new_committee", |b| {
+        b.iter(|| {
+            verify_sync_aggregate(
+                &network,
+                committee,
+                header,
+                aggregate,
+                update.signature_slot,
+            )
+            .unwrap()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, decode, accumulator, state_proofs, bls);
+criterion_main!(benches);
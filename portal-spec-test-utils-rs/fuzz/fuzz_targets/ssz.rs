@@ -0,0 +1,9 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use portal_spec_test_utils_rs::fuzz::{CountingAllocator, Target};
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fuzz_target!(|data: &[u8]| Target::Ssz.check(data));
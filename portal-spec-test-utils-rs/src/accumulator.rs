@@ -0,0 +1,238 @@
+use crate::content::{BlockHeaderProof, HeaderWithProof};
+use crate::header::Header;
+use crate::ssz::{PathElement, SszError, SszType, SszValue};
+use ethnum::U256;
+use std::fs;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// Headers per epoch of the pre-merge historical hashes accumulator.
+pub const EPOCH_SIZE: u64 = 8192;
+
+#[derive(Error, Debug)]
+pub enum AccumulatorError {
+    #[error("failed to read {path}: {source}")]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("invalid epoch accumulator: {0}")]
+    Ssz(#[from] SszError),
+    #[error("block {0} is past the end of the epoch accumulator")]
+    NotInEpoch(u64),
+    #[error("block {number} is 0x{}, the epoch accumulator has 0x{}", hex::encode(.header), hex::encode(.record))]
+    HashMismatch {
+        number: u64,
+        header: [u8; 32],
+        record: [u8; 32],
+    },
+}
+
+/// The epoch of the accumulator a pre-merge block is recorded in.
+pub fn epoch_index(block_number: u64) -> u64 {
+    block_number / EPOCH_SIZE
+}
+
+/// The position of a block's record within its epoch.
+pub fn epoch_offset(block_number: u64) -> u64 {
+    block_number % EPOCH_SIZE
+}
+
+/// The file an epoch is stored in, as in `history/accumulator`.
+pub fn epoch_file_name(epoch_index: u64) -> String {
+    format!("epoch-accumulator-{:05}.ssz", epoch_index)
+}
+
+pub fn header_record_type() -> SszType {
+    SszType::container([
+        ("block_hash", SszType::bytes32()),
+        ("total_difficulty", SszType::uint256()),
+    ])
+}
+
+pub fn epoch_accumulator_type() -> SszType {
+    SszType::list(header_record_type(), EPOCH_SIZE as usize)
+}
+
+/// Generalized index of the block hash at `offset` in an epoch accumulator.
+pub fn block_hash_gindex(offset: u64) -> u64 {
+    epoch_accumulator_type()
+        .generalized_index(&[
+            PathElement::Index(offset as usize),
+            PathElement::Field("block_hash"),
+        ])
+        .expect("path exists in the epoch accumulator")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HeaderRecord {
+    pub block_hash: [u8; 32],
+    pub total_difficulty: U256,
+}
+
+impl HeaderRecord {
+    fn to_ssz(self) -> SszValue {
+        SszValue::Container(vec![
+            SszValue::Bytes(self.block_hash.to_vec()),
+            SszValue::Uint(self.total_difficulty),
+        ])
+    }
+}
+
+/// The header records of one epoch, at most `EPOCH_SIZE` of them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EpochAccumulator {
+    pub records: Vec<HeaderRecord>,
+}
+
+impl EpochAccumulator {
+    pub fn decode(data: &[u8]) -> Result<EpochAccumulator, AccumulatorError> {
+        let value = epoch_accumulator_type().decode(data)?;
+        let records = value
+            .as_items()
+            .unwrap_or_default()
+            .iter()
+            .map(|record| {
+                let fields = record.as_items().unwrap_or_default();
+                HeaderRecord {
+                    block_hash: fields[0]
+                        .as_bytes()
+                        .and_then(|bytes| bytes.try_into().ok())
+                        .unwrap_or_default(),
+                    total_difficulty: fields[1].as_uint().unwrap_or_default(),
+                }
+            })
+            .collect();
+        Ok(EpochAccumulator { records })
+    }
+
+    pub fn load(path: &Path) -> Result<EpochAccumulator, AccumulatorError> {
+        let data = fs::read(path).map_err(|source| AccumulatorError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        EpochAccumulator::decode(&data)
+    }
+
+    fn to_ssz(&self) -> SszValue {
+        SszValue::Sequence(self.records.iter().map(|record| record.to_ssz()).collect())
+    }
+
+    /// The root recorded for this epoch in the historical epochs of the accumulator.
+    pub fn root(&self) -> [u8; 32] {
+        epoch_accumulator_type()
+            .hash_tree_root(&self.to_ssz())
+            .expect("records match the epoch accumulator type")
+    }
+
+    /// The record of a block, assuming this is the epoch the block belongs to.
+    pub fn record(&self, block_number: u64) -> Option<&HeaderRecord> {
+        self.records.get(epoch_offset(block_number) as usize)
+    }
+
+    /// The 15 hash branch from a block's hash to the epoch root, as carried in
+    /// `BlockProofHistoricalHashesAccumulator`.
+    pub fn prove(&self, header: &Header) -> Result<Vec<[u8; 32]>, AccumulatorError> {
+        let record = self
+            .record(header.number)
+            .ok_or(AccumulatorError::NotInEpoch(header.number))?;
+        let hash = header.hash();
+        if record.block_hash != hash {
+            return Err(AccumulatorError::HashMismatch {
+                number: header.number,
+                header: hash,
+                record: record.block_hash,
+            });
+        }
+        let tree = epoch_accumulator_type()
+            .merkle_tree(&self.to_ssz())
+            .expect("records match the epoch accumulator type");
+        Ok(tree
+            .prove(block_hash_gindex(epoch_offset(header.number)))
+            .expect("record is in the tree"))
+    }
+
+    /// The history network content value of a pre-merge header.
+    pub fn header_with_proof(&self, header: Header) -> Result<HeaderWithProof, AccumulatorError> {
+        let proof = self.prove(&header)?;
+        Ok(HeaderWithProof {
+            header,
+            proof: BlockHeaderProof::HistoricalHashesAccumulator(proof),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ssz::verify_merkle_proof;
+    use crate::vector::{network_dir, VectorFile};
+
+    fn epoch_122() -> EpochAccumulator {
+        let path = network_dir("mainnet")
+            .join("history/accumulator")
+            .join(epoch_file_name(epoch_index(1000010)));
+        EpochAccumulator::load(&path).unwrap()
+    }
+
+    fn header_with_proof_1000010() -> (Vec<u8>, HeaderWithProof) {
+        let root = network_dir("mainnet");
+        let file =
+            VectorFile::load(&root, &root.join("history/headers_with_proof/1000010.yaml")).unwrap();
+        let value = file.content_entries().unwrap()[0]
+            .content_value
+            .clone()
+            .unwrap();
+        let decoded = HeaderWithProof::decode(&value).unwrap();
+        (value, decoded)
+    }
+
+    #[test]
+    fn test_epoch_index_and_offset() {
+        assert_eq!(epoch_index(1000010), 122);
+        assert_eq!(epoch_offset(1000010), 1000010 - 122 * 8192);
+        assert_eq!(epoch_index(15537393), 1896);
+        assert_eq!(epoch_file_name(122), "epoch-accumulator-00122.ssz");
+    }
+
+    #[test]
+    fn test_builds_proof_for_block_1000010() {
+        let epoch = epoch_122();
+        assert_eq!(epoch.records.len(), EPOCH_SIZE as usize);
+        let (value, expected) = header_with_proof_1000010();
+        let record = epoch.record(1000010).unwrap();
+        assert_eq!(record.block_hash, expected.header.hash());
+        assert!(record.total_difficulty > U256::ZERO);
+
+        let header_with_proof = epoch.header_with_proof(expected.header.clone()).unwrap();
+        assert_eq!(header_with_proof, expected);
+        assert_eq!(header_with_proof.encode(), value);
+        let BlockHeaderProof::HistoricalHashesAccumulator(proof) = &header_with_proof.proof else {
+            panic!("expected an accumulator proof");
+        };
+        assert!(verify_merkle_proof(
+            &expected.header.hash(),
+            proof,
+            block_hash_gindex(epoch_offset(1000010)),
+            &epoch.root()
+        ));
+    }
+
+    #[test]
+    fn test_rejects_header_from_another_epoch() {
+        let epoch = epoch_122();
+        let (_, mut expected) = header_with_proof_1000010();
+        expected.header.number += EPOCH_SIZE;
+        assert!(matches!(
+            epoch.prove(&expected.header),
+            Err(AccumulatorError::HashMismatch { .. })
+        ));
+        let partial = EpochAccumulator {
+            records: epoch.records[..10].to_vec(),
+        };
+        assert!(matches!(
+            partial.prove(&expected.header),
+            Err(AccumulatorError::NotInEpoch(_))
+        ));
+    }
+}
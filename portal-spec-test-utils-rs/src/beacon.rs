@@ -0,0 +1,844 @@
+use crate::block_proof::SLOTS_PER_HISTORICAL_ROOT;
+use crate::content::ContentError;
+use crate::content_key::BeaconContentKey;
+use crate::network::{Network, SLOTS_PER_EPOCH};
+use crate::ssz::{
+    decode_byte_lists, merkleize, mix_in_length, pack, u64_chunk, SszError, SszReader,
+};
+use crate::ExecutionFork;
+use ethnum::U256;
+use std::fmt;
+
+pub const SYNC_COMMITTEE_SIZE: usize = 512;
+pub const HISTORICAL_ROOTS_LIMIT: usize = 1 << 24;
+/// Generalized index of the execution payload in a beacon block body.
+pub const EXECUTION_PAYLOAD_GINDEX: u64 = 25;
+
+/// Beacon forks with light client data served on the beacon subnetwork.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum BeaconFork {
+    Capella,
+    Deneb,
+    Electra,
+}
+
+impl BeaconFork {
+    pub const ALL: [BeaconFork; 3] = [BeaconFork::Capella, BeaconFork::Deneb, BeaconFork::Electra];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            BeaconFork::Capella => "capella",
+            BeaconFork::Deneb => "deneb",
+            BeaconFork::Electra => "electra",
+        }
+    }
+
+    /// The execution fork activated together with this beacon fork.
+    pub fn execution_fork(&self) -> ExecutionFork {
+        match self {
+            BeaconFork::Capella => ExecutionFork::Shanghai,
+            BeaconFork::Deneb => ExecutionFork::Cancun,
+            BeaconFork::Electra => ExecutionFork::Prague,
+        }
+    }
+
+    fn finality_branch_depth(&self) -> usize {
+        match self {
+            BeaconFork::Electra => 7,
+            _ => 6,
+        }
+    }
+
+    fn sync_committee_branch_depth(&self) -> usize {
+        match self {
+            BeaconFork::Electra => 6,
+            _ => 5,
+        }
+    }
+
+    fn historical_summaries_proof_depth(&self) -> usize {
+        match self {
+            BeaconFork::Electra => 6,
+            _ => 5,
+        }
+    }
+
+    /// Generalized index of the finalized checkpoint root in the beacon state.
+    pub fn finalized_root_gindex(&self) -> u64 {
+        match self {
+            BeaconFork::Electra => 169,
+            _ => 105,
+        }
+    }
+
+    pub fn current_sync_committee_gindex(&self) -> u64 {
+        match self {
+            BeaconFork::Electra => 86,
+            _ => 54,
+        }
+    }
+
+    pub fn next_sync_committee_gindex(&self) -> u64 {
+        self.current_sync_committee_gindex() + 1
+    }
+
+    pub fn historical_summaries_gindex(&self) -> u64 {
+        match self {
+            BeaconFork::Electra => 91,
+            _ => 59,
+        }
+    }
+}
+
+impl fmt::Display for BeaconFork {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BeaconBlockHeader {
+    pub slot: u64,
+    pub proposer_index: u64,
+    pub parent_root: [u8; 32],
+    pub state_root: [u8; 32],
+    pub body_root: [u8; 32],
+}
+
+impl BeaconBlockHeader {
+    fn read(reader: &mut SszReader) -> Result<BeaconBlockHeader, ContentError> {
+        Ok(BeaconBlockHeader {
+            slot: reader.u64()?,
+            proposer_index: reader.u64()?,
+            parent_root: reader.array()?,
+            state_root: reader.array()?,
+            body_root: reader.array()?,
+        })
+    }
+
+    pub fn hash_tree_root(&self) -> [u8; 32] {
+        let chunks = [
+            u64_chunk(self.slot),
+            u64_chunk(self.proposer_index),
+            self.parent_root,
+            self.state_root,
+            self.body_root,
+        ];
+        merkleize(&chunks, chunks.len())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExecutionPayloadHeader {
+    pub parent_hash: [u8; 32],
+    pub fee_recipient: [u8; 20],
+    pub state_root: [u8; 32],
+    pub receipts_root: [u8; 32],
+    pub logs_bloom: Vec<u8>,
+    pub prev_randao: [u8; 32],
+    pub block_number: u64,
+    pub gas_limit: u64,
+    pub gas_used: u64,
+    pub timestamp: u64,
+    pub extra_data: Vec<u8>,
+    pub base_fee_per_gas: U256,
+    pub block_hash: [u8; 32],
+    pub transactions_root: [u8; 32],
+    pub withdrawals_root: [u8; 32],
+    pub blob_gas_used: Option<u64>,
+    pub excess_blob_gas: Option<u64>,
+}
+
+impl ExecutionPayloadHeader {
+    pub fn decode(fork: BeaconFork, data: &[u8]) -> Result<ExecutionPayloadHeader, ContentError> {
+        let mut reader = SszReader::new(data);
+        let parent_hash = reader.array()?;
+        let fee_recipient = reader.array()?;
+        let state_root = reader.array()?;
+        let receipts_root = reader.array()?;
+        let logs_bloom = reader.bytes(256)?.to_vec();
+        let prev_randao = reader.array()?;
+        let block_number = reader.u64()?;
+        let gas_limit = reader.u64()?;
+        let gas_used = reader.u64()?;
+        let timestamp = reader.u64()?;
+        reader.offset()?;
+        let base_fee_per_gas = reader.u256()?;
+        let block_hash = reader.array()?;
+        let transactions_root = reader.array()?;
+        let withdrawals_root = reader.array()?;
+        let (blob_gas_used, excess_blob_gas) = if fork >= BeaconFork::Deneb {
+            (Some(reader.u64()?), Some(reader.u64()?))
+        } else {
+            (None, None)
+        };
+        let fields = reader.finish()?;
+
+        Ok(ExecutionPayloadHeader {
+            parent_hash,
+            fee_recipient,
+            state_root,
+            receipts_root,
+            logs_bloom,
+            prev_randao,
+            block_number,
+            gas_limit,
+            gas_used,
+            timestamp,
+            extra_data: fields[0].to_vec(),
+            base_fee_per_gas,
+            block_hash,
+            transactions_root,
+            withdrawals_root,
+            blob_gas_used,
+            excess_blob_gas,
+        })
+    }
+
+    pub fn hash_tree_root(&self) -> [u8; 32] {
+        let mut fee_recipient = [0u8; 32];
+        fee_recipient[..20].copy_from_slice(&self.fee_recipient);
+        let mut chunks = vec![
+            self.parent_hash,
+            fee_recipient,
+            self.state_root,
+            self.receipts_root,
+            merkleize(&pack(&self.logs_bloom), 8),
+            self.prev_randao,
+            u64_chunk(self.block_number),
+            u64_chunk(self.gas_limit),
+            u64_chunk(self.gas_used),
+            u64_chunk(self.timestamp),
+            mix_in_length(
+                &merkleize(&pack(&self.extra_data), 1),
+                self.extra_data.len(),
+            ),
+            self.base_fee_per_gas.to_le_bytes(),
+            self.block_hash,
+            self.transactions_root,
+            self.withdrawals_root,
+        ];
+        chunks.extend(self.blob_gas_used.map(u64_chunk));
+        chunks.extend(self.excess_blob_gas.map(u64_chunk));
+        merkleize(&chunks, chunks.len())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LightClientHeader {
+    pub beacon: BeaconBlockHeader,
+    pub execution: ExecutionPayloadHeader,
+    pub execution_branch: Vec<[u8; 32]>,
+}
+
+impl LightClientHeader {
+    pub fn decode(fork: BeaconFork, data: &[u8]) -> Result<LightClientHeader, ContentError> {
+        let mut reader = SszReader::new(data);
+        let beacon = BeaconBlockHeader::read(&mut reader)?;
+        reader.offset()?;
+        let execution_branch = reader.roots(4)?;
+        let fields = reader.finish()?;
+        Ok(LightClientHeader {
+            beacon,
+            execution: ExecutionPayloadHeader::decode(fork, fields[0])?,
+            execution_branch,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyncCommittee {
+    pub pubkeys: Vec<[u8; 48]>,
+    pub aggregate_pubkey: [u8; 48],
+}
+
+impl SyncCommittee {
+    fn read(reader: &mut SszReader) -> Result<SyncCommittee, ContentError> {
+        Ok(SyncCommittee {
+            pubkeys: (0..SYNC_COMMITTEE_SIZE)
+                .map(|_| reader.array())
+                .collect::<Result<_, _>>()?,
+            aggregate_pubkey: reader.array()?,
+        })
+    }
+
+    pub fn hash_tree_root(&self) -> [u8; 32] {
+        let pubkey_root = |pubkey: &[u8; 48]| merkleize(&pack(pubkey), 2);
+        let pubkeys: Vec<_> = self.pubkeys.iter().map(pubkey_root).collect();
+        merkleize(
+            &[
+                merkleize(&pubkeys, SYNC_COMMITTEE_SIZE),
+                pubkey_root(&self.aggregate_pubkey),
+            ],
+            2,
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyncAggregate {
+    pub sync_committee_bits: [u8; 64],
+    pub sync_committee_signature: [u8; 96],
+}
+
+impl SyncAggregate {
+    fn read(reader: &mut SszReader) -> Result<SyncAggregate, ContentError> {
+        Ok(SyncAggregate {
+            sync_committee_bits: reader.array()?,
+            sync_committee_signature: reader.array()?,
+        })
+    }
+
+    pub fn participants(&self) -> u32 {
+        self.sync_committee_bits
+            .iter()
+            .map(|b| b.count_ones())
+            .sum()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LightClientBootstrap {
+    pub header: LightClientHeader,
+    pub current_sync_committee: SyncCommittee,
+    pub current_sync_committee_branch: Vec<[u8; 32]>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LightClientUpdate {
+    pub attested_header: LightClientHeader,
+    pub next_sync_committee: SyncCommittee,
+    pub next_sync_committee_branch: Vec<[u8; 32]>,
+    pub finalized_header: LightClientHeader,
+    pub finality_branch: Vec<[u8; 32]>,
+    pub sync_aggregate: SyncAggregate,
+    pub signature_slot: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LightClientFinalityUpdate {
+    pub attested_header: LightClientHeader,
+    pub finalized_header: LightClientHeader,
+    pub finality_branch: Vec<[u8; 32]>,
+    pub sync_aggregate: SyncAggregate,
+    pub signature_slot: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LightClientOptimisticUpdate {
+    pub attested_header: LightClientHeader,
+    pub sync_aggregate: SyncAggregate,
+    pub signature_slot: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HistoricalSummary {
+    pub block_summary_root: [u8; 32],
+    pub state_summary_root: [u8; 32],
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HistoricalSummariesWithProof {
+    pub epoch: u64,
+    pub historical_summaries: Vec<HistoricalSummary>,
+    pub proof: Vec<[u8; 32]>,
+}
+
+impl HistoricalSummariesWithProof {
+    /// Root of the `historical_summaries` list the proof anchors in the beacon state.
+    pub fn summaries_root(&self) -> [u8; 32] {
+        let roots: Vec<_> = self
+            .historical_summaries
+            .iter()
+            .map(|summary| merkleize(&[summary.block_summary_root, summary.state_summary_root], 2))
+            .collect();
+        mix_in_length(&merkleize(&roots, HISTORICAL_ROOTS_LIMIT), roots.len())
+    }
+
+    /// The earliest epoch of a state holding these summaries, one appended per
+    /// period since Capella.
+    pub fn min_epoch(&self, network: &Network) -> u64 {
+        let periods = self.historical_summaries.len() as u64;
+        network.capella.epoch + periods * (SLOTS_PER_HISTORICAL_ROOT / SLOTS_PER_EPOCH)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BeaconPayload {
+    Bootstrap(LightClientBootstrap),
+    Update(LightClientUpdate),
+    FinalityUpdate(LightClientFinalityUpdate),
+    OptimisticUpdate(LightClientOptimisticUpdate),
+    HistoricalSummaries(HistoricalSummariesWithProof),
+}
+
+impl BeaconPayload {
+    /// The epoch of the (attested) header, whose fork version the value's digest
+    /// is computed from. Historical summaries only bound it from below by their
+    /// count: the spec-test states they are built from carry arbitrary epochs.
+    pub fn epoch(&self, network: &Network) -> u64 {
+        let header = match self {
+            BeaconPayload::Bootstrap(bootstrap) => &bootstrap.header,
+            BeaconPayload::Update(update) => &update.attested_header,
+            BeaconPayload::FinalityUpdate(update) => &update.attested_header,
+            BeaconPayload::OptimisticUpdate(update) => &update.attested_header,
+            BeaconPayload::HistoricalSummaries(summaries) => return summaries.min_epoch(network),
+        };
+        header.beacon.slot / SLOTS_PER_EPOCH
+    }
+
+    /// The digest of the payload's epoch, if it is not the digest of `fork`.
+    fn expected_digest(&self, network: &Network, fork: BeaconFork) -> Option<[u8; 4]> {
+        let expected = network.fork_digest_at(self.epoch(network));
+        let matches = match self {
+            BeaconPayload::HistoricalSummaries(_) => network
+                .beacon_fork(expected)
+                .is_none_or(|earliest| fork >= earliest),
+            _ => network.fork_digest(fork) == expected,
+        };
+        (!matches).then_some(expected)
+    }
+}
+
+/// A beacon value with the fork its digest prefix names.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ForkedPayload {
+    pub fork_digest: [u8; 4],
+    pub fork: BeaconFork,
+    pub payload: BeaconPayload,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BeaconValue {
+    Single(Box<ForkedPayload>),
+    Updates(Vec<ForkedPayload>),
+}
+
+fn decode_bootstrap(fork: BeaconFork, data: &[u8]) -> Result<LightClientBootstrap, ContentError> {
+    let mut reader = SszReader::new(data);
+    reader.offset()?;
+    let current_sync_committee = SyncCommittee::read(&mut reader)?;
+    let current_sync_committee_branch = reader.roots(fork.sync_committee_branch_depth())?;
+    let fields = reader.finish()?;
+    Ok(LightClientBootstrap {
+        header: LightClientHeader::decode(fork, fields[0])?,
+        current_sync_committee,
+        current_sync_committee_branch,
+    })
+}
+
+fn decode_update(fork: BeaconFork, data: &[u8]) -> Result<LightClientUpdate, ContentError> {
+    let mut reader = SszReader::new(data);
+    reader.offset()?;
+    let next_sync_committee = SyncCommittee::read(&mut reader)?;
+    let next_sync_committee_branch = reader.roots(fork.sync_committee_branch_depth())?;
+    reader.offset()?;
+    let finality_branch = reader.roots(fork.finality_branch_depth())?;
+    let sync_aggregate = SyncAggregate::read(&mut reader)?;
+    let signature_slot = reader.u64()?;
+    let fields = reader.finish()?;
+    Ok(LightClientUpdate {
+        attested_header: LightClientHeader::decode(fork, fields[0])?,
+        next_sync_committee,
+        next_sync_committee_branch,
+        finalized_header: LightClientHeader::decode(fork, fields[1])?,
+        finality_branch,
+        sync_aggregate,
+        signature_slot,
+    })
+}
+
+fn decode_finality_update(
+    fork: BeaconFork,
+    data: &[u8],
+) -> Result<LightClientFinalityUpdate, ContentError> {
+    let mut reader = SszReader::new(data);
+    reader.offset()?;
+    reader.offset()?;
+    let finality_branch = reader.roots(fork.finality_branch_depth())?;
+    let sync_aggregate = SyncAggregate::read(&mut reader)?;
+    let signature_slot = reader.u64()?;
+    let fields = reader.finish()?;
+    Ok(LightClientFinalityUpdate {
+        attested_header: LightClientHeader::decode(fork, fields[0])?,
+        finalized_header: LightClientHeader::decode(fork, fields[1])?,
+        finality_branch,
+        sync_aggregate,
+        signature_slot,
+    })
+}
+
+fn decode_optimistic_update(
+    fork: BeaconFork,
+    data: &[u8],
+) -> Result<LightClientOptimisticUpdate, ContentError> {
+    let mut reader = SszReader::new(data);
+    reader.offset()?;
+    let sync_aggregate = SyncAggregate::read(&mut reader)?;
+    let signature_slot = reader.u64()?;
+    let fields = reader.finish()?;
+    Ok(LightClientOptimisticUpdate {
+        attested_header: LightClientHeader::decode(fork, fields[0])?,
+        sync_aggregate,
+        signature_slot,
+    })
+}
+
+fn decode_historical_summaries(
+    fork: BeaconFork,
+    data: &[u8],
+) -> Result<HistoricalSummariesWithProof, ContentError> {
+    let mut reader = SszReader::new(data);
+    let epoch = reader.u64()?;
+    reader.offset()?;
+    let proof = reader.roots(fork.historical_summaries_proof_depth())?;
+    let fields = reader.finish()?;
+    if fields[0].len() % 64 != 0 {
+        return Err(SszError::InvalidLength {
+            len: fields[0].len(),
+            size: 64,
+        }
+        .into());
+    }
+    let historical_summaries = fields[0]
+        .chunks(64)
+        .map(|chunk| HistoricalSummary {
+            block_summary_root: chunk[..32].try_into().unwrap(),
+            state_summary_root: chunk[32..].try_into().unwrap(),
+        })
+        .collect();
+    Ok(HistoricalSummariesWithProof {
+        epoch,
+        historical_summaries,
+        proof,
+    })
+}
+
+fn decode_forked(
+    network: &Network,
+    data: &[u8],
+    decode_payload: impl Fn(BeaconFork, &[u8]) -> Result<BeaconPayload, ContentError>,
+) -> Result<ForkedPayload, ContentError> {
+    if data.len() < 4 {
+        return Err(SszError::UnexpectedEnd { len: 4, pos: 0 }.into());
+    }
+    let fork_digest: [u8; 4] = data[..4].try_into().unwrap();
+    let fork = network
+        .beacon_fork(fork_digest)
+        .ok_or(ContentError::UnknownForkDigest(fork_digest))?;
+    let wrong_digest = |expected| ContentError::WrongForkDigest {
+        found: fork_digest,
+        expected,
+    };
+    let payload = match decode_payload(fork, &data[4..]) {
+        Ok(payload) => payload,
+        Err(error) => {
+            // A value that decodes in the layout of the fork of its own epoch was only mislabelled.
+            for other in BeaconFork::ALL.into_iter().filter(|other| *other != fork) {
+                let Ok(payload) = decode_payload(other, &data[4..]) else {
+                    continue;
+                };
+                if payload.expected_digest(network, other).is_none() {
+                    return Err(wrong_digest(network.fork_digest(other)));
+                }
+            }
+            return Err(error);
+        }
+    };
+    if let Some(expected) = payload.expected_digest(network, fork) {
+        return Err(wrong_digest(expected));
+    }
+    Ok(ForkedPayload {
+        fork_digest,
+        fork,
+        payload,
+    })
+}
+
+/// Decodes a beacon value, its fork named by a digest of the network's fork versions.
+pub fn decode(
+    network: &Network,
+    key: &BeaconContentKey,
+    data: &[u8],
+) -> Result<BeaconValue, ContentError> {
+    let single = |decode_payload: fn(BeaconFork, &[u8]) -> Result<BeaconPayload, ContentError>| {
+        decode_forked(network, data, decode_payload)
+            .map(|forked| BeaconValue::Single(Box::new(forked)))
+    };
+    match key {
+        BeaconContentKey::LightClientBootstrap { .. } => {
+            single(|fork, data| decode_bootstrap(fork, data).map(BeaconPayload::Bootstrap))
+        }
+        BeaconContentKey::LightClientUpdatesByRange { .. } => decode_byte_lists(data)?
+            .into_iter()
+            .map(|update| {
+                decode_forked(network, update, |fork, data| {
+                    decode_update(fork, data).map(BeaconPayload::Update)
+                })
+            })
+            .collect::<Result<_, _>>()
+            .map(BeaconValue::Updates),
+        BeaconContentKey::LightClientFinalityUpdate { .. } => single(|fork, data| {
+            decode_finality_update(fork, data).map(BeaconPayload::FinalityUpdate)
+        }),
+        BeaconContentKey::LightClientOptimisticUpdate { .. } => single(|fork, data| {
+            decode_optimistic_update(fork, data).map(BeaconPayload::OptimisticUpdate)
+        }),
+        BeaconContentKey::HistoricalSummariesWithProof { .. } => single(|fork, data| {
+            decode_historical_summaries(fork, data).map(BeaconPayload::HistoricalSummaries)
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::content_key::ContentKey;
+    use crate::ssz::verify_merkle_proof;
+    use crate::vector::{network_dir, VectorFile};
+
+    fn load(relative: &str) -> (BeaconContentKey, BeaconValue) {
+        let root = network_dir("mainnet");
+        let file = VectorFile::load(&root, &root.join(relative)).unwrap();
+        let entry = file.content_entries().unwrap().remove(0);
+        let ContentKey::Beacon(key) = ContentKey::decode(&entry.content_key).unwrap() else {
+            panic!("expected a beacon key");
+        };
+        let value = decode(
+            &Network::mainnet(),
+            &key,
+            entry.content_value.as_ref().unwrap(),
+        )
+        .unwrap();
+        (key, value)
+    }
+
+    fn single(value: BeaconValue) -> ForkedPayload {
+        match value {
+            BeaconValue::Single(forked) => *forked,
+            BeaconValue::Updates(_) => panic!("expected a single value"),
+        }
+    }
+
+    #[test]
+    fn test_decode_bootstrap() {
+        let (_, value) = load("beacon_chain/light_client/bootstrap.yaml");
+        let ForkedPayload {
+            fork: BeaconFork::Capella,
+            payload: BeaconPayload::Bootstrap(bootstrap),
+            ..
+        } = single(value)
+        else {
+            panic!("expected a capella bootstrap");
+        };
+        assert_eq!(bootstrap.header.beacon.slot, 6718368);
+        assert_eq!(bootstrap.current_sync_committee.pubkeys.len(), 512);
+    }
+
+    #[test]
+    fn test_decode_updates_by_range() {
+        let (key, value) = load("beacon_chain/light_client/updates.yaml");
+        let BeaconContentKey::LightClientUpdatesByRange { count, .. } = key else {
+            panic!("expected an updates key");
+        };
+        let BeaconValue::Updates(updates) = value else {
+            panic!("expected updates");
+        };
+        assert_eq!(updates.len() as u64, count);
+    }
+
+    #[test]
+    fn test_decode_historical_summaries() {
+        let (key, value) =
+            load("beacon_chain/historical_summaries_with_proof/deneb/historical_summaries_with_proof.yaml");
+        let ForkedPayload {
+            fork: BeaconFork::Deneb,
+            payload: BeaconPayload::HistoricalSummaries(summaries),
+            ..
+        } = single(value)
+        else {
+            panic!("expected deneb historical summaries");
+        };
+        assert_eq!(
+            key,
+            BeaconContentKey::HistoricalSummariesWithProof {
+                epoch: summaries.epoch
+            }
+        );
+        assert!(!summaries.historical_summaries.is_empty());
+    }
+
+    #[test]
+    fn test_fork_digest_matches_epoch() {
+        let root = network_dir("mainnet");
+        let path = root.join("beacon_chain/light_client/bootstrap.yaml");
+        let entry = VectorFile::load(&root, &path)
+            .unwrap()
+            .content_entries()
+            .unwrap()
+            .remove(0);
+        let ContentKey::Beacon(key) = ContentKey::decode(&entry.content_key).unwrap() else {
+            panic!("expected a beacon key");
+        };
+        let mainnet = Network::mainnet();
+        let mut value = entry.content_value.unwrap();
+        let BeaconValue::Single(forked) = decode(&mainnet, &key, &value).unwrap() else {
+            panic!("expected a single value");
+        };
+        let capella = mainnet.fork_digest(BeaconFork::Capella);
+        assert_eq!(
+            mainnet.fork_digest_at(forked.payload.epoch(&mainnet)),
+            capella
+        );
+
+        // Mislabelled as Deneb, whose header layout the value does not decode in.
+        let deneb = mainnet.fork_digest(BeaconFork::Deneb);
+        value[..4].copy_from_slice(&deneb);
+        assert_eq!(
+            decode(&mainnet, &key, &value),
+            Err(ContentError::WrongForkDigest {
+                found: deneb,
+                expected: capella
+            })
+        );
+    }
+
+    #[test]
+    fn test_summaries_digest_matches_count() {
+        let root = network_dir("mainnet");
+        let path = root.join(
+            "beacon_chain/historical_summaries_with_proof/deneb/historical_summaries_with_proof.yaml",
+        );
+        let entry = VectorFile::load(&root, &path)
+            .unwrap()
+            .content_entries()
+            .unwrap()
+            .remove(0);
+        let ContentKey::Beacon(key) = ContentKey::decode(&entry.content_key).unwrap() else {
+            panic!("expected a beacon key");
+        };
+        let mainnet = Network::mainnet();
+        let capella = mainnet.fork_digest(BeaconFork::Capella);
+        let deneb = mainnet.fork_digest(BeaconFork::Deneb);
+
+        // Three summaries fit a Capella state; a later fork's digest is accepted.
+        let mut value = entry.content_value.unwrap();
+        value[..4].copy_from_slice(&capella);
+        assert!(decode(&mainnet, &key, &value).is_ok());
+
+        // 300 summaries are only held from Deneb on, so the Capella digest is wrong.
+        value.resize(value.len() + 297 * 64, 0);
+        assert_eq!(
+            decode(&mainnet, &key, &value),
+            Err(ContentError::WrongForkDigest {
+                found: capella,
+                expected: deneb
+            })
+        );
+        value[..4].copy_from_slice(&deneb);
+        let BeaconValue::Single(forked) = decode(&mainnet, &key, &value).unwrap() else {
+            panic!("expected a single value");
+        };
+        assert_eq!(
+            mainnet.fork_digest_at(forked.payload.epoch(&mainnet)),
+            deneb
+        );
+    }
+
+    #[test]
+    fn test_light_client_branches() {
+        let (key, value) = load("beacon_chain/light_client/bootstrap.yaml");
+        let BeaconContentKey::LightClientBootstrap { block_root } = key else {
+            panic!("expected a bootstrap key");
+        };
+        let ForkedPayload {
+            fork,
+            payload: BeaconPayload::Bootstrap(bootstrap),
+            ..
+        } = single(value)
+        else {
+            panic!("expected a bootstrap");
+        };
+        let header = &bootstrap.header;
+        assert_eq!(header.beacon.hash_tree_root(), block_root);
+        assert!(verify_merkle_proof(
+            &bootstrap.current_sync_committee.hash_tree_root(),
+            &bootstrap.current_sync_committee_branch,
+            fork.current_sync_committee_gindex(),
+            &header.beacon.state_root,
+        ));
+        assert!(verify_merkle_proof(
+            &header.execution.hash_tree_root(),
+            &header.execution_branch,
+            EXECUTION_PAYLOAD_GINDEX,
+            &header.beacon.body_root,
+        ));
+
+        let (_, value) = load("beacon_chain/light_client/finality_update.yaml");
+        let ForkedPayload {
+            fork,
+            payload: BeaconPayload::FinalityUpdate(update),
+            ..
+        } = single(value)
+        else {
+            panic!("expected a finality update");
+        };
+        assert!(verify_merkle_proof(
+            &update.finalized_header.beacon.hash_tree_root(),
+            &update.finality_branch,
+            fork.finalized_root_gindex(),
+            &update.attested_header.beacon.state_root,
+        ));
+
+        let (_, value) = load("beacon_chain/light_client/updates.yaml");
+        let BeaconValue::Updates(updates) = value else {
+            panic!("expected updates");
+        };
+        for forked in updates {
+            let BeaconPayload::Update(update) = forked.payload else {
+                panic!("expected an update");
+            };
+            assert!(verify_merkle_proof(
+                &update.next_sync_committee.hash_tree_root(),
+                &update.next_sync_committee_branch,
+                forked.fork.next_sync_committee_gindex(),
+                &update.attested_header.beacon.state_root,
+            ));
+        }
+    }
+
+    #[test]
+    fn test_historical_summaries_proof() {
+        let relative =
+            "beacon_chain/historical_summaries_with_proof/deneb/historical_summaries_with_proof.yaml";
+        let (_, value) = load(relative);
+        let ForkedPayload {
+            fork,
+            payload: BeaconPayload::HistoricalSummaries(summaries),
+            ..
+        } = single(value)
+        else {
+            panic!("expected historical summaries");
+        };
+        let yaml: serde_yaml::Value = serde_yaml::from_str(
+            &std::fs::read_to_string(network_dir("mainnet").join(relative)).unwrap(),
+        )
+        .unwrap();
+        let root = |name: &str| -> [u8; 32] {
+            hex::decode(yaml[name].as_str().unwrap().trim_start_matches("0x"))
+                .unwrap()
+                .try_into()
+                .unwrap()
+        };
+        assert_eq!(
+            summaries.summaries_root(),
+            root("historical_summaries_root")
+        );
+        assert!(verify_merkle_proof(
+            &summaries.summaries_root(),
+            &summaries.proof,
+            fork.historical_summaries_gindex(),
+            &root("beacon_state_root"),
+        ));
+    }
+}
@@ -0,0 +1,286 @@
+use serde::Deserialize;
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// Slowdown of a benchmark's mean over its baseline that counts as a regression.
+pub const DEFAULT_THRESHOLD: f64 = 0.10;
+
+#[derive(Error, Debug)]
+pub enum BenchError {
+    #[error("failed to read {path}: {source}")]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("failed to parse {path}: {source}")]
+    Json {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+    #[error("no benchmark results saved as {name:?} in {path}")]
+    NoMeasurements { name: String, path: PathBuf },
+}
+
+/// The throughput a benchmark declares for one iteration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum Throughput {
+    Bytes(u64),
+    BytesDecimal(u64),
+    Elements(u64),
+}
+
+#[derive(Deserialize)]
+struct BenchmarkJson {
+    full_id: String,
+    throughput: Option<Throughput>,
+}
+
+#[derive(Deserialize)]
+struct Estimate {
+    point_estimate: f64,
+}
+
+#[derive(Deserialize)]
+struct EstimatesJson {
+    mean: Estimate,
+}
+
+/// One benchmark's result as criterion records it in `target/criterion`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Measurement {
+    pub id: String,
+    pub mean_ns: f64,
+    pub throughput: Option<Throughput>,
+}
+
+impl Measurement {
+    /// Parses the `benchmark.json` and `estimates.json` criterion writes per run.
+    pub fn parse(benchmark: &str, estimates: &str) -> Result<Measurement, serde_json::Error> {
+        let benchmark: BenchmarkJson = serde_json::from_str(benchmark)?;
+        let estimates: EstimatesJson = serde_json::from_str(estimates)?;
+        Ok(Measurement {
+            id: benchmark.full_id,
+            mean_ns: estimates.mean.point_estimate,
+            throughput: benchmark.throughput,
+        })
+    }
+
+    /// Throughput at the mean time, as `1015.4 MiB/s` or `79.8 Kelem/s`.
+    pub fn rate(&self) -> Option<String> {
+        let per_second = |amount: u64| amount as f64 * 1e9 / self.mean_ns;
+        Some(match self.throughput? {
+            Throughput::Bytes(bytes) | Throughput::BytesDecimal(bytes) => {
+                format!("{:.1} MiB/s", per_second(bytes) / (1024.0 * 1024.0))
+            }
+            Throughput::Elements(elements) => {
+                let rate = per_second(elements);
+                if rate >= 1000.0 {
+                    format!("{:.1} Kelem/s", rate / 1000.0)
+                } else {
+                    format!("{:.1} elem/s", rate)
+                }
+            }
+        })
+    }
+}
+
+fn read(path: &Path) -> Result<String, BenchError> {
+    fs::read_to_string(path).map_err(|source| BenchError::Io {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+/// Every benchmark below `criterion_dir` with results saved under `baseline`,
+/// `new` being the latest run. Sorted by id, and an error when there are none,
+/// as for a misspelled baseline.
+pub fn load(criterion_dir: &Path, baseline: &str) -> Result<Vec<Measurement>, BenchError> {
+    let mut measurements = Vec::new();
+    let mut dirs = vec![criterion_dir.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        let entries = fs::read_dir(&dir).map_err(|source| BenchError::Io {
+            path: dir.clone(),
+            source,
+        })?;
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if !path.is_dir() {
+                continue;
+            }
+            let benchmark = path.join("benchmark.json");
+            if path.file_name().and_then(|n| n.to_str()) == Some(baseline) && benchmark.exists() {
+                let estimates = path.join("estimates.json");
+                let measurement = Measurement::parse(&read(&benchmark)?, &read(&estimates)?)
+                    .map_err(|source| BenchError::Json {
+                        path: estimates,
+                        source,
+                    })?;
+                measurements.push(measurement);
+            } else {
+                dirs.push(path);
+            }
+        }
+    }
+    if measurements.is_empty() {
+        return Err(BenchError::NoMeasurements {
+            name: baseline.to_string(),
+            path: criterion_dir.to_path_buf(),
+        });
+    }
+    measurements.sort_by(|a, b| a.id.cmp(&b.id));
+    Ok(measurements)
+}
+
+/// A benchmark's latest result next to its baseline, if it has one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comparison {
+    pub current: Measurement,
+    pub baseline_ns: Option<f64>,
+}
+
+impl Comparison {
+    /// Relative change of the mean time, positive when slower.
+    pub fn change(&self) -> Option<f64> {
+        self.baseline_ns
+            .map(|baseline| (self.current.mean_ns - baseline) / baseline)
+    }
+
+    pub fn regressed(&self, threshold: f64) -> bool {
+        self.change().is_some_and(|change| change > threshold)
+    }
+}
+
+/// Ids of the benchmarks the baseline has no result for.
+pub fn missing_baseline(comparisons: &[Comparison]) -> Vec<&str> {
+    comparisons
+        .iter()
+        .filter(|comparison| comparison.baseline_ns.is_none())
+        .map(|comparison| comparison.current.id.as_str())
+        .collect()
+}
+
+pub fn compare(baseline: &[Measurement], current: &[Measurement]) -> Vec<Comparison> {
+    current
+        .iter()
+        .map(|measurement| Comparison {
+            current: measurement.clone(),
+            baseline_ns: baseline
+                .iter()
+                .find(|b| b.id == measurement.id)
+                .map(|b| b.mean_ns),
+        })
+        .collect()
+}
+
+fn format_time(ns: f64) -> String {
+    match ns {
+        ns if ns >= 1e6 => format!("{:.2} ms", ns / 1e6),
+        ns if ns >= 1e3 => format!("{:.2} µs", ns / 1e3),
+        ns => format!("{:.0} ns", ns),
+    }
+}
+
+/// A Markdown table of the comparisons, flagging those slower than `threshold`.
+pub fn render(comparisons: &[Comparison], threshold: f64) -> String {
+    let mut out =
+        String::from("| benchmark | mean | throughput | vs baseline |\n|---|---:|---:|---:|\n");
+    for comparison in comparisons {
+        let change = match comparison.change() {
+            Some(change) if comparison.regressed(threshold) => {
+                format!("{:+.1}% regressed", change * 100.0)
+            }
+            Some(change) => format!("{:+.1}%", change * 100.0),
+            None => "-".to_string(),
+        };
+        let _ = writeln!(
+            out,
+            "| {} | {} | {} | {} |",
+            comparison.current.id,
+            format_time(comparison.current.mean_ns),
+            comparison.current.rate().unwrap_or_else(|| "-".to_string()),
+            change
+        );
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn measurement(id: &str, mean_ns: f64, throughput: Throughput) -> Measurement {
+        Measurement {
+            id: id.to_string(),
+            mean_ns,
+            throughput: Some(throughput),
+        }
+    }
+
+    #[test]
+    fn test_parse_criterion_output() {
+        let benchmark = r#"{"group_id":"decode","function_id":"mainnet","value_str":null,"throughput":{"Bytes":842556},"full_id":"decode/mainnet","directory_name":"decode/mainnet","title":"decode/mainnet"}"#;
+        let estimates = r#"{"mean":{"confidence_interval":{"confidence_level":0.95,"lower_bound":777831.3,"upper_bound":800229.6},"point_estimate":787927.4,"standard_error":5744.1},"median":{"point_estimate":785243.5}}"#;
+        let parsed = Measurement::parse(benchmark, estimates).unwrap();
+        assert_eq!(
+            parsed,
+            measurement("decode/mainnet", 787927.4, Throughput::Bytes(842556))
+        );
+        assert_eq!(parsed.rate().unwrap(), "1019.8 MiB/s");
+        assert_eq!(
+            measurement("bls/verify_update", 1.69e6, Throughput::Elements(1))
+                .rate()
+                .unwrap(),
+            "591.7 elem/s"
+        );
+    }
+
+    #[test]
+    fn test_regression_threshold() {
+        let baseline = [
+            measurement("state/verify_proofs", 225e3, Throughput::Elements(18)),
+            measurement("bls/verify_update", 1.7e6, Throughput::Elements(1)),
+        ];
+        let current = [
+            measurement("bls/verify_update", 1.8e6, Throughput::Elements(1)),
+            measurement("state/verify_proofs", 270e3, Throughput::Elements(18)),
+            measurement("decode/mainnet", 790e3, Throughput::Bytes(842556)),
+        ];
+        let comparisons = compare(&baseline, &current);
+        let regressed: Vec<_> = comparisons
+            .iter()
+            .filter(|c| c.regressed(DEFAULT_THRESHOLD))
+            .map(|c| c.current.id.as_str())
+            .collect();
+        assert_eq!(regressed, ["state/verify_proofs"]);
+        let table = render(&comparisons, DEFAULT_THRESHOLD);
+        assert!(table.contains("| bls/verify_update | 1.80 ms | 555.6 elem/s | +5.9% |\n"));
+        assert!(table
+            .contains("| state/verify_proofs | 270.00 µs | 66.7 Kelem/s | +20.0% regressed |\n"));
+        assert!(table.contains("| decode/mainnet | 790.00 µs | 1017.1 MiB/s | - |\n"));
+        assert_eq!(missing_baseline(&comparisons), ["decode/mainnet"]);
+    }
+
+    #[test]
+    fn test_load_requires_saved_results() {
+        let dir = tempfile::tempdir().unwrap();
+        let saved = dir.path().join("decode/mainnet/main");
+        fs::create_dir_all(&saved).unwrap();
+        fs::write(
+            saved.join("benchmark.json"),
+            r#"{"full_id":"decode/mainnet","throughput":{"Bytes":842556}}"#,
+        )
+        .unwrap();
+        fs::write(
+            saved.join("estimates.json"),
+            r#"{"mean":{"point_estimate":787927.4}}"#,
+        )
+        .unwrap();
+        assert_eq!(load(dir.path(), "main").unwrap().len(), 1);
+        assert!(matches!(
+            load(dir.path(), "mian"),
+            Err(BenchError::NoMeasurements { name, .. }) if name == "mian"
+        ));
+    }
+}
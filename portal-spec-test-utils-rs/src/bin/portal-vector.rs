@@ -0,0 +1,830 @@
+use clap::{Parser, Subcommand};
+use portal_spec_test_utils_rs::bench::{self, compare, render, DEFAULT_THRESHOLD};
+use portal_spec_test_utils_rs::block_proof;
+use portal_spec_test_utils_rs::chain::validate_vectors;
+use portal_spec_test_utils_rs::conformance::{run, self_test, RpcClient};
+use portal_spec_test_utils_rs::convert::{self, join, split_all};
+use portal_spec_test_utils_rs::coverage::{required_cells, scan};
+use portal_spec_test_utils_rs::discv5::Dissector;
+use portal_spec_test_utils_rs::distance::{
+    assign_storage, deterministic_node_id, render_assignments, DataRadius, StorageNode,
+};
+use portal_spec_test_utils_rs::fuzz::{seeds, Target};
+use portal_spec_test_utils_rs::gossip::{
+    gossip, render_outcomes, vector_content_keys, OfferLimits, Peer,
+};
+use portal_spec_test_utils_rs::invalid::{generate, stale_files, write_all};
+use portal_spec_test_utils_rs::lint::{lint_dir, Severity};
+use portal_spec_test_utils_rs::manifest::{build, verify};
+use portal_spec_test_utils_rs::network::Network;
+use portal_spec_test_utils_rs::pcap;
+use portal_spec_test_utils_rs::rpc::{serve, MockNode};
+use portal_spec_test_utils_rs::show::{describe_content, describe_entry, diff, Node};
+use portal_spec_test_utils_rs::simulator::{LookupStats, SimulationConfig, Simulator};
+use portal_spec_test_utils_rs::vector::{
+    decode_hex, network_dir, spec_tests_dir, ContentEntry, VectorFile,
+};
+use sha2::{Digest, Sha256};
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::Arc;
+
+#[derive(Parser, Debug)]
+#[command(
+    author,
+    version,
+    about = "Tooling for the Portal Network spec test vectors"
+)]
+struct Args {
+    #[arg(
+        long,
+        global = true,
+        default_value = "mainnet",
+        help = "mainnet, sepolia, holesky, or the path of a devnet chain config"
+    )]
+    network: String,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Check every vector file against its schema and the YAML style rules
+    Lint {
+        #[arg(help = "Directory to lint, defaults to the repository's tests directory")]
+        dir: Option<PathBuf>,
+
+        #[arg(long, help = "Treat warnings as errors")]
+        deny_warnings: bool,
+    },
+    /// Derive invalid variants of the vectors into `invalid` directories
+    GenInvalid {
+        #[arg(help = "Network directory, defaults to tests/<network>")]
+        dir: Option<PathBuf>,
+
+        #[arg(long, help = "Only report files that are missing or out of date")]
+        check: bool,
+    },
+    /// Decode the content keys and values of a vector file and print their fields
+    Show {
+        #[arg(help = "Vector file to decode", required_unless_present = "key")]
+        file: Option<PathBuf>,
+
+        #[arg(long, conflicts_with = "file", help = "Hex encoded content key")]
+        key: Option<String>,
+
+        #[arg(long, requires = "key", help = "Hex encoded content value")]
+        value: Option<String>,
+
+        #[arg(long, help = "Only show the entry with this label")]
+        entry: Option<String>,
+
+        #[arg(long, help = "Print long values in full")]
+        full: bool,
+    },
+    /// Compare two vector files field by field
+    Diff { left: PathBuf, right: PathBuf },
+    /// Check the header vectors against the consensus rules of their fork
+    CheckChain {
+        #[arg(help = "Network directory, defaults to tests/<network>")]
+        dir: Option<PathBuf>,
+    },
+    /// Print which of a set of deterministic nodes stores each vector
+    Assign {
+        #[arg(help = "Network directory, defaults to tests/<network>")]
+        dir: Option<PathBuf>,
+
+        #[arg(
+            long,
+            default_value_t = 8,
+            help = "Number of nodes, keyed by the seeds node-0, node-1, ..."
+        )]
+        nodes: usize,
+
+        #[arg(
+            long,
+            default_value_t = 254,
+            help = "Every node stores content below a distance of 2^radius_log2"
+        )]
+        radius_log2: u32,
+    },
+    /// Plan the OFFERs a bridge sends to gossip the vectors and model the ACCEPTs
+    Gossip {
+        #[arg(help = "Network directory, defaults to tests/<network>")]
+        dir: Option<PathBuf>,
+
+        #[arg(
+            long,
+            default_value_t = 8,
+            help = "Number of peers, keyed by the seeds node-0, node-1, ..."
+        )]
+        nodes: usize,
+
+        #[arg(
+            long,
+            default_value_t = 254,
+            help = "Every peer stores content below a distance of 2^radius_log2"
+        )]
+        radius_log2: u32,
+
+        #[arg(long, default_value_t = 4, help = "Peers each key is offered to")]
+        fan_out: usize,
+
+        #[arg(long, help = "Keys each peer accepts per OFFER before rate limiting")]
+        accept_limit: Option<usize>,
+    },
+    /// Simulate content lookups over a network of in-process nodes under churn
+    Simulate {
+        #[arg(
+            help = "Vector directory to seed content from, defaults to tests/<network>/history/hive"
+        )]
+        dir: Option<PathBuf>,
+
+        #[arg(long, default_value_t = 256)]
+        nodes: usize,
+
+        #[arg(long, default_value_t = 251)]
+        radius_log2: u32,
+
+        #[arg(long, default_value_t = 500)]
+        lookups: usize,
+
+        #[arg(
+            long,
+            value_delimiter = ',',
+            default_values_t = [0.0, 0.1, 0.25, 0.5],
+            help = "Shares of nodes taken offline, one run each"
+        )]
+        churn: Vec<f64>,
+
+        #[arg(long, default_value_t = 1)]
+        seed: u64,
+    },
+    /// Serve the vectors over JSON-RPC, on HTTP and WebSocket, as a mock Portal node
+    Serve {
+        #[arg(help = "Network directory, defaults to tests/<network>")]
+        dir: Option<PathBuf>,
+
+        #[arg(long, default_value = "127.0.0.1:8545")]
+        addr: String,
+    },
+    /// Replay the vectors against a Portal client's JSON-RPC and report per vector
+    Conformance {
+        #[arg(help = "Network directory, defaults to tests/<network>")]
+        dir: Option<PathBuf>,
+
+        #[arg(long, default_value = "http://127.0.0.1:8545")]
+        url: String,
+
+        #[arg(long, help = "Run against an in-process validating mock node instead")]
+        self_test: bool,
+    },
+    /// Build a beacon_block_proof file from an SSZ block and the state closing its period
+    BlockProof {
+        #[arg(help = "SSZ encoded SignedBeaconBlock")]
+        block: PathBuf,
+
+        #[arg(help = "SSZ encoded BeaconState at the first slot of the next period")]
+        state: PathBuf,
+
+        #[arg(
+            long,
+            help = "SSZ historical_summaries to check the block roots against"
+        )]
+        summaries: Option<PathBuf>,
+
+        #[arg(
+            long,
+            default_value = ".",
+            help = "Directory to write the proof file to"
+        )]
+        out: PathBuf,
+    },
+    /// Report which vectors cover each content type in each fork
+    Coverage {
+        #[arg(help = "Network directory, defaults to tests/<network>")]
+        dir: Option<PathBuf>,
+
+        #[arg(long, help = "Print the report as JSON instead of Markdown")]
+        json: bool,
+    },
+    /// Write or check the MANIFEST.sha256 of every file under the tests directory
+    Manifest {
+        #[arg(help = "Directory to cover, defaults to the repository's tests directory")]
+        dir: Option<PathBuf>,
+
+        #[arg(
+            long,
+            help = "Verify the files against the manifest instead of writing it"
+        )]
+        check: bool,
+    },
+    /// Decode the discv5 and Portal TALKREQ traffic of a pcap or pcapng capture
+    Dissect {
+        #[arg(help = "Capture file, pcap or pcapng")]
+        capture: PathBuf,
+
+        #[arg(
+            long,
+            help = "Node id the captured packets were sent to, hex, repeatable"
+        )]
+        node_id: Vec<String>,
+
+        #[arg(
+            long,
+            help = "AES-128 session key to decrypt messages with, hex, repeatable"
+        )]
+        key: Vec<String>,
+    },
+    /// Split the JSON collections into per-block YAML files, keeping the comments of existing ones
+    ToYaml {
+        #[arg(help = "Network directory, defaults to tests/<network>")]
+        dir: Option<PathBuf>,
+
+        #[arg(long, help = "Only report files that are missing or out of date")]
+        check: bool,
+    },
+    /// Join per-block YAML files into a JSON collection keyed by block number
+    ToJson {
+        #[arg(required = true, help = "YAML vector files")]
+        files: Vec<PathBuf>,
+    },
+    /// Write the vectors as seed corpora for the cargo-fuzz targets
+    FuzzCorpus {
+        #[arg(help = "Network directory, defaults to tests/<network>")]
+        dir: Option<PathBuf>,
+
+        #[arg(
+            long,
+            default_value = "fuzz/corpus",
+            help = "Directory to write one corpus per target to"
+        )]
+        out: PathBuf,
+    },
+    /// Print the latest benchmark throughput and fail on regressions against a saved baseline
+    BenchCheck {
+        #[arg(long, default_value = "target/criterion")]
+        criterion_dir: PathBuf,
+
+        #[arg(
+            long,
+            help = "Baseline saved with `cargo bench -- --save-baseline <name>`"
+        )]
+        baseline: Option<String>,
+
+        #[arg(
+            long,
+            default_value_t = DEFAULT_THRESHOLD * 100.0,
+            help = "Slowdown of the mean, in percent, that fails the check"
+        )]
+        threshold: f64,
+    },
+}
+
+fn lint(dir: Option<PathBuf>, deny_warnings: bool) -> Result<ExitCode, Box<dyn Error>> {
+    let dir = dir.unwrap_or_else(spec_tests_dir);
+    let issues = lint_dir(&dir)?;
+    for issue in &issues {
+        println!("{}", issue);
+    }
+
+    let failing = issues
+        .iter()
+        .filter(|issue| deny_warnings || issue.severity() == Severity::Error)
+        .count();
+    println!("{} issue(s), {} failing", issues.len(), failing);
+    Ok(if failing == 0 {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
+
+fn gen_invalid(
+    network: &Network,
+    dir: Option<PathBuf>,
+    check: bool,
+) -> Result<ExitCode, Box<dyn Error>> {
+    let dir = dir.unwrap_or_else(|| network_dir(&network.name));
+    let vectors = generate(network, &dir)?;
+    if check {
+        let stale = stale_files(&dir, &vectors);
+        for path in &stale {
+            println!("out of date: {}", path.display());
+        }
+        return Ok(if stale.is_empty() {
+            ExitCode::SUCCESS
+        } else {
+            ExitCode::FAILURE
+        });
+    }
+
+    let written = write_all(&dir, &vectors)?;
+    println!("wrote {} invalid vector(s)", written.len());
+    Ok(ExitCode::SUCCESS)
+}
+
+fn load_entries(path: &Path) -> Result<Vec<ContentEntry>, Box<dyn Error>> {
+    let file = VectorFile::load(Path::new(""), path)?;
+    Ok(file.content_entries()?)
+}
+
+fn show(
+    network: &Network,
+    file: Option<PathBuf>,
+    key: Option<String>,
+    value: Option<String>,
+    entry: Option<String>,
+    full: bool,
+) -> Result<ExitCode, Box<dyn Error>> {
+    if let Some(key) = key {
+        let value = value.map(|v| decode_hex(&v)).transpose()?;
+        print!(
+            "{}",
+            describe_content(network, &decode_hex(&key)?, value.as_deref()).render(full)
+        );
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    let path = file.expect("clap requires a file without --key");
+    let entries = load_entries(&path)?;
+    let mut shown = 0;
+    for content in entries
+        .iter()
+        .filter(|content| entry.as_ref().is_none_or(|label| *label == content.label))
+    {
+        if !content.label.is_empty() {
+            println!("== {} ==", content.label);
+        }
+        print!("{}", describe_entry(network, content).render(full));
+        shown += 1;
+    }
+    if shown == 0 {
+        eprintln!("no matching entries in {}", path.display());
+        return Ok(ExitCode::FAILURE);
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn diff_files(network: &Network, left: &Path, right: &Path) -> Result<ExitCode, Box<dyn Error>> {
+    let left_entries = load_entries(left)?;
+    let right_entries = load_entries(right)?;
+    let find = |entries: &[ContentEntry], label: &str| -> Option<Node> {
+        entries
+            .iter()
+            .find(|entry| entry.label == label)
+            .map(|entry| describe_entry(network, entry))
+    };
+
+    let mut labels: Vec<&str> = left_entries.iter().map(|e| e.label.as_str()).collect();
+    for entry in &right_entries {
+        if !labels.contains(&entry.label.as_str()) {
+            labels.push(&entry.label);
+        }
+    }
+
+    let mut changed = 0;
+    for label in labels {
+        let name = if label.is_empty() { "(root)" } else { label };
+        let differences = match (find(&left_entries, label), find(&right_entries, label)) {
+            (Some(a), Some(b)) => diff(&a, &b),
+            (Some(_), None) => {
+                println!("- {}: only in {}", name, left.display());
+                changed += 1;
+                continue;
+            }
+            (None, Some(_)) => {
+                println!("+ {}: only in {}", name, right.display());
+                changed += 1;
+                continue;
+            }
+            (None, None) => continue,
+        };
+        if differences.is_empty() {
+            continue;
+        }
+        println!("== {} ==", name);
+        for difference in &differences {
+            println!("{}", difference);
+        }
+        changed += 1;
+    }
+
+    println!("{} entr(ies) differ", changed);
+    Ok(if changed == 0 {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
+
+fn check_chain(network: &Network, dir: Option<PathBuf>) -> Result<ExitCode, Box<dyn Error>> {
+    let dir = dir.unwrap_or_else(|| network_dir(&network.name));
+    let violations = validate_vectors(network, &dir)?;
+    for violation in &violations {
+        println!("{}", violation);
+    }
+    println!("{} violation(s)", violations.len());
+    Ok(if violations.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
+
+fn assign(
+    network: &Network,
+    dir: Option<PathBuf>,
+    count: usize,
+    radius_log2: u32,
+) -> Result<ExitCode, Box<dyn Error>> {
+    let dir = dir.unwrap_or_else(|| network_dir(&network.name));
+    let nodes: Vec<StorageNode> = (0..count)
+        .map(|i| StorageNode {
+            node_id: deterministic_node_id(i),
+            radius: DataRadius::from_log2(radius_log2),
+        })
+        .collect();
+    let assignments = assign_storage(&dir, &nodes)?;
+    print!("{}", render_assignments(&nodes, &assignments));
+    Ok(ExitCode::SUCCESS)
+}
+
+fn gossip_vectors(
+    network: &Network,
+    dir: Option<PathBuf>,
+    count: usize,
+    radius_log2: u32,
+    fan_out: usize,
+    accept_limit: Option<usize>,
+) -> Result<ExitCode, Box<dyn Error>> {
+    let dir = dir.unwrap_or_else(|| network_dir(&network.name));
+    let mut peers: Vec<Peer> = (0..count)
+        .map(|i| Peer {
+            accept_limit,
+            ..Peer::new(deterministic_node_id(i), DataRadius::from_log2(radius_log2))
+        })
+        .collect();
+    let limits = OfferLimits {
+        fan_out,
+        ..OfferLimits::default()
+    };
+    let keys = vector_content_keys(&dir)?;
+    let outcomes = gossip(&keys, &mut peers, &limits)?;
+    print!("{}", render_outcomes(&peers, &outcomes));
+    Ok(ExitCode::SUCCESS)
+}
+
+fn print_stats(label: &str, stats: &LookupStats) {
+    let histogram: Vec<String> = stats
+        .hop_histogram
+        .iter()
+        .map(|(hops, count)| format!("{}:{}", hops, count))
+        .collect();
+    println!(
+        "  {:<8} {:>4}/{:<4} found ({:>5.1}%), mean hops {:.2}, max {}, timeouts {}, hops [{}]",
+        label,
+        stats.successes,
+        stats.lookups,
+        stats.success_rate() * 100.0,
+        stats.mean_hops,
+        stats.max_hops,
+        stats.timeouts,
+        histogram.join(" ")
+    );
+}
+
+fn simulate(
+    network: &Network,
+    dir: Option<PathBuf>,
+    config: SimulationConfig,
+    lookups: usize,
+    churn: &[f64],
+) -> Result<ExitCode, Box<dyn Error>> {
+    let dir = dir.unwrap_or_else(|| network_dir(&network.name).join("history/hive"));
+    let mut simulator = Simulator::new(config);
+    let seeded = simulator.seed_from_vectors(&dir)?;
+    println!(
+        "{} nodes, radius 2^{:.0}, {} content items from {}",
+        config.nodes,
+        config.radius.0.as_f64().log2(),
+        seeded,
+        dir.display()
+    );
+    for share in churn {
+        let mut run = simulator.clone();
+        run.apply_churn(*share);
+        println!(
+            "churn {:.0}% ({} nodes online)",
+            share * 100.0,
+            run.online_count()
+        );
+        print_stats("content", &run.run_content_lookups(lookups));
+        print_stats("nodes", &run.run_node_lookups(lookups));
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn serve_vectors(
+    network: &Network,
+    dir: Option<PathBuf>,
+    addr: &str,
+) -> Result<ExitCode, Box<dyn Error>> {
+    let dir = dir.unwrap_or_else(|| network_dir(&network.name));
+    let node = Arc::new(MockNode::from_vectors(network, &dir)?);
+    let runtime = tokio::runtime::Runtime::new()?;
+    runtime.block_on(async {
+        let listener = tokio::net::TcpListener::bind(addr).await?;
+        println!(
+            "Serving {} as {} on http://{addr} and ws://{addr}",
+            dir.display(),
+            node.enr,
+            addr = listener.local_addr()?
+        );
+        serve(node, listener).await
+    })?;
+    Ok(ExitCode::SUCCESS)
+}
+
+fn conformance(
+    network: &Network,
+    dir: Option<PathBuf>,
+    url: &str,
+    mock: bool,
+) -> Result<ExitCode, Box<dyn Error>> {
+    let dir = dir.unwrap_or_else(|| network_dir(&network.name));
+    let report = if mock {
+        self_test(network, &dir)?
+    } else {
+        run(&mut RpcClient::new(url)?, network, &dir)?
+    };
+    print!("{}", report.render());
+    Ok(if report.failed() == 0 {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
+
+fn gen_block_proof(
+    network: &Network,
+    block: &Path,
+    state: &Path,
+    summaries: Option<&Path>,
+    out: &Path,
+) -> Result<ExitCode, Box<dyn Error>> {
+    let summaries = summaries.map(std::fs::read).transpose()?;
+    let proof = block_proof::generate(
+        network,
+        &std::fs::read(block)?,
+        &std::fs::read(state)?,
+        summaries.as_deref(),
+    )?;
+    let path = out.join(proof.file_name());
+    std::fs::write(&path, proof.to_yaml())?;
+    println!("wrote {}", path.display());
+    Ok(ExitCode::SUCCESS)
+}
+
+fn coverage(
+    network: &Network,
+    dir: Option<PathBuf>,
+    json: bool,
+) -> Result<ExitCode, Box<dyn Error>> {
+    let dir = dir.unwrap_or_else(|| network_dir(&network.name));
+    let required = required_cells();
+    let coverage = scan(network, &dir)?;
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&coverage.to_json(&required))?
+        );
+    } else {
+        print!("{}", coverage.to_markdown(&required));
+    }
+    Ok(if coverage.missing(&required).is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
+
+fn manifest(dir: Option<PathBuf>, check: bool) -> Result<ExitCode, Box<dyn Error>> {
+    let dir = dir.unwrap_or_else(spec_tests_dir);
+    if check {
+        let changes = verify(&dir)?;
+        for change in &changes {
+            println!("{}", change);
+        }
+        println!("{} change(s) not in the manifest", changes.len());
+        return Ok(if changes.is_empty() {
+            ExitCode::SUCCESS
+        } else {
+            ExitCode::FAILURE
+        });
+    }
+
+    let manifest = build(&dir)?;
+    let path = manifest.write(&dir)?;
+    println!(
+        "wrote {} with {} file(s)",
+        path.display(),
+        manifest.files.len()
+    );
+    Ok(ExitCode::SUCCESS)
+}
+
+fn to_yaml(
+    network: &Network,
+    dir: Option<PathBuf>,
+    check: bool,
+) -> Result<ExitCode, Box<dyn Error>> {
+    let dir = dir.unwrap_or_else(|| network_dir(&network.name));
+    let split = split_all(&dir)?;
+    let stale = convert::stale_files(&dir, &split);
+    if check {
+        for path in &stale {
+            println!("out of date: {}", path.display());
+        }
+        return Ok(if stale.is_empty() {
+            ExitCode::SUCCESS
+        } else {
+            ExitCode::FAILURE
+        });
+    }
+
+    convert::write_all(&dir, &split)?;
+    println!(
+        "wrote {} per-block file(s), {} changed",
+        split.len(),
+        stale.len()
+    );
+    Ok(ExitCode::SUCCESS)
+}
+
+fn to_json(files: &[PathBuf]) -> Result<ExitCode, Box<dyn Error>> {
+    let files = files
+        .iter()
+        .map(|path| VectorFile::load(Path::new(""), path))
+        .collect::<Result<Vec<_>, _>>()?;
+    println!("{}", serde_json::to_string_pretty(&join(&files)?)?);
+    Ok(ExitCode::SUCCESS)
+}
+
+fn decode_fixed<const N: usize>(hex: &str) -> Result<[u8; N], Box<dyn Error>> {
+    decode_hex(hex)?.try_into().map_err(|bytes: Vec<u8>| {
+        format!("expected {} bytes, {} has {}", N, hex, bytes.len()).into()
+    })
+}
+
+fn dissect(
+    capture: &Path,
+    node_ids: &[String],
+    keys: &[String],
+) -> Result<ExitCode, Box<dyn Error>> {
+    let node_ids = node_ids
+        .iter()
+        .map(|id| decode_fixed(id))
+        .collect::<Result<_, _>>()?;
+    let keys = keys
+        .iter()
+        .map(|key| decode_fixed(key))
+        .collect::<Result<_, _>>()?;
+    let mut dissector = Dissector::new(node_ids, keys);
+    let data = pcap::read(capture)?;
+    let frames = pcap::frames(&data)?;
+    let datagrams = pcap::datagrams(&frames)?;
+    for datagram in &datagrams {
+        println!("{}", dissector.dissect(datagram));
+    }
+    println!("{}", pcap::Summary::new(&frames, &datagrams));
+    Ok(ExitCode::SUCCESS)
+}
+
+fn fuzz_corpus(
+    network: &Network,
+    dir: Option<PathBuf>,
+    out: &Path,
+) -> Result<ExitCode, Box<dyn Error>> {
+    let dir = dir.unwrap_or_else(|| network_dir(&network.name));
+    for target in Target::ALL {
+        let seeds = seeds(target, &dir)?;
+        let corpus = out.join(target.name());
+        fs::create_dir_all(&corpus)?;
+        for seed in &seeds {
+            fs::write(corpus.join(hex::encode(Sha256::digest(seed))), seed)?;
+        }
+        println!("{}: {} seed(s)", target, seeds.len());
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn bench_check(
+    criterion_dir: &Path,
+    baseline: Option<&str>,
+    threshold: f64,
+) -> Result<ExitCode, Box<dyn Error>> {
+    let current = bench::load(criterion_dir, "new")?;
+    let saved = match baseline {
+        Some(name) => bench::load(criterion_dir, name)?,
+        None => Vec::new(),
+    };
+    let comparisons = compare(&saved, &current);
+    print!("{}", render(&comparisons, threshold / 100.0));
+    let regressed = comparisons
+        .iter()
+        .filter(|comparison| comparison.regressed(threshold / 100.0))
+        .count();
+    let mut missing = Vec::new();
+    if let Some(name) = baseline {
+        missing = bench::missing_baseline(&comparisons);
+        for id in &missing {
+            println!("{} has no result in baseline {}", id, name);
+        }
+        println!(
+            "{} benchmark(s) regressed by more than {}%",
+            regressed, threshold
+        );
+    }
+    Ok(if regressed == 0 && missing.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
+
+fn main() -> Result<ExitCode, Box<dyn Error>> {
+    let args = Args::parse();
+    let network = &Network::resolve(&args.network)?;
+    match args.command {
+        Command::Lint { dir, deny_warnings } => lint(dir, deny_warnings),
+        Command::GenInvalid { dir, check } => gen_invalid(network, dir, check),
+        Command::Show {
+            file,
+            key,
+            value,
+            entry,
+            full,
+        } => show(network, file, key, value, entry, full),
+        Command::Diff { left, right } => diff_files(network, &left, &right),
+        Command::CheckChain { dir } => check_chain(network, dir),
+        Command::Assign {
+            dir,
+            nodes,
+            radius_log2,
+        } => assign(network, dir, nodes, radius_log2),
+        Command::Gossip {
+            dir,
+            nodes,
+            radius_log2,
+            fan_out,
+            accept_limit,
+        } => gossip_vectors(network, dir, nodes, radius_log2, fan_out, accept_limit),
+        Command::Simulate {
+            dir,
+            nodes,
+            radius_log2,
+            lookups,
+            churn,
+            seed,
+        } => {
+            let config = SimulationConfig {
+                nodes,
+                radius: DataRadius::from_log2(radius_log2),
+                seed,
+                ..SimulationConfig::default()
+            };
+            simulate(network, dir, config, lookups, &churn)
+        }
+        Command::Serve { dir, addr } => serve_vectors(network, dir, &addr),
+        Command::Conformance {
+            dir,
+            url,
+            self_test,
+        } => conformance(network, dir, &url, self_test),
+        Command::BlockProof {
+            block,
+            state,
+            summaries,
+            out,
+        } => gen_block_proof(network, &block, &state, summaries.as_deref(), &out),
+        Command::Coverage { dir, json } => coverage(network, dir, json),
+        Command::Manifest { dir, check } => manifest(dir, check),
+        Command::Dissect {
+            capture,
+            node_id,
+            key,
+        } => dissect(&capture, &node_id, &key),
+        Command::ToYaml { dir, check } => to_yaml(network, dir, check),
+        Command::ToJson { files } => to_json(&files),
+        Command::FuzzCorpus { dir, out } => fuzz_corpus(network, dir, &out),
+        Command::BenchCheck {
+            criterion_dir,
+            baseline,
+            threshold,
+        } => bench_check(&criterion_dir, baseline.as_deref(), threshold),
+    }
+}
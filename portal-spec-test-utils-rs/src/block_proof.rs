@@ -0,0 +1,831 @@
+use crate::content::{BlockHeaderProof, BlockProofHistoricalRoots, BlockProofHistoricalSummaries};
+use crate::network::Network;
+use crate::ssz::{concat_generalized_indices, PathElement, SszError, SszType, SszValue};
+use crate::vector::{decode_hex, encode_hex};
+use crate::yaml::Document;
+use serde_json::Value;
+use std::fmt;
+use thiserror::Error;
+
+pub const SLOTS_PER_HISTORICAL_ROOT: u64 = 8192;
+
+/// Generalized index of the body in a beacon block.
+const BODY_GINDEX: u64 = 12;
+
+/// Byte offset of `block_roots` in a beacon state, the same in every fork.
+const STATE_BLOCK_ROOTS_OFFSET: usize = 176;
+const STATE_SLOT_OFFSET: usize = 40;
+const ROOTS_VECTOR_SIZE: usize = SLOTS_PER_HISTORICAL_ROOT as usize * 32;
+
+const MAX_VALIDATORS_PER_COMMITTEE: usize = 2048;
+const MAX_COMMITTEES_PER_SLOT: usize = 64;
+const MAX_BLOB_COMMITMENTS_PER_BLOCK: usize = 4096;
+const SYNC_COMMITTEE_SIZE: usize = 512;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum BlockProofError {
+    #[error("invalid SSZ: {0}")]
+    Ssz(#[from] SszError),
+    #[error("slot {0} is before Bellatrix and has no execution payload")]
+    PreBellatrix(u64),
+    #[error("block at slot {0} has an empty execution payload")]
+    EmptyPayload(u64),
+    #[error("beacon state is {0} bytes, too short for its block roots")]
+    ShortState(usize),
+    #[error("beacon state is at slot {found}, the block's period needs slot {expected}")]
+    StateSlot { expected: u64, found: u64 },
+    #[error("block root of slot {0} does not match the state's block_roots")]
+    BlockRootMismatch(u64),
+    #[error("no historical summary for period {0}")]
+    MissingSummary(u64),
+    #[error("block_roots of period {0} do not match its historical summary")]
+    SummaryMismatch(u64),
+    #[error("invalid proof file: {0}")]
+    Parse(String),
+}
+
+/// Forks with an execution payload in the beacon block body.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum BlockFork {
+    Bellatrix,
+    Capella,
+    Deneb,
+    Electra,
+}
+
+impl BlockFork {
+    pub fn name(&self) -> &'static str {
+        match self {
+            BlockFork::Bellatrix => "bellatrix",
+            BlockFork::Capella => "capella",
+            BlockFork::Deneb => "deneb",
+            BlockFork::Electra => "electra",
+        }
+    }
+}
+
+impl fmt::Display for BlockFork {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+fn signature() -> SszType {
+    SszType::ByteVector(96)
+}
+
+fn pubkey() -> SszType {
+    SszType::ByteVector(48)
+}
+
+fn address() -> SszType {
+    SszType::ByteVector(20)
+}
+
+fn beacon_block_header() -> SszType {
+    SszType::container([
+        ("slot", SszType::uint64()),
+        ("proposer_index", SszType::uint64()),
+        ("parent_root", SszType::bytes32()),
+        ("state_root", SszType::bytes32()),
+        ("body_root", SszType::bytes32()),
+    ])
+}
+
+fn checkpoint() -> SszType {
+    SszType::container([("epoch", SszType::uint64()), ("root", SszType::bytes32())])
+}
+
+fn attestation_data() -> SszType {
+    SszType::container([
+        ("slot", SszType::uint64()),
+        ("index", SszType::uint64()),
+        ("beacon_block_root", SszType::bytes32()),
+        ("source", checkpoint()),
+        ("target", checkpoint()),
+    ])
+}
+
+fn attester_slashing(fork: BlockFork) -> SszType {
+    let indices = match fork {
+        BlockFork::Electra => MAX_VALIDATORS_PER_COMMITTEE * MAX_COMMITTEES_PER_SLOT,
+        _ => MAX_VALIDATORS_PER_COMMITTEE,
+    };
+    let indexed = SszType::container([
+        (
+            "attesting_indices",
+            SszType::list(SszType::uint64(), indices),
+        ),
+        ("data", attestation_data()),
+        ("signature", signature()),
+    ]);
+    SszType::container([
+        ("attestation_1", indexed.clone()),
+        ("attestation_2", indexed),
+    ])
+}
+
+fn attestation(fork: BlockFork) -> SszType {
+    match fork {
+        BlockFork::Electra => SszType::container([
+            (
+                "aggregation_bits",
+                SszType::Bitlist(MAX_VALIDATORS_PER_COMMITTEE * MAX_COMMITTEES_PER_SLOT),
+            ),
+            ("data", attestation_data()),
+            ("signature", signature()),
+            (
+                "committee_bits",
+                SszType::Bitvector(MAX_COMMITTEES_PER_SLOT),
+            ),
+        ]),
+        _ => SszType::container([
+            (
+                "aggregation_bits",
+                SszType::Bitlist(MAX_VALIDATORS_PER_COMMITTEE),
+            ),
+            ("data", attestation_data()),
+            ("signature", signature()),
+        ]),
+    }
+}
+
+fn deposit() -> SszType {
+    SszType::container([
+        ("proof", SszType::vector(SszType::bytes32(), 33)),
+        (
+            "data",
+            SszType::container([
+                ("pubkey", pubkey()),
+                ("withdrawal_credentials", SszType::bytes32()),
+                ("amount", SszType::uint64()),
+                ("signature", signature()),
+            ]),
+        ),
+    ])
+}
+
+fn execution_payload(fork: BlockFork) -> SszType {
+    let mut fields = vec![
+        ("parent_hash", SszType::bytes32()),
+        ("fee_recipient", address()),
+        ("state_root", SszType::bytes32()),
+        ("receipts_root", SszType::bytes32()),
+        ("logs_bloom", SszType::ByteVector(256)),
+        ("prev_randao", SszType::bytes32()),
+        ("block_number", SszType::uint64()),
+        ("gas_limit", SszType::uint64()),
+        ("gas_used", SszType::uint64()),
+        ("timestamp", SszType::uint64()),
+        ("extra_data", SszType::ByteList(32)),
+        ("base_fee_per_gas", SszType::uint256()),
+        ("block_hash", SszType::bytes32()),
+        (
+            "transactions",
+            SszType::list(SszType::ByteList(1 << 30), 1 << 20),
+        ),
+    ];
+    if fork >= BlockFork::Capella {
+        let withdrawal = SszType::container([
+            ("index", SszType::uint64()),
+            ("validator_index", SszType::uint64()),
+            ("address", address()),
+            ("amount", SszType::uint64()),
+        ]);
+        fields.push(("withdrawals", SszType::list(withdrawal, 16)));
+    }
+    if fork >= BlockFork::Deneb {
+        fields.push(("blob_gas_used", SszType::uint64()));
+        fields.push(("excess_blob_gas", SszType::uint64()));
+    }
+    SszType::container(fields)
+}
+
+fn execution_requests() -> SszType {
+    let deposit = SszType::container([
+        ("pubkey", pubkey()),
+        ("withdrawal_credentials", SszType::bytes32()),
+        ("amount", SszType::uint64()),
+        ("signature", signature()),
+        ("index", SszType::uint64()),
+    ]);
+    let withdrawal = SszType::container([
+        ("source_address", address()),
+        ("validator_pubkey", pubkey()),
+        ("amount", SszType::uint64()),
+    ]);
+    let consolidation = SszType::container([
+        ("source_address", address()),
+        ("source_pubkey", pubkey()),
+        ("target_pubkey", pubkey()),
+    ]);
+    SszType::container([
+        ("deposits", SszType::list(deposit, 8192)),
+        ("withdrawals", SszType::list(withdrawal, 16)),
+        ("consolidations", SszType::list(consolidation, 2)),
+    ])
+}
+
+/// The mainnet `BeaconBlockBody` of a fork.
+pub fn body_type(fork: BlockFork) -> SszType {
+    let signed_header = SszType::container([
+        ("message", beacon_block_header()),
+        ("signature", signature()),
+    ]);
+    let voluntary_exit = SszType::container([
+        (
+            "message",
+            SszType::container([
+                ("epoch", SszType::uint64()),
+                ("validator_index", SszType::uint64()),
+            ]),
+        ),
+        ("signature", signature()),
+    ]);
+    let (max_attester_slashings, max_attestations) = match fork {
+        BlockFork::Electra => (1, 8),
+        _ => (2, 128),
+    };
+    let mut fields = vec![
+        ("randao_reveal", signature()),
+        (
+            "eth1_data",
+            SszType::container([
+                ("deposit_root", SszType::bytes32()),
+                ("deposit_count", SszType::uint64()),
+                ("block_hash", SszType::bytes32()),
+            ]),
+        ),
+        ("graffiti", SszType::bytes32()),
+        (
+            "proposer_slashings",
+            SszType::list(
+                SszType::container([
+                    ("signed_header_1", signed_header.clone()),
+                    ("signed_header_2", signed_header),
+                ]),
+                16,
+            ),
+        ),
+        (
+            "attester_slashings",
+            SszType::list(attester_slashing(fork), max_attester_slashings),
+        ),
+        (
+            "attestations",
+            SszType::list(attestation(fork), max_attestations),
+        ),
+        ("deposits", SszType::list(deposit(), 16)),
+        ("voluntary_exits", SszType::list(voluntary_exit, 16)),
+        (
+            "sync_aggregate",
+            SszType::container([
+                (
+                    "sync_committee_bits",
+                    SszType::Bitvector(SYNC_COMMITTEE_SIZE),
+                ),
+                ("sync_committee_signature", signature()),
+            ]),
+        ),
+        ("execution_payload", execution_payload(fork)),
+    ];
+    if fork >= BlockFork::Capella {
+        let change = SszType::container([
+            (
+                "message",
+                SszType::container([
+                    ("validator_index", SszType::uint64()),
+                    ("from_bls_pubkey", pubkey()),
+                    ("to_execution_address", address()),
+                ]),
+            ),
+            ("signature", signature()),
+        ]);
+        fields.push(("bls_to_execution_changes", SszType::list(change, 16)));
+    }
+    if fork >= BlockFork::Deneb {
+        fields.push((
+            "blob_kzg_commitments",
+            SszType::list(SszType::ByteVector(48), MAX_BLOB_COMMITMENTS_PER_BLOCK),
+        ));
+    }
+    if fork >= BlockFork::Electra {
+        fields.push(("execution_requests", execution_requests()));
+    }
+    SszType::container(fields)
+}
+
+/// Generalized index of the block hash in a beacon block body, one level
+/// deeper from Deneb on as the execution payload outgrows 16 fields.
+pub fn block_hash_gindex(fork: BlockFork) -> u64 {
+    body_type(fork)
+        .generalized_index(&[
+            PathElement::Field("execution_payload"),
+            PathElement::Field("block_hash"),
+        ])
+        .expect("the body has an execution payload")
+}
+
+/// The mainnet `SignedBeaconBlock` of a fork.
+pub fn signed_block_type(fork: BlockFork) -> SszType {
+    SszType::container([
+        (
+            "message",
+            SszType::container([
+                ("slot", SszType::uint64()),
+                ("proposer_index", SszType::uint64()),
+                ("parent_root", SszType::bytes32()),
+                ("state_root", SszType::bytes32()),
+                ("body", body_type(fork)),
+            ]),
+        ),
+        ("signature", signature()),
+    ])
+}
+
+/// Proofs from an execution block hash to the block roots of its period, as in
+/// the `block_proofs_bellatrix` and `block_proofs_capella` vectors.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockProof {
+    pub block_number: u64,
+    pub block_hash: [u8; 32],
+    pub body_proof: Vec<[u8; 32]>,
+    pub body_root: [u8; 32],
+    pub header_proof: Vec<[u8; 32]>,
+    pub header_root: [u8; 32],
+    /// Proof into the `HistoricalBatch` before Capella, into `block_roots` after.
+    pub block_roots_proof: Vec<[u8; 32]>,
+    pub slot: u64,
+    pub fork: BlockFork,
+}
+
+impl BlockProof {
+    /// Whether the proof goes against `historical_roots` rather than `historical_summaries`.
+    pub fn is_historical_roots(&self) -> bool {
+        self.fork == BlockFork::Bellatrix
+    }
+
+    fn proof_field(&self) -> &'static str {
+        if self.is_historical_roots() {
+            "historical_roots_proof"
+        } else {
+            "historical_summaries_proof"
+        }
+    }
+
+    /// The proof a `HeaderWithProof` carries for the block: the body and header
+    /// proofs together lead from the block hash to the beacon block root.
+    pub fn to_block_header_proof(&self) -> BlockHeaderProof {
+        let execution_block_proof = [&self.body_proof[..], &self.header_proof[..]].concat();
+        if self.is_historical_roots() {
+            return BlockHeaderProof::HistoricalRoots(BlockProofHistoricalRoots {
+                beacon_block_proof: self.block_roots_proof.clone(),
+                beacon_block_root: self.header_root,
+                execution_block_proof,
+                slot: self.slot,
+            });
+        }
+        let proof = BlockProofHistoricalSummaries {
+            beacon_block_proof: self.block_roots_proof.clone(),
+            beacon_block_root: self.header_root,
+            execution_block_proof,
+            slot: self.slot,
+        };
+        if self.fork == BlockFork::Capella {
+            BlockHeaderProof::HistoricalSummaries(proof)
+        } else {
+            BlockHeaderProof::HistoricalSummariesDeneb(proof)
+        }
+    }
+
+    /// The file name the vectors use for this proof.
+    pub fn file_name(&self) -> String {
+        if self.is_historical_roots() {
+            format!(
+                "beacon_block_proof-{}-{}.yaml",
+                self.block_number,
+                hex::encode(self.block_hash)
+            )
+        } else {
+            format!("beacon_block_proof-{}.yaml", self.block_number)
+        }
+    }
+
+    pub fn to_yaml(&self) -> String {
+        let mut out = format!("# block number: {}\n", self.block_number);
+        push_root(&mut out, "execution_block_header", &self.block_hash);
+        push_branch(&mut out, "beacon_block_body_proof", &self.body_proof);
+        push_root(&mut out, "beacon_block_body_root", &self.body_root);
+        push_branch(&mut out, "beacon_block_header_proof", &self.header_proof);
+        push_root(&mut out, "beacon_block_header_root", &self.header_root);
+        push_branch(&mut out, self.proof_field(), &self.block_roots_proof);
+        out.push_str(&format!("slot: {}\n", self.slot));
+        out
+    }
+
+    /// Reads a proof back from its YAML file, named as `file_name` gives.
+    pub fn from_yaml(
+        network: &Network,
+        file_name: &str,
+        text: &str,
+    ) -> Result<BlockProof, BlockProofError> {
+        let document = Document::parse(text)
+            .and_then(|document| document.to_value())
+            .map_err(|e| BlockProofError::Parse(e.to_string()))?;
+        let root = |value: &Value| -> Result<[u8; 32], BlockProofError> {
+            let text = value
+                .as_str()
+                .ok_or_else(|| BlockProofError::Parse("expected a hex string".to_string()))?;
+            decode_hex(text)
+                .ok()
+                .and_then(|bytes| bytes.try_into().ok())
+                .ok_or_else(|| BlockProofError::Parse(format!("invalid root {}", text)))
+        };
+        let field = |name: &str| root(&document[name]);
+        let branch = |name: &str| -> Result<Vec<[u8; 32]>, BlockProofError> {
+            document[name]
+                .as_array()
+                .ok_or_else(|| BlockProofError::Parse(format!("missing {}", name)))?
+                .iter()
+                .map(root)
+                .collect()
+        };
+        let slot = document["slot"]
+            .as_u64()
+            .ok_or_else(|| BlockProofError::Parse("missing slot".to_string()))?;
+        let block_number = file_name
+            .strip_prefix("beacon_block_proof-")
+            .and_then(|rest| rest.split(['-', '.']).next())
+            .and_then(|number| number.parse().ok())
+            .ok_or_else(|| BlockProofError::Parse(format!("{} names no block", file_name)))?;
+        let fork = network
+            .block_fork(slot)
+            .ok_or(BlockProofError::PreBellatrix(slot))?;
+        let proof_field = if fork == BlockFork::Bellatrix {
+            "historical_roots_proof"
+        } else {
+            "historical_summaries_proof"
+        };
+        Ok(BlockProof {
+            block_number,
+            block_hash: field("execution_block_header")?,
+            body_proof: branch("beacon_block_body_proof")?,
+            body_root: field("beacon_block_body_root")?,
+            header_proof: branch("beacon_block_header_proof")?,
+            header_root: field("beacon_block_header_root")?,
+            block_roots_proof: branch(proof_field)?,
+            slot,
+            fork,
+        })
+    }
+}
+
+fn push_root(out: &mut String, name: &str, root: &[u8; 32]) {
+    out.push_str(&format!("{}: \"{}\"\n", name, encode_hex(root)));
+}
+
+fn push_branch(out: &mut String, name: &str, branch: &[[u8; 32]]) {
+    out.push_str(&format!("{}:\n", name));
+    for node in branch {
+        out.push_str(&format!("  - \"{}\"\n", encode_hex(node)));
+    }
+}
+
+/// Reads the slot of a `SignedBeaconBlock` without knowing its fork.
+fn block_slot(block: &[u8]) -> Result<u64, BlockProofError> {
+    let offset = block
+        .get(..4)
+        .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()) as usize)
+        .ok_or(SszError::UnexpectedEnd { len: 4, pos: 0 })?;
+    let slot = block
+        .get(offset..offset + 8)
+        .ok_or(SszError::InvalidOffset(offset))?;
+    Ok(u64::from_le_bytes(slot.try_into().unwrap()))
+}
+
+fn roots_value(data: &[u8]) -> SszValue {
+    SszValue::Sequence(
+        data.chunks(32)
+            .map(|root| SszValue::Bytes(root.to_vec()))
+            .collect(),
+    )
+}
+
+/// Builds the proofs of a block from its SSZ `SignedBeaconBlock` and the SSZ
+/// `BeaconState` at the end of its period, the first slot of the next one.
+///
+/// From Capella on, `historical_summaries` are checked to commit to the
+/// state's block roots when given.
+pub fn generate(
+    network: &Network,
+    block: &[u8],
+    state: &[u8],
+    historical_summaries: Option<&[u8]>,
+) -> Result<BlockProof, BlockProofError> {
+    let slot = block_slot(block)?;
+    let fork = network
+        .block_fork(slot)
+        .ok_or(BlockProofError::PreBellatrix(slot))?;
+    let signed_type = signed_block_type(fork);
+    let SszValue::Container(signed) = signed_type.decode(block)? else {
+        unreachable!("containers decode to containers");
+    };
+    let SszType::Container(signed_fields) = &signed_type else {
+        unreachable!();
+    };
+    let tree = signed_fields[0].1.merkle_tree(&signed[0])?;
+    let body_gindex = block_hash_gindex(fork);
+    let block_hash_gindex = concat_generalized_indices(&[BODY_GINDEX, body_gindex]);
+    let block_hash = tree.node_at(block_hash_gindex).unwrap();
+    if block_hash == [0u8; 32] {
+        return Err(BlockProofError::EmptyPayload(slot));
+    }
+    let mut body_proof = tree.prove(block_hash_gindex).unwrap();
+    let header_proof = body_proof.split_off(body_gindex.ilog2() as usize);
+    let block_number = signed[0].as_items().unwrap()[4].as_items().unwrap()[9]
+        .as_items()
+        .unwrap()[6]
+        .as_u64()
+        .unwrap();
+
+    let period = slot / SLOTS_PER_HISTORICAL_ROOT;
+    let roots_end = STATE_BLOCK_ROOTS_OFFSET + 2 * ROOTS_VECTOR_SIZE;
+    if state.len() < roots_end {
+        return Err(BlockProofError::ShortState(state.len()));
+    }
+    let state_slot = u64::from_le_bytes(
+        state[STATE_SLOT_OFFSET..STATE_SLOT_OFFSET + 8]
+            .try_into()
+            .unwrap(),
+    );
+    let expected = (period + 1) * SLOTS_PER_HISTORICAL_ROOT;
+    if state_slot != expected {
+        return Err(BlockProofError::StateSlot {
+            expected,
+            found: state_slot,
+        });
+    }
+    let block_roots =
+        &state[STATE_BLOCK_ROOTS_OFFSET..STATE_BLOCK_ROOTS_OFFSET + ROOTS_VECTOR_SIZE];
+    let index = (slot % SLOTS_PER_HISTORICAL_ROOT) as usize;
+    let header_root = tree.root();
+    if block_roots[index * 32..(index + 1) * 32] != header_root {
+        return Err(BlockProofError::BlockRootMismatch(slot));
+    }
+
+    let roots_type = SszType::vector(SszType::bytes32(), SLOTS_PER_HISTORICAL_ROOT as usize);
+    let block_roots_proof = if fork == BlockFork::Bellatrix {
+        let batch_type = SszType::container([
+            ("block_roots", roots_type.clone()),
+            ("state_roots", roots_type),
+        ]);
+        let batch = SszValue::Container(vec![
+            roots_value(block_roots),
+            roots_value(&state[STATE_BLOCK_ROOTS_OFFSET + ROOTS_VECTOR_SIZE..roots_end]),
+        ]);
+        let gindex = batch_type
+            .generalized_index(&[PathElement::Field("block_roots"), PathElement::Index(index)])?;
+        batch_type.merkle_tree(&batch)?.prove(gindex).unwrap()
+    } else {
+        let roots_tree = roots_type.merkle_tree(&roots_value(block_roots))?;
+        if let Some(summaries) = historical_summaries {
+            let summary_index = period - network.capella_slot() / SLOTS_PER_HISTORICAL_ROOT;
+            let at = summary_index as usize * 64;
+            let summary = summaries
+                .get(at..at + 32)
+                .ok_or(BlockProofError::MissingSummary(period))?;
+            if summary != roots_tree.root() {
+                return Err(BlockProofError::SummaryMismatch(period));
+            }
+        }
+        let gindex = roots_type.generalized_index(&[PathElement::Index(index)])?;
+        roots_tree.prove(gindex).unwrap()
+    };
+
+    Ok(BlockProof {
+        block_number,
+        block_hash,
+        body_proof,
+        body_root: tree.node_at(BODY_GINDEX).unwrap(),
+        header_proof,
+        header_root,
+        block_roots_proof,
+        slot,
+        fork,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ssz::verify_merkle_proof;
+    use crate::vector::network_dir;
+
+    const BELLATRIX_SLOT: u64 = 4636672;
+    const CAPELLA_SLOT: u64 = 6209536;
+    const DENEB_SLOT: u64 = 8626176;
+    const ELECTRA_SLOT: u64 = 11649024;
+
+    fn fork_at(slot: u64) -> BlockFork {
+        Network::mainnet().block_fork(slot).unwrap()
+    }
+
+    fn default_value(ty: &SszType) -> SszValue {
+        match ty {
+            SszType::Uint(_) => SszValue::uint(0),
+            SszType::Boolean => SszValue::Bool(false),
+            SszType::ByteVector(length) => SszValue::Bytes(vec![0; *length]),
+            SszType::ByteList(_) => SszValue::Bytes(Vec::new()),
+            SszType::Bitvector(length) => SszValue::Bits(vec![false; *length]),
+            SszType::Bitlist(_) => SszValue::Bits(Vec::new()),
+            SszType::Vector(element, length) => {
+                SszValue::Sequence(vec![default_value(element); *length])
+            }
+            SszType::List(..) => SszValue::Sequence(Vec::new()),
+            SszType::Container(fields) => {
+                SszValue::Container(fields.iter().map(|(_, f)| default_value(f)).collect())
+            }
+            SszType::Union(_) => SszValue::Union(0, None),
+        }
+    }
+
+    fn field_mut<'a>(value: &'a mut SszValue, path: &[usize]) -> &'a mut SszValue {
+        path.iter().fold(value, |value, index| match value {
+            SszValue::Container(items) | SszValue::Sequence(items) => &mut items[*index],
+            _ => panic!("expected a composite value"),
+        })
+    }
+
+    /// A block at `slot` and the state closing its period.
+    fn block_and_state(slot: u64) -> (Vec<u8>, Vec<u8>, [u8; 32]) {
+        let fork = fork_at(slot);
+        let ty = signed_block_type(fork);
+        let mut block = default_value(&ty);
+        *field_mut(&mut block, &[0, 0]) = SszValue::uint(slot);
+        *field_mut(&mut block, &[0, 4, 9, 6]) = SszValue::uint(20_000_000);
+        *field_mut(&mut block, &[0, 4, 9, 12]) = SszValue::Bytes(vec![0xab; 32]);
+        *field_mut(&mut block, &[0, 4, 9, 13]) =
+            SszValue::Sequence(vec![SszValue::Bytes(vec![0x02, 0xf8])]);
+        let SszType::Container(fields) = &ty else {
+            unreachable!();
+        };
+        let header_root = fields[0]
+            .1
+            .hash_tree_root(field_mut(&mut block, &[0]))
+            .unwrap();
+
+        let mut state = vec![0u8; STATE_BLOCK_ROOTS_OFFSET + 2 * ROOTS_VECTOR_SIZE + 100];
+        let state_slot = (slot / SLOTS_PER_HISTORICAL_ROOT + 1) * SLOTS_PER_HISTORICAL_ROOT;
+        state[STATE_SLOT_OFFSET..STATE_SLOT_OFFSET + 8].copy_from_slice(&state_slot.to_le_bytes());
+        for (i, root) in state
+            [STATE_BLOCK_ROOTS_OFFSET..STATE_BLOCK_ROOTS_OFFSET + 2 * ROOTS_VECTOR_SIZE]
+            .chunks_mut(32)
+            .enumerate()
+        {
+            root[..8].copy_from_slice(&(i as u64).to_le_bytes());
+        }
+        let at = STATE_BLOCK_ROOTS_OFFSET + (slot % SLOTS_PER_HISTORICAL_ROOT) as usize * 32;
+        state[at..at + 32].copy_from_slice(&header_root);
+        (ty.encode(&block).unwrap(), state, header_root)
+    }
+
+    #[test]
+    fn test_generated_proofs_verify() {
+        assert_eq!(block_hash_gindex(BlockFork::Bellatrix), 412);
+        assert_eq!(block_hash_gindex(BlockFork::Capella), 412);
+        assert_eq!(block_hash_gindex(BlockFork::Deneb), 812);
+        assert_eq!(block_hash_gindex(BlockFork::Electra), 812);
+        for (slot, body_len, proof_len) in [
+            (4_700_013, 8, 14),
+            (CAPELLA_SLOT + 2, 8, 13),
+            (DENEB_SLOT + 100, 9, 13),
+            (ELECTRA_SLOT + 8191, 9, 13),
+        ] {
+            let (block, state, header_root) = block_and_state(slot);
+            let proof = generate(&Network::mainnet(), &block, &state, None).unwrap();
+            assert_eq!(proof.block_hash, [0xab; 32]);
+            assert_eq!(proof.block_number, 20_000_000);
+            assert_eq!(proof.header_root, header_root);
+            assert_eq!(proof.body_proof.len(), body_len);
+            assert_eq!(proof.header_proof.len(), 3);
+            assert_eq!(proof.block_roots_proof.len(), proof_len);
+            assert!(verify_merkle_proof(
+                &proof.block_hash,
+                &proof.body_proof,
+                block_hash_gindex(fork_at(slot)),
+                &proof.body_root
+            ));
+            assert!(verify_merkle_proof(
+                &proof.body_root,
+                &proof.header_proof,
+                BODY_GINDEX,
+                &proof.header_root
+            ));
+            assert_eq!(
+                BlockProof::from_yaml(&Network::mainnet(), &proof.file_name(), &proof.to_yaml())
+                    .unwrap(),
+                proof
+            );
+
+            let mut early = state.clone();
+            early[STATE_SLOT_OFFSET] ^= 1;
+            assert!(matches!(
+                generate(&Network::mainnet(), &block, &early, None),
+                Err(BlockProofError::StateSlot { .. })
+            ));
+        }
+
+        let (block, state, _) = block_and_state(CAPELLA_SLOT + 2);
+        let roots_type = SszType::vector(SszType::bytes32(), 8192);
+        let roots = &state[STATE_BLOCK_ROOTS_OFFSET..STATE_BLOCK_ROOTS_OFFSET + ROOTS_VECTOR_SIZE];
+        let mut summaries = roots_type
+            .hash_tree_root(&roots_value(roots))
+            .unwrap()
+            .to_vec();
+        summaries.extend_from_slice(&[0u8; 32]);
+        assert!(generate(&Network::mainnet(), &block, &state, Some(&summaries)).is_ok());
+        summaries[0] ^= 1;
+        assert_eq!(
+            generate(&Network::mainnet(), &block, &state, Some(&summaries)),
+            Err(BlockProofError::SummaryMismatch(CAPELLA_SLOT / 8192))
+        );
+    }
+
+    #[test]
+    fn test_cancun_header_proof_round_trip() {
+        for (slot, execution_len) in [(CAPELLA_SLOT + 2, 11), (DENEB_SLOT + 100, 12)] {
+            let (block, state, header_root) = block_and_state(slot);
+            let proof = generate(&Network::mainnet(), &block, &state, None)
+                .unwrap()
+                .to_block_header_proof();
+            let (BlockHeaderProof::HistoricalSummaries(summaries)
+            | BlockHeaderProof::HistoricalSummariesDeneb(summaries)) = &proof
+            else {
+                panic!("expected a historical summaries proof, got {:?}", proof);
+            };
+            assert_eq!(summaries.execution_block_proof.len(), execution_len);
+            assert_eq!(
+                matches!(proof, BlockHeaderProof::HistoricalSummariesDeneb(_)),
+                slot >= DENEB_SLOT
+            );
+            assert!(verify_merkle_proof(
+                &[0xab; 32],
+                &summaries.execution_block_proof,
+                concat_generalized_indices(&[BODY_GINDEX, block_hash_gindex(fork_at(slot))]),
+                &header_root
+            ));
+            assert_eq!(BlockHeaderProof::decode(&proof.encode()).unwrap(), proof);
+        }
+    }
+
+    #[test]
+    fn test_yaml_matches_vector_layout() {
+        let dir = network_dir("mainnet").join("history/headers_with_proof");
+        let mut checked = 0;
+        for era in ["block_proofs_bellatrix", "block_proofs_capella"] {
+            for entry in std::fs::read_dir(dir.join(era)).unwrap() {
+                let path = entry.unwrap().path();
+                if path.extension().and_then(|e| e.to_str()) != Some("yaml") {
+                    continue;
+                }
+                let text = std::fs::read_to_string(&path).unwrap();
+                let name = path.file_name().unwrap().to_str().unwrap();
+                let proof = BlockProof::from_yaml(&Network::mainnet(), name, &text).unwrap();
+                assert_eq!(proof.file_name(), name);
+                // The published Capella proofs predate the quoted layout with a
+                // block number comment, so only their values are compared.
+                let value = |text: &str| Document::parse(text).unwrap().to_value().unwrap();
+                assert_eq!(value(&proof.to_yaml()), value(&text), "{}", path.display());
+                if era == "block_proofs_bellatrix" {
+                    assert_eq!(proof.to_yaml(), text, "{}", path.display());
+                }
+                checked += 1;
+            }
+        }
+        assert_eq!(checked, 6);
+    }
+
+    #[test]
+    fn test_rejects_blocks_without_payload() {
+        let (block, state, _) = block_and_state(BELLATRIX_SLOT + 5);
+        let ty = signed_block_type(BlockFork::Bellatrix);
+        let mut value = ty.decode(&block).unwrap();
+        *field_mut(&mut value, &[0, 4, 9, 12]) = SszValue::Bytes(vec![0; 32]);
+        assert_eq!(
+            generate(
+                &Network::mainnet(),
+                &ty.encode(&value).unwrap(),
+                &state,
+                None
+            ),
+            Err(BlockProofError::EmptyPayload(BELLATRIX_SLOT + 5))
+        );
+
+        let (mut block, state, _) = block_and_state(CAPELLA_SLOT);
+        block[100..108].copy_from_slice(&(BELLATRIX_SLOT - 1).to_le_bytes());
+        assert_eq!(
+            generate(&Network::mainnet(), &block, &state, None),
+            Err(BlockProofError::PreBellatrix(BELLATRIX_SLOT - 1))
+        );
+    }
+}
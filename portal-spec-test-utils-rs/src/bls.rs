@@ -0,0 +1,234 @@
+use crate::beacon::{BeaconBlockHeader, SyncAggregate, SyncCommittee};
+use crate::network::{compute_signing_root, Network, DOMAIN_SYNC_COMMITTEE, SLOTS_PER_EPOCH};
+use blst::min_pk::{PublicKey, Signature};
+use blst::BLST_ERROR;
+use thiserror::Error;
+
+/// Ciphersuite of the consensus layer's proof-of-possession signatures.
+pub const DST: &[u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_";
+pub const MIN_SYNC_COMMITTEE_PARTICIPANTS: usize = 1;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum BlsError {
+    #[error("sync committee public key {index} is not a valid G1 point")]
+    PublicKey { index: usize },
+    #[error("sync committee signature is not a valid G2 point")]
+    SignatureEncoding,
+    #[error(
+        "{0} sync committee participant(s), at least {MIN_SYNC_COMMITTEE_PARTICIPANTS} needed"
+    )]
+    TooFewParticipants(usize),
+    #[error("sync committee signature does not verify")]
+    InvalidSignature,
+}
+
+/// The decompressed public keys of a sync committee. Decompressing and
+/// subgroup checking 512 keys costs more than a verification, so a committee's
+/// keys are meant to be reused for every update of its period.
+#[derive(Debug, Clone)]
+pub struct SyncCommitteeKeys {
+    keys: Vec<PublicKey>,
+}
+
+impl SyncCommitteeKeys {
+    pub fn new(committee: &SyncCommittee) -> Result<SyncCommitteeKeys, BlsError> {
+        let keys = committee
+            .pubkeys
+            .iter()
+            .enumerate()
+            .map(|(index, pubkey)| {
+                PublicKey::key_validate(pubkey).map_err(|_| BlsError::PublicKey { index })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(SyncCommitteeKeys { keys })
+    }
+
+    /// Checks the aggregate signature of the participating members over the
+    /// attested header, in the domain of the fork active the slot before `signature_slot`.
+    pub fn verify(
+        &self,
+        network: &Network,
+        attested_header: &BeaconBlockHeader,
+        aggregate: &SyncAggregate,
+        signature_slot: u64,
+    ) -> Result<(), BlsError> {
+        let participants: Vec<&PublicKey> = self
+            .keys
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| aggregate.sync_committee_bits[i / 8] >> (i % 8) & 1 == 1)
+            .map(|(_, key)| key)
+            .collect();
+        if participants.len() < MIN_SYNC_COMMITTEE_PARTICIPANTS {
+            return Err(BlsError::TooFewParticipants(participants.len()));
+        }
+        let signature = Signature::sig_validate(&aggregate.sync_committee_signature, true)
+            .map_err(|_| BlsError::SignatureEncoding)?;
+        let epoch = signature_slot.saturating_sub(1) / SLOTS_PER_EPOCH;
+        let domain = network.domain(DOMAIN_SYNC_COMMITTEE, epoch);
+        let signing_root = compute_signing_root(&attested_header.hash_tree_root(), &domain);
+        match signature.fast_aggregate_verify(false, &signing_root, DST, &participants) {
+            BLST_ERROR::BLST_SUCCESS => Ok(()),
+            _ => Err(BlsError::InvalidSignature),
+        }
+    }
+}
+
+/// `SyncCommitteeKeys::verify` for a single update.
+pub fn verify_sync_aggregate(
+    network: &Network,
+    committee: &SyncCommittee,
+    attested_header: &BeaconBlockHeader,
+    aggregate: &SyncAggregate,
+    signature_slot: u64,
+) -> Result<(), BlsError> {
+    SyncCommitteeKeys::new(committee)?.verify(network, attested_header, aggregate, signature_slot)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::beacon::{BeaconPayload, BeaconValue};
+    use crate::content::ContentValue;
+    use crate::content_key::ContentKey;
+    use crate::vector::{network_dir, VectorFile};
+
+    fn load(relative: &str) -> Vec<BeaconPayload> {
+        let root = network_dir("mainnet");
+        let file = VectorFile::load(&root, &root.join(relative)).unwrap();
+        let entry = file.content_entries().unwrap().remove(0);
+        let key = ContentKey::decode(&entry.content_key).unwrap();
+        let value = entry.content_value.unwrap();
+        let ContentValue::Beacon(value) =
+            ContentValue::decode(&Network::mainnet(), &key, &value).unwrap()
+        else {
+            panic!("expected a beacon value");
+        };
+        match value {
+            BeaconValue::Single(forked) => vec![forked.payload],
+            BeaconValue::Updates(updates) => {
+                updates.into_iter().map(|forked| forked.payload).collect()
+            }
+        }
+    }
+
+    fn bootstrap_keys() -> SyncCommitteeKeys {
+        let [BeaconPayload::Bootstrap(bootstrap)] =
+            &load("beacon_chain/light_client/bootstrap.yaml")[..]
+        else {
+            panic!("expected a bootstrap");
+        };
+        SyncCommitteeKeys::new(&bootstrap.current_sync_committee).unwrap()
+    }
+
+    #[test]
+    fn test_light_client_updates_verify() {
+        let mainnet = Network::mainnet();
+        let keys = bootstrap_keys();
+        let [BeaconPayload::FinalityUpdate(finality)] =
+            &load("beacon_chain/light_client/finality_update.yaml")[..]
+        else {
+            panic!("expected a finality update");
+        };
+        keys.verify(
+            &mainnet,
+            &finality.attested_header.beacon,
+            &finality.sync_aggregate,
+            finality.signature_slot,
+        )
+        .unwrap();
+        let [BeaconPayload::OptimisticUpdate(optimistic)] =
+            &load("beacon_chain/light_client/optimistic_update.yaml")[..]
+        else {
+            panic!("expected an optimistic update");
+        };
+        keys.verify(
+            &mainnet,
+            &optimistic.attested_header.beacon,
+            &optimistic.sync_aggregate,
+            optimistic.signature_slot,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_updates_by_range_chain() {
+        let mainnet = Network::mainnet();
+        let updates: Vec<_> = load("beacon_chain/light_client/updates.yaml")
+            .into_iter()
+            .map(|payload| match payload {
+                BeaconPayload::Update(update) => update,
+                other => panic!("expected an update, got {:?}", other),
+            })
+            .collect();
+        assert_eq!(updates.len(), 4);
+        // Each period's update is signed by the committee its predecessor announced.
+        for pair in updates.windows(2) {
+            verify_sync_aggregate(
+                &mainnet,
+                &pair[0].next_sync_committee,
+                &pair[1].attested_header.beacon,
+                &pair[1].sync_aggregate,
+                pair[1].signature_slot,
+            )
+            .unwrap();
+        }
+        let last = &updates[3];
+        assert_eq!(
+            verify_sync_aggregate(
+                &mainnet,
+                &last.next_sync_committee,
+                &last.attested_header.beacon,
+                &last.sync_aggregate,
+                last.signature_slot,
+            ),
+            Err(BlsError::InvalidSignature)
+        );
+    }
+
+    #[test]
+    fn test_rejects_tampered_aggregates() {
+        let mainnet = Network::mainnet();
+        let keys = bootstrap_keys();
+        let [BeaconPayload::FinalityUpdate(finality)] =
+            &load("beacon_chain/light_client/finality_update.yaml")[..]
+        else {
+            panic!("expected a finality update");
+        };
+        let verify = |header: &BeaconBlockHeader, aggregate: &SyncAggregate| {
+            keys.verify(&mainnet, header, aggregate, finality.signature_slot)
+        };
+
+        let mut header = finality.attested_header.beacon.clone();
+        header.slot += 1;
+        assert_eq!(
+            verify(&header, &finality.sync_aggregate),
+            Err(BlsError::InvalidSignature)
+        );
+
+        let mut aggregate = finality.sync_aggregate.clone();
+        let participant = aggregate
+            .sync_committee_bits
+            .iter()
+            .position(|byte| *byte != 0)
+            .unwrap();
+        aggregate.sync_committee_bits[participant] &=
+            aggregate.sync_committee_bits[participant] - 1;
+        assert_eq!(
+            verify(&finality.attested_header.beacon, &aggregate),
+            Err(BlsError::InvalidSignature)
+        );
+
+        aggregate.sync_committee_bits = [0; 64];
+        assert_eq!(
+            verify(&finality.attested_header.beacon, &aggregate),
+            Err(BlsError::TooFewParticipants(0))
+        );
+        aggregate.sync_committee_signature = [0xff; 96];
+        aggregate.sync_committee_bits[0] = 1;
+        assert_eq!(
+            verify(&finality.attested_header.beacon, &aggregate),
+            Err(BlsError::SignatureEncoding)
+        );
+    }
+}
@@ -0,0 +1,513 @@
+use crate::content::{BlockHeaderProof, ContentValue};
+use crate::content_key::{ContentKey, HistoryContentKey};
+use crate::header::Header;
+use crate::network::Network;
+use crate::vector::{discover, VectorError, VectorKind};
+use crate::ExecutionFork;
+use ethnum::U256;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+
+pub const GAS_LIMIT_BOUND_DIVISOR: u64 = 1024;
+pub const MIN_GAS_LIMIT: u64 = 5000;
+pub const MAX_EXTRA_DATA_SIZE: usize = 32;
+pub const ELASTICITY_MULTIPLIER: u64 = 2;
+pub const BASE_FEE_MAX_CHANGE_DENOMINATOR: u64 = 8;
+pub const INITIAL_BASE_FEE: u64 = 1_000_000_000;
+
+/// keccak256 of the RLP of an empty list, the ommers hash of every post-merge header.
+pub const EMPTY_OMMERS_HASH: [u8; 32] = [
+    0x1d, 0xcc, 0x4d, 0xe8, 0xde, 0xc7, 0x5d, 0x7a, 0xab, 0x85, 0xb5, 0x67, 0xb6, 0xcc, 0xd4, 0x1a,
+    0xd3, 0x12, 0x45, 0x1b, 0x94, 0x8a, 0x74, 0x13, 0xf0, 0xa1, 0x42, 0xfd, 0x40, 0xd4, 0x93, 0x47,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChainRule {
+    ParentHash,
+    Number,
+    Timestamp,
+    GasLimit,
+    GasUsed,
+    ExtraData,
+    BaseFee,
+    ForkFields,
+    Difficulty,
+    Nonce,
+    Ommers,
+    TerminalTotalDifficulty,
+}
+
+impl ChainRule {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ChainRule::ParentHash => "parent-hash",
+            ChainRule::Number => "number",
+            ChainRule::Timestamp => "timestamp",
+            ChainRule::GasLimit => "gas-limit",
+            ChainRule::GasUsed => "gas-used",
+            ChainRule::ExtraData => "extra-data",
+            ChainRule::BaseFee => "base-fee",
+            ChainRule::ForkFields => "fork-fields",
+            ChainRule::Difficulty => "difficulty",
+            ChainRule::Nonce => "nonce",
+            ChainRule::Ommers => "ommers",
+            ChainRule::TerminalTotalDifficulty => "terminal-total-difficulty",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChainViolation {
+    pub number: u64,
+    pub rule: ChainRule,
+    pub message: String,
+}
+
+impl fmt::Display for ChainViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "block {}: {}: {}",
+            self.number,
+            self.rule.name(),
+            self.message
+        )
+    }
+}
+
+fn violation(header: &Header, rule: ChainRule, message: String) -> ChainViolation {
+    ChainViolation {
+        number: header.number,
+        rule,
+        message,
+    }
+}
+
+/// The base fee a child of `parent` must carry under EIP-1559.
+pub fn expected_base_fee(network: &Network, parent: &Header) -> U256 {
+    if network.execution_fork(parent.number) < ExecutionFork::London {
+        return U256::from(INITIAL_BASE_FEE);
+    }
+    let parent_base_fee = parent.base_fee_per_gas.unwrap_or_default();
+    let target = U256::from(parent.gas_limit / ELASTICITY_MULTIPLIER);
+    let used = U256::from(parent.gas_used);
+    let denominator = U256::from(BASE_FEE_MAX_CHANGE_DENOMINATOR);
+    if target == U256::ZERO || used == target {
+        parent_base_fee
+    } else if used > target {
+        let delta = parent_base_fee * (used - target) / target / denominator;
+        parent_base_fee + delta.max(U256::ONE)
+    } else {
+        let delta = parent_base_fee * (target - used) / target / denominator;
+        parent_base_fee.saturating_sub(delta)
+    }
+}
+
+/// Checks the rules that only depend on the header itself and its fork.
+pub fn validate_header(network: &Network, header: &Header) -> Vec<ChainViolation> {
+    let mut violations = Vec::new();
+    let fork = network.execution_fork(header.number);
+
+    if header.gas_used > header.gas_limit {
+        violations.push(violation(
+            header,
+            ChainRule::GasUsed,
+            format!(
+                "gas used {} exceeds gas limit {}",
+                header.gas_used, header.gas_limit
+            ),
+        ));
+    }
+    if header.extra_data.len() > MAX_EXTRA_DATA_SIZE {
+        violations.push(violation(
+            header,
+            ChainRule::ExtraData,
+            format!("extra data is {} bytes", header.extra_data.len()),
+        ));
+    }
+
+    let expected_fields = [
+        (
+            "base_fee_per_gas",
+            header.base_fee_per_gas.is_some(),
+            ExecutionFork::London,
+        ),
+        (
+            "withdrawals_root",
+            header.withdrawals_root.is_some(),
+            ExecutionFork::Shanghai,
+        ),
+        (
+            "blob_gas_used",
+            header.blob_gas_used.is_some(),
+            ExecutionFork::Cancun,
+        ),
+        (
+            "excess_blob_gas",
+            header.excess_blob_gas.is_some(),
+            ExecutionFork::Cancun,
+        ),
+        (
+            "parent_beacon_block_root",
+            header.parent_beacon_block_root.is_some(),
+            ExecutionFork::Cancun,
+        ),
+        (
+            "requests_hash",
+            header.requests_hash.is_some(),
+            ExecutionFork::Prague,
+        ),
+    ];
+    for (field, present, since) in expected_fields {
+        if present != (fork >= since) {
+            violations.push(violation(
+                header,
+                ChainRule::ForkFields,
+                format!(
+                    "{} is {} in a {} header",
+                    field,
+                    if present { "present" } else { "missing" },
+                    fork.name()
+                ),
+            ));
+        }
+    }
+
+    if fork >= ExecutionFork::Merge {
+        if header.difficulty != U256::ZERO {
+            violations.push(violation(
+                header,
+                ChainRule::Difficulty,
+                format!("post-merge difficulty is {}", header.difficulty),
+            ));
+        }
+        if header.nonce != [0; 8] {
+            violations.push(violation(
+                header,
+                ChainRule::Nonce,
+                format!("post-merge nonce is 0x{}", hex::encode(header.nonce)),
+            ));
+        }
+        if header.ommers_hash != EMPTY_OMMERS_HASH {
+            violations.push(violation(
+                header,
+                ChainRule::Ommers,
+                "post-merge header has ommers".to_string(),
+            ));
+        }
+    } else if header.difficulty == U256::ZERO {
+        violations.push(violation(
+            header,
+            ChainRule::Difficulty,
+            "proof of work header has zero difficulty".to_string(),
+        ));
+    }
+    violations
+}
+
+/// Checks the rules linking a header to its parent.
+pub fn validate_pair(network: &Network, parent: &Header, header: &Header) -> Vec<ChainViolation> {
+    let mut violations = Vec::new();
+    let fork = network.execution_fork(header.number);
+
+    if header.parent_hash != parent.hash() {
+        violations.push(violation(
+            header,
+            ChainRule::ParentHash,
+            format!(
+                "parent hash 0x{} does not match block {} hash 0x{}",
+                hex::encode(header.parent_hash),
+                parent.number,
+                hex::encode(parent.hash())
+            ),
+        ));
+    }
+    if header.number != parent.number + 1 {
+        violations.push(violation(
+            header,
+            ChainRule::Number,
+            format!("follows block {}", parent.number),
+        ));
+    }
+    if header.timestamp <= parent.timestamp {
+        violations.push(violation(
+            header,
+            ChainRule::Timestamp,
+            format!(
+                "timestamp {} is not after parent timestamp {}",
+                header.timestamp, parent.timestamp
+            ),
+        ));
+    }
+
+    // The London fork block doubles the limit to keep the gas target unchanged.
+    let parent_gas_limit = if header.number == network.activation_block(ExecutionFork::London) {
+        parent.gas_limit * ELASTICITY_MULTIPLIER
+    } else {
+        parent.gas_limit
+    };
+    let bound = parent_gas_limit / GAS_LIMIT_BOUND_DIVISOR;
+    if header.gas_limit.abs_diff(parent_gas_limit) >= bound || header.gas_limit < MIN_GAS_LIMIT {
+        violations.push(violation(
+            header,
+            ChainRule::GasLimit,
+            format!(
+                "gas limit {} is out of bounds for parent limit {}",
+                header.gas_limit, parent_gas_limit
+            ),
+        ));
+    }
+
+    if fork >= ExecutionFork::London {
+        let expected = expected_base_fee(network, parent);
+        if header.base_fee_per_gas != Some(expected) {
+            violations.push(violation(
+                header,
+                ChainRule::BaseFee,
+                format!(
+                    "base fee {:?} does not match expected {}",
+                    header.base_fee_per_gas, expected
+                ),
+            ));
+        }
+    }
+    violations
+}
+
+/// Total difficulty of the header, read from its accumulator proof when it has one.
+pub fn total_difficulty_from_proof(proof: &BlockHeaderProof) -> Option<U256> {
+    match proof {
+        BlockHeaderProof::HistoricalHashesAccumulator(proof) => Some(U256::from_le_bytes(proof[0])),
+        _ => None,
+    }
+}
+
+/// Validates consecutive headers, tracking total difficulty from `total_difficulty`,
+/// the total difficulty of the first header, when given.
+pub fn validate_chain(
+    network: &Network,
+    headers: &[Header],
+    total_difficulty: Option<U256>,
+) -> Vec<ChainViolation> {
+    let mut violations: Vec<ChainViolation> = headers
+        .iter()
+        .flat_map(|header| validate_header(network, header))
+        .collect();
+    for pair in headers.windows(2) {
+        violations.extend(validate_pair(network, &pair[0], &pair[1]));
+    }
+
+    let Some(mut total_difficulty) = total_difficulty else {
+        return violations;
+    };
+    let ttd = U256::from(network.terminal_total_difficulty);
+    for (index, header) in headers.iter().enumerate() {
+        if index > 0 {
+            total_difficulty += header.difficulty;
+        }
+        let parent_total_difficulty = total_difficulty - header.difficulty;
+        let is_pos = network.execution_fork(header.number) >= ExecutionFork::Merge;
+        // A proof of work block is valid only while its parent is below the terminal
+        // total difficulty, and the first proof of stake block must follow one at or above it.
+        let broken = if is_pos {
+            header.number == network.merge_block() && parent_total_difficulty < ttd
+        } else {
+            parent_total_difficulty >= ttd
+        };
+        if broken {
+            violations.push(violation(
+                header,
+                ChainRule::TerminalTotalDifficulty,
+                format!(
+                    "parent total difficulty {} is {} the terminal total difficulty",
+                    parent_total_difficulty,
+                    if is_pos { "below" } else { "at or above" }
+                ),
+            ));
+        }
+    }
+    violations
+}
+
+/// Runs the chain rules over every header vector below `root`, chaining the ones
+/// with consecutive numbers.
+pub fn validate_vectors(
+    network: &Network,
+    root: &Path,
+) -> Result<Vec<ChainViolation>, VectorError> {
+    let mut headers: BTreeMap<u64, (Header, Option<U256>)> = BTreeMap::new();
+    for file in discover(root)? {
+        if !matches!(
+            file.kind,
+            Some(VectorKind::HistoryHeaderWithProof | VectorKind::HistoryHive)
+        ) {
+            continue;
+        }
+        for entry in file.content_entries()? {
+            let (Ok(key), Some(value)) = (
+                ContentKey::decode(&entry.content_key),
+                entry.content_value.as_ref(),
+            ) else {
+                continue;
+            };
+            if !matches!(
+                key,
+                ContentKey::History(HistoryContentKey::BlockHeaderByHash(_))
+            ) {
+                continue;
+            }
+            let Ok(ContentValue::HeaderWithProof(value)) =
+                ContentValue::decode(network, &key, value)
+            else {
+                continue;
+            };
+            let total_difficulty = total_difficulty_from_proof(&value.proof);
+            headers.insert(value.header.number, (value.header, total_difficulty));
+        }
+    }
+
+    let mut violations = Vec::new();
+    let mut run: Vec<(Header, Option<U256>)> = Vec::new();
+    for (number, entry) in headers {
+        if run
+            .last()
+            .is_some_and(|(last, _)| last.number + 1 != number)
+        {
+            let (chain, tds): (Vec<_>, Vec<_>) = std::mem::take(&mut run).into_iter().unzip();
+            violations.extend(validate_chain(network, &chain, tds[0]));
+        }
+        run.push(entry);
+    }
+    if !run.is_empty() {
+        let (chain, tds): (Vec<_>, Vec<_>) = run.into_iter().unzip();
+        violations.extend(validate_chain(network, &chain, tds[0]));
+    }
+    Ok(violations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::content::HeaderWithProof;
+    use crate::vector::{network_dir, VectorFile};
+
+    fn load_headers(relative: &str) -> Vec<HeaderWithProof> {
+        let root = network_dir("mainnet");
+        let file = VectorFile::load(&root, &root.join(relative)).unwrap();
+        let mut headers: Vec<HeaderWithProof> = file
+            .content_entries()
+            .unwrap()
+            .iter()
+            .map(|entry| HeaderWithProof::decode(entry.content_value.as_ref().unwrap()).unwrap())
+            .collect();
+        headers.sort_by_key(|value| value.header.number);
+        headers
+    }
+
+    #[test]
+    fn test_range_1000001_to_1000010_is_valid() {
+        let headers: Vec<Header> = load_headers("history/headers_with_proof/1000001-1000010.json")
+            .into_iter()
+            .map(|value| value.header)
+            .collect();
+        assert_eq!(headers.len(), 10);
+        assert_eq!(validate_chain(&Network::mainnet(), &headers, None), vec![]);
+
+        let mut broken = headers.clone();
+        broken[4].parent_hash[0] ^= 1;
+        broken[6].timestamp = broken[5].timestamp;
+        let rules: Vec<ChainRule> = validate_chain(&Network::mainnet(), &broken, None)
+            .into_iter()
+            .map(|v| v.rule)
+            .collect();
+        // Each edit also changes the hash the next header points to.
+        assert_eq!(
+            rules,
+            vec![
+                ChainRule::ParentHash,
+                ChainRule::ParentHash,
+                ChainRule::Timestamp,
+                ChainRule::ParentHash
+            ]
+        );
+    }
+
+    #[test]
+    fn test_terminal_total_difficulty_transition() {
+        let mut values = load_headers("history/headers_with_proof/15537392.yaml");
+        values.extend(load_headers("history/headers_with_proof/15537393.yaml"));
+        let total_difficulty = total_difficulty_from_proof(&values[0].proof);
+        let headers: Vec<Header> = values.into_iter().map(|value| value.header).collect();
+        let mainnet = Network::mainnet();
+        assert_eq!(validate_chain(&mainnet, &headers, total_difficulty), vec![]);
+
+        // Pretend 15537392 already reached the terminal total difficulty.
+        let too_high = Some(U256::from(mainnet.terminal_total_difficulty));
+        let violations = validate_chain(&mainnet, &headers, too_high);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].number, 15537393);
+        assert_eq!(violations[0].rule, ChainRule::TerminalTotalDifficulty);
+    }
+
+    #[test]
+    fn test_base_fee_formula() {
+        let mut parent = load_headers("history/headers_with_proof/15537393.yaml")
+            .remove(0)
+            .header;
+        parent.base_fee_per_gas = Some(U256::new(1_000_000_000));
+        parent.gas_limit = 30_000_000;
+        parent.gas_used = 30_000_000;
+        let mainnet = Network::mainnet();
+        assert_eq!(
+            expected_base_fee(&mainnet, &parent),
+            U256::new(1_125_000_000)
+        );
+        parent.gas_used = 15_000_000;
+        assert_eq!(
+            expected_base_fee(&mainnet, &parent),
+            U256::new(1_000_000_000)
+        );
+        parent.gas_used = 0;
+        assert_eq!(expected_base_fee(&mainnet, &parent), U256::new(875_000_000));
+
+        parent.number = ExecutionFork::London.activation_block() - 1;
+        assert_eq!(
+            expected_base_fee(&mainnet, &parent),
+            U256::new(INITIAL_BASE_FEE as u128)
+        );
+    }
+
+    #[test]
+    fn test_post_merge_header_rules() {
+        let mut header = load_headers("history/headers_with_proof/15537393.yaml")
+            .remove(0)
+            .header;
+        header.number = ExecutionFork::Merge.activation_block();
+        let rules = |network: &Network, header: &Header| -> Vec<ChainRule> {
+            validate_header(network, header)
+                .into_iter()
+                .map(|v| v.rule)
+                .collect()
+        };
+        assert_eq!(
+            rules(&Network::mainnet(), &header),
+            vec![ChainRule::Difficulty, ChainRule::Nonce]
+        );
+
+        // Holesky launched as proof of stake, so no proof of work header is valid there.
+        let pre_merge = load_headers("history/headers_with_proof/1000010.yaml")
+            .remove(0)
+            .header;
+        assert_eq!(rules(&Network::mainnet(), &pre_merge), vec![]);
+        assert!(rules(&Network::holesky(), &pre_merge).contains(&ChainRule::Difficulty));
+    }
+
+    #[test]
+    fn test_mainnet_header_vectors_pass() {
+        assert_eq!(
+            validate_vectors(&Network::mainnet(), &network_dir("mainnet")).unwrap(),
+            vec![]
+        );
+    }
+}
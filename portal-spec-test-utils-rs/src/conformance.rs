@@ -0,0 +1,514 @@
+use crate::content::{BlockHeaderProof, ContentValue, StateValue};
+use crate::content_key::{ContentKey, HistoryContentKey, Subnetwork};
+use crate::invalid::{expected_error, ErrorClass};
+use crate::network::Network;
+use crate::rpc::{error_code, serve, MockNode, RpcError};
+use crate::vector::{decode_hex, discover, encode_hex, ContentEntry, VectorError, VectorKind};
+use crate::ExecutionFork;
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use thiserror::Error;
+
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Error, Debug)]
+pub enum ConformanceError {
+    #[error("unsupported endpoint {0}, expected http://host:port")]
+    InvalidUrl(String),
+    #[error("connection to {addr} failed: {source}")]
+    Io { addr: String, source: io::Error },
+    #[error("malformed response: {0}")]
+    Response(String),
+    #[error("{0}")]
+    Rpc(RpcError),
+    #[error(transparent)]
+    Vector(#[from] VectorError),
+}
+
+/// A blocking JSON-RPC client over HTTP, one connection per call.
+#[derive(Debug)]
+pub struct RpcClient {
+    addr: String,
+    path: String,
+    next_id: u64,
+}
+
+impl RpcClient {
+    pub fn new(url: &str) -> Result<RpcClient, ConformanceError> {
+        let rest = url
+            .strip_prefix("http://")
+            .ok_or_else(|| ConformanceError::InvalidUrl(url.to_string()))?;
+        let (addr, path) = match rest.find('/') {
+            Some(at) => (&rest[..at], &rest[at..]),
+            None => (rest, "/"),
+        };
+        if addr.is_empty() {
+            return Err(ConformanceError::InvalidUrl(url.to_string()));
+        }
+        Ok(RpcClient {
+            addr: addr.to_string(),
+            path: path.to_string(),
+            next_id: 1,
+        })
+    }
+
+    pub fn call(&mut self, method: &str, params: Value) -> Result<Value, ConformanceError> {
+        let id = self.next_id;
+        self.next_id += 1;
+        let body = json!({"jsonrpc": "2.0", "id": id, "method": method, "params": params});
+        let response: Value = serde_json::from_slice(&self.post(&body.to_string())?)
+            .map_err(|e| ConformanceError::Response(e.to_string()))?;
+        if let Some(error) = response.get("error") {
+            let mut rpc_error = RpcError::new(
+                error.get("code").and_then(Value::as_i64).unwrap_or(0),
+                error
+                    .get("message")
+                    .and_then(Value::as_str)
+                    .unwrap_or_default(),
+            );
+            rpc_error.data = error.get("data").cloned();
+            return Err(ConformanceError::Rpc(rpc_error));
+        }
+        response
+            .get("result")
+            .cloned()
+            .ok_or_else(|| ConformanceError::Response("no result or error".to_string()))
+    }
+
+    fn post(&self, body: &str) -> Result<Vec<u8>, ConformanceError> {
+        let io_error = |source| ConformanceError::Io {
+            addr: self.addr.clone(),
+            source,
+        };
+        let mut stream = TcpStream::connect(&self.addr).map_err(io_error)?;
+        stream.set_read_timeout(Some(TIMEOUT)).map_err(io_error)?;
+        let request = format!(
+            "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\n\
+             Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+            self.path,
+            self.addr,
+            body.len(),
+            body
+        );
+        stream.write_all(request.as_bytes()).map_err(io_error)?;
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).map_err(io_error)?;
+
+        let malformed = |message: &str| ConformanceError::Response(message.to_string());
+        let split = response
+            .windows(4)
+            .position(|window| window == b"\r\n\r\n")
+            .ok_or_else(|| malformed("no end of headers"))?;
+        let head = String::from_utf8_lossy(&response[..split]).to_ascii_lowercase();
+        let body = &response[split + 4..];
+        let status = head.lines().next().unwrap_or_default();
+        if status.split(' ').nth(1) != Some("200") {
+            return Err(ConformanceError::Response(format!(
+                "HTTP status {}",
+                status
+            )));
+        }
+        if head.contains("transfer-encoding: chunked") {
+            return dechunk(body).ok_or_else(|| malformed("bad chunked encoding"));
+        }
+        Ok(body.to_vec())
+    }
+}
+
+fn dechunk(mut body: &[u8]) -> Option<Vec<u8>> {
+    let mut out = Vec::new();
+    loop {
+        let line_end = body.windows(2).position(|window| window == b"\r\n")?;
+        let size = std::str::from_utf8(&body[..line_end]).ok()?;
+        let size = usize::from_str_radix(size.split(';').next()?.trim(), 16).ok()?;
+        body = &body[line_end + 2..];
+        if size == 0 {
+            return Some(out);
+        }
+        out.extend_from_slice(body.get(..size)?);
+        body = body.get(size + 2..)?;
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Passed,
+    Failed(String),
+    Skipped(String),
+}
+
+/// The outcome for one content entry of a vector file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VectorResult {
+    pub file: PathBuf,
+    pub label: String,
+    pub outcome: Outcome,
+}
+
+impl fmt::Display for VectorResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (status, detail) = match &self.outcome {
+            Outcome::Passed => ("PASS", ""),
+            Outcome::Failed(detail) => ("FAIL", detail.as_str()),
+            Outcome::Skipped(detail) => ("SKIP", detail.as_str()),
+        };
+        write!(f, "{} {}", status, self.file.display())?;
+        if !self.label.is_empty() {
+            write!(f, " ({})", self.label)?;
+        }
+        if !detail.is_empty() {
+            write!(f, ": {}", detail)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Report {
+    pub results: Vec<VectorResult>,
+}
+
+impl Report {
+    fn count(&self, matches: fn(&Outcome) -> bool) -> usize {
+        self.results.iter().filter(|r| matches(&r.outcome)).count()
+    }
+
+    pub fn passed(&self) -> usize {
+        self.count(|outcome| *outcome == Outcome::Passed)
+    }
+
+    pub fn failed(&self) -> usize {
+        self.count(|outcome| matches!(outcome, Outcome::Failed(_)))
+    }
+
+    pub fn skipped(&self) -> usize {
+        self.count(|outcome| matches!(outcome, Outcome::Skipped(_)))
+    }
+
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        for result in &self.results {
+            out.push_str(&format!("{}\n", result));
+        }
+        out.push_str(&format!(
+            "{} passed, {} failed, {} skipped\n",
+            self.passed(),
+            self.failed(),
+            self.skipped()
+        ));
+        out
+    }
+}
+
+fn method(subnetwork: Subnetwork, name: &str) -> String {
+    format!("portal_{}{}", subnetwork, name)
+}
+
+/// The subnetwork an invalid vector belongs to, from the top directory it lives in.
+fn path_subnetwork(relative: &Path) -> Option<Subnetwork> {
+    match relative.components().next()?.as_os_str().to_str()? {
+        "history" => Some(Subnetwork::History),
+        "state" => Some(Subnetwork::State),
+        "beacon_chain" => Some(Subnetwork::Beacon),
+        _ => None,
+    }
+}
+
+/// Values a client may return for stored content: the value itself, or the
+/// retrieval form of a state offer.
+fn retrievable(key: &ContentKey, entry: &ContentEntry, stored: &[u8]) -> Vec<Vec<u8>> {
+    let mut accepted = vec![stored.to_vec()];
+    accepted.extend(entry.content_value.clone());
+    if let ContentKey::State(key) = key {
+        if let Ok(value) = StateValue::decode(key, stored) {
+            accepted.push(value.retrieval_value());
+        }
+    }
+    accepted
+}
+
+fn store_and_retrieve(
+    client: &mut RpcClient,
+    key: &ContentKey,
+    entry: &ContentEntry,
+    value: &[u8],
+) -> Result<Outcome, ConformanceError> {
+    let subnetwork = key.subnetwork();
+    let content_key = encode_hex(&entry.content_key);
+    match client.call(
+        &method(subnetwork, "Store"),
+        json!([content_key, encode_hex(value)]),
+    ) {
+        Ok(Value::Bool(true)) => {}
+        Ok(other) => return Ok(Outcome::Failed(format!("Store returned {}", other))),
+        Err(ConformanceError::Rpc(error)) => {
+            return Ok(Outcome::Failed(format!("Store rejected it: {}", error)))
+        }
+        Err(e) => return Err(e),
+    }
+    let retrieved = match client.call(&method(subnetwork, "LocalContent"), json!([content_key])) {
+        Ok(Value::String(text)) => decode_hex(&text).ok(),
+        Ok(_) => None,
+        Err(ConformanceError::Rpc(error)) => {
+            return Ok(Outcome::Failed(format!("LocalContent failed: {}", error)))
+        }
+        Err(e) => return Err(e),
+    };
+    Ok(match retrieved {
+        Some(retrieved) if retrievable(key, entry, value).contains(&retrieved) => Outcome::Passed,
+        Some(_) => Outcome::Failed("LocalContent returned a different value".to_string()),
+        None => Outcome::Failed("LocalContent did not return a hex string".to_string()),
+    })
+}
+
+fn offer_invalid(
+    client: &mut RpcClient,
+    subnetwork: Subnetwork,
+    entry: &ContentEntry,
+    value: &[u8],
+    expected: ErrorClass,
+) -> Result<Outcome, ConformanceError> {
+    let result = client.call(
+        &method(subnetwork, "Store"),
+        json!([encode_hex(&entry.content_key), encode_hex(value)]),
+    );
+    Ok(match result {
+        Ok(result) => rejection(Ok(result), expected),
+        Err(ConformanceError::Rpc(error)) => rejection(Err(error), expected),
+        Err(e) => return Err(e),
+    })
+}
+
+/// Passes a Store of invalid content only when the client rejects it as
+/// `expected`, or without a class but with an invalid content or params code.
+fn rejection(result: Result<Value, RpcError>, expected: ErrorClass) -> Outcome {
+    match result {
+        Ok(Value::Bool(true)) => Outcome::Failed(format!("accepted, expected {}", expected)),
+        Ok(other) => Outcome::Failed(format!(
+            "Store returned {}, expected a {} error",
+            other, expected
+        )),
+        Err(error) => match error.class() {
+            Some(class) if class == expected => Outcome::Passed,
+            Some(class) => Outcome::Failed(format!("rejected as {}, expected {}", class, expected)),
+            None if matches!(
+                error.code,
+                error_code::INVALID_CONTENT | error_code::INVALID_PARAMS
+            ) =>
+            {
+                Outcome::Passed
+            }
+            None => Outcome::Failed(format!(
+                "rejected with an unrelated error: {}, expected {}",
+                error, expected
+            )),
+        },
+    }
+}
+
+/// Stores every valid vector below `dir` that decodes on `network` and reads it
+/// back, then offers every invalid vector and checks it is rejected.
+pub fn run(
+    client: &mut RpcClient,
+    network: &Network,
+    dir: &Path,
+) -> Result<Report, ConformanceError> {
+    let files = discover(dir)?;
+    let mut valid = Vec::new();
+    for file in &files {
+        if file.kind.is_none() || file.kind == Some(VectorKind::Invalid) {
+            continue;
+        }
+        for entry in file.content_entries()? {
+            if let Ok(key) = ContentKey::decode(&entry.content_key) {
+                valid.push((file.relative.clone(), key, entry));
+            }
+        }
+    }
+    // Bodies and receipts are validated against headers, which have to go first.
+    let is_header = |key: &ContentKey| {
+        matches!(
+            key,
+            ContentKey::History(
+                HistoryContentKey::BlockHeaderByHash(_) | HistoryContentKey::BlockHeaderByNumber(_)
+            )
+        )
+    };
+    valid.sort_by_key(|(_, key, _)| !is_header(key));
+
+    let mut report = Report::default();
+    let mut headers = HashSet::new();
+    for (file, key, entry) in valid {
+        let Some(value) = entry.offer_value.as_ref().or(entry.content_value.as_ref()) else {
+            continue;
+        };
+        let outcome = match (&key, ContentValue::decode(network, &key, value)) {
+            (_, Err(_)) => Outcome::Skipped(format!("does not decode on {}", network.name)),
+            // A client has nothing to verify these against, so it must refuse them.
+            (_, Ok(ContentValue::HeaderWithProof(header_with_proof)))
+                if matches!(header_with_proof.proof, BlockHeaderProof::None)
+                    && network.execution_fork(header_with_proof.header.number)
+                        >= ExecutionFork::Merge =>
+            {
+                Outcome::Skipped("post-merge header without a proof".to_string())
+            }
+            (
+                ContentKey::History(
+                    HistoryContentKey::BlockBody(hash) | HistoryContentKey::Receipts(hash),
+                ),
+                _,
+            ) if !headers.contains(hash) => Outcome::Skipped(format!(
+                "no provable header for block {} among the vectors",
+                encode_hex(hash)
+            )),
+            (_, Ok(decoded)) => {
+                if let ContentValue::HeaderWithProof(header_with_proof) = decoded {
+                    headers.insert(header_with_proof.header.hash());
+                }
+                store_and_retrieve(client, &key, &entry, value)?
+            }
+        };
+        report.results.push(VectorResult {
+            file,
+            label: entry.label.clone(),
+            outcome,
+        });
+    }
+
+    for file in &files {
+        if file.kind != Some(VectorKind::Invalid) {
+            continue;
+        }
+        let (Some(subnetwork), Some(expected)) =
+            (path_subnetwork(&file.relative), expected_error(file)?)
+        else {
+            continue;
+        };
+        for entry in file.content_entries()? {
+            let Some(value) = entry.offer_value.as_ref().or(entry.content_value.as_ref()) else {
+                continue;
+            };
+            let outcome = offer_invalid(client, subnetwork, &entry, value, expected)?;
+            report.results.push(VectorResult {
+                file: file.relative.clone(),
+                label: entry.label.clone(),
+                outcome,
+            });
+        }
+    }
+    Ok(report)
+}
+
+/// Runs the harness against an in-process validating mock node.
+pub fn self_test(network: &Network, dir: &Path) -> Result<Report, ConformanceError> {
+    let node = Arc::new(MockNode::validating(network, dir)?);
+    let io_error = |source| ConformanceError::Io {
+        addr: "127.0.0.1:0".to_string(),
+        source,
+    };
+    let runtime = tokio::runtime::Runtime::new().map_err(io_error)?;
+    let listener = runtime
+        .block_on(tokio::net::TcpListener::bind("127.0.0.1:0"))
+        .map_err(io_error)?;
+    let addr = listener.local_addr().map_err(io_error)?;
+    runtime.spawn(serve(node, listener));
+    run(
+        &mut RpcClient::new(&format!("http://{}", addr))?,
+        network,
+        dir,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vector::network_dir;
+
+    #[test]
+    fn test_self_test_passes() {
+        let report = self_test(&Network::mainnet(), &network_dir("mainnet")).unwrap();
+        assert_eq!(report.failed(), 0, "{}", report.render());
+        // Block 17139055's body, in its JSON collection and its per-block file, and
+        // the three post-merge hive headers without proofs, their bodies and receipts.
+        assert_eq!(report.skipped(), 11, "{}", report.render());
+        let invalid = report
+            .results
+            .iter()
+            .filter(|r| r.file.components().any(|c| c.as_os_str() == "invalid"))
+            .count();
+        assert_eq!(invalid, 221);
+        assert!(report.passed() > 200);
+    }
+
+    #[test]
+    fn test_run_skips_other_network_values() {
+        let report = self_test(&Network::sepolia(), &network_dir("mainnet")).unwrap();
+        let beacon: Vec<_> = report
+            .results
+            .iter()
+            .filter(|r| {
+                r.file.starts_with("beacon_chain")
+                    && !r.file.components().any(|c| c.as_os_str() == "invalid")
+            })
+            .collect();
+        assert!(!beacon.is_empty());
+        assert!(beacon
+            .iter()
+            .all(|r| r.outcome == Outcome::Skipped("does not decode on sepolia".to_string())));
+    }
+
+    #[test]
+    fn test_rejection_needs_the_expected_reason() {
+        let expected = ErrorClass::InvalidProof;
+        let classed = |class: ErrorClass| {
+            let mut error = RpcError::new(error_code::INVALID_CONTENT, "invalid");
+            error.data = Some(json!({"class": class.name()}));
+            Err(error)
+        };
+        assert_eq!(rejection(classed(expected), expected), Outcome::Passed);
+        assert!(matches!(
+            rejection(classed(ErrorClass::InvalidSsz), expected),
+            Outcome::Failed(_)
+        ));
+        for code in [error_code::INVALID_CONTENT, error_code::INVALID_PARAMS] {
+            assert_eq!(
+                rejection(Err(RpcError::new(code, "invalid")), expected),
+                Outcome::Passed
+            );
+        }
+        let not_found = RpcError::new(error_code::METHOD_NOT_FOUND, "Method not found");
+        for result in [
+            Err(not_found),
+            Ok(json!(false)),
+            Ok(Value::Null),
+            Ok(json!(true)),
+        ] {
+            assert!(matches!(rejection(result, expected), Outcome::Failed(_)));
+        }
+    }
+
+    #[test]
+    fn test_client_urls_and_chunks() {
+        let client = RpcClient::new("http://127.0.0.1:8545").unwrap();
+        assert_eq!(
+            (client.addr.as_str(), client.path.as_str()),
+            ("127.0.0.1:8545", "/")
+        );
+        let client = RpcClient::new("http://localhost:8545/rpc").unwrap();
+        assert_eq!(client.path, "/rpc");
+        assert!(RpcClient::new("ws://127.0.0.1:8545").is_err());
+        assert!(RpcClient::new("http:///").is_err());
+
+        assert_eq!(
+            dechunk(b"4\r\n{\"a\"\r\n3;x=1\r\n:1}\r\n0\r\n\r\n").unwrap(),
+            b"{\"a\":1}"
+        );
+        assert_eq!(dechunk(b"9\r\nshort\r\n"), None);
+    }
+}
@@ -0,0 +1,631 @@
+use crate::beacon::{self, BeaconValue};
+use crate::content_key::{ContentKey, ContentKeyError, HistoryContentKey, StateContentKey};
+use crate::header::Header;
+use crate::network::Network;
+use crate::rlp::{self, RlpError};
+use crate::ssz::{decode_byte_lists, SszError, SszReader};
+use crate::trie::TrieNode;
+use thiserror::Error;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ContentError {
+    #[error("invalid content key: {0}")]
+    Key(#[from] ContentKeyError),
+    #[error("invalid ssz: {0}")]
+    Ssz(#[from] SszError),
+    #[error("invalid rlp: {0}")]
+    Rlp(#[from] RlpError),
+    #[error("unknown block header proof selector 0x{0:02x}")]
+    UnknownProofSelector(u8),
+    #[error("unknown fork digest 0x{}", hex::encode(.0))]
+    UnknownForkDigest([u8; 4]),
+    #[error("fork digest 0x{} does not match 0x{} of the value's epoch", hex::encode(.found), hex::encode(.expected))]
+    WrongForkDigest { found: [u8; 4], expected: [u8; 4] },
+}
+
+/// Roots of the `execution_block_proof` up to Capella, a fixed size vector.
+const CAPELLA_EXECUTION_PROOF_LEN: usize = 11;
+/// Limit of the `execution_block_proof` list from Deneb on.
+const DENEB_EXECUTION_PROOF_LIMIT: usize = 12;
+/// Size of a Capella `BlockProofHistoricalSummaries`, which tells it apart
+/// from the variable size Deneb one.
+const CAPELLA_SUMMARIES_PROOF_SIZE: usize = (13 + 1 + CAPELLA_EXECUTION_PROOF_LEN) * 32 + 8;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockProofHistoricalRoots {
+    pub beacon_block_proof: Vec<[u8; 32]>,
+    pub beacon_block_root: [u8; 32],
+    pub execution_block_proof: Vec<[u8; 32]>,
+    pub slot: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockProofHistoricalSummaries {
+    pub beacon_block_proof: Vec<[u8; 32]>,
+    pub beacon_block_root: [u8; 32],
+    pub execution_block_proof: Vec<[u8; 32]>,
+    pub slot: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BlockHeaderProof {
+    None,
+    HistoricalHashesAccumulator(Vec<[u8; 32]>),
+    HistoricalRoots(BlockProofHistoricalRoots),
+    HistoricalSummaries(BlockProofHistoricalSummaries),
+    /// From Deneb on the execution block proof is a list, under the same selector.
+    HistoricalSummariesDeneb(BlockProofHistoricalSummaries),
+}
+
+impl BlockHeaderProof {
+    pub fn decode(data: &[u8]) -> Result<BlockHeaderProof, ContentError> {
+        let (&selector, rest) = data
+            .split_first()
+            .ok_or(SszError::UnexpectedEnd { len: 1, pos: 0 })?;
+        let mut reader = SszReader::new(rest);
+        let proof = match selector {
+            0x00 => BlockHeaderProof::None,
+            0x01 => BlockHeaderProof::HistoricalHashesAccumulator(reader.roots(15)?),
+            0x02 => BlockHeaderProof::HistoricalRoots(BlockProofHistoricalRoots {
+                beacon_block_proof: reader.roots(14)?,
+                beacon_block_root: reader.array()?,
+                execution_block_proof: reader.roots(CAPELLA_EXECUTION_PROOF_LEN)?,
+                slot: reader.u64()?,
+            }),
+            0x03 if rest.len() == CAPELLA_SUMMARIES_PROOF_SIZE => {
+                BlockHeaderProof::HistoricalSummaries(BlockProofHistoricalSummaries {
+                    beacon_block_proof: reader.roots(13)?,
+                    beacon_block_root: reader.array()?,
+                    execution_block_proof: reader.roots(CAPELLA_EXECUTION_PROOF_LEN)?,
+                    slot: reader.u64()?,
+                })
+            }
+            0x03 => {
+                let beacon_block_proof = reader.roots(13)?;
+                let beacon_block_root = reader.array()?;
+                reader.offset()?;
+                let slot = reader.u64()?;
+                let list = reader.finish()?[0];
+                if !list.len().is_multiple_of(32) {
+                    return Err(SszError::InvalidLength {
+                        len: list.len(),
+                        size: 32,
+                    }
+                    .into());
+                }
+                if list.len() / 32 > DENEB_EXECUTION_PROOF_LIMIT {
+                    return Err(SszError::TooLong {
+                        len: list.len() / 32,
+                        limit: DENEB_EXECUTION_PROOF_LIMIT,
+                    }
+                    .into());
+                }
+                let execution_block_proof = SszReader::new(list).roots(list.len() / 32)?;
+                return Ok(BlockHeaderProof::HistoricalSummariesDeneb(
+                    BlockProofHistoricalSummaries {
+                        beacon_block_proof,
+                        beacon_block_root,
+                        execution_block_proof,
+                        slot,
+                    },
+                ));
+            }
+            other => return Err(ContentError::UnknownProofSelector(other)),
+        };
+        reader.finish()?;
+        Ok(proof)
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let concat = |hashes: &[[u8; 32]]| hashes.concat();
+        match self {
+            BlockHeaderProof::None => vec![0x00],
+            BlockHeaderProof::HistoricalHashesAccumulator(proof) => {
+                [&[0x01][..], &concat(proof)].concat()
+            }
+            BlockHeaderProof::HistoricalRoots(proof) => [
+                &[0x02][..],
+                &concat(&proof.beacon_block_proof),
+                &proof.beacon_block_root,
+                &concat(&proof.execution_block_proof),
+                &proof.slot.to_le_bytes(),
+            ]
+            .concat(),
+            BlockHeaderProof::HistoricalSummaries(proof) => [
+                &[0x03][..],
+                &concat(&proof.beacon_block_proof),
+                &proof.beacon_block_root,
+                &concat(&proof.execution_block_proof),
+                &proof.slot.to_le_bytes(),
+            ]
+            .concat(),
+            BlockHeaderProof::HistoricalSummariesDeneb(proof) => {
+                let fixed = proof.beacon_block_proof.len() * 32 + 32 + 4 + 8;
+                [
+                    &[0x03][..],
+                    &concat(&proof.beacon_block_proof),
+                    &proof.beacon_block_root,
+                    &(fixed as u32).to_le_bytes(),
+                    &proof.slot.to_le_bytes(),
+                    &concat(&proof.execution_block_proof),
+                ]
+                .concat()
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HeaderWithProof {
+    pub header: Header,
+    pub proof: BlockHeaderProof,
+}
+
+impl HeaderWithProof {
+    pub fn decode(data: &[u8]) -> Result<HeaderWithProof, ContentError> {
+        let mut reader = SszReader::new(data);
+        reader.offset()?;
+        reader.offset()?;
+        let fields = reader.finish()?;
+        Ok(HeaderWithProof {
+            header: Header::decode(fields[0])?,
+            proof: BlockHeaderProof::decode(fields[1])?,
+        })
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let header = self.header.encode();
+        let mut out = Vec::new();
+        out.extend_from_slice(&8u32.to_le_bytes());
+        out.extend_from_slice(&(8 + header.len() as u32).to_le_bytes());
+        out.extend_from_slice(&header);
+        out.extend_from_slice(&self.proof.encode());
+        out
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Withdrawal {
+    pub index: u64,
+    pub validator_index: u64,
+    pub address: [u8; 20],
+    pub amount: u64,
+}
+
+impl Withdrawal {
+    pub fn decode(data: &[u8]) -> Result<Withdrawal, RlpError> {
+        let item = rlp::decode(data)?;
+        let fields = item.as_list()?;
+        if fields.len() != 4 {
+            return Err(RlpError::ItemCount {
+                expected: 4,
+                found: fields.len(),
+            });
+        }
+        Ok(Withdrawal {
+            index: fields[0].as_u64()?,
+            validator_index: fields[1].as_u64()?,
+            address: fields[2].as_fixed()?,
+            amount: fields[3].as_u64()?,
+        })
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut payload = Vec::new();
+        rlp::encode_u64(&mut payload, self.index);
+        rlp::encode_u64(&mut payload, self.validator_index);
+        rlp::encode_bytes(&mut payload, &self.address);
+        rlp::encode_u64(&mut payload, self.amount);
+        let mut out = Vec::new();
+        rlp::encode_list(&mut out, &payload);
+        out
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockBody {
+    /// Encoded transactions, typed ones prefixed with their type byte.
+    pub transactions: Vec<Vec<u8>>,
+    pub uncles: Vec<Header>,
+    pub withdrawals: Option<Vec<Withdrawal>>,
+}
+
+impl BlockBody {
+    pub fn decode(data: &[u8]) -> Result<BlockBody, ContentError> {
+        let mut reader = SszReader::new(data);
+        let first = data
+            .get(..4)
+            .map(|b| u32::from_le_bytes(b.try_into().unwrap()))
+            .ok_or(SszError::UnexpectedEnd { len: 4, pos: 0 })?;
+        let field_count = match first {
+            8 => 2,
+            12 => 3,
+            other => return Err(SszError::InvalidOffset(other as usize).into()),
+        };
+        for _ in 0..field_count {
+            reader.offset()?;
+        }
+        let fields = reader.finish()?;
+
+        let transactions = decode_byte_lists(fields[0])?
+            .into_iter()
+            .map(<[u8]>::to_vec)
+            .collect();
+        let uncles_item = rlp::decode(fields[1])?;
+        let mut uncles = Vec::new();
+        for uncle in uncles_item.as_list()? {
+            let mut encoded = Vec::new();
+            rlp::encode_item(&mut encoded, uncle);
+            uncles.push(Header::decode(&encoded)?);
+        }
+        let withdrawals = fields
+            .get(2)
+            .map(|field| {
+                decode_byte_lists(field)?
+                    .into_iter()
+                    .map(|w| Withdrawal::decode(w).map_err(ContentError::from))
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .transpose()?;
+
+        Ok(BlockBody {
+            transactions,
+            uncles,
+            withdrawals,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Log {
+    pub address: [u8; 20],
+    pub topics: Vec<[u8; 32]>,
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Receipt {
+    pub tx_type: u8,
+    /// The post-state root before Byzantium, the status byte after.
+    pub status: Vec<u8>,
+    pub cumulative_gas_used: u64,
+    pub logs_bloom: Vec<u8>,
+    pub logs: Vec<Log>,
+}
+
+impl Receipt {
+    pub fn decode(data: &[u8]) -> Result<Receipt, RlpError> {
+        let (tx_type, payload) = match data.first() {
+            Some(&first @ 0x00..=0x7f) => (first, &data[1..]),
+            _ => (0, data),
+        };
+        let item = rlp::decode(payload)?;
+        let fields = item.as_list()?;
+        if fields.len() != 4 {
+            return Err(RlpError::ItemCount {
+                expected: 4,
+                found: fields.len(),
+            });
+        }
+        let mut logs = Vec::new();
+        for log in fields[3].as_list()? {
+            let parts = log.as_list()?;
+            if parts.len() != 3 {
+                return Err(RlpError::ItemCount {
+                    expected: 3,
+                    found: parts.len(),
+                });
+            }
+            logs.push(Log {
+                address: parts[0].as_fixed()?,
+                topics: parts[1]
+                    .as_list()?
+                    .iter()
+                    .map(|topic| topic.as_fixed())
+                    .collect::<Result<_, _>>()?,
+                data: parts[2].as_bytes()?.to_vec(),
+            });
+        }
+        Ok(Receipt {
+            tx_type,
+            status: fields[0].as_bytes()?.to_vec(),
+            cumulative_gas_used: fields[1].as_u64()?,
+            logs_bloom: fields[2].as_fixed::<256>()?.to_vec(),
+            logs,
+        })
+    }
+}
+
+pub fn decode_receipts(data: &[u8]) -> Result<Vec<Receipt>, ContentError> {
+    decode_byte_lists(data)?
+        .into_iter()
+        .map(|receipt| Receipt::decode(receipt).map_err(ContentError::from))
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StateValue {
+    TrieNode(TrieNode),
+    ContractBytecode(Vec<u8>),
+    AccountTrieNodeWithProof {
+        proof: Vec<TrieNode>,
+        block_hash: [u8; 32],
+    },
+    ContractStorageTrieNodeWithProof {
+        storage_proof: Vec<TrieNode>,
+        account_proof: Vec<TrieNode>,
+        block_hash: [u8; 32],
+    },
+    ContractBytecodeWithProof {
+        code: Vec<u8>,
+        account_proof: Vec<TrieNode>,
+        block_hash: [u8; 32],
+    },
+}
+
+fn decode_proof(data: &[u8]) -> Result<Vec<TrieNode>, ContentError> {
+    decode_byte_lists(data)?
+        .into_iter()
+        .map(|node| TrieNode::decode(node).map_err(ContentError::from))
+        .collect()
+}
+
+impl StateValue {
+    /// Decodes either the retrieval or the offer form, told apart by the first offset.
+    pub fn decode(key: &StateContentKey, data: &[u8]) -> Result<StateValue, ContentError> {
+        let mut reader = SszReader::new(data);
+        if data.get(..4) == Some(&4u32.to_le_bytes()[..]) {
+            reader.offset()?;
+            let fields = reader.finish()?;
+            return Ok(match key {
+                StateContentKey::ContractBytecode { .. } => {
+                    StateValue::ContractBytecode(fields[0].to_vec())
+                }
+                _ => StateValue::TrieNode(TrieNode::decode(fields[0])?),
+            });
+        }
+
+        reader.offset()?;
+        if let StateContentKey::AccountTrieNode { .. } = key {
+            let block_hash = reader.array()?;
+            let fields = reader.finish()?;
+            return Ok(StateValue::AccountTrieNodeWithProof {
+                proof: decode_proof(fields[0])?,
+                block_hash,
+            });
+        }
+        reader.offset()?;
+        let block_hash = reader.array()?;
+        let fields = reader.finish()?;
+        Ok(match key {
+            StateContentKey::ContractBytecode { .. } => StateValue::ContractBytecodeWithProof {
+                code: fields[0].to_vec(),
+                account_proof: decode_proof(fields[1])?,
+                block_hash,
+            },
+            _ => StateValue::ContractStorageTrieNodeWithProof {
+                storage_proof: decode_proof(fields[0])?,
+                account_proof: decode_proof(fields[1])?,
+                block_hash,
+            },
+        })
+    }
+
+    /// The retrieval form of the value: the requested node or code, without proofs.
+    pub fn retrieval_value(&self) -> Vec<u8> {
+        let payload = match self {
+            StateValue::TrieNode(node) => &node.encoded[..],
+            StateValue::ContractBytecode(code)
+            | StateValue::ContractBytecodeWithProof { code, .. } => code,
+            StateValue::AccountTrieNodeWithProof { proof: nodes, .. }
+            | StateValue::ContractStorageTrieNodeWithProof {
+                storage_proof: nodes,
+                ..
+            } => nodes.last().map_or(&[][..], |node| &node.encoded),
+        };
+        let mut out = 4u32.to_le_bytes().to_vec();
+        out.extend_from_slice(payload);
+        out
+    }
+}
+
+/// A content value decoded according to its content key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ContentValue {
+    HeaderWithProof(Box<HeaderWithProof>),
+    BlockBody(BlockBody),
+    Receipts(Vec<Receipt>),
+    State(StateValue),
+    Beacon(BeaconValue),
+}
+
+impl ContentValue {
+    pub fn decode(
+        network: &Network,
+        key: &ContentKey,
+        data: &[u8],
+    ) -> Result<ContentValue, ContentError> {
+        Ok(match key {
+            ContentKey::History(
+                HistoryContentKey::BlockHeaderByHash(_) | HistoryContentKey::BlockHeaderByNumber(_),
+            ) => ContentValue::HeaderWithProof(Box::new(HeaderWithProof::decode(data)?)),
+            ContentKey::History(HistoryContentKey::BlockBody(_)) => {
+                ContentValue::BlockBody(BlockBody::decode(data)?)
+            }
+            ContentKey::History(HistoryContentKey::Receipts(_)) => {
+                ContentValue::Receipts(decode_receipts(data)?)
+            }
+            ContentKey::State(key) => ContentValue::State(StateValue::decode(key, data)?),
+            ContentKey::Beacon(key) => ContentValue::Beacon(beacon::decode(network, key, data)?),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vector::{discover, network_dir, VectorKind};
+
+    #[test]
+    fn test_every_vector_value_decodes() {
+        let mut decoded = 0;
+        for file in discover(&network_dir("mainnet")).unwrap() {
+            if file.kind == Some(VectorKind::Invalid) {
+                continue;
+            }
+            for entry in file.content_entries().unwrap() {
+                let key = ContentKey::decode(&entry.content_key).unwrap();
+                for value in [&entry.content_value, &entry.offer_value]
+                    .into_iter()
+                    .flatten()
+                {
+                    ContentValue::decode(&Network::mainnet(), &key, value).unwrap_or_else(|e| {
+                        panic!("{} {}: {}", file.relative.display(), entry.label, e)
+                    });
+                    decoded += 1;
+                }
+            }
+        }
+        assert!(decoded > 50);
+    }
+
+    #[test]
+    fn test_shanghai_body_has_withdrawals() {
+        let root = network_dir("mainnet");
+        let file =
+            crate::vector::VectorFile::load(&root, &root.join("history/bodies/17139055.json"))
+                .unwrap();
+        let entry = &file.content_entries().unwrap()[0];
+        let body = BlockBody::decode(entry.content_value.as_ref().unwrap()).unwrap();
+        assert!(!body.transactions.is_empty());
+        assert!(!body.withdrawals.unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_header_with_proof_round_trip() {
+        let mut encoded = 0;
+        for file in discover(&network_dir("mainnet")).unwrap() {
+            if file.kind == Some(VectorKind::Invalid) {
+                continue;
+            }
+            for entry in file.content_entries().unwrap() {
+                let key = ContentKey::decode(&entry.content_key).unwrap();
+                let Some(value) = entry.content_value.filter(|_| {
+                    matches!(
+                        key,
+                        ContentKey::History(HistoryContentKey::BlockHeaderByHash(_))
+                            | ContentKey::History(HistoryContentKey::BlockHeaderByNumber(_))
+                    )
+                }) else {
+                    continue;
+                };
+                let header_with_proof = HeaderWithProof::decode(&value).unwrap();
+                assert_eq!(
+                    header_with_proof.encode(),
+                    value,
+                    "{}",
+                    file.relative.display()
+                );
+                encoded += 1;
+            }
+        }
+        assert!(encoded > 10);
+    }
+
+    #[test]
+    fn test_rejects_bad_offsets() {
+        assert!(HeaderWithProof::decode(&[0x09, 0, 0, 0, 0x08, 0, 0, 0]).is_err());
+        assert!(decode_byte_lists(&[0x03, 0, 0, 0]).is_err());
+        assert_eq!(
+            BlockHeaderProof::decode(&[0x07]),
+            Err(ContentError::UnknownProofSelector(0x07))
+        );
+    }
+
+    #[test]
+    fn test_rejects_empty_proof() {
+        let empty = ContentError::Ssz(SszError::UnexpectedEnd { len: 1, pos: 0 });
+        assert_eq!(BlockHeaderProof::decode(&[]), Err(empty.clone()));
+
+        // A header whose proof offset points at the end of the value.
+        let root = network_dir("mainnet");
+        let file = crate::vector::VectorFile::load(
+            &root,
+            &root.join("history/headers_with_proof/14764013.yaml"),
+        )
+        .unwrap();
+        let value = file.content_entries().unwrap()[0]
+            .content_value
+            .clone()
+            .unwrap();
+        let proof_offset = u32::from_le_bytes(value[4..8].try_into().unwrap()) as usize;
+        assert_eq!(HeaderWithProof::decode(&value[..proof_offset]), Err(empty));
+    }
+
+    #[test]
+    fn test_deneb_summaries_proof() {
+        // The Cancun header of the hive collection, with a proof laid out as a
+        // `BlockProofHistoricalSummariesDeneb`.
+        let root = network_dir("mainnet");
+        let file = crate::vector::VectorFile::load(
+            &root,
+            &root.join("history/hive/test_data_collection_of_forks_blocks.yaml"),
+        )
+        .unwrap();
+        let value = file.content_entries().unwrap()[15]
+            .content_value
+            .clone()
+            .unwrap();
+        let header = HeaderWithProof::decode(&value).unwrap().header;
+        assert!(header.parent_beacon_block_root.is_some());
+
+        let root_bytes = |n: u8| [n; 32];
+        let mut proof = vec![0x03];
+        (0..13).for_each(|n| proof.extend_from_slice(&root_bytes(n)));
+        proof.extend_from_slice(&root_bytes(0xbb));
+        proof.extend_from_slice(&460u32.to_le_bytes());
+        proof.extend_from_slice(&8_900_000u64.to_le_bytes());
+        (0..12).for_each(|n| proof.extend_from_slice(&root_bytes(0xe0 + n)));
+        let mut value = HeaderWithProof {
+            header,
+            proof: BlockHeaderProof::None,
+        }
+        .encode();
+        value.truncate(value.len() - 1);
+        value.extend_from_slice(&proof);
+
+        let decoded = HeaderWithProof::decode(&value).unwrap();
+        let BlockHeaderProof::HistoricalSummariesDeneb(deneb) = &decoded.proof else {
+            panic!("expected a Deneb proof, got {:?}", decoded.proof);
+        };
+        assert_eq!(deneb.beacon_block_root, [0xbb; 32]);
+        assert_eq!(deneb.execution_block_proof.len(), 12);
+        assert_eq!(deneb.execution_block_proof[11], [0xeb; 32]);
+        assert_eq!(deneb.slot, 8_900_000);
+        assert_eq!(decoded.encode(), value);
+
+        // A thirteenth execution root is over the list limit.
+        proof.extend_from_slice(&root_bytes(0xff));
+        assert_eq!(
+            BlockHeaderProof::decode(&proof),
+            Err(ContentError::Ssz(SszError::TooLong { len: 13, limit: 12 }))
+        );
+    }
+
+    #[test]
+    fn test_typed_receipt_bytes() {
+        let mut fields = Vec::new();
+        rlp::encode_bytes(&mut fields, &[1]);
+        rlp::encode_bytes(&mut fields, &[0x52, 0x08]);
+        rlp::encode_bytes(&mut fields, &[0; 256]);
+        rlp::encode_list(&mut fields, &[]);
+        let mut receipt = Vec::new();
+        rlp::encode_list(&mut receipt, &fields);
+        // 0x7f is the last EIP-2718 type byte.
+        for tx_type in [0x01, 0x7f] {
+            let typed = [&[tx_type][..], &receipt].concat();
+            assert_eq!(Receipt::decode(&typed).unwrap().tx_type, tx_type);
+        }
+        assert_eq!(Receipt::decode(&receipt).unwrap().tx_type, 0);
+    }
+}
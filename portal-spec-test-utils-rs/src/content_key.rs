@@ -0,0 +1,416 @@
+use sha2::{Digest, Sha256};
+use std::fmt;
+use thiserror::Error;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ContentKeyError {
+    #[error("content key is empty")]
+    Empty,
+    #[error("unknown content key selector 0x{0:02x}")]
+    UnknownSelector(u8),
+    #[error("expected {expected} bytes after selector 0x{selector:02x}, found {found}")]
+    InvalidLength {
+        selector: u8,
+        expected: usize,
+        found: usize,
+    },
+    #[error("invalid ssz offset {0}")]
+    InvalidOffset(u32),
+    #[error("invalid nibbles encoding")]
+    InvalidNibbles,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Subnetwork {
+    History,
+    State,
+    Beacon,
+}
+
+impl fmt::Display for Subnetwork {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Subnetwork::History => write!(f, "history"),
+            Subnetwork::State => write!(f, "state"),
+            Subnetwork::Beacon => write!(f, "beacon"),
+        }
+    }
+}
+
+impl Subnetwork {
+    /// The discv5 TALKREQ protocol id the subnetwork's messages are sent under.
+    pub fn protocol_id(&self) -> [u8; 2] {
+        match self {
+            Subnetwork::State => [0x50, 0x0a],
+            Subnetwork::History => [0x50, 0x0b],
+            Subnetwork::Beacon => [0x50, 0x0c],
+        }
+    }
+
+    pub fn from_protocol_id(protocol_id: &[u8]) -> Option<Subnetwork> {
+        [Subnetwork::History, Subnetwork::State, Subnetwork::Beacon]
+            .into_iter()
+            .find(|subnetwork| subnetwork.protocol_id() == protocol_id)
+    }
+}
+
+/// A trie path, one nibble per element.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Nibbles(pub Vec<u8>);
+
+impl Nibbles {
+    /// Decodes the packed form: a flag nibble (0 even, 1 odd), then the nibbles.
+    pub fn decode(data: &[u8]) -> Result<Nibbles, ContentKeyError> {
+        let (first, rest) = data.split_first().ok_or(ContentKeyError::InvalidNibbles)?;
+        let mut nibbles = Vec::with_capacity(rest.len() * 2 + 1);
+        match first >> 4 {
+            0 if first & 0x0f == 0 => {}
+            1 => nibbles.push(first & 0x0f),
+            _ => return Err(ContentKeyError::InvalidNibbles),
+        }
+        for byte in rest {
+            nibbles.push(byte >> 4);
+            nibbles.push(byte & 0x0f);
+        }
+        Ok(Nibbles(nibbles))
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(self.0.len() / 2 + 1);
+        let rest = if self.0.len() % 2 == 1 {
+            out.push(0x10 | self.0[0]);
+            &self.0[1..]
+        } else {
+            out.push(0x00);
+            &self.0[..]
+        };
+        for pair in rest.chunks(2) {
+            out.push((pair[0] << 4) | pair[1]);
+        }
+        out
+    }
+}
+
+impl fmt::Display for Nibbles {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[")?;
+        for nibble in &self.0 {
+            write!(f, "{:x}", nibble)?;
+        }
+        write!(f, "]")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HistoryContentKey {
+    BlockHeaderByHash([u8; 32]),
+    BlockBody([u8; 32]),
+    Receipts([u8; 32]),
+    BlockHeaderByNumber(u64),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StateContentKey {
+    AccountTrieNode {
+        path: Nibbles,
+        node_hash: [u8; 32],
+    },
+    ContractStorageTrieNode {
+        address: [u8; 20],
+        path: Nibbles,
+        node_hash: [u8; 32],
+    },
+    ContractBytecode {
+        address: [u8; 20],
+        code_hash: [u8; 32],
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BeaconContentKey {
+    LightClientBootstrap { block_root: [u8; 32] },
+    LightClientUpdatesByRange { start_period: u64, count: u64 },
+    LightClientFinalityUpdate { finalized_slot: u64 },
+    LightClientOptimisticUpdate { signature_slot: u64 },
+    HistoricalSummariesWithProof { epoch: u64 },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ContentKey {
+    History(HistoryContentKey),
+    State(StateContentKey),
+    Beacon(BeaconContentKey),
+}
+
+fn fixed<const N: usize>(selector: u8, data: &[u8]) -> Result<[u8; N], ContentKeyError> {
+    data.try_into().map_err(|_| ContentKeyError::InvalidLength {
+        selector,
+        expected: N,
+        found: data.len(),
+    })
+}
+
+fn read_u64(data: &[u8]) -> u64 {
+    u64::from_le_bytes(data.try_into().expect("caller checked the length"))
+}
+
+/// Splits a container with one variable `Nibbles` field at `offset_at`.
+fn decode_path(
+    selector: u8,
+    data: &[u8],
+    offset_at: usize,
+    fixed_len: usize,
+) -> Result<Nibbles, ContentKeyError> {
+    if data.len() <= fixed_len {
+        return Err(ContentKeyError::InvalidLength {
+            selector,
+            expected: fixed_len + 1,
+            found: data.len(),
+        });
+    }
+    let offset = u32::from_le_bytes(data[offset_at..offset_at + 4].try_into().unwrap());
+    if offset as usize != fixed_len {
+        return Err(ContentKeyError::InvalidOffset(offset));
+    }
+    Nibbles::decode(&data[fixed_len..])
+}
+
+impl ContentKey {
+    pub fn decode(data: &[u8]) -> Result<ContentKey, ContentKeyError> {
+        let (&selector, rest) = data.split_first().ok_or(ContentKeyError::Empty)?;
+        let key = match selector {
+            0x00 => {
+                ContentKey::History(HistoryContentKey::BlockHeaderByHash(fixed(selector, rest)?))
+            }
+            0x01 => ContentKey::History(HistoryContentKey::BlockBody(fixed(selector, rest)?)),
+            0x02 => ContentKey::History(HistoryContentKey::Receipts(fixed(selector, rest)?)),
+            0x03 => ContentKey::History(HistoryContentKey::BlockHeaderByNumber(
+                u64::from_le_bytes(fixed(selector, rest)?),
+            )),
+            0x10 => ContentKey::Beacon(BeaconContentKey::LightClientBootstrap {
+                block_root: fixed(selector, rest)?,
+            }),
+            0x11 => {
+                let range: [u8; 16] = fixed(selector, rest)?;
+                ContentKey::Beacon(BeaconContentKey::LightClientUpdatesByRange {
+                    start_period: read_u64(&range[..8]),
+                    count: read_u64(&range[8..]),
+                })
+            }
+            0x12 => ContentKey::Beacon(BeaconContentKey::LightClientFinalityUpdate {
+                finalized_slot: u64::from_le_bytes(fixed(selector, rest)?),
+            }),
+            0x13 => ContentKey::Beacon(BeaconContentKey::LightClientOptimisticUpdate {
+                signature_slot: u64::from_le_bytes(fixed(selector, rest)?),
+            }),
+            0x14 => ContentKey::Beacon(BeaconContentKey::HistoricalSummariesWithProof {
+                epoch: u64::from_le_bytes(fixed(selector, rest)?),
+            }),
+            0x20 => {
+                let path = decode_path(selector, rest, 0, 36)?;
+                ContentKey::State(StateContentKey::AccountTrieNode {
+                    path,
+                    node_hash: fixed(selector, &rest[4..36])?,
+                })
+            }
+            0x21 => {
+                let path = decode_path(selector, rest, 20, 56)?;
+                ContentKey::State(StateContentKey::ContractStorageTrieNode {
+                    address: fixed(selector, &rest[..20])?,
+                    path,
+                    node_hash: fixed(selector, &rest[24..56])?,
+                })
+            }
+            0x22 => {
+                let body: [u8; 52] = fixed(selector, rest)?;
+                ContentKey::State(StateContentKey::ContractBytecode {
+                    address: body[..20].try_into().unwrap(),
+                    code_hash: body[20..].try_into().unwrap(),
+                })
+            }
+            other => return Err(ContentKeyError::UnknownSelector(other)),
+        };
+        Ok(key)
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = vec![self.selector()];
+        match self {
+            ContentKey::History(key) => match key {
+                HistoryContentKey::BlockHeaderByHash(hash)
+                | HistoryContentKey::BlockBody(hash)
+                | HistoryContentKey::Receipts(hash) => out.extend_from_slice(hash),
+                HistoryContentKey::BlockHeaderByNumber(number) => {
+                    out.extend_from_slice(&number.to_le_bytes())
+                }
+            },
+            ContentKey::Beacon(key) => match key {
+                BeaconContentKey::LightClientBootstrap { block_root } => {
+                    out.extend_from_slice(block_root)
+                }
+                BeaconContentKey::LightClientUpdatesByRange {
+                    start_period,
+                    count,
+                } => {
+                    out.extend_from_slice(&start_period.to_le_bytes());
+                    out.extend_from_slice(&count.to_le_bytes());
+                }
+                BeaconContentKey::LightClientFinalityUpdate {
+                    finalized_slot: slot,
+                }
+                | BeaconContentKey::LightClientOptimisticUpdate {
+                    signature_slot: slot,
+                }
+                | BeaconContentKey::HistoricalSummariesWithProof { epoch: slot } => {
+                    out.extend_from_slice(&slot.to_le_bytes())
+                }
+            },
+            ContentKey::State(key) => match key {
+                StateContentKey::AccountTrieNode { path, node_hash } => {
+                    out.extend_from_slice(&36u32.to_le_bytes());
+                    out.extend_from_slice(node_hash);
+                    out.extend_from_slice(&path.encode());
+                }
+                StateContentKey::ContractStorageTrieNode {
+                    address,
+                    path,
+                    node_hash,
+                } => {
+                    out.extend_from_slice(address);
+                    out.extend_from_slice(&56u32.to_le_bytes());
+                    out.extend_from_slice(node_hash);
+                    out.extend_from_slice(&path.encode());
+                }
+                StateContentKey::ContractBytecode { address, code_hash } => {
+                    out.extend_from_slice(address);
+                    out.extend_from_slice(code_hash);
+                }
+            },
+        }
+        out
+    }
+
+    pub fn selector(&self) -> u8 {
+        match self {
+            ContentKey::History(HistoryContentKey::BlockHeaderByHash(_)) => 0x00,
+            ContentKey::History(HistoryContentKey::BlockBody(_)) => 0x01,
+            ContentKey::History(HistoryContentKey::Receipts(_)) => 0x02,
+            ContentKey::History(HistoryContentKey::BlockHeaderByNumber(_)) => 0x03,
+            ContentKey::Beacon(BeaconContentKey::LightClientBootstrap { .. }) => 0x10,
+            ContentKey::Beacon(BeaconContentKey::LightClientUpdatesByRange { .. }) => 0x11,
+            ContentKey::Beacon(BeaconContentKey::LightClientFinalityUpdate { .. }) => 0x12,
+            ContentKey::Beacon(BeaconContentKey::LightClientOptimisticUpdate { .. }) => 0x13,
+            ContentKey::Beacon(BeaconContentKey::HistoricalSummariesWithProof { .. }) => 0x14,
+            ContentKey::State(StateContentKey::AccountTrieNode { .. }) => 0x20,
+            ContentKey::State(StateContentKey::ContractStorageTrieNode { .. }) => 0x21,
+            ContentKey::State(StateContentKey::ContractBytecode { .. }) => 0x22,
+        }
+    }
+
+    pub fn subnetwork(&self) -> Subnetwork {
+        match self {
+            ContentKey::History(_) => Subnetwork::History,
+            ContentKey::State(_) => Subnetwork::State,
+            ContentKey::Beacon(_) => Subnetwork::Beacon,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ContentKey::History(HistoryContentKey::BlockHeaderByHash(_)) => "block_header_by_hash",
+            ContentKey::History(HistoryContentKey::BlockBody(_)) => "block_body",
+            ContentKey::History(HistoryContentKey::Receipts(_)) => "receipts",
+            ContentKey::History(HistoryContentKey::BlockHeaderByNumber(_)) => {
+                "block_header_by_number"
+            }
+            ContentKey::Beacon(BeaconContentKey::LightClientBootstrap { .. }) => {
+                "light_client_bootstrap"
+            }
+            ContentKey::Beacon(BeaconContentKey::LightClientUpdatesByRange { .. }) => {
+                "light_client_updates_by_range"
+            }
+            ContentKey::Beacon(BeaconContentKey::LightClientFinalityUpdate { .. }) => {
+                "light_client_finality_update"
+            }
+            ContentKey::Beacon(BeaconContentKey::LightClientOptimisticUpdate { .. }) => {
+                "light_client_optimistic_update"
+            }
+            ContentKey::Beacon(BeaconContentKey::HistoricalSummariesWithProof { .. }) => {
+                "historical_summaries_with_proof"
+            }
+            ContentKey::State(StateContentKey::AccountTrieNode { .. }) => "account_trie_node",
+            ContentKey::State(StateContentKey::ContractStorageTrieNode { .. }) => {
+                "contract_storage_trie_node"
+            }
+            ContentKey::State(StateContentKey::ContractBytecode { .. }) => "contract_bytecode",
+        }
+    }
+
+    /// The content id is the sha256 of the encoded key on every subnetwork.
+    pub fn content_id(&self) -> [u8; 32] {
+        content_id(&self.encode())
+    }
+}
+
+pub fn content_id(content_key: &[u8]) -> [u8; 32] {
+    Sha256::digest(content_key).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vector::{decode_hex, discover, network_dir};
+
+    #[test]
+    fn test_all_vector_keys_round_trip() {
+        let mut decoded = 0;
+        for file in discover(&network_dir("mainnet")).unwrap() {
+            if file.kind == Some(crate::vector::VectorKind::Invalid) {
+                continue;
+            }
+            for entry in file.content_entries().unwrap() {
+                let key = ContentKey::decode(&entry.content_key).unwrap_or_else(|e| {
+                    panic!("{} {}: {}", file.relative.display(), entry.label, e)
+                });
+                assert_eq!(key.encode(), entry.content_key);
+                decoded += 1;
+            }
+        }
+        assert!(decoded > 50);
+    }
+
+    #[test]
+    fn test_state_key_content_id() {
+        let doc: serde_yaml::Value = serde_yaml::from_str(
+            &std::fs::read_to_string(
+                network_dir("mainnet").join("state/serialization/account_trie_node_key.yaml"),
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        let key = decode_hex(doc["content_key"].as_str().unwrap()).unwrap();
+        let id = decode_hex(doc["content_id"].as_str().unwrap()).unwrap();
+        let ContentKey::State(StateContentKey::AccountTrieNode { path, .. }) =
+            ContentKey::decode(&key).unwrap()
+        else {
+            panic!("expected an account trie node key");
+        };
+        assert_eq!(path.0, vec![8, 6, 7, 9, 14, 8, 14, 13]);
+        assert_eq!(content_id(&key).to_vec(), id);
+    }
+
+    #[test]
+    fn test_rejects_bad_keys() {
+        assert_eq!(ContentKey::decode(&[]), Err(ContentKeyError::Empty));
+        assert_eq!(
+            ContentKey::decode(&[0x09]),
+            Err(ContentKeyError::UnknownSelector(0x09))
+        );
+        assert!(ContentKey::decode(&[0x00, 0x01]).is_err());
+        assert_eq!(
+            Nibbles::decode(&[0x20]),
+            Err(ContentKeyError::InvalidNibbles)
+        );
+        assert_eq!(Nibbles(vec![1, 2, 3]).encode(), vec![0x11, 0x23]);
+    }
+}
@@ -0,0 +1,277 @@
+use crate::content::HeaderWithProof;
+use crate::get_flair;
+use crate::header::Header;
+use crate::vector::{discover, encode_hex, VectorError, VectorFile, VectorFormat, VectorKind};
+use crate::yaml::{Document, Node, Scalar, YamlError};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ConvertError {
+    #[error(transparent)]
+    Vector(#[from] VectorError),
+    #[error("failed to parse {path}: {source}")]
+    Yaml { path: PathBuf, source: YamlError },
+    #[error("{path}: entry {label} is not keyed by a block number")]
+    NotABlock { path: PathBuf, label: String },
+    #[error("{path}: not a header, body or receipts collection")]
+    UnknownKind { path: PathBuf },
+}
+
+/// A per-block YAML file split out of a JSON collection, relative to the network directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SplitFile {
+    pub relative: PathBuf,
+    pub text: String,
+}
+
+/// JSON maps of block number to content, such as `history/bodies/17139055.json`.
+pub fn is_collection(file: &VectorFile) -> bool {
+    file.format == VectorFormat::Json
+        && matches!(
+            file.kind,
+            Some(
+                VectorKind::HistoryHeaderWithProof
+                    | VectorKind::HistoryBlockBody
+                    | VectorKind::HistoryReceipts
+            )
+        )
+}
+
+/// Every header carried by a header content value in `files`, by block hash.
+pub fn known_headers(files: &[VectorFile]) -> HashMap<[u8; 32], Header> {
+    let mut headers = HashMap::new();
+    for file in files.iter().filter(|f| f.kind != Some(VectorKind::Invalid)) {
+        for entry in file.content_entries().unwrap_or_default() {
+            let Some(value) = entry.content_value.as_ref() else {
+                continue;
+            };
+            if entry.content_key.first() != Some(&0x00) {
+                continue;
+            }
+            if let Ok(decoded) = HeaderWithProof::decode(value) {
+                headers.insert(decoded.header.hash(), decoded.header);
+            }
+        }
+    }
+    headers
+}
+
+/// `YYYY-MM-DD HH:MM:SS UTC` of a unix timestamp.
+pub fn format_timestamp(timestamp: u64) -> String {
+    let days = (timestamp / 86400) as i64;
+    let seconds = timestamp % 86400;
+    // Civil date from days since the epoch, after Howard Hinnant's `civil_from_days`.
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+        year,
+        month,
+        day,
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+/// The comments heading a new per-block file.
+pub fn provenance(kind: VectorKind, block_number: u64, header: Option<&Header>) -> Vec<String> {
+    let content = match kind {
+        VectorKind::HistoryHeaderWithProof => "HeaderWithProof",
+        VectorKind::HistoryBlockBody => "BlockBody",
+        VectorKind::HistoryReceipts => "Receipts",
+        other => other.name(),
+    };
+    let mut comments = vec![
+        format!("# History {} content key & value", content),
+        String::new(),
+        format!("# Block #{}{}", block_number, get_flair(block_number)),
+    ];
+    if let Some(header) = header {
+        comments.push(format!(
+            "# Timestamp: {}",
+            format_timestamp(header.timestamp)
+        ));
+    }
+    comments
+}
+
+fn parse_existing(root: &Path, relative: &Path) -> Result<Option<Document>, ConvertError> {
+    let path = root.join(relative);
+    let Ok(text) = fs::read_to_string(&path) else {
+        return Ok(None);
+    };
+    Document::parse(&text)
+        .map(Some)
+        .map_err(|source| ConvertError::Yaml { path, source })
+}
+
+/// Splits a JSON collection into one YAML file per block. A file that already
+/// exists keeps its comments and only has its values replaced.
+pub fn split(
+    root: &Path,
+    file: &VectorFile,
+    headers: &HashMap<[u8; 32], Header>,
+) -> Result<Vec<SplitFile>, ConvertError> {
+    let kind = file.kind.ok_or_else(|| ConvertError::UnknownKind {
+        path: file.path.clone(),
+    })?;
+    let mut split = Vec::new();
+    for entry in file.content_entries()? {
+        let block_number: u64 = entry.label.parse().map_err(|_| ConvertError::NotABlock {
+            path: file.path.clone(),
+            label: entry.label.clone(),
+        })?;
+        let relative = file
+            .relative
+            .with_file_name(format!("{}.yaml", block_number));
+        let mut document = match parse_existing(root, &relative)? {
+            Some(document) => document,
+            None => {
+                let header = entry
+                    .content_key
+                    .get(1..33)
+                    .and_then(|hash| headers.get(hash));
+                let mut document = Document::default();
+                document.entry("content_key").comments = provenance(kind, block_number, header);
+                document
+            }
+        };
+        let hex = |bytes: &[u8]| Node::Scalar(Scalar::double_quoted(encode_hex(bytes)));
+        document.set("content_key", hex(&entry.content_key));
+        if let Some(value) = &entry.content_value {
+            document.set("content_value", hex(value));
+        }
+        split.push(SplitFile {
+            relative,
+            text: document.render(),
+        });
+    }
+    Ok(split)
+}
+
+/// Splits every JSON collection below a network directory.
+pub fn split_all(root: &Path) -> Result<Vec<SplitFile>, ConvertError> {
+    let files = discover(root)?;
+    let headers = known_headers(&files);
+    let mut split = Vec::new();
+    for file in files.iter().filter(|f| is_collection(f)) {
+        split.extend(self::split(root, file, &headers)?);
+    }
+    Ok(split)
+}
+
+pub fn write_all(root: &Path, files: &[SplitFile]) -> io::Result<()> {
+    for file in files {
+        fs::write(root.join(&file.relative), &file.text)?;
+    }
+    Ok(())
+}
+
+/// Returns the files that are missing or differ from what `split_all` produces.
+pub fn stale_files(root: &Path, files: &[SplitFile]) -> Vec<PathBuf> {
+    files
+        .iter()
+        .filter(|file| {
+            fs::read_to_string(root.join(&file.relative))
+                .ok()
+                .as_deref()
+                != Some(file.text.as_str())
+        })
+        .map(|file| file.relative.clone())
+        .collect()
+}
+
+/// Joins per-block YAML files back into a JSON collection keyed by file stem.
+pub fn join(files: &[VectorFile]) -> Result<Value, ConvertError> {
+    let mut collection = Map::new();
+    for file in files {
+        let stem = file
+            .path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or_default()
+            .to_string();
+        collection.insert(stem, file.document()?);
+    }
+    Ok(Value::Object(collection))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vector::network_dir;
+
+    #[test]
+    fn test_format_timestamp() {
+        assert_eq!(format_timestamp(0), "1970-01-01 00:00:00 UTC");
+        assert_eq!(format_timestamp(1438269988), "2015-07-30 15:26:28 UTC");
+        assert_eq!(format_timestamp(1709251199), "2024-02-29 23:59:59 UTC");
+    }
+
+    #[test]
+    fn test_collections_are_split() {
+        let root = network_dir("mainnet");
+        let split = split_all(&root).unwrap();
+        assert_eq!(split.len(), 11);
+        assert_eq!(stale_files(&root, &split), Vec::<PathBuf>::new());
+
+        let body = split
+            .iter()
+            .find(|f| f.relative == Path::new("history/bodies/17139055.yaml"))
+            .unwrap();
+        assert!(body
+            .text
+            .starts_with("# History BlockBody content key & value\n\n# Block #17139055 (post-shanghai)\ncontent_key: \"0x01"));
+        let header = split
+            .iter()
+            .find(|f| f.relative == Path::new("history/headers_with_proof/1000001.yaml"))
+            .unwrap();
+        assert!(header
+            .text
+            .contains("# Block #1000001\n# Timestamp: 2016-02-13 "));
+    }
+
+    #[test]
+    fn test_unknown_kind_is_not_split() {
+        let root = network_dir("mainnet");
+        let mut file = VectorFile::load(
+            &root,
+            &root.join("history/headers_with_proof/1000001-1000010.json"),
+        )
+        .unwrap();
+        file.kind = None;
+        assert!(matches!(
+            split(&root, &file, &HashMap::new()),
+            Err(ConvertError::UnknownKind { path }) if path == file.path
+        ));
+    }
+
+    #[test]
+    fn test_join_matches_collection() {
+        let root = network_dir("mainnet");
+        let load = |p: &str| VectorFile::load(&root, &root.join(p)).unwrap();
+        let files: Vec<VectorFile> = (1000001..=1000010)
+            .map(|n| load(&format!("history/headers_with_proof/{}.yaml", n)))
+            .collect();
+        assert_eq!(
+            join(&files).unwrap(),
+            load("history/headers_with_proof/1000001-1000010.json")
+                .document()
+                .unwrap()
+        );
+    }
+}
@@ -0,0 +1,480 @@
+use crate::beacon::{BeaconPayload, BeaconValue};
+use crate::content::{BlockHeaderProof, ContentValue, StateValue};
+use crate::content_key::{ContentKey, HistoryContentKey, StateContentKey};
+use crate::network::Network;
+use crate::vector::{discover, VectorError, VectorFile, VectorKind};
+use crate::ExecutionFork;
+use serde_json::{json, Map, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::path::Path;
+
+/// The rows of the coverage matrix.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ContentType {
+    HeaderAccumulator,
+    HeaderHistoricalRoots,
+    HeaderHistoricalSummaries,
+    HeaderWithoutProof,
+    BlockBody,
+    Receipts,
+    AccountTrieNode,
+    ContractStorageTrieNode,
+    ContractBytecode,
+    Bootstrap,
+    Updates,
+    FinalityUpdate,
+    OptimisticUpdate,
+    HistoricalSummaries,
+}
+
+impl ContentType {
+    pub const ALL: [ContentType; 14] = [
+        ContentType::HeaderAccumulator,
+        ContentType::HeaderHistoricalRoots,
+        ContentType::HeaderHistoricalSummaries,
+        ContentType::HeaderWithoutProof,
+        ContentType::BlockBody,
+        ContentType::Receipts,
+        ContentType::AccountTrieNode,
+        ContentType::ContractStorageTrieNode,
+        ContentType::ContractBytecode,
+        ContentType::Bootstrap,
+        ContentType::Updates,
+        ContentType::FinalityUpdate,
+        ContentType::OptimisticUpdate,
+        ContentType::HistoricalSummaries,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ContentType::HeaderAccumulator => "header_accumulator_proof",
+            ContentType::HeaderHistoricalRoots => "header_historical_roots_proof",
+            ContentType::HeaderHistoricalSummaries => "header_historical_summaries_proof",
+            ContentType::HeaderWithoutProof => "header_without_proof",
+            ContentType::BlockBody => "block_body",
+            ContentType::Receipts => "receipts",
+            ContentType::AccountTrieNode => "account_trie_node",
+            ContentType::ContractStorageTrieNode => "contract_storage_trie_node",
+            ContentType::ContractBytecode => "contract_bytecode",
+            ContentType::Bootstrap => "light_client_bootstrap",
+            ContentType::Updates => "light_client_updates",
+            ContentType::FinalityUpdate => "light_client_finality_update",
+            ContentType::OptimisticUpdate => "light_client_optimistic_update",
+            ContentType::HistoricalSummaries => "historical_summaries",
+        }
+    }
+}
+
+impl fmt::Display for ContentType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Cells every network directory should fill: each header proof in the era it
+/// was produced for, history content from London on, the state network's
+/// fork, and light client data in every light client fork.
+pub fn required_cells() -> Vec<(ContentType, ExecutionFork)> {
+    use ContentType::*;
+    use ExecutionFork::*;
+    let mut cells = vec![
+        (HeaderAccumulator, London),
+        (HeaderHistoricalRoots, Merge),
+        (AccountTrieNode, Shanghai),
+        (ContractStorageTrieNode, Shanghai),
+        (ContractBytecode, Shanghai),
+    ];
+    for fork in [Shanghai, Cancun, Prague] {
+        cells.push((HeaderHistoricalSummaries, fork));
+        for row in [
+            Bootstrap,
+            Updates,
+            FinalityUpdate,
+            OptimisticUpdate,
+            HistoricalSummaries,
+        ] {
+            cells.push((row, fork));
+        }
+    }
+    for fork in [London, Merge, Shanghai, Cancun, Prague] {
+        cells.push((BlockBody, fork));
+        cells.push((Receipts, fork));
+    }
+    cells.sort();
+    cells
+}
+
+/// The vectors of a network directory by content type and fork. Vectors whose
+/// block cannot be told are kept under a `None` fork.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Coverage {
+    pub network: String,
+    cells: BTreeMap<(ContentType, Option<ExecutionFork>), Vec<String>>,
+}
+
+/// The block number a file is named after, such as `17034870.yaml` or
+/// `beacon_block_proof-17034870.yaml`, or the first `# Block N` or `# Block #N`
+/// comment in it.
+fn file_block_number(file: &VectorFile) -> Option<u64> {
+    let stem = file.relative.file_stem()?.to_str()?;
+    let digits: String = stem.chars().take_while(char::is_ascii_digit).collect();
+    if !digits.is_empty() {
+        return digits.parse().ok();
+    }
+    if let Some(number) = stem
+        .split('-')
+        .skip(1)
+        .find(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit()))
+    {
+        return number.parse().ok();
+    }
+    file.text
+        .lines()
+        .find_map(|line| line.strip_prefix("# Block "))
+        .and_then(|rest| rest.trim_start_matches('#').split_whitespace().next())
+        .and_then(|number| number.replace(',', "").parse().ok())
+}
+
+/// Scans the valid vectors below `dir`. Bodies, receipts and state content are
+/// placed by the header of their block when one is among the vectors. Block
+/// proof files count towards the header proof they are built for.
+pub fn scan(network: &Network, dir: &Path) -> Result<Coverage, VectorError> {
+    let mut decoded = Vec::new();
+    let mut proofs = Vec::new();
+    let mut numbers: HashMap<[u8; 32], u64> = HashMap::new();
+    for file in discover(dir)? {
+        if file.kind.is_none() || file.kind == Some(VectorKind::Invalid) {
+            continue;
+        }
+        let fallback = file_block_number(&file);
+        let proof_row = match file.kind {
+            Some(VectorKind::BlockProofBellatrix) => Some(ContentType::HeaderHistoricalRoots),
+            Some(VectorKind::BlockProofCapella) => Some(ContentType::HeaderHistoricalSummaries),
+            _ => None,
+        };
+        if let Some(row) = proof_row {
+            let fork = fallback.map(|number| network.execution_fork(number));
+            proofs.push((file.relative.display().to_string(), row, fork));
+            continue;
+        }
+        for entry in file.content_entries()? {
+            let Ok(key) = ContentKey::decode(&entry.content_key) else {
+                continue;
+            };
+            let Some(Ok(value)) = entry
+                .offer_value
+                .as_ref()
+                .or(entry.content_value.as_ref())
+                .map(|data| ContentValue::decode(network, &key, data))
+            else {
+                continue;
+            };
+            if let ContentValue::HeaderWithProof(value) = &value {
+                numbers.insert(value.header.hash(), value.header.number);
+            }
+            let name = if entry.label.is_empty() {
+                file.relative.display().to_string()
+            } else {
+                format!("{}#{}", file.relative.display(), entry.label)
+            };
+            decoded.push((name, key, value, fallback));
+        }
+    }
+
+    let mut coverage = Coverage {
+        network: network.name.clone(),
+        ..Coverage::default()
+    };
+    for (name, row, fork) in proofs {
+        coverage.cells.entry((row, fork)).or_default().push(name);
+    }
+    for (name, key, value, fallback) in decoded {
+        let block_fork = |hash: &[u8; 32]| {
+            numbers
+                .get(hash)
+                .copied()
+                .or(fallback)
+                .map(|number| network.execution_fork(number))
+        };
+        let cells: Vec<(ContentType, Option<ExecutionFork>)> = match (&key, &value) {
+            (_, ContentValue::HeaderWithProof(value)) => {
+                let row = match value.proof {
+                    BlockHeaderProof::None => ContentType::HeaderWithoutProof,
+                    BlockHeaderProof::HistoricalHashesAccumulator(_) => {
+                        ContentType::HeaderAccumulator
+                    }
+                    BlockHeaderProof::HistoricalRoots(_) => ContentType::HeaderHistoricalRoots,
+                    BlockHeaderProof::HistoricalSummaries(_)
+                    | BlockHeaderProof::HistoricalSummariesDeneb(_) => {
+                        ContentType::HeaderHistoricalSummaries
+                    }
+                };
+                vec![(row, Some(network.execution_fork(value.header.number)))]
+            }
+            (ContentKey::History(HistoryContentKey::BlockBody(hash)), _) => {
+                vec![(ContentType::BlockBody, block_fork(hash))]
+            }
+            (ContentKey::History(HistoryContentKey::Receipts(hash)), _) => {
+                vec![(ContentType::Receipts, block_fork(hash))]
+            }
+            (ContentKey::State(key), ContentValue::State(state)) => {
+                let row = match key {
+                    StateContentKey::AccountTrieNode { .. } => ContentType::AccountTrieNode,
+                    StateContentKey::ContractStorageTrieNode { .. } => {
+                        ContentType::ContractStorageTrieNode
+                    }
+                    StateContentKey::ContractBytecode { .. } => ContentType::ContractBytecode,
+                };
+                let fork = match state {
+                    StateValue::AccountTrieNodeWithProof { block_hash, .. }
+                    | StateValue::ContractStorageTrieNodeWithProof { block_hash, .. }
+                    | StateValue::ContractBytecodeWithProof { block_hash, .. } => {
+                        block_fork(block_hash)
+                    }
+                    _ => fallback.map(|number| network.execution_fork(number)),
+                };
+                vec![(row, fork)]
+            }
+            (_, ContentValue::Beacon(beacon)) => {
+                let forked = match beacon {
+                    BeaconValue::Single(forked) => vec![forked.as_ref()],
+                    BeaconValue::Updates(updates) => updates.iter().collect(),
+                };
+                forked
+                    .into_iter()
+                    .map(|forked| {
+                        let row = match forked.payload {
+                            BeaconPayload::Bootstrap(_) => ContentType::Bootstrap,
+                            BeaconPayload::Update(_) => ContentType::Updates,
+                            BeaconPayload::FinalityUpdate(_) => ContentType::FinalityUpdate,
+                            BeaconPayload::OptimisticUpdate(_) => ContentType::OptimisticUpdate,
+                            BeaconPayload::HistoricalSummaries(_) => {
+                                ContentType::HistoricalSummaries
+                            }
+                        };
+                        (row, Some(forked.fork.execution_fork()))
+                    })
+                    .collect()
+            }
+            _ => Vec::new(),
+        };
+        for cell in cells {
+            let vectors = coverage.cells.entry(cell).or_default();
+            if !vectors.contains(&name) {
+                vectors.push(name.clone());
+            }
+        }
+    }
+    Ok(coverage)
+}
+
+impl Coverage {
+    pub fn vectors(&self, row: ContentType, fork: Option<ExecutionFork>) -> &[String] {
+        self.cells.get(&(row, fork)).map_or(&[], Vec::as_slice)
+    }
+
+    /// Required cells without a single vector.
+    pub fn missing(
+        &self,
+        required: &[(ContentType, ExecutionFork)],
+    ) -> Vec<(ContentType, ExecutionFork)> {
+        required
+            .iter()
+            .filter(|(row, fork)| self.vectors(*row, Some(*fork)).is_empty())
+            .copied()
+            .collect()
+    }
+
+    /// Forks with vectors or requirements in order, then `None` when some vectors
+    /// could not be placed.
+    fn columns(&self, required: &[(ContentType, ExecutionFork)]) -> Vec<Option<ExecutionFork>> {
+        let mut columns: Vec<Option<ExecutionFork>> = ExecutionFork::ALL
+            .into_iter()
+            .filter(|fork| {
+                required.iter().any(|(_, required)| required == fork)
+                    || self.cells.keys().any(|(_, cell)| *cell == Some(*fork))
+            })
+            .map(Some)
+            .collect();
+        if self.cells.keys().any(|(_, fork)| fork.is_none()) {
+            columns.push(None);
+        }
+        columns
+    }
+
+    pub fn to_markdown(&self, required: &[(ContentType, ExecutionFork)]) -> String {
+        let columns = self.columns(required);
+        let column_name = |fork: &Option<ExecutionFork>| fork.map_or("unknown", |f| f.name());
+        let mut out = format!("# Vector coverage of {}\n\n", self.network);
+        out.push_str("| content |");
+        for column in &columns {
+            out.push_str(&format!(" {} |", column_name(column)));
+        }
+        out.push_str("\n|---|");
+        out.push_str(&"---|".repeat(columns.len()));
+        out.push('\n');
+        for row in ContentType::ALL {
+            out.push_str(&format!("| {} |", row));
+            for column in &columns {
+                let vectors = self.vectors(row, *column);
+                let required = column.is_some_and(|fork| required.contains(&(row, fork)));
+                let cell = match vectors {
+                    [] if required => "**missing**".to_string(),
+                    vectors => vectors.join("<br>"),
+                };
+                out.push_str(&format!(" {} |", cell));
+            }
+            out.push('\n');
+        }
+        let missing = self.missing(required);
+        out.push_str(&format!("\n{} required cell(s) missing\n", missing.len()));
+        out
+    }
+
+    pub fn to_json(&self, required: &[(ContentType, ExecutionFork)]) -> Value {
+        let columns = self.columns(required);
+        let mut rows = Map::new();
+        for row in ContentType::ALL {
+            let mut cells = Map::new();
+            for column in &columns {
+                let name = column.map_or("unknown", |fork| fork.name());
+                cells.insert(name.to_string(), json!(self.vectors(row, *column)));
+            }
+            rows.insert(row.name().to_string(), Value::Object(cells));
+        }
+        let missing: Vec<Value> = self
+            .missing(required)
+            .into_iter()
+            .map(|(row, fork)| json!({ "content": row.name(), "fork": fork.name() }))
+            .collect();
+        json!({
+            "network": self.network,
+            "forks": columns
+                .iter()
+                .map(|column| column.map_or("unknown", |fork| fork.name()))
+                .collect::<Vec<_>>(),
+            "rows": rows,
+            "missing": missing,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::convert::provenance;
+    use crate::vector::{network_dir, VectorFormat};
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_mainnet_coverage() {
+        let coverage = scan(&Network::mainnet(), &network_dir("mainnet")).unwrap();
+        let cell = |row, fork| coverage.vectors(row, Some(fork));
+        assert!(cell(ContentType::HeaderAccumulator, ExecutionFork::London)
+            .contains(&"history/headers_with_proof/15537393.yaml".to_string()));
+        assert!(cell(ContentType::BlockBody, ExecutionFork::Shanghai)
+            .contains(&"history/bodies/17139055.json#17139055".to_string()));
+        assert!(!cell(ContentType::Bootstrap, ExecutionFork::Shanghai).is_empty());
+        assert!(!cell(ContentType::AccountTrieNode, ExecutionFork::Shanghai).is_empty());
+        assert!(cell(
+            ContentType::HeaderHistoricalSummaries,
+            ExecutionFork::London
+        )
+        .is_empty());
+        assert!(cell(ContentType::HeaderHistoricalRoots, ExecutionFork::Merge).contains(
+            &"history/headers_with_proof/block_proofs_bellatrix/beacon_block_proof-15539558-cdf9ed89b0c43cda17398dc4da9cfc505e5ccd19f7c39e3b43474180f1051e01.yaml"
+                .to_string()
+        ));
+        assert!(cell(
+            ContentType::HeaderHistoricalSummaries,
+            ExecutionFork::Shanghai
+        )
+        .contains(
+            &"history/headers_with_proof/block_proofs_capella/beacon_block_proof-17034870.yaml"
+                .to_string()
+        ));
+    }
+
+    #[test]
+    fn test_mainnet_has_no_false_misses() {
+        let coverage = scan(&Network::mainnet(), &network_dir("mainnet")).unwrap();
+        let missing = coverage.missing(&required_cells());
+        for cell in [
+            (ContentType::HeaderHistoricalRoots, ExecutionFork::Merge),
+            (
+                ContentType::HeaderHistoricalSummaries,
+                ExecutionFork::Shanghai,
+            ),
+        ] {
+            assert!(!missing.contains(&cell), "{:?}", cell);
+        }
+        // What is left are forks no vector of the content type exists for.
+        use ContentType::*;
+        use ExecutionFork::*;
+        assert_eq!(
+            missing,
+            vec![
+                (HeaderHistoricalSummaries, Cancun),
+                (HeaderHistoricalSummaries, Prague),
+                (BlockBody, Prague),
+                (Receipts, Prague),
+                (Bootstrap, Cancun),
+                (Bootstrap, Prague),
+                (Updates, Cancun),
+                (Updates, Prague),
+                (FinalityUpdate, Cancun),
+                (FinalityUpdate, Prague),
+                (OptimisticUpdate, Cancun),
+                (OptimisticUpdate, Prague),
+                (HistoricalSummaries, Shanghai),
+                (HistoricalSummaries, Prague),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_columns_match_provenance_flair() {
+        let mainnet = Network::mainnet();
+        let file = |text: &str| VectorFile {
+            path: PathBuf::from("bodies.yaml"),
+            relative: PathBuf::from("history/bodies/bodies.yaml"),
+            kind: Some(VectorKind::HistoryBlockBody),
+            format: VectorFormat::Yaml,
+            text: text.to_string(),
+        };
+        for fork in &ExecutionFork::ALL[1..] {
+            let first = fork.activation_block();
+            let comments = provenance(VectorKind::HistoryBlockBody, first, None);
+            let number = file_block_number(&file(&comments.join("\n"))).unwrap();
+            assert_eq!(number, first);
+            assert_eq!(mainnet.execution_fork(number), *fork);
+            assert!(comments[2].ends_with(&format!("(post-{})", fork.name())));
+            assert_ne!(mainnet.execution_fork(first - 1), *fork);
+        }
+        assert_eq!(
+            file_block_number(&file("# Block 1,150,000\n")),
+            Some(1150000)
+        );
+    }
+
+    #[test]
+    fn test_missing_cells_are_reported() {
+        let coverage = scan(&Network::mainnet(), &network_dir("mainnet")).unwrap();
+        let required = [
+            (ContentType::BlockBody, ExecutionFork::Shanghai),
+            (ContentType::Receipts, ExecutionFork::Prague),
+        ];
+        assert_eq!(
+            coverage.missing(&required),
+            vec![(ContentType::Receipts, ExecutionFork::Prague)]
+        );
+        let markdown = coverage.to_markdown(&required);
+        assert!(markdown.contains(" **missing** |\n| account_trie_node |"));
+        assert!(markdown.ends_with("\n1 required cell(s) missing\n"));
+        let json = coverage.to_json(&required);
+        assert_eq!(json["missing"][0]["fork"], "prague");
+        assert_eq!(json["forks"][0], "frontier");
+        assert_eq!(json["rows"]["receipts"]["prague"], json!([]));
+    }
+}
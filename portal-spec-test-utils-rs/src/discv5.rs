@@ -0,0 +1,631 @@
+use crate::content_key::{ContentKey, Subnetwork};
+use crate::pcap::Datagram;
+use crate::rlp::{self, RlpError, RlpItem};
+use crate::utp;
+use crate::wire::{AcceptCode, ContentResponse, Message};
+use aes::Aes128;
+use aes_gcm::aead::{Aead, Payload};
+use aes_gcm::{Aes128Gcm, KeyInit};
+use ctr::cipher::{KeyIvInit, StreamCipher};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use thiserror::Error;
+
+pub const PROTOCOL_ID: &[u8; 6] = b"discv5";
+/// TALKREQ protocol id of uTP streams between Portal nodes.
+pub const UTP_PROTOCOL_ID: &[u8] = b"utp";
+
+const MASKING_IV_SIZE: usize = 16;
+const STATIC_HEADER_SIZE: usize = 23;
+/// Smallest packet the spec allows to be sent.
+const MIN_PACKET_SIZE: usize = 63;
+
+type Aes128Ctr = ctr::Ctr128BE<Aes128>;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum Discv5Error {
+    #[error("packet of {0} bytes is too short for discv5")]
+    TooShort(usize),
+    #[error("no node id unmasks the packet header")]
+    NotUnmasked,
+    #[error("truncated authdata")]
+    TruncatedAuthdata,
+    #[error("unknown packet flag {0}")]
+    UnknownFlag(u8),
+    #[error("no session key decrypts the message")]
+    NotDecrypted,
+    #[error("empty message")]
+    EmptyMessage,
+    #[error("invalid rlp: {0}")]
+    Rlp(#[from] RlpError),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Authdata {
+    Message {
+        src_id: [u8; 32],
+    },
+    WhoAreYou {
+        id_nonce: [u8; 16],
+        enr_seq: u64,
+    },
+    Handshake {
+        src_id: [u8; 32],
+        id_signature: Vec<u8>,
+        ephemeral_key: Vec<u8>,
+        record: Option<Vec<u8>>,
+    },
+}
+
+/// A discv5 packet with its header unmasked and its message still encrypted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Packet {
+    pub dest_id: [u8; 32],
+    pub version: u16,
+    pub nonce: [u8; 12],
+    pub authdata: Authdata,
+    /// Masking IV and unmasked header, the additional data of the message cipher.
+    pub header_data: Vec<u8>,
+    pub message: Vec<u8>,
+}
+
+impl Packet {
+    /// Unmasks the header with the first of `node_ids` it was masked for.
+    pub fn unmask(data: &[u8], node_ids: &[[u8; 32]]) -> Result<Packet, Discv5Error> {
+        if data.len() < MIN_PACKET_SIZE {
+            return Err(Discv5Error::TooShort(data.len()));
+        }
+        let (masking_iv, masked) = data.split_at(MASKING_IV_SIZE);
+        for dest_id in node_ids {
+            let mut cipher = Aes128Ctr::new(dest_id[..16].into(), masking_iv.into());
+            let mut header = masked[..STATIC_HEADER_SIZE].to_vec();
+            cipher.apply_keystream(&mut header);
+            if &header[..6] != PROTOCOL_ID {
+                continue;
+            }
+            let authdata_size = u16::from_be_bytes([header[21], header[22]]) as usize;
+            let mut authdata = masked
+                .get(STATIC_HEADER_SIZE..STATIC_HEADER_SIZE + authdata_size)
+                .ok_or(Discv5Error::TruncatedAuthdata)?
+                .to_vec();
+            cipher.apply_keystream(&mut authdata);
+            let flag = header[8];
+            return Ok(Packet {
+                dest_id: *dest_id,
+                version: u16::from_be_bytes([header[6], header[7]]),
+                nonce: header[9..21].try_into().unwrap(),
+                authdata: Authdata::decode(flag, &authdata)?,
+                header_data: [masking_iv, &header, &authdata].concat(),
+                message: masked[STATIC_HEADER_SIZE + authdata_size..].to_vec(),
+            });
+        }
+        Err(Discv5Error::NotUnmasked)
+    }
+
+    /// Decrypts the message with the first of `keys` that authenticates it.
+    pub fn decrypt(&self, keys: &[[u8; 16]]) -> Result<Discv5Message, Discv5Error> {
+        let plaintext = keys
+            .iter()
+            .find_map(|key| {
+                Aes128Gcm::new(key.into())
+                    .decrypt(
+                        (&self.nonce).into(),
+                        Payload {
+                            msg: &self.message,
+                            aad: &self.header_data,
+                        },
+                    )
+                    .ok()
+            })
+            .ok_or(Discv5Error::NotDecrypted)?;
+        Discv5Message::decode(&plaintext)
+    }
+}
+
+impl Authdata {
+    fn decode(flag: u8, data: &[u8]) -> Result<Authdata, Discv5Error> {
+        let truncated = || Discv5Error::TruncatedAuthdata;
+        let src_id = || -> Result<[u8; 32], Discv5Error> {
+            Ok(data.get(..32).ok_or_else(truncated)?.try_into().unwrap())
+        };
+        Ok(match flag {
+            0 => Authdata::Message { src_id: src_id()? },
+            1 => Authdata::WhoAreYou {
+                id_nonce: data.get(..16).ok_or_else(truncated)?.try_into().unwrap(),
+                enr_seq: u64::from_be_bytes(
+                    data.get(16..24).ok_or_else(truncated)?.try_into().unwrap(),
+                ),
+            },
+            2 => {
+                let sizes = data.get(32..34).ok_or_else(truncated)?;
+                let signature_end = 34 + sizes[0] as usize;
+                let key_end = signature_end + sizes[1] as usize;
+                let record = data.get(key_end..).ok_or_else(truncated)?;
+                Authdata::Handshake {
+                    src_id: src_id()?,
+                    id_signature: data[34..signature_end].to_vec(),
+                    ephemeral_key: data[signature_end..key_end].to_vec(),
+                    record: (!record.is_empty()).then(|| record.to_vec()),
+                }
+            }
+            other => return Err(Discv5Error::UnknownFlag(other)),
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Discv5Message {
+    Ping {
+        req_id: Vec<u8>,
+        enr_seq: u64,
+    },
+    Pong {
+        req_id: Vec<u8>,
+        enr_seq: u64,
+        ip: Vec<u8>,
+        port: u16,
+    },
+    FindNode {
+        req_id: Vec<u8>,
+        distances: Vec<u64>,
+    },
+    Nodes {
+        req_id: Vec<u8>,
+        total: u64,
+        enrs: Vec<Vec<u8>>,
+    },
+    TalkReq {
+        req_id: Vec<u8>,
+        protocol: Vec<u8>,
+        request: Vec<u8>,
+    },
+    TalkResp {
+        req_id: Vec<u8>,
+        response: Vec<u8>,
+    },
+    Other {
+        message_type: u8,
+        data: Vec<u8>,
+    },
+}
+
+impl Discv5Message {
+    pub fn decode(plaintext: &[u8]) -> Result<Discv5Message, Discv5Error> {
+        let (&message_type, data) = plaintext.split_first().ok_or(Discv5Error::EmptyMessage)?;
+        if !(1..=6).contains(&message_type) {
+            return Ok(Discv5Message::Other {
+                message_type,
+                data: data.to_vec(),
+            });
+        }
+        let item = rlp::decode(data)?;
+        let fields = item.as_list()?;
+        let field = |index: usize| {
+            fields.get(index).ok_or(RlpError::ItemCount {
+                expected: index + 1,
+                found: fields.len(),
+            })
+        };
+        let bytes =
+            |index: usize| -> Result<Vec<u8>, RlpError> { Ok(field(index)?.as_bytes()?.to_vec()) };
+        let req_id = bytes(0)?;
+        Ok(match message_type {
+            1 => Discv5Message::Ping {
+                req_id,
+                enr_seq: field(1)?.as_u64()?,
+            },
+            2 => Discv5Message::Pong {
+                req_id,
+                enr_seq: field(1)?.as_u64()?,
+                ip: bytes(2)?,
+                port: field(3)?.as_u64()? as u16,
+            },
+            3 => Discv5Message::FindNode {
+                req_id,
+                distances: field(1)?
+                    .as_list()?
+                    .iter()
+                    .map(RlpItem::as_u64)
+                    .collect::<Result<_, _>>()?,
+            },
+            4 => Discv5Message::Nodes {
+                req_id,
+                total: field(1)?.as_u64()?,
+                enrs: field(2)?
+                    .as_list()?
+                    .iter()
+                    .map(|enr| {
+                        let mut out = Vec::new();
+                        rlp::encode_item(&mut out, enr);
+                        out
+                    })
+                    .collect(),
+            },
+            5 => Discv5Message::TalkReq {
+                req_id,
+                protocol: bytes(1)?,
+                request: bytes(2)?,
+            },
+            _ => Discv5Message::TalkResp {
+                req_id,
+                response: bytes(1)?,
+            },
+        })
+    }
+}
+
+fn short(id: &[u8]) -> String {
+    format!("0x{}", hex::encode(&id[..id.len().min(4)]))
+}
+
+fn protocol_name(protocol: &[u8]) -> String {
+    match Subnetwork::from_protocol_id(protocol) {
+        Some(subnetwork) => subnetwork.to_string(),
+        None if protocol == UTP_PROTOCOL_ID => "utp".to_string(),
+        None => format!("0x{}", hex::encode(protocol)),
+    }
+}
+
+fn describe_key(key: &[u8]) -> String {
+    match ContentKey::decode(key) {
+        Ok(decoded) => format!("{} 0x{}", decoded.name(), hex::encode(key)),
+        Err(_) => format!("0x{}", hex::encode(key)),
+    }
+}
+
+/// One line describing a Portal wire message.
+pub fn describe_portal_message(payload: &[u8]) -> String {
+    let message = match Message::decode(payload) {
+        Ok(message) => message,
+        Err(e) => return format!("undecodable Portal message: {}", e),
+    };
+    let detail = match &message {
+        Message::Ping(ping) | Message::Pong(ping) => format!(
+            "enr_seq={} payload_type={}",
+            ping.enr_seq,
+            ping.payload.payload_type()
+        ),
+        Message::FindNodes { distances } => format!("distances={:?}", distances),
+        Message::Nodes { total, enrs } => format!("total={} enrs={}", total, enrs.len()),
+        Message::FindContent { content_key } => describe_key(content_key),
+        Message::Content(ContentResponse::ConnectionId(id)) => {
+            format!("connection_id=0x{}", hex::encode(id))
+        }
+        Message::Content(ContentResponse::Content(content)) => format!("{} bytes", content.len()),
+        Message::Content(ContentResponse::Enrs(enrs)) => format!("enrs={}", enrs.len()),
+        Message::Offer { content_keys } => {
+            let keys: Vec<String> = content_keys.iter().map(|k| describe_key(k)).collect();
+            format!("{} key(s): {}", content_keys.len(), keys.join(", "))
+        }
+        Message::Accept {
+            connection_id,
+            accept_codes,
+        } => {
+            let codes: Vec<&str> = accept_codes.iter().map(AcceptCode::name).collect();
+            format!(
+                "connection_id=0x{} [{}]",
+                hex::encode(connection_id),
+                codes.join(", ")
+            )
+        }
+    };
+    format!("{} {}", message.name(), detail)
+}
+
+fn describe_utp(payload: &[u8]) -> String {
+    match utp::Packet::decode(payload) {
+        Ok(packet) => format!(
+            "uTP {:?} connection_id={} seq_nr={} ack_nr={} payload={} bytes",
+            packet.packet_type,
+            packet.connection_id,
+            packet.seq_nr,
+            packet.ack_nr,
+            packet.payload.len()
+        ),
+        Err(e) => format!("undecodable uTP packet: {}", e),
+    }
+}
+
+/// Turns the datagrams of a capture into one line each, remembering TALKREQ
+/// protocols so the TALKRESP that answers them can be decoded too.
+#[derive(Debug, Clone, Default)]
+pub struct Dissector {
+    pub node_ids: Vec<[u8; 32]>,
+    pub session_keys: Vec<[u8; 16]>,
+    talk_requests: HashMap<(SocketAddr, Vec<u8>), Vec<u8>>,
+}
+
+impl Dissector {
+    pub fn new(node_ids: Vec<[u8; 32]>, session_keys: Vec<[u8; 16]>) -> Dissector {
+        Dissector {
+            node_ids,
+            session_keys,
+            talk_requests: HashMap::new(),
+        }
+    }
+
+    pub fn dissect(&mut self, datagram: &Datagram) -> String {
+        format!(
+            "{}.{:06} {} -> {} {}",
+            datagram.timestamp.as_secs(),
+            datagram.timestamp.subsec_micros(),
+            datagram.source,
+            datagram.destination,
+            self.describe(datagram)
+        )
+    }
+
+    fn describe(&mut self, datagram: &Datagram) -> String {
+        let packet = match Packet::unmask(&datagram.payload, &self.node_ids) {
+            Ok(packet) => packet,
+            Err(e) => return format!("udp {} bytes: {}", datagram.payload.len(), e),
+        };
+        let (kind, src_id) = match &packet.authdata {
+            Authdata::WhoAreYou { id_nonce, enr_seq } => {
+                return format!(
+                    "WHOAREYOU to {} id_nonce=0x{} enr_seq={}",
+                    short(&packet.dest_id),
+                    hex::encode(id_nonce),
+                    enr_seq
+                )
+            }
+            Authdata::Message { src_id } => ("message", src_id),
+            Authdata::Handshake { src_id, record, .. } => (
+                if record.is_some() {
+                    "handshake with record"
+                } else {
+                    "handshake"
+                },
+                src_id,
+            ),
+        };
+        let message = match packet.decrypt(&self.session_keys) {
+            Ok(message) => self.describe_message(datagram, message),
+            Err(Discv5Error::NotDecrypted) => {
+                format!("{} encrypted bytes", packet.message.len())
+            }
+            Err(e) => e.to_string(),
+        };
+        format!(
+            "{} {} -> {}: {}",
+            kind,
+            short(src_id),
+            short(&packet.dest_id),
+            message
+        )
+    }
+
+    fn describe_message(&mut self, datagram: &Datagram, message: Discv5Message) -> String {
+        match message {
+            Discv5Message::Ping { req_id, enr_seq } => {
+                format!("PING req={} enr_seq={}", short(&req_id), enr_seq)
+            }
+            Discv5Message::Pong {
+                req_id,
+                enr_seq,
+                ip,
+                port,
+            } => format!(
+                "PONG req={} enr_seq={} recipient=0x{}:{}",
+                short(&req_id),
+                enr_seq,
+                hex::encode(ip),
+                port
+            ),
+            Discv5Message::FindNode { req_id, distances } => {
+                format!("FINDNODE req={} distances={:?}", short(&req_id), distances)
+            }
+            Discv5Message::Nodes {
+                req_id,
+                total,
+                enrs,
+            } => format!(
+                "NODES req={} total={} enrs={}",
+                short(&req_id),
+                total,
+                enrs.len()
+            ),
+            Discv5Message::TalkReq {
+                req_id,
+                protocol,
+                request,
+            } => {
+                let detail = self.describe_talk(&protocol, &request);
+                let line = format!(
+                    "TALKREQ req={} {}: {}",
+                    short(&req_id),
+                    protocol_name(&protocol),
+                    detail
+                );
+                self.talk_requests
+                    .insert((datagram.source, req_id), protocol);
+                line
+            }
+            Discv5Message::TalkResp { req_id, response } => {
+                match self
+                    .talk_requests
+                    .remove(&(datagram.destination, req_id.clone()))
+                {
+                    Some(protocol) => format!(
+                        "TALKRESP req={} {}: {}",
+                        short(&req_id),
+                        protocol_name(&protocol),
+                        self.describe_talk(&protocol, &response)
+                    ),
+                    None => format!("TALKRESP req={} {} bytes", short(&req_id), response.len()),
+                }
+            }
+            Discv5Message::Other { message_type, data } => {
+                format!("message type {} {} bytes", message_type, data.len())
+            }
+        }
+    }
+
+    fn describe_talk(&self, protocol: &[u8], payload: &[u8]) -> String {
+        if Subnetwork::from_protocol_id(protocol).is_some() {
+            describe_portal_message(payload)
+        } else if protocol == UTP_PROTOCOL_ID {
+            describe_utp(payload)
+        } else if payload.is_empty() {
+            "empty".to_string()
+        } else {
+            format!("{} bytes", payload.len())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pcap;
+    use std::time::Duration;
+
+    const SRC_ID: &str = "aaaa8419e9f49d0083561b48287df592939a8d19947d8c0ef88f2a4856a69fbb";
+    const DEST_ID: &str = "bbbb9d047f0488c0b5a93c1c3f2d8bafc7c8ff337024a55434a0d0555de64db9";
+
+    fn id(hex_str: &str) -> [u8; 32] {
+        hex::decode(hex_str).unwrap().try_into().unwrap()
+    }
+
+    /// Masks and encrypts an ordinary message packet, as a client would send it.
+    fn message_packet(
+        src_id: [u8; 32],
+        dest_id: [u8; 32],
+        key: [u8; 16],
+        nonce: [u8; 12],
+        plaintext: &[u8],
+    ) -> Vec<u8> {
+        let masking_iv = [0x11u8; 16];
+        let mut header = PROTOCOL_ID.to_vec();
+        header.extend_from_slice(&[0x00, 0x01, 0x00]);
+        header.extend_from_slice(&nonce);
+        header.extend_from_slice(&32u16.to_be_bytes());
+        header.extend_from_slice(&src_id);
+        let aad = [&masking_iv[..], &header].concat();
+        let message = Aes128Gcm::new((&key).into())
+            .encrypt(
+                (&nonce).into(),
+                Payload {
+                    msg: plaintext,
+                    aad: &aad,
+                },
+            )
+            .unwrap();
+        Aes128Ctr::new(dest_id[..16].into(), (&masking_iv).into()).apply_keystream(&mut header);
+        [&masking_iv[..], &header, &message].concat()
+    }
+
+    fn talk(message_type: u8, fields: &[&[u8]]) -> Vec<u8> {
+        let mut payload = Vec::new();
+        for field in fields {
+            rlp::encode_bytes(&mut payload, field);
+        }
+        let mut out = vec![message_type];
+        rlp::encode_list(&mut out, &payload);
+        out
+    }
+
+    #[test]
+    fn test_spec_vectors() {
+        let ping = hex::decode(concat!(
+            "00000000000000000000000000000000088b3d4342774649325f313964a39e55",
+            "ea96c005ad52be8c7560413a7008f16c9e6d2f43bbea8814a546b7409ce783d3",
+            "4c4f53245d08dab84102ed931f66d1492acb308fa1c6715b9d139b81acbdcc"
+        ))
+        .unwrap();
+        let packet = Packet::unmask(&ping, &[id(SRC_ID), id(DEST_ID)]).unwrap();
+        assert_eq!(packet.dest_id, id(DEST_ID));
+        assert_eq!(packet.nonce, [0xff; 12]);
+        assert_eq!(packet.authdata, Authdata::Message { src_id: id(SRC_ID) });
+        assert_eq!(
+            packet.decrypt(&[[0x01; 16], [0x00; 16]]).unwrap(),
+            Discv5Message::Ping {
+                req_id: vec![0, 0, 0, 1],
+                enr_seq: 2
+            }
+        );
+        assert_eq!(
+            packet.decrypt(&[[0x01; 16]]),
+            Err(Discv5Error::NotDecrypted)
+        );
+        assert_eq!(
+            Packet::unmask(&ping, &[id(SRC_ID)]),
+            Err(Discv5Error::NotUnmasked)
+        );
+
+        let whoareyou = hex::decode(concat!(
+            "00000000000000000000000000000000088b3d434277464933a1ccc59f5967ad",
+            "1d6035f15e528627dde75cd68292f9e6c27d6b66c8100a873fcbaed4e16b8d"
+        ))
+        .unwrap();
+        let packet = Packet::unmask(&whoareyou, &[id(DEST_ID)]).unwrap();
+        assert_eq!(
+            packet.authdata,
+            Authdata::WhoAreYou {
+                id_nonce: hex::decode("0102030405060708090a0b0c0d0e0f10")
+                    .unwrap()
+                    .try_into()
+                    .unwrap(),
+                enr_seq: 0
+            }
+        );
+    }
+
+    #[test]
+    fn test_dissects_portal_talk_from_capture() {
+        let (a, b) = (id(SRC_ID), id(DEST_ID));
+        let (key_ab, key_ba) = ([0x0a; 16], [0x0b; 16]);
+        let find_content = Message::FindContent {
+            content_key: hex::decode(
+                "00720704f3aa11c53cf344ea069db95cecb81ad7453c8f276b2a1062979611f09c",
+            )
+            .unwrap(),
+        }
+        .encode()
+        .unwrap();
+        let content = Message::Content(ContentResponse::ConnectionId([0x12, 0x34]))
+            .encode()
+            .unwrap();
+        let request = talk(
+            5,
+            &[&[7], &Subnetwork::History.protocol_id(), &find_content],
+        );
+        let response = talk(6, &[&[7], &content]);
+        let datagram = |source: &str, destination: &str, payload: Vec<u8>| pcap::Datagram {
+            timestamp: Duration::from_secs(1_700_000_000),
+            source: source.parse().unwrap(),
+            destination: destination.parse().unwrap(),
+            payload,
+        };
+        let capture = pcap::encode(&[
+            datagram(
+                "10.0.0.1:9000",
+                "10.0.0.2:9000",
+                message_packet(a, b, key_ab, [1; 12], &request),
+            ),
+            datagram(
+                "10.0.0.2:9000",
+                "10.0.0.1:9000",
+                message_packet(b, a, key_ba, [2; 12], &response),
+            ),
+            datagram("10.0.0.3:53", "10.0.0.1:53", vec![0u8; 80]),
+        ]);
+
+        let datagrams = pcap::parse(&capture).unwrap();
+        let mut dissector = Dissector::new(vec![a, b], vec![key_ab, key_ba]);
+        let lines: Vec<String> = datagrams.iter().map(|d| dissector.dissect(d)).collect();
+        assert_eq!(
+            lines[0],
+            "1700000000.000000 10.0.0.1:9000 -> 10.0.0.2:9000 message 0xaaaa8419 -> 0xbbbb9d04: \
+             TALKREQ req=0x07 history: FINDCONTENT block_header_by_hash \
+             0x00720704f3aa11c53cf344ea069db95cecb81ad7453c8f276b2a1062979611f09c"
+        );
+        assert!(lines[1].ends_with("TALKRESP req=0x07 history: CONTENT connection_id=0x1234"));
+        assert!(lines[2].ends_with("udp 80 bytes: no node id unmasks the packet header"));
+
+        let mut without_keys = Dissector::new(vec![a, b], Vec::new());
+        assert!(without_keys
+            .dissect(&datagrams[0])
+            .ends_with("encrypted bytes"));
+    }
+}
@@ -0,0 +1,220 @@
+use crate::content_key::content_id;
+use crate::enr::{deterministic_key, node_id};
+use crate::vector::{discover, VectorError, VectorKind};
+use ethnum::U256;
+use std::fmt;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+/// XOR distance between two ids, read as a big endian integer.
+pub fn distance(a: &[u8; 32], b: &[u8; 32]) -> U256 {
+    U256::from_be_bytes(*a) ^ U256::from_be_bytes(*b)
+}
+
+/// Bit length of the distance, from 1 to 256, or `None` for equal ids.
+pub fn log2_distance(a: &[u8; 32], b: &[u8; 32]) -> Option<u16> {
+    let distance = distance(a, b);
+    (distance != U256::ZERO).then(|| 256 - distance.leading_zeros() as u16)
+}
+
+/// Index of the k-bucket that `other` falls in from `local`'s point of view.
+pub fn bucket_index(local: &[u8; 32], other: &[u8; 32]) -> Option<usize> {
+    log2_distance(local, other).map(|log2| log2 as usize - 1)
+}
+
+/// The distance up to which a node stores content.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct DataRadius(pub U256);
+
+impl DataRadius {
+    pub const MAX: DataRadius = DataRadius(U256::MAX);
+    pub const ZERO: DataRadius = DataRadius(U256::ZERO);
+
+    /// Radius covering every distance below `2^bits`.
+    pub fn from_log2(bits: u32) -> DataRadius {
+        if bits >= 256 {
+            return DataRadius::MAX;
+        }
+        DataRadius((U256::ONE << bits) - 1)
+    }
+
+    pub fn contains(&self, node_id: &[u8; 32], content_id: &[u8; 32]) -> bool {
+        distance(node_id, content_id) <= self.0
+    }
+
+    /// Share of the key space inside the radius.
+    pub fn fraction(&self) -> f64 {
+        self.0.as_f64() / U256::MAX.as_f64()
+    }
+}
+
+impl fmt::Display for DataRadius {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "0x{}", hex::encode(self.0.to_be_bytes()))
+    }
+}
+
+pub fn is_within_radius(node_id: &[u8; 32], radius: DataRadius, content_id: &[u8; 32]) -> bool {
+    radius.contains(node_id, content_id)
+}
+
+/// The node id of the `index`th test node, the same on every run and for
+/// every command that builds a node set.
+pub fn deterministic_node_id(index: usize) -> [u8; 32] {
+    node_id(deterministic_key(format!("node-{}", index).as_bytes()).verifying_key())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StorageNode {
+    pub node_id: [u8; 32],
+    pub radius: DataRadius,
+}
+
+/// The nodes responsible for one content entry of a vector file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Assignment {
+    pub file: PathBuf,
+    pub label: String,
+    pub content_key: Vec<u8>,
+    pub content_id: [u8; 32],
+    /// Indices into the node set, closest first.
+    pub stored_by: Vec<usize>,
+}
+
+/// Indices of the nodes whose radius covers the content id, closest first.
+pub fn nodes_storing(content_id: &[u8; 32], nodes: &[StorageNode]) -> Vec<usize> {
+    let mut indices: Vec<usize> = (0..nodes.len())
+        .filter(|i| nodes[*i].radius.contains(&nodes[*i].node_id, content_id))
+        .collect();
+    indices.sort_by_key(|i| distance(&nodes[*i].node_id, content_id));
+    indices
+}
+
+/// Works out which nodes store each content entry of the vectors below `root`.
+pub fn assign_storage(root: &Path, nodes: &[StorageNode]) -> Result<Vec<Assignment>, VectorError> {
+    let mut assignments = Vec::new();
+    for file in discover(root)? {
+        if file.kind.is_none() || file.kind == Some(VectorKind::Invalid) {
+            continue;
+        }
+        for entry in file.content_entries()? {
+            let content_id = content_id(&entry.content_key);
+            assignments.push(Assignment {
+                file: file.relative.clone(),
+                label: entry.label,
+                content_key: entry.content_key,
+                content_id,
+                stored_by: nodes_storing(&content_id, nodes),
+            });
+        }
+    }
+    Ok(assignments)
+}
+
+/// Renders nodes and assignments as a YAML fixture.
+pub fn render_assignments(nodes: &[StorageNode], assignments: &[Assignment]) -> String {
+    let mut out = String::new();
+    out.push_str(
+        "# Which node stores each content entry, by XOR distance between node and content id.\n",
+    );
+    out.push_str("# Generated with `portal-vector assign`.\n");
+    out.push_str("nodes:\n");
+    for node in nodes {
+        let _ = writeln!(out, "  - node_id: \"0x{}\"", hex::encode(node.node_id));
+        let _ = writeln!(out, "    radius: \"{}\"", node.radius);
+    }
+    out.push_str("content:\n");
+    for assignment in assignments {
+        let _ = writeln!(out, "  - file: \"{}\"", assignment.file.display());
+        if !assignment.label.is_empty() {
+            let _ = writeln!(out, "    label: \"{}\"", assignment.label);
+        }
+        let _ = writeln!(
+            out,
+            "    content_key: \"0x{}\"",
+            hex::encode(&assignment.content_key)
+        );
+        let _ = writeln!(
+            out,
+            "    content_id: \"0x{}\"",
+            hex::encode(assignment.content_id)
+        );
+        let stored_by: Vec<String> = assignment.stored_by.iter().map(|i| i.to_string()).collect();
+        let _ = writeln!(out, "    stored_by: [{}]", stored_by.join(", "));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vector::network_dir;
+
+    fn id(first: u8, last: u8) -> [u8; 32] {
+        let mut id = [0u8; 32];
+        id[0] = first;
+        id[31] = last;
+        id
+    }
+
+    #[test]
+    fn test_distance_and_buckets() {
+        let a = id(0x00, 0x01);
+        assert_eq!(distance(&a, &a), U256::ZERO);
+        assert_eq!(log2_distance(&a, &a), None);
+        assert_eq!(distance(&a, &id(0x00, 0x03)), U256::new(2));
+        assert_eq!(log2_distance(&a, &id(0x00, 0x03)), Some(2));
+        assert_eq!(log2_distance(&a, &id(0x80, 0x01)), Some(256));
+        assert_eq!(bucket_index(&a, &id(0x80, 0x01)), Some(255));
+        assert_eq!(bucket_index(&a, &id(0x00, 0x00)), Some(0));
+        assert_eq!(
+            distance(&id(0x12, 0), &id(0x34, 0)),
+            distance(&id(0x34, 0), &id(0x12, 0))
+        );
+    }
+
+    #[test]
+    fn test_deterministic_node_ids() {
+        assert_eq!(deterministic_node_id(3), deterministic_node_id(3));
+        assert_ne!(deterministic_node_id(3), deterministic_node_id(4));
+    }
+
+    #[test]
+    fn test_radius() {
+        let node = id(0x00, 0x00);
+        assert!(DataRadius::MAX.contains(&node, &[0xff; 32]));
+        assert!(DataRadius::ZERO.contains(&node, &node));
+        assert!(!DataRadius::ZERO.contains(&node, &id(0, 1)));
+        assert!(DataRadius::from_log2(2).contains(&node, &id(0, 3)));
+        assert!(!DataRadius::from_log2(2).contains(&node, &id(0, 4)));
+        assert_eq!(DataRadius::from_log2(256), DataRadius::MAX);
+        assert!((DataRadius::from_log2(255).fraction() - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_assign_mainnet_vectors() {
+        let nodes = [
+            StorageNode {
+                node_id: [0u8; 32],
+                radius: DataRadius::MAX,
+            },
+            StorageNode {
+                node_id: [0xff; 32],
+                radius: DataRadius::from_log2(255),
+            },
+            StorageNode {
+                node_id: [0x55; 32],
+                radius: DataRadius::ZERO,
+            },
+        ];
+        let assignments = assign_storage(&network_dir("mainnet"), &nodes).unwrap();
+        assert!(assignments.len() > 50);
+        for assignment in &assignments {
+            let high_bit = assignment.content_id[0] & 0x80 != 0;
+            let expected = if high_bit { vec![1, 0] } else { vec![0] };
+            assert_eq!(assignment.stored_by, expected, "{}", assignment.label);
+        }
+        let rendered = render_assignments(&nodes, &assignments[..1]);
+        assert!(rendered.contains("stored_by: ["));
+    }
+}
@@ -0,0 +1,145 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// Type, length and reserved fields preceding every entry's data.
+pub const HEADER_SIZE: usize = 8;
+/// Entry type of the RLP headers in `history/headers/*.e2s`.
+pub const RLP_HEADER: u16 = 0x00ff;
+pub const VERSION: u16 = 0x3265;
+
+#[derive(Error, Debug)]
+pub enum E2StoreError {
+    #[error("failed to read {path}: {source}")]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("entry at offset {offset} needs {needed} bytes, {available} left")]
+    Truncated {
+        offset: usize,
+        needed: u64,
+        available: usize,
+    },
+    #[error("entry at offset {offset} has reserved bytes 0x{reserved:04x}")]
+    Reserved { offset: usize, reserved: u16 },
+}
+
+/// One type-length-value record of an e2store file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub entry_type: u16,
+    pub data: Vec<u8>,
+}
+
+impl Entry {
+    pub fn encode(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.entry_type.to_le_bytes());
+        out.extend_from_slice(&(self.data.len() as u32).to_le_bytes());
+        out.extend_from_slice(&[0, 0]);
+        out.extend_from_slice(&self.data);
+    }
+}
+
+pub fn decode(data: &[u8]) -> Result<Vec<Entry>, E2StoreError> {
+    let mut entries = Vec::new();
+    let mut offset = 0;
+    while offset < data.len() {
+        let rest = &data[offset..];
+        if rest.len() < HEADER_SIZE {
+            return Err(E2StoreError::Truncated {
+                offset,
+                needed: HEADER_SIZE as u64,
+                available: rest.len(),
+            });
+        }
+        let entry_type = u16::from_le_bytes([rest[0], rest[1]]);
+        let length = u32::from_le_bytes([rest[2], rest[3], rest[4], rest[5]]);
+        let reserved = u16::from_le_bytes([rest[6], rest[7]]);
+        if reserved != 0 {
+            return Err(E2StoreError::Reserved { offset, reserved });
+        }
+        // Checked before copying so a forged length cannot trigger a large allocation.
+        let body = &rest[HEADER_SIZE..];
+        if (body.len() as u64) < u64::from(length) {
+            return Err(E2StoreError::Truncated {
+                offset,
+                needed: HEADER_SIZE as u64 + u64::from(length),
+                available: rest.len(),
+            });
+        }
+        entries.push(Entry {
+            entry_type,
+            data: body[..length as usize].to_vec(),
+        });
+        offset += HEADER_SIZE + length as usize;
+    }
+    Ok(entries)
+}
+
+pub fn encode(entries: &[Entry]) -> Vec<u8> {
+    let mut out = Vec::new();
+    for entry in entries {
+        entry.encode(&mut out);
+    }
+    out
+}
+
+pub fn load(path: &Path) -> Result<Vec<Entry>, E2StoreError> {
+    let data = fs::read(path).map_err(|source| E2StoreError::Io {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    decode(&data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::content::HeaderWithProof;
+    use crate::header::Header;
+    use crate::vector::{network_dir, VectorFile};
+
+    #[test]
+    fn test_headers_match_vectors() {
+        let root = network_dir("mainnet");
+        let path = root.join("history/headers/1000001-1000010.e2s");
+        let entries = load(&path).unwrap();
+        assert_eq!(entries.len(), 10);
+        assert_eq!(encode(&entries), fs::read(&path).unwrap());
+
+        let collection = VectorFile::load(
+            &root,
+            &root.join("history/headers_with_proof/1000001-1000010.json"),
+        )
+        .unwrap();
+        for (entry, vector) in entries.iter().zip(collection.content_entries().unwrap()) {
+            assert_eq!(entry.entry_type, RLP_HEADER);
+            let header = Header::decode(&entry.data).unwrap();
+            let expected = HeaderWithProof::decode(&vector.content_value.unwrap()).unwrap();
+            assert_eq!(header, expected.header);
+        }
+    }
+
+    #[test]
+    fn test_rejects_forged_lengths() {
+        let mut data = encode(&[Entry {
+            entry_type: VERSION,
+            data: Vec::new(),
+        }]);
+        data.extend_from_slice(&[0xff, 0x00, 0xff, 0xff, 0xff, 0xff, 0x00, 0x00, 0x01]);
+        assert!(matches!(
+            decode(&data),
+            Err(E2StoreError::Truncated {
+                offset: 8,
+                needed: 0x1_0000_0007,
+                available: 9
+            })
+        ));
+        data[14] = 1;
+        assert!(matches!(
+            decode(&data),
+            Err(E2StoreError::Reserved { offset: 8, .. })
+        ));
+    }
+}
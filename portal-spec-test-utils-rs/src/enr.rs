@@ -0,0 +1,392 @@
+use crate::header::keccak256;
+use crate::rlp::{self, RlpError};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use k256::ecdsa::signature::hazmat::{PrehashSigner, PrehashVerifier};
+use k256::ecdsa::{Signature, SigningKey, VerifyingKey};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::net::Ipv4Addr;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// Maximum size of an encoded record, from EIP-778.
+pub const MAX_ENR_SIZE: usize = 300;
+pub const ENR_PREFIX: &str = "enr:";
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum EnrError {
+    #[error("record text does not start with \"enr:\"")]
+    MissingPrefix,
+    #[error("invalid base64: {0}")]
+    Base64(String),
+    #[error("invalid rlp: {0}")]
+    Rlp(#[from] RlpError),
+    #[error("record of {0} bytes exceeds 300")]
+    TooLarge(usize),
+    #[error("record has {0} items, expected a signature, a sequence number and key/value pairs")]
+    InvalidItemCount(usize),
+    #[error("keys are not sorted and unique at {0:?}")]
+    UnsortedKeys(String),
+    #[error("unsupported identity scheme {0:?}")]
+    UnsupportedScheme(String),
+    #[error("invalid secp256k1 public key")]
+    InvalidPublicKey,
+    #[error("invalid signature")]
+    InvalidSignature,
+}
+
+/// A node record. Values are kept RLP encoded so unknown keys survive a round trip.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Enr {
+    pub seq: u64,
+    pub signature: Vec<u8>,
+    pub pairs: BTreeMap<Vec<u8>, Vec<u8>>,
+}
+
+impl Enr {
+    /// Decodes the RLP form and checks the v4 signature.
+    pub fn decode(data: &[u8]) -> Result<Enr, EnrError> {
+        if data.len() > MAX_ENR_SIZE {
+            return Err(EnrError::TooLarge(data.len()));
+        }
+        let item = rlp::decode(data)?;
+        let items = item.as_list()?;
+        if items.len() < 2 || items.len() % 2 != 0 {
+            return Err(EnrError::InvalidItemCount(items.len()));
+        }
+        let mut pairs = BTreeMap::new();
+        let mut previous: Option<&[u8]> = None;
+        for pair in items[2..].chunks(2) {
+            let key = pair[0].as_bytes()?;
+            if previous.is_some_and(|previous| previous >= key) {
+                return Err(EnrError::UnsortedKeys(
+                    String::from_utf8_lossy(key).into_owned(),
+                ));
+            }
+            previous = Some(key);
+            let mut value = Vec::new();
+            rlp::encode_item(&mut value, &pair[1]);
+            pairs.insert(key.to_vec(), value);
+        }
+        let enr = Enr {
+            seq: items[1].as_u64()?,
+            signature: items[0].as_bytes()?.to_vec(),
+            pairs,
+        };
+        enr.verify()?;
+        Ok(enr)
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut payload = Vec::new();
+        rlp::encode_bytes(&mut payload, &self.signature);
+        payload.extend(self.content_payload());
+        let mut out = Vec::new();
+        rlp::encode_list(&mut out, &payload);
+        out
+    }
+
+    fn content_payload(&self) -> Vec<u8> {
+        let mut payload = Vec::new();
+        rlp::encode_u64(&mut payload, self.seq);
+        for (key, value) in &self.pairs {
+            rlp::encode_bytes(&mut payload, key);
+            payload.extend_from_slice(value);
+        }
+        payload
+    }
+
+    /// Hash of `[seq, k, v, ...]` that the v4 scheme signs.
+    pub fn signing_hash(&self) -> [u8; 32] {
+        let mut content = Vec::new();
+        rlp::encode_list(&mut content, &self.content_payload());
+        keccak256(&content)
+    }
+
+    pub fn verify(&self) -> Result<(), EnrError> {
+        match self.id().as_deref() {
+            Some("v4") => {}
+            other => return Err(EnrError::UnsupportedScheme(other.unwrap_or("").to_string())),
+        }
+        let key = self.verifying_key()?;
+        let signature =
+            Signature::from_slice(&self.signature).map_err(|_| EnrError::InvalidSignature)?;
+        key.verify_prehash(&self.signing_hash(), &signature)
+            .map_err(|_| EnrError::InvalidSignature)
+    }
+
+    fn get(&self, key: &str) -> Option<rlp::RlpItem<'_>> {
+        self.pairs
+            .get(key.as_bytes())
+            .and_then(|value| rlp::decode(value).ok())
+    }
+
+    fn get_bytes(&self, key: &str) -> Option<Vec<u8>> {
+        self.get(key)?.as_bytes().ok().map(<[u8]>::to_vec)
+    }
+
+    fn get_port(&self, key: &str) -> Option<u16> {
+        self.get(key)?
+            .as_u64()
+            .ok()
+            .and_then(|port| u16::try_from(port).ok())
+    }
+
+    pub fn id(&self) -> Option<String> {
+        self.get_bytes("id")
+            .and_then(|id| String::from_utf8(id).ok())
+    }
+
+    /// The compressed secp256k1 public key.
+    pub fn public_key(&self) -> Option<[u8; 33]> {
+        self.get_bytes("secp256k1")?.try_into().ok()
+    }
+
+    fn verifying_key(&self) -> Result<VerifyingKey, EnrError> {
+        let public_key = self.public_key().ok_or(EnrError::InvalidPublicKey)?;
+        VerifyingKey::from_sec1_bytes(&public_key).map_err(|_| EnrError::InvalidPublicKey)
+    }
+
+    /// Keccak of the uncompressed public key without its prefix byte.
+    pub fn node_id(&self) -> Option<[u8; 32]> {
+        let key = self.verifying_key().ok()?;
+        Some(node_id(&key))
+    }
+
+    pub fn ip4(&self) -> Option<Ipv4Addr> {
+        let octets: [u8; 4] = self.get_bytes("ip")?.try_into().ok()?;
+        Some(Ipv4Addr::from(octets))
+    }
+
+    pub fn udp(&self) -> Option<u16> {
+        self.get_port("udp")
+    }
+
+    pub fn tcp(&self) -> Option<u16> {
+        self.get_port("tcp")
+    }
+
+    /// The Portal client string under `c`, such as "t 0.1.1".
+    pub fn client(&self) -> Option<String> {
+        self.get_bytes("c")
+            .and_then(|client| String::from_utf8(client).ok())
+    }
+}
+
+impl fmt::Display for Enr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", ENR_PREFIX, URL_SAFE_NO_PAD.encode(self.encode()))
+    }
+}
+
+impl FromStr for Enr {
+    type Err = EnrError;
+
+    fn from_str(text: &str) -> Result<Enr, EnrError> {
+        let encoded = text
+            .strip_prefix(ENR_PREFIX)
+            .ok_or(EnrError::MissingPrefix)?;
+        let data = URL_SAFE_NO_PAD
+            .decode(encoded)
+            .map_err(|e| EnrError::Base64(e.to_string()))?;
+        Enr::decode(&data)
+    }
+}
+
+pub fn node_id(key: &VerifyingKey) -> [u8; 32] {
+    keccak256(&key.to_encoded_point(false).as_bytes()[1..])
+}
+
+/// A signing key derived from a seed, so test nodes get the same records on every run.
+pub fn deterministic_key(seed: &[u8]) -> SigningKey {
+    let mut secret = keccak256(seed);
+    loop {
+        if let Ok(key) = SigningKey::from_slice(&secret) {
+            return key;
+        }
+        secret = keccak256(&secret);
+    }
+}
+
+/// Collects key/value pairs and signs them into a v4 record.
+#[derive(Debug, Clone)]
+pub struct EnrBuilder {
+    seq: u64,
+    pairs: BTreeMap<Vec<u8>, Vec<u8>>,
+}
+
+impl Default for EnrBuilder {
+    fn default() -> Self {
+        EnrBuilder::new()
+    }
+}
+
+impl EnrBuilder {
+    pub fn new() -> Self {
+        EnrBuilder {
+            seq: 1,
+            pairs: BTreeMap::new(),
+        }
+    }
+
+    pub fn seq(mut self, seq: u64) -> Self {
+        self.seq = seq;
+        self
+    }
+
+    /// Sets a byte string value.
+    pub fn bytes(mut self, key: &str, value: &[u8]) -> Self {
+        let mut encoded = Vec::new();
+        rlp::encode_bytes(&mut encoded, value);
+        self.pairs.insert(key.as_bytes().to_vec(), encoded);
+        self
+    }
+
+    fn port(mut self, key: &str, port: u16) -> Self {
+        let mut encoded = Vec::new();
+        rlp::encode_u64(&mut encoded, port as u64);
+        self.pairs.insert(key.as_bytes().to_vec(), encoded);
+        self
+    }
+
+    pub fn ip4(self, ip: Ipv4Addr) -> Self {
+        self.bytes("ip", &ip.octets())
+    }
+
+    pub fn udp(self, port: u16) -> Self {
+        self.port("udp", port)
+    }
+
+    pub fn tcp(self, port: u16) -> Self {
+        self.port("tcp", port)
+    }
+
+    pub fn client(self, client: &str) -> Self {
+        self.bytes("c", client.as_bytes())
+    }
+
+    pub fn build(self, key: &SigningKey) -> Result<Enr, EnrError> {
+        let public_key = key.verifying_key().to_encoded_point(true);
+        let builder = self
+            .bytes("id", b"v4")
+            .bytes("secp256k1", public_key.as_bytes());
+        let mut enr = Enr {
+            seq: builder.seq,
+            signature: Vec::new(),
+            pairs: builder.pairs,
+        };
+        let signature: Signature = key
+            .sign_prehash(&enr.signing_hash())
+            .map_err(|_| EnrError::InvalidSignature)?;
+        enr.signature = signature.to_vec();
+        let size = enr.encode().len();
+        if size > MAX_ENR_SIZE {
+            return Err(EnrError::TooLarge(size));
+        }
+        Ok(enr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wire::{ContentResponse, Message};
+
+    // The example record of EIP-778 and the key that signed it.
+    const EXAMPLE: &str = "enr:-IS4QHCYrYZbAKWCBRlAy5zzaDZXJBGkcnh4MHcBFZntXNFrdvJjX04jRzjzCBOonrkTfj499SZuOh8R33Ls8RRcy5wBgmlkgnY0gmlwhH8AAAGJc2VjcDI1NmsxoQPKY0yuDUmstAHYpMa2_oxVtw0RW_QAdpzBQA8yWM0xOIN1ZHCCdl8";
+    const EXAMPLE_KEY: &str = "b71c71a67e1177ad4e901695e1b4b9ee17ae16c6668d313eac2f96dbcda3f291";
+    const EXAMPLE_NODE_ID: &str =
+        "a448f24c6d18e575453db13171562b71999873db5b286df957af199ec94617f7";
+
+    #[test]
+    fn test_parse_example_record() {
+        let enr: Enr = EXAMPLE.parse().unwrap();
+        assert_eq!(enr.seq, 1);
+        assert_eq!(enr.id().as_deref(), Some("v4"));
+        assert_eq!(enr.ip4(), Some(Ipv4Addr::LOCALHOST));
+        assert_eq!(enr.udp(), Some(30303));
+        assert_eq!(enr.tcp(), None);
+        assert_eq!(hex::encode(enr.node_id().unwrap()), EXAMPLE_NODE_ID);
+        assert_eq!(enr.to_string(), EXAMPLE);
+    }
+
+    #[test]
+    fn test_signing_reproduces_example() {
+        let key = SigningKey::from_slice(&hex::decode(EXAMPLE_KEY).unwrap()).unwrap();
+        let enr = EnrBuilder::new()
+            .ip4(Ipv4Addr::LOCALHOST)
+            .udp(30303)
+            .build(&key)
+            .unwrap();
+        assert_eq!(enr.to_string(), EXAMPLE);
+        assert_eq!(node_id(key.verifying_key()), enr.node_id().unwrap());
+    }
+
+    #[test]
+    fn test_deterministic_records() {
+        let build = || {
+            EnrBuilder::new()
+                .seq(7)
+                .ip4(Ipv4Addr::new(10, 0, 0, 3))
+                .udp(9009)
+                .client("t 0.1.1")
+                .build(&deterministic_key(b"node-3"))
+                .unwrap()
+        };
+        let enr = build();
+        assert_eq!(enr, build());
+        let parsed: Enr = enr.to_string().parse().unwrap();
+        assert_eq!(parsed, enr);
+        assert_eq!(parsed.client().as_deref(), Some("t 0.1.1"));
+        assert_ne!(
+            enr.node_id(),
+            EnrBuilder::new()
+                .build(&deterministic_key(b"node-4"))
+                .unwrap()
+                .node_id()
+        );
+    }
+
+    #[test]
+    fn test_rejects_invalid_records() {
+        let enr: Enr = EXAMPLE.parse().unwrap();
+        let mut tampered = enr.clone();
+        tampered.seq = 2;
+        assert_eq!(
+            Enr::decode(&tampered.encode()),
+            Err(EnrError::InvalidSignature)
+        );
+        assert_eq!(
+            EXAMPLE.trim_start_matches("enr:").parse::<Enr>(),
+            Err(EnrError::MissingPrefix)
+        );
+
+        // Swap the "ip" and "id" pairs so the keys are no longer sorted.
+        let data = enr.encode();
+        let id = hex::decode("826964827634").unwrap();
+        let ip = hex::decode("826970847f000001").unwrap();
+        let start = data.windows(id.len()).position(|w| w == id).unwrap();
+        let mut swapped = data[..start].to_vec();
+        swapped.extend(&ip);
+        swapped.extend(&id);
+        swapped.extend(&data[start + id.len() + ip.len()..]);
+        assert!(matches!(
+            Enr::decode(&swapped),
+            Err(EnrError::UnsortedKeys(_))
+        ));
+        assert_eq!(Enr::decode(&[0u8; 301]), Err(EnrError::TooLarge(301)));
+    }
+
+    #[test]
+    fn test_wire_messages_carry_records() {
+        let enr: Enr = EXAMPLE.parse().unwrap();
+        let message = Message::Content(ContentResponse::Enrs(vec![enr.encode()]));
+        let Message::Content(ContentResponse::Enrs(enrs)) =
+            Message::decode(&message.encode().unwrap()).unwrap()
+        else {
+            panic!("expected enrs");
+        };
+        assert_eq!(Enr::decode(&enrs[0]).unwrap(), enr);
+    }
+}
@@ -0,0 +1,355 @@
+//! Checks shared by the cargo-fuzz targets in `fuzz/` and the proptest corpus
+//! in `tests/fuzz.rs`. Each decoder must not panic, must allocate in proportion
+//! to its input, and must give back the input byte for byte when re-encoded.
+
+use crate::accumulator::epoch_accumulator_type;
+use crate::block_proof::{signed_block_type, BlockFork};
+use crate::content::{HeaderWithProof, StateValue};
+use crate::content_key::ContentKey;
+use crate::e2store;
+use crate::rlp;
+use crate::ssz::SszType;
+use crate::trie::{NodeRef, TrieNode, TrieNodeKind};
+use crate::vector::{decode_hex, discover, VectorError, VectorKind};
+use crate::wire::Message;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// Bytes a decoder may allocate per input byte, on top of `ALLOCATION_SLACK`.
+pub const ALLOCATION_FACTOR: usize = 64;
+pub const ALLOCATION_SLACK: usize = 64 * 1024;
+
+thread_local! {
+    static LIVE: Cell<isize> = const { Cell::new(0) };
+    static PEAK: Cell<isize> = const { Cell::new(0) };
+}
+
+fn record(delta: isize) {
+    let _ = LIVE.try_with(|live| {
+        let now = live.get() + delta;
+        live.set(now);
+        let _ = PEAK.try_with(|peak| peak.set(peak.get().max(now)));
+    });
+}
+
+/// The system allocator, counting the bytes live on each thread. Install it
+/// with `#[global_allocator]` for `peak_allocation` to see anything.
+pub struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        record(layout.size() as isize);
+        System.alloc(layout)
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        record(layout.size() as isize);
+        System.alloc_zeroed(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        record(-(layout.size() as isize));
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        record(new_size as isize - layout.size() as isize);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+/// Runs `f` and returns the most bytes it had allocated at once on this thread.
+pub fn peak_allocation<T>(f: impl FnOnce() -> T) -> (T, usize) {
+    let start = LIVE.with(Cell::get);
+    PEAK.with(|peak| peak.set(start));
+    let result = f();
+    let peak = PEAK.with(Cell::get);
+    (result, (peak - start).max(0) as usize)
+}
+
+pub fn allocation_limit(input_len: usize) -> usize {
+    input_len * ALLOCATION_FACTOR + ALLOCATION_SLACK
+}
+
+/// What the first byte of an SSZ fuzz input decodes the rest as.
+enum SszCase {
+    Type(SszType),
+    HeaderWithProof,
+    Message,
+}
+
+fn ssz_cases() -> &'static [(String, SszCase)] {
+    static CASES: OnceLock<Vec<(String, SszCase)>> = OnceLock::new();
+    CASES.get_or_init(|| {
+        let mut cases = vec![
+            ("header_with_proof".to_string(), SszCase::HeaderWithProof),
+            ("message".to_string(), SszCase::Message),
+            (
+                "epoch_accumulator".to_string(),
+                SszCase::Type(epoch_accumulator_type()),
+            ),
+        ];
+        for id in 0..=7 {
+            let body = Message::ssz_type(id).expect("every message id has a body type");
+            cases.push((format!("message_body_{}", id), SszCase::Type(body)));
+        }
+        for fork in [
+            BlockFork::Bellatrix,
+            BlockFork::Capella,
+            BlockFork::Deneb,
+            BlockFork::Electra,
+        ] {
+            cases.push((
+                format!("signed_block_{}", fork.name()),
+                SszCase::Type(signed_block_type(fork)),
+            ));
+        }
+        cases
+    })
+}
+
+/// The selector byte of an SSZ fuzz input for the case named `name`.
+pub fn ssz_selector(name: &str) -> Option<u8> {
+    ssz_cases()
+        .iter()
+        .position(|(case, _)| case == name)
+        .map(|index| index as u8)
+}
+
+fn check_ssz(data: &[u8]) {
+    let Some((&selector, data)) = data.split_first() else {
+        return;
+    };
+    let cases = ssz_cases();
+    let (name, case) = &cases[selector as usize % cases.len()];
+    let encoded = match case {
+        SszCase::Type(ssz_type) => ssz_type
+            .decode(data)
+            .ok()
+            .map(|value| ssz_type.encode(&value).expect("decoded values encode")),
+        SszCase::HeaderWithProof => HeaderWithProof::decode(data).ok().map(|v| v.encode()),
+        SszCase::Message => Message::decode(data)
+            .ok()
+            .map(|message| message.encode().expect("decoded messages encode")),
+    };
+    if let Some(encoded) = encoded {
+        assert_round_trip(name, data, &encoded);
+    }
+}
+
+fn check_rlp(data: &[u8]) {
+    if let Ok(item) = rlp::decode(data) {
+        let mut encoded = Vec::new();
+        rlp::encode_item(&mut encoded, &item);
+        assert_round_trip("rlp", data, &encoded);
+    }
+}
+
+fn check_e2store(data: &[u8]) {
+    if let Ok(entries) = e2store::decode(data) {
+        assert_round_trip("e2store", data, &e2store::encode(&entries));
+    }
+}
+
+fn check_trie_node(data: &[u8]) {
+    let Ok(node) = TrieNode::decode(data) else {
+        return;
+    };
+    assert_round_trip("trie_node", data, &node.encoded);
+    let inline = match &node.kind {
+        TrieNodeKind::Branch { children, .. } => children.iter().flatten().collect(),
+        TrieNodeKind::Extension { child, .. } => vec![child],
+        TrieNodeKind::Leaf { .. } => Vec::new(),
+    };
+    for child in inline {
+        if let NodeRef::Inline(encoded) = child {
+            check_trie_node(encoded);
+        }
+    }
+}
+
+fn check_content_key(data: &[u8]) {
+    if let Ok(key) = ContentKey::decode(data) {
+        assert_round_trip("content_key", data, &key.encode());
+        key.content_id();
+    }
+}
+
+fn assert_round_trip(name: &str, data: &[u8], encoded: &[u8]) {
+    assert!(
+        encoded == data,
+        "{} decoded 0x{} but encodes it as 0x{}",
+        name,
+        hex::encode(data),
+        hex::encode(encoded)
+    );
+}
+
+/// A decoder under fuzzing, one per cargo-fuzz target.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Target {
+    Ssz,
+    Rlp,
+    E2store,
+    TrieNode,
+    ContentKey,
+}
+
+impl Target {
+    pub const ALL: [Target; 5] = [
+        Target::Ssz,
+        Target::Rlp,
+        Target::E2store,
+        Target::TrieNode,
+        Target::ContentKey,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Target::Ssz => "ssz",
+            Target::Rlp => "rlp",
+            Target::E2store => "e2store",
+            Target::TrieNode => "trie_node",
+            Target::ContentKey => "content_key",
+        }
+    }
+
+    /// Decodes `data` and panics on a broken invariant, as a fuzz target body.
+    pub fn check(&self, data: &[u8]) {
+        if *self == Target::Ssz {
+            ssz_cases();
+        }
+        let ((), peak) = peak_allocation(|| match self {
+            Target::Ssz => check_ssz(data),
+            Target::Rlp => check_rlp(data),
+            Target::E2store => check_e2store(data),
+            Target::TrieNode => check_trie_node(data),
+            Target::ContentKey => check_content_key(data),
+        });
+        assert!(
+            peak <= allocation_limit(data.len()),
+            "{} allocated {} bytes for {} input bytes",
+            self,
+            peak,
+            data.len()
+        );
+    }
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+fn trie_nodes(value: StateValue) -> Vec<TrieNode> {
+    match value {
+        StateValue::TrieNode(node) => vec![node],
+        StateValue::ContractBytecode(_) => Vec::new(),
+        StateValue::AccountTrieNodeWithProof { proof, .. } => proof,
+        StateValue::ContractStorageTrieNodeWithProof {
+            storage_proof,
+            account_proof,
+            ..
+        } => storage_proof.into_iter().chain(account_proof).collect(),
+        StateValue::ContractBytecodeWithProof { account_proof, .. } => account_proof,
+    }
+}
+
+/// Lists nested past `rlp::MAX_DEPTH`, which the decoders must reject without recursing.
+fn nested_lists() -> Vec<u8> {
+    let mut out = vec![0xc0];
+    for _ in 0..rlp::MAX_DEPTH * 4 {
+        let payload = std::mem::take(&mut out);
+        rlp::encode_list(&mut out, &payload);
+    }
+    out
+}
+
+/// Inputs for `target` taken from the vectors of a network directory, invalid
+/// variants included. They seed both the proptest corpus and `fuzz/corpus`.
+pub fn seeds(target: Target, root: &Path) -> Result<Vec<Vec<u8>>, VectorError> {
+    let mut seeds = Vec::new();
+    let with_selector = |name: &str, data: &[u8]| {
+        let mut seed = vec![ssz_selector(name).expect("known case")];
+        seed.extend_from_slice(data);
+        seed
+    };
+    for file in discover(root)? {
+        if file.kind == Some(VectorKind::WireMessages) && target == Target::Ssz {
+            for message in file.document()?.as_array().into_iter().flatten() {
+                if let Some(Ok(bytes)) = message["message"].as_str().map(decode_hex) {
+                    seeds.push(with_selector("message", &bytes));
+                }
+            }
+            continue;
+        }
+        for entry in file.content_entries()? {
+            let values = [&entry.content_value, &entry.offer_value];
+            let values = values.iter().filter_map(|v| v.as_deref());
+            match target {
+                Target::ContentKey => seeds.push(entry.content_key.clone()),
+                Target::Ssz | Target::Rlp if entry.content_key.first() == Some(&0x00) => {
+                    for value in values {
+                        if target == Target::Ssz {
+                            seeds.push(with_selector("header_with_proof", value));
+                        } else if let Ok(decoded) = HeaderWithProof::decode(value) {
+                            seeds.push(decoded.header.encode());
+                        }
+                    }
+                }
+                Target::TrieNode | Target::Rlp => {
+                    let Ok(ContentKey::State(key)) = ContentKey::decode(&entry.content_key) else {
+                        continue;
+                    };
+                    for value in values {
+                        if let Ok(decoded) = StateValue::decode(&key, value) {
+                            seeds.extend(trie_nodes(decoded).into_iter().map(|node| node.encoded));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    let headers = root.join("history/headers");
+    if matches!(target, Target::E2store | Target::Rlp) && headers.is_dir() {
+        let io_error = |source| VectorError::Io {
+            path: headers.clone(),
+            source,
+        };
+        for dir_entry in fs::read_dir(&headers).map_err(io_error)? {
+            let path = dir_entry.map_err(io_error)?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("e2s") {
+                continue;
+            }
+            let data = fs::read(&path).map_err(io_error)?;
+            match e2store::decode(&data) {
+                Ok(entries) if target == Target::Rlp => {
+                    seeds.extend(entries.into_iter().map(|entry| entry.data))
+                }
+                _ => seeds.push(data),
+            }
+        }
+    }
+    let accumulator = root.join("history/accumulator/epoch-accumulator-00122.ssz");
+    if target == Target::Ssz {
+        if let Ok(data) = fs::read(&accumulator) {
+            // The first 16 records, a valid list that keeps the seed small.
+            seeds.push(with_selector(
+                "epoch_accumulator",
+                &data[..data.len().min(16 * 64)],
+            ));
+        }
+    }
+    if matches!(target, Target::Rlp | Target::TrieNode) {
+        seeds.push(nested_lists());
+    }
+    seeds.sort();
+    seeds.dedup();
+    Ok(seeds)
+}
@@ -0,0 +1,372 @@
+use crate::content_key::content_id;
+use crate::distance::{nodes_storing, DataRadius, StorageNode};
+use crate::vector::{discover, VectorError, VectorKind};
+use crate::wire::{AcceptCode, Message, MAX_CONTENT_KEYS_PER_OFFER};
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::Write;
+use std::path::Path;
+use thiserror::Error;
+
+/// Room for the Portal message in a TALKREQ that keeps the discv5 packet at
+/// 1280 bytes: 87 bytes of header, authdata and tag, 19 of message type and RLP.
+pub const MAX_TALKREQ_PAYLOAD_SIZE: usize = 1174;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum GossipError {
+    #[error("content key of {len} bytes does not fit in an OFFER of {limit} bytes")]
+    KeyTooLarge { len: usize, limit: usize },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OfferLimits {
+    pub max_keys: usize,
+    /// Encoded OFFER size, message id included.
+    pub max_message_size: usize,
+    /// Peers each key is offered to, closest first.
+    pub fan_out: usize,
+}
+
+impl Default for OfferLimits {
+    fn default() -> Self {
+        OfferLimits {
+            max_keys: MAX_CONTENT_KEYS_PER_OFFER,
+            max_message_size: MAX_TALKREQ_PAYLOAD_SIZE,
+            fan_out: 4,
+        }
+    }
+}
+
+/// A simulated peer and the state that decides its ACCEPT codes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Peer {
+    pub node_id: [u8; 32],
+    pub radius: DataRadius,
+    pub stored: HashSet<[u8; 32]>,
+    /// Content ids already being received from another node.
+    pub inbound: HashSet<[u8; 32]>,
+    /// Keys accepted per OFFER before the rest are answered with `RateLimited`.
+    pub accept_limit: Option<usize>,
+}
+
+impl Peer {
+    pub fn new(node_id: [u8; 32], radius: DataRadius) -> Peer {
+        Peer {
+            node_id,
+            radius,
+            stored: HashSet::new(),
+            inbound: HashSet::new(),
+            accept_limit: None,
+        }
+    }
+
+    pub fn storage_node(&self) -> StorageNode {
+        StorageNode {
+            node_id: self.node_id,
+            radius: self.radius,
+        }
+    }
+
+    /// The ACCEPT codes this peer answers an OFFER with, leaving its state as is.
+    pub fn respond(&self, content_keys: &[Vec<u8>]) -> Vec<AcceptCode> {
+        let mut offered = HashSet::new();
+        let mut accepted = 0;
+        content_keys
+            .iter()
+            .map(|key| {
+                let id = content_id(key);
+                if !offered.insert(id) {
+                    AcceptCode::Declined
+                } else if self.stored.contains(&id) {
+                    AcceptCode::AlreadyStored
+                } else if !self.radius.contains(&self.node_id, &id) {
+                    AcceptCode::NotWithinRadius
+                } else if self.inbound.contains(&id) {
+                    AcceptCode::InboundTransferInProgress
+                } else if self.accept_limit.is_some_and(|limit| accepted >= limit) {
+                    AcceptCode::RateLimited
+                } else {
+                    accepted += 1;
+                    AcceptCode::Accepted
+                }
+            })
+            .collect()
+    }
+
+    /// Answers an OFFER and stores what was accepted, as if the transfer completed.
+    pub fn receive(&mut self, content_keys: &[Vec<u8>]) -> Vec<AcceptCode> {
+        let codes = self.respond(content_keys);
+        for (key, code) in content_keys.iter().zip(&codes) {
+            if *code == AcceptCode::Accepted {
+                self.stored.insert(content_id(key));
+            }
+        }
+        codes
+    }
+}
+
+/// One OFFER a bridge should send.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlannedOffer {
+    /// Index into the peers the plan was made for.
+    pub peer: usize,
+    pub content_keys: Vec<Vec<u8>>,
+}
+
+impl PlannedOffer {
+    pub fn message(&self) -> Message {
+        Message::Offer {
+            content_keys: self.content_keys.clone(),
+        }
+    }
+}
+
+/// A planned OFFER and the ACCEPT codes the peer answered with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OfferOutcome {
+    pub offer: PlannedOffer,
+    pub accept_codes: Vec<AcceptCode>,
+}
+
+/// Size of an encoded OFFER: message id, list offset, and an offset per key.
+pub fn offer_size(content_keys: &[Vec<u8>]) -> usize {
+    1 + 4 + content_keys.iter().map(|key| 4 + key.len()).sum::<usize>()
+}
+
+/// The distinct content keys of the valid vectors below `dir`, in file order.
+pub fn vector_content_keys(dir: &Path) -> Result<Vec<Vec<u8>>, VectorError> {
+    let mut seen = HashSet::new();
+    let mut keys = Vec::new();
+    for file in discover(dir)? {
+        if file.kind.is_none() || file.kind == Some(VectorKind::Invalid) {
+            continue;
+        }
+        for entry in file.content_entries()? {
+            if seen.insert(entry.content_key.clone()) {
+                keys.push(entry.content_key);
+            }
+        }
+    }
+    Ok(keys)
+}
+
+/// Offers each key to the `fan_out` closest peers whose radius covers it, and
+/// batches each peer's keys, in the order given, into OFFERs within the limits.
+pub fn plan(
+    content_keys: &[Vec<u8>],
+    peers: &[Peer],
+    limits: &OfferLimits,
+) -> Result<Vec<PlannedOffer>, GossipError> {
+    let nodes: Vec<StorageNode> = peers.iter().map(Peer::storage_node).collect();
+    let mut per_peer: BTreeMap<usize, Vec<Vec<u8>>> = BTreeMap::new();
+    for key in content_keys {
+        if offer_size(std::slice::from_ref(key)) > limits.max_message_size {
+            return Err(GossipError::KeyTooLarge {
+                len: key.len(),
+                limit: limits.max_message_size,
+            });
+        }
+        for peer in nodes_storing(&content_id(key), &nodes)
+            .into_iter()
+            .take(limits.fan_out)
+        {
+            per_peer.entry(peer).or_default().push(key.clone());
+        }
+    }
+
+    let mut offers = Vec::new();
+    for (peer, keys) in per_peer {
+        let mut batch: Vec<Vec<u8>> = Vec::new();
+        let mut size = offer_size(&[]);
+        for key in keys {
+            if !batch.is_empty()
+                && (batch.len() == limits.max_keys
+                    || size + 4 + key.len() > limits.max_message_size)
+            {
+                offers.push(PlannedOffer {
+                    peer,
+                    content_keys: std::mem::take(&mut batch),
+                });
+                size = offer_size(&[]);
+            }
+            size += 4 + key.len();
+            batch.push(key);
+        }
+        if !batch.is_empty() {
+            offers.push(PlannedOffer {
+                peer,
+                content_keys: batch,
+            });
+        }
+    }
+    Ok(offers)
+}
+
+/// Plans the OFFERs and delivers them in order, updating what each peer stores.
+pub fn gossip(
+    content_keys: &[Vec<u8>],
+    peers: &mut [Peer],
+    limits: &OfferLimits,
+) -> Result<Vec<OfferOutcome>, GossipError> {
+    Ok(plan(content_keys, peers, limits)?
+        .into_iter()
+        .map(|offer| OfferOutcome {
+            accept_codes: peers[offer.peer].receive(&offer.content_keys),
+            offer,
+        })
+        .collect())
+}
+
+/// Renders the outcomes per peer, with the number of keys per ACCEPT code.
+pub fn render_outcomes(peers: &[Peer], outcomes: &[OfferOutcome]) -> String {
+    let mut out = String::new();
+    for (index, peer) in peers.iter().enumerate() {
+        let offers: Vec<&OfferOutcome> = outcomes
+            .iter()
+            .filter(|outcome| outcome.offer.peer == index)
+            .collect();
+        let keys: usize = offers.iter().map(|o| o.offer.content_keys.len()).sum();
+        let _ = writeln!(
+            out,
+            "peer {} 0x{} radius {}: {} offer(s), {} key(s)",
+            index,
+            hex::encode(peer.node_id),
+            peer.radius,
+            offers.len(),
+            keys
+        );
+        for outcome in offers {
+            let mut counts: BTreeMap<u8, usize> = BTreeMap::new();
+            for code in &outcome.accept_codes {
+                *counts.entry(code.byte()).or_default() += 1;
+            }
+            let counts: Vec<String> = counts
+                .into_iter()
+                .map(|(byte, count)| {
+                    let code = AcceptCode::from_byte(byte).expect("known accept code");
+                    format!("{} {}", count, code.name())
+                })
+                .collect();
+            let _ = writeln!(
+                out,
+                "  OFFER {} key(s), {} bytes: {}",
+                outcome.offer.content_keys.len(),
+                offer_size(&outcome.offer.content_keys),
+                counts.join(", ")
+            );
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vector::network_dir;
+
+    fn key(index: u16) -> Vec<u8> {
+        let mut key = vec![0x00];
+        key.extend_from_slice(&[index as u8; 30]);
+        key.extend_from_slice(&index.to_be_bytes());
+        key
+    }
+
+    #[test]
+    fn test_batches_respect_limits() {
+        let keys: Vec<Vec<u8>> = (0..100).map(key).collect();
+        let peers = [Peer::new([0u8; 32], DataRadius::MAX)];
+        let offers = plan(&keys, &peers, &OfferLimits::default()).unwrap();
+        for offer in &offers {
+            let encoded = offer.message().encode().unwrap();
+            assert_eq!(encoded.len(), offer_size(&offer.content_keys));
+            assert!(encoded.len() <= MAX_TALKREQ_PAYLOAD_SIZE);
+        }
+        // 33 byte keys take 37 bytes each, so 31 fit after the 5 byte prefix.
+        assert_eq!(
+            offers
+                .iter()
+                .map(|o| o.content_keys.len())
+                .collect::<Vec<_>>(),
+            vec![31, 31, 31, 7]
+        );
+        let small = OfferLimits {
+            max_keys: 10,
+            ..OfferLimits::default()
+        };
+        assert_eq!(plan(&keys, &peers, &small).unwrap().len(), 10);
+        assert_eq!(
+            plan(&[vec![0u8; 2000]], &peers, &small),
+            Err(GossipError::KeyTooLarge {
+                len: 2000,
+                limit: MAX_TALKREQ_PAYLOAD_SIZE
+            })
+        );
+    }
+
+    #[test]
+    fn test_accept_codes() {
+        let keys: Vec<Vec<u8>> = (0..6).map(key).collect();
+        let ids: Vec<[u8; 32]> = keys.iter().map(|k| content_id(k)).collect();
+        let node_id = ids[5];
+        let mut peer = Peer::new(node_id, DataRadius::MAX);
+        peer.stored.insert(ids[0]);
+        peer.inbound.insert(ids[1]);
+        peer.accept_limit = Some(2);
+        let offered = [
+            keys[0].clone(),
+            keys[1].clone(),
+            keys[2].clone(),
+            keys[2].clone(),
+            keys[3].clone(),
+            keys[4].clone(),
+        ];
+        assert_eq!(
+            peer.receive(&offered),
+            vec![
+                AcceptCode::AlreadyStored,
+                AcceptCode::InboundTransferInProgress,
+                AcceptCode::Accepted,
+                AcceptCode::Declined,
+                AcceptCode::Accepted,
+                AcceptCode::RateLimited,
+            ]
+        );
+        assert_eq!(peer.respond(&keys[2..3]), vec![AcceptCode::AlreadyStored]);
+        peer.radius = DataRadius::ZERO;
+        assert_eq!(peer.respond(&keys[4..5]), vec![AcceptCode::NotWithinRadius]);
+        assert_eq!(peer.respond(&keys[5..6]), vec![AcceptCode::Accepted]);
+    }
+
+    #[test]
+    fn test_gossip_mainnet_vectors() {
+        let keys = vector_content_keys(&network_dir("mainnet")).unwrap();
+        let mut peers: Vec<Peer> = (0..8u8)
+            .map(|i| Peer::new([i * 0x20; 32], DataRadius::from_log2(254)))
+            .collect();
+        let limits = OfferLimits {
+            fan_out: 2,
+            ..OfferLimits::default()
+        };
+        let first = gossip(&keys, &mut peers, &limits).unwrap();
+        assert!(first
+            .iter()
+            .all(|o| o.accept_codes.iter().all(|c| *c == AcceptCode::Accepted)));
+        let offered: usize = first.iter().map(|o| o.offer.content_keys.len()).sum();
+        let covered = keys
+            .iter()
+            .map(|key| {
+                let nodes: Vec<StorageNode> = peers.iter().map(Peer::storage_node).collect();
+                nodes_storing(&content_id(key), &nodes).len().min(2)
+            })
+            .sum::<usize>();
+        assert_eq!(offered, covered);
+
+        // Gossiping the same content again only finds it stored.
+        let second = gossip(&keys, &mut peers, &limits).unwrap();
+        assert_eq!(second.len(), first.len());
+        assert!(second.iter().all(|o| o
+            .accept_codes
+            .iter()
+            .all(|c| *c == AcceptCode::AlreadyStored)));
+        assert!(render_outcomes(&peers, &second).contains("already_stored"));
+    }
+}
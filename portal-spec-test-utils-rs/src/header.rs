@@ -0,0 +1,161 @@
+use crate::rlp::{self, RlpError, RlpItem};
+use ethnum::U256;
+use sha3::{Digest, Keccak256};
+
+pub fn keccak256(data: &[u8]) -> [u8; 32] {
+    Keccak256::digest(data).into()
+}
+
+/// An execution block header, with the optional fields added by later forks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Header {
+    pub parent_hash: [u8; 32],
+    pub ommers_hash: [u8; 32],
+    pub beneficiary: [u8; 20],
+    pub state_root: [u8; 32],
+    pub transactions_root: [u8; 32],
+    pub receipts_root: [u8; 32],
+    pub logs_bloom: Vec<u8>,
+    pub difficulty: U256,
+    pub number: u64,
+    pub gas_limit: u64,
+    pub gas_used: u64,
+    pub timestamp: u64,
+    pub extra_data: Vec<u8>,
+    pub mix_hash: [u8; 32],
+    pub nonce: [u8; 8],
+    pub base_fee_per_gas: Option<U256>,
+    pub withdrawals_root: Option<[u8; 32]>,
+    pub blob_gas_used: Option<u64>,
+    pub excess_blob_gas: Option<u64>,
+    pub parent_beacon_block_root: Option<[u8; 32]>,
+    pub requests_hash: Option<[u8; 32]>,
+}
+
+impl Header {
+    pub fn decode(data: &[u8]) -> Result<Header, RlpError> {
+        let item = rlp::decode(data)?;
+        let fields = item.as_list()?;
+        if !(15..=21).contains(&fields.len()) {
+            return Err(RlpError::ItemCount {
+                expected: 15,
+                found: fields.len(),
+            });
+        }
+        let optional = |index: usize| fields.get(index);
+
+        Ok(Header {
+            parent_hash: fields[0].as_fixed()?,
+            ommers_hash: fields[1].as_fixed()?,
+            beneficiary: fields[2].as_fixed()?,
+            state_root: fields[3].as_fixed()?,
+            transactions_root: fields[4].as_fixed()?,
+            receipts_root: fields[5].as_fixed()?,
+            logs_bloom: fields[6].as_fixed::<256>()?.to_vec(),
+            difficulty: fields[7].as_u256()?,
+            number: fields[8].as_u64()?,
+            gas_limit: fields[9].as_u64()?,
+            gas_used: fields[10].as_u64()?,
+            timestamp: fields[11].as_u64()?,
+            extra_data: fields[12].as_bytes()?.to_vec(),
+            mix_hash: fields[13].as_fixed()?,
+            nonce: fields[14].as_fixed()?,
+            base_fee_per_gas: optional(15).map(RlpItem::as_u256).transpose()?,
+            withdrawals_root: optional(16).map(RlpItem::as_fixed).transpose()?,
+            blob_gas_used: optional(17).map(RlpItem::as_u64).transpose()?,
+            excess_blob_gas: optional(18).map(RlpItem::as_u64).transpose()?,
+            parent_beacon_block_root: optional(19).map(RlpItem::as_fixed).transpose()?,
+            requests_hash: optional(20).map(RlpItem::as_fixed).transpose()?,
+        })
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut payload = Vec::new();
+        rlp::encode_bytes(&mut payload, &self.parent_hash);
+        rlp::encode_bytes(&mut payload, &self.ommers_hash);
+        rlp::encode_bytes(&mut payload, &self.beneficiary);
+        rlp::encode_bytes(&mut payload, &self.state_root);
+        rlp::encode_bytes(&mut payload, &self.transactions_root);
+        rlp::encode_bytes(&mut payload, &self.receipts_root);
+        rlp::encode_bytes(&mut payload, &self.logs_bloom);
+        rlp::encode_u256(&mut payload, self.difficulty);
+        rlp::encode_u64(&mut payload, self.number);
+        rlp::encode_u64(&mut payload, self.gas_limit);
+        rlp::encode_u64(&mut payload, self.gas_used);
+        rlp::encode_u64(&mut payload, self.timestamp);
+        rlp::encode_bytes(&mut payload, &self.extra_data);
+        rlp::encode_bytes(&mut payload, &self.mix_hash);
+        rlp::encode_bytes(&mut payload, &self.nonce);
+        if let Some(base_fee) = self.base_fee_per_gas {
+            rlp::encode_u256(&mut payload, base_fee);
+        }
+        if let Some(root) = &self.withdrawals_root {
+            rlp::encode_bytes(&mut payload, root);
+        }
+        if let Some(gas) = self.blob_gas_used {
+            rlp::encode_u64(&mut payload, gas);
+        }
+        if let Some(gas) = self.excess_blob_gas {
+            rlp::encode_u64(&mut payload, gas);
+        }
+        if let Some(root) = &self.parent_beacon_block_root {
+            rlp::encode_bytes(&mut payload, root);
+        }
+        if let Some(hash) = &self.requests_hash {
+            rlp::encode_bytes(&mut payload, hash);
+        }
+
+        let mut out = Vec::new();
+        rlp::encode_list(&mut out, &payload);
+        out
+    }
+
+    pub fn hash(&self) -> [u8; 32] {
+        keccak256(&self.encode())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vector::{decode_hex, network_dir, VectorFile};
+
+    #[test]
+    fn test_decode_header_1000010() {
+        let root = network_dir("mainnet");
+        let path = root.join("history/headers_with_proof/1000010.yaml");
+        let file = VectorFile::load(&root, &path).unwrap();
+        let entry = &file.content_entries().unwrap()[0];
+        let value = entry.content_value.as_ref().unwrap();
+        let header_end = u32::from_le_bytes(value[4..8].try_into().unwrap()) as usize;
+        let header = Header::decode(&value[8..header_end]).unwrap();
+
+        assert_eq!(header.number, 1000010);
+        assert_eq!(header.base_fee_per_gas, None);
+        assert_eq!(header.encode(), value[8..header_end]);
+        assert_eq!(header.hash().to_vec(), entry.content_key[1..]);
+    }
+
+    #[test]
+    fn test_headers_across_forks_round_trip() {
+        let root = network_dir("mainnet");
+        let path = root.join("history/hive/test_data_collection_of_forks_blocks.yaml");
+        let file = VectorFile::load(&root, &path).unwrap();
+        let mut cancun = 0;
+        for entry in file.content_entries().unwrap() {
+            if entry.content_key[0] != 0x00 {
+                continue;
+            }
+            let value = entry.content_value.unwrap();
+            let header_end = u32::from_le_bytes(value[4..8].try_into().unwrap()) as usize;
+            let header = Header::decode(&value[8..header_end]).unwrap();
+            assert_eq!(header.encode(), value[8..header_end], "{}", header.number);
+            assert_eq!(header.hash().to_vec(), entry.content_key[1..]);
+            if header.parent_beacon_block_root.is_some() {
+                cancun += 1;
+            }
+        }
+        assert!(cancun > 0);
+        assert!(Header::decode(&decode_hex("0xc0").unwrap()).is_err());
+    }
+}
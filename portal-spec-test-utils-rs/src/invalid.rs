@@ -0,0 +1,794 @@
+use crate::content_key::ContentKey;
+use crate::network::Network;
+use crate::rlp;
+use crate::vector::{
+    decode_hex, discover, encode_hex, ContentEntry, VectorError, VectorFile, VectorKind,
+};
+use serde_json::Value;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Values above this size are not mutated, the variants would bloat the tree.
+pub const MAX_VALUE_SIZE: usize = 64 * 1024;
+
+const ACCUMULATOR_PROOF_SELECTOR: u8 = 0x01;
+const HISTORICAL_ROOTS_PROOF_SELECTOR: u8 = 0x02;
+const HISTORICAL_SUMMARIES_PROOF_SELECTOR: u8 = 0x03;
+const ACCUMULATOR_PROOF_LEN: usize = 15 * 32;
+
+/// The class of error a client is expected to raise when it rejects an invalid vector.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ErrorClass {
+    InvalidContentKey,
+    InvalidSsz,
+    ContentMismatch,
+    InvalidProof,
+    ForkMismatch,
+}
+
+impl ErrorClass {
+    pub const ALL: [ErrorClass; 5] = [
+        ErrorClass::InvalidContentKey,
+        ErrorClass::InvalidSsz,
+        ErrorClass::ContentMismatch,
+        ErrorClass::InvalidProof,
+        ErrorClass::ForkMismatch,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ErrorClass::InvalidContentKey => "invalid_content_key",
+            ErrorClass::InvalidSsz => "invalid_ssz",
+            ErrorClass::ContentMismatch => "content_mismatch",
+            ErrorClass::InvalidProof => "invalid_proof",
+            ErrorClass::ForkMismatch => "fork_mismatch",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<ErrorClass> {
+        ErrorClass::ALL
+            .into_iter()
+            .find(|class| class.name() == name)
+    }
+}
+
+impl fmt::Display for ErrorClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Mutation {
+    FlippedProofByte,
+    WrongProofSelector,
+    WrongKeySelector,
+    UnknownKeySelector,
+    TruncatedSszOffset,
+    OffsetOutOfBounds,
+    MismatchedBlockHash,
+    EmptyValue,
+    PreMergeHeaderInCapellaProof,
+    WrongForkDigest,
+    TamperedListItem,
+}
+
+impl Mutation {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Mutation::FlippedProofByte => "flipped_proof_byte",
+            Mutation::WrongProofSelector => "wrong_proof_selector",
+            Mutation::WrongKeySelector => "wrong_key_selector",
+            Mutation::UnknownKeySelector => "unknown_key_selector",
+            Mutation::TruncatedSszOffset => "truncated_ssz_offset",
+            Mutation::OffsetOutOfBounds => "offset_out_of_bounds",
+            Mutation::MismatchedBlockHash => "mismatched_block_hash",
+            Mutation::EmptyValue => "empty_value",
+            Mutation::PreMergeHeaderInCapellaProof => "pre_merge_header_in_capella_proof",
+            Mutation::WrongForkDigest => "wrong_fork_digest",
+            Mutation::TamperedListItem => "tampered_list_item",
+        }
+    }
+}
+
+impl fmt::Display for Mutation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// An invalid variant derived from a valid vector.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidVector {
+    /// The source file, relative to the network directory.
+    pub source: PathBuf,
+    pub label: String,
+    pub mutation: Mutation,
+    pub expected_error: ErrorClass,
+    pub detail: String,
+    pub content_key: Vec<u8>,
+    pub content_value: Vec<u8>,
+    /// Set when `content_value` is a state offer value rather than a retrieval value.
+    pub offer: bool,
+    pub state_root: Option<Vec<u8>>,
+}
+
+impl InvalidVector {
+    fn new(
+        source: &VectorFile,
+        entry: &ContentEntry,
+        mutation: Mutation,
+        expected_error: ErrorClass,
+        detail: String,
+    ) -> Self {
+        InvalidVector {
+            source: source.relative.clone(),
+            label: entry.label.clone(),
+            mutation,
+            expected_error,
+            detail,
+            content_key: entry.content_key.clone(),
+            content_value: Vec::new(),
+            offer: entry.offer_value.is_some(),
+            state_root: entry.state_root.clone(),
+        }
+    }
+
+    fn with_key(mut self, key: Vec<u8>) -> Self {
+        self.content_key = key;
+        self
+    }
+
+    fn with_value(mut self, value: Vec<u8>) -> Self {
+        self.content_value = value;
+        self
+    }
+
+    /// The path of the generated file, relative to the network directory.
+    pub fn output_path(&self) -> PathBuf {
+        let stem = self
+            .source
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("vector");
+        let mut name = stem.to_string();
+        if !self.label.is_empty() {
+            name.push('-');
+            name.push_str(&self.label.replace('/', "-"));
+        }
+        let file_name = format!("{}-{}.yaml", name, self.mutation);
+        self.source
+            .parent()
+            .unwrap_or(Path::new(""))
+            .join("invalid")
+            .join(file_name)
+    }
+
+    pub fn to_yaml(&self) -> String {
+        let mut out = String::new();
+        out.push_str(&format!("# Invalid variant of {}", self.source.display()));
+        if !self.label.is_empty() {
+            out.push_str(&format!(" (entry {})", self.label));
+        }
+        out.push('\n');
+        out.push_str(&format!("# Mutation: {}, {}\n", self.mutation, self.detail));
+        out.push_str("# Generated by `portal-vector gen-invalid`, do not edit by hand\n\n");
+        if let Some(root) = &self.state_root {
+            out.push_str(&format!("state_root: \"{}\"\n", encode_hex(root)));
+        }
+        out.push_str(&format!(
+            "content_key: \"{}\"\n",
+            encode_hex(&self.content_key)
+        ));
+        let value_field = if self.offer {
+            "content_value_offer"
+        } else {
+            "content_value"
+        };
+        out.push_str(&format!(
+            "{}: \"{}\"\n",
+            value_field,
+            encode_hex(&self.content_value)
+        ));
+        out.push_str(&format!("expected_error: \"{}\"\n", self.expected_error));
+        out
+    }
+}
+
+/// Reads back the expected error class of a generated file.
+pub fn expected_error(file: &VectorFile) -> Result<Option<ErrorClass>, VectorError> {
+    let document = file.document()?;
+    Ok(document
+        .get("expected_error")
+        .and_then(Value::as_str)
+        .and_then(ErrorClass::from_name))
+}
+
+/// Derives the invalid variants of every vector below the directory `root` of `network`.
+pub fn generate(network: &Network, root: &Path) -> Result<Vec<InvalidVector>, VectorError> {
+    let files = discover(root)?;
+    let capella_proof = files
+        .iter()
+        .find(|file| file.kind == Some(VectorKind::BlockProofCapella))
+        .map(encode_capella_proof)
+        .transpose()?;
+
+    let mut vectors = Vec::new();
+    for file in &files {
+        let Some(kind) = file.kind else {
+            continue;
+        };
+        if !matches!(
+            kind,
+            VectorKind::HistoryHeaderWithProof
+                | VectorKind::HistoryBlockBody
+                | VectorKind::HistoryReceipts
+                | VectorKind::StateValidation
+                | VectorKind::BeaconLightClient
+        ) {
+            continue;
+        }
+        // Collections are covered by their per-block files.
+        if file.relative.extension().and_then(|e| e.to_str()) == Some("json") {
+            continue;
+        }
+        for entry in file.content_entries()? {
+            vectors.extend(mutate(
+                network,
+                file,
+                kind,
+                &entry,
+                capella_proof.as_deref(),
+            ));
+        }
+    }
+    Ok(vectors)
+}
+
+/// Writes the generated vectors below `root`, replacing any stale `invalid` files.
+pub fn write_all(root: &Path, vectors: &[InvalidVector]) -> io::Result<Vec<PathBuf>> {
+    let mut written = Vec::new();
+    for vector in vectors {
+        let path = root.join(vector.output_path());
+        if let Some(parent) = path.parent() {
+            if !written.iter().any(|p: &PathBuf| p.parent() == Some(parent)) {
+                if parent.exists() {
+                    fs::remove_dir_all(parent)?;
+                }
+                fs::create_dir_all(parent)?;
+            }
+        }
+        fs::write(&path, vector.to_yaml())?;
+        written.push(path);
+    }
+    Ok(written)
+}
+
+/// Returns the paths whose content on disk differs from what the generator produces.
+pub fn stale_files(root: &Path, vectors: &[InvalidVector]) -> Vec<PathBuf> {
+    vectors
+        .iter()
+        .filter(|vector| {
+            let path = root.join(vector.output_path());
+            fs::read_to_string(&path).ok().as_deref() != Some(vector.to_yaml().as_str())
+        })
+        .map(|vector| vector.output_path())
+        .collect()
+}
+
+pub fn mutate(
+    network: &Network,
+    file: &VectorFile,
+    kind: VectorKind,
+    entry: &ContentEntry,
+    capella_proof: Option<&[u8]>,
+) -> Vec<InvalidVector> {
+    let value = match (&entry.offer_value, &entry.content_value) {
+        (Some(offer), _) => offer,
+        (None, Some(value)) => value,
+        (None, None) => return Vec::new(),
+    };
+    if value.len() > MAX_VALUE_SIZE || value.len() < 8 {
+        return Vec::new();
+    }
+    // A vector without a key has no selector to mutate, nor content to decode.
+    let Some(&selector) = entry.content_key.first() else {
+        return Vec::new();
+    };
+
+    let new = |mutation, error, detail: String| {
+        InvalidVector::new(file, entry, mutation, error, detail).with_value(value.clone())
+    };
+    let mut out = Vec::new();
+
+    if let Some(offset) = proof_byte_offset(kind, &entry.content_key, value) {
+        let mut mutated = value.clone();
+        mutated[offset] ^= 0xff;
+        out.push(
+            new(
+                Mutation::FlippedProofByte,
+                ErrorClass::InvalidProof,
+                format!("byte {} of the value inverted", offset),
+            )
+            .with_value(mutated),
+        );
+    }
+
+    if kind == VectorKind::HistoryHeaderWithProof {
+        if let Some(proof_start) = read_offset(value, 4) {
+            if value.get(proof_start) == Some(&ACCUMULATOR_PROOF_SELECTOR) {
+                let mut mutated = value.clone();
+                mutated[proof_start] = HISTORICAL_ROOTS_PROOF_SELECTOR;
+                out.push(
+                    new(
+                        Mutation::WrongProofSelector,
+                        ErrorClass::InvalidSsz,
+                        "accumulator proof tagged as a historical roots proof".to_string(),
+                    )
+                    .with_value(mutated),
+                );
+            }
+        }
+    }
+
+    if let Some(wrong) = sibling_selector(selector) {
+        let mut key = entry.content_key.clone();
+        key[0] = wrong;
+        // Keys of another layout no longer decode, the rest are a valid key for other content.
+        let error = match ContentKey::decode(&key) {
+            Ok(_) => ErrorClass::ContentMismatch,
+            Err(_) => ErrorClass::InvalidContentKey,
+        };
+        out.push(
+            new(
+                Mutation::WrongKeySelector,
+                error,
+                format!(
+                    "content key selector 0x{:02x} replaced by 0x{:02x}",
+                    selector, wrong
+                ),
+            )
+            .with_key(key),
+        );
+    }
+
+    let mut key = entry.content_key.clone();
+    key[0] = 0xff;
+    out.push(
+        new(
+            Mutation::UnknownKeySelector,
+            ErrorClass::InvalidContentKey,
+            "content key selector replaced by 0xff".to_string(),
+        )
+        .with_key(key),
+    );
+
+    let ssz_start = ssz_start(kind);
+    if let Some(first_offset) = read_offset(value, ssz_start) {
+        out.push(
+            new(
+                Mutation::TruncatedSszOffset,
+                ErrorClass::InvalidSsz,
+                format!(
+                    "value cut to {} bytes, inside the first offset",
+                    ssz_start + 2
+                ),
+            )
+            .with_value(value[..ssz_start + 2].to_vec()),
+        );
+
+        let mut mutated = value.clone();
+        let out_of_bounds = (value.len() - ssz_start + 1) as u32;
+        mutated[ssz_start..ssz_start + 4].copy_from_slice(&out_of_bounds.to_le_bytes());
+        out.push(
+            new(
+                Mutation::OffsetOutOfBounds,
+                ErrorClass::InvalidSsz,
+                format!(
+                    "first offset {} replaced by {}, past the end of the value",
+                    first_offset, out_of_bounds
+                ),
+            )
+            .with_value(mutated),
+        );
+    }
+
+    if let Some(mutated) = mismatch_block_hash(kind, &entry.content_key, value) {
+        let (key, value, detail) = mutated;
+        out.push(
+            new(
+                Mutation::MismatchedBlockHash,
+                ErrorClass::ContentMismatch,
+                detail,
+            )
+            .with_key(key)
+            .with_value(value),
+        );
+    }
+
+    // No bytes is the valid encoding of an empty receipt list, which the header does not match.
+    let empty_error = match kind {
+        VectorKind::HistoryReceipts => ErrorClass::ContentMismatch,
+        _ => ErrorClass::InvalidSsz,
+    };
+    out.push(
+        new(
+            Mutation::EmptyValue,
+            empty_error,
+            "value replaced by zero bytes".to_string(),
+        )
+        .with_value(Vec::new()),
+    );
+
+    if let (VectorKind::HistoryHeaderWithProof, Some(capella_proof)) = (kind, capella_proof) {
+        if let Some(header_end) = read_offset(value, 4) {
+            let proof = value.get(header_end..).unwrap_or_default();
+            if proof.len() == 1 + ACCUMULATOR_PROOF_LEN && proof[0] == ACCUMULATOR_PROOF_SELECTOR {
+                let mut mutated = value[..header_end].to_vec();
+                mutated.push(HISTORICAL_SUMMARIES_PROOF_SELECTOR);
+                mutated.extend_from_slice(capella_proof);
+                out.push(
+                    new(
+                        Mutation::PreMergeHeaderInCapellaProof,
+                        ErrorClass::ForkMismatch,
+                        "pre-merge header paired with a historical summaries proof".to_string(),
+                    )
+                    .with_value(mutated),
+                );
+            }
+        }
+    }
+
+    if let Some((offset, detail)) = tampered_list_item(kind, value) {
+        let mut mutated = value.clone();
+        mutated[offset] ^= 0x01;
+        out.push(
+            new(
+                Mutation::TamperedListItem,
+                ErrorClass::ContentMismatch,
+                detail.to_string(),
+            )
+            .with_value(mutated),
+        );
+    }
+
+    if let Some(wrong) = sibling_fork_digest(network, kind, value) {
+        let mut mutated = value.clone();
+        mutated[..4].copy_from_slice(&wrong);
+        out.push(
+            new(
+                Mutation::WrongForkDigest,
+                ErrorClass::ForkMismatch,
+                format!(
+                    "fork digest 0x{} replaced by 0x{} of a neighbouring fork",
+                    hex::encode(&value[..4]),
+                    hex::encode(wrong)
+                ),
+            )
+            .with_value(mutated),
+        );
+    }
+
+    out
+}
+
+/// The digest of the fork after the one prefixing a beacon value, or before the last one.
+fn sibling_fork_digest(network: &Network, kind: VectorKind, value: &[u8]) -> Option<[u8; 4]> {
+    if kind != VectorKind::BeaconLightClient {
+        return None;
+    }
+    let digests = network.fork_digests();
+    let index = digests
+        .iter()
+        .position(|(digest, _)| value.starts_with(digest))?;
+    let sibling = if index + 1 < digests.len() {
+        index + 1
+    } else {
+        index - 1
+    };
+    Some(digests[sibling].0)
+}
+
+fn read_offset(value: &[u8], at: usize) -> Option<usize> {
+    let bytes = value.get(at..at + 4)?;
+    Some(u32::from_le_bytes(bytes.try_into().ok()?) as usize)
+}
+
+/// Picks a byte of the first transaction or receipt that keeps the value
+/// decodable but changes the trie root the header commits to.
+fn tampered_list_item(kind: VectorKind, value: &[u8]) -> Option<(usize, &'static str)> {
+    match kind {
+        // The last byte of the first transaction, inside its signature.
+        VectorKind::HistoryBlockBody => {
+            let list_start = read_offset(value, 0)?;
+            let list_end = read_offset(value, 4)?.checked_sub(list_start)?;
+            if list_end == 0 {
+                return None;
+            }
+            let first = read_offset(value, list_start)?;
+            let end = if first > 4 {
+                read_offset(value, list_start + 4)?
+            } else {
+                list_end
+            };
+            let offset = (list_start + end).checked_sub(1)?;
+            (end > first && offset < value.len())
+                .then_some((offset, "last byte of the first transaction changed"))
+        }
+        // The first logs bloom byte of the first receipt, after its status and gas used.
+        VectorKind::HistoryReceipts => {
+            let mut at = read_offset(value, 0)?;
+            if *value.get(at)? < 0x7f {
+                at += 1;
+            }
+            let (_, list_header, _) = rlp::decode_header(value.get(at..)?).ok()?;
+            at += list_header;
+            for _ in 0..2 {
+                let (_, header, len) = rlp::decode_header(value.get(at..)?).ok()?;
+                at += header + len;
+            }
+            let (_, bloom_header, bloom_len) = rlp::decode_header(value.get(at..)?).ok()?;
+            let offset = at + bloom_header;
+            (bloom_len == 256 && offset < value.len())
+                .then_some((offset, "first logs bloom byte of the first receipt changed"))
+        }
+        _ => None,
+    }
+}
+
+/// Beacon content values start with a 4-byte fork digest before the SSZ payload.
+fn ssz_start(kind: VectorKind) -> usize {
+    match kind {
+        VectorKind::BeaconLightClient => 4,
+        _ => 0,
+    }
+}
+
+/// Picks a byte inside a proof of the value, if the value carries one.
+fn proof_byte_offset(kind: VectorKind, key: &[u8], value: &[u8]) -> Option<usize> {
+    let offset = match (kind, *key.first()?) {
+        (VectorKind::HistoryHeaderWithProof, _) => {
+            let proof_start = read_offset(value, 4)?;
+            proof_start + 1 + value.len().checked_sub(proof_start)? / 2
+        }
+        // The first trie node of the proof, behind the list offset table.
+        (VectorKind::StateValidation, 0x20 | 0x21) => {
+            let proof_start = read_offset(value, 0)?;
+            proof_start + read_offset(value, proof_start)? + 8
+        }
+        (VectorKind::StateValidation, 0x22) => {
+            let proof_start = read_offset(value, 4)?;
+            proof_start + read_offset(value, proof_start)? + 8
+        }
+        // Finality branch of a LightClientFinalityUpdate, after the two header offsets.
+        (VectorKind::BeaconLightClient, 0x12) => 4 + 8 + 16,
+        // Sync committee branch of a LightClientBootstrap, after the header offset and committee.
+        (VectorKind::BeaconLightClient, 0x10) => 4 + 4 + 512 * 48 + 48 + 16,
+        _ => return None,
+    };
+    (offset < value.len()).then_some(offset)
+}
+
+fn sibling_selector(selector: u8) -> Option<u8> {
+    Some(match selector {
+        0x00 => 0x01,
+        0x01 => 0x02,
+        0x02 => 0x00,
+        0x03 => 0x00,
+        0x20 => 0x21,
+        0x21 => 0x20,
+        0x22 => 0x20,
+        0x10 => 0x11,
+        0x11 => 0x10,
+        0x12 => 0x13,
+        0x13 => 0x12,
+        0x14 => 0x12,
+        _ => return None,
+    })
+}
+
+type Mismatched = (Vec<u8>, Vec<u8>, String);
+
+fn mismatch_block_hash(kind: VectorKind, key: &[u8], value: &[u8]) -> Option<Mismatched> {
+    match kind {
+        VectorKind::HistoryHeaderWithProof
+        | VectorKind::HistoryBlockBody
+        | VectorKind::HistoryReceipts
+            if key.len() == 33 =>
+        {
+            let mut key = key.to_vec();
+            key[32] ^= 0x01;
+            Some((
+                key,
+                value.to_vec(),
+                "last byte of the block hash in the content key changed".to_string(),
+            ))
+        }
+        VectorKind::StateValidation => {
+            let at = if *key.first()? == 0x20 { 4 } else { 8 };
+            let mut value = value.to_vec();
+            *value.get_mut(at + 31)? ^= 0x01;
+            Some((
+                key.to_vec(),
+                value,
+                "last byte of the block hash in the offer value changed".to_string(),
+            ))
+        }
+        _ => None,
+    }
+}
+
+/// Encodes a Capella block proof YAML file as an SSZ `BlockProofHistoricalSummaries`.
+pub(crate) fn encode_capella_proof(file: &VectorFile) -> Result<Vec<u8>, VectorError> {
+    let document = file.document()?;
+    let parse_error = |message: String| VectorError::Parse {
+        path: file.path.clone(),
+        message,
+    };
+    let hashes = |field: &str| -> Result<Vec<Vec<u8>>, VectorError> {
+        document
+            .get(field)
+            .and_then(Value::as_array)
+            .ok_or_else(|| parse_error(format!("missing {}", field)))?
+            .iter()
+            .map(|v| {
+                let text = v
+                    .as_str()
+                    .ok_or_else(|| parse_error(format!("{} is not hex", field)))?;
+                decode_hex(text).map_err(|e| parse_error(e.to_string()))
+            })
+            .collect()
+    };
+    let hash = |field: &str| -> Result<Vec<u8>, VectorError> {
+        let text = document
+            .get(field)
+            .and_then(Value::as_str)
+            .ok_or_else(|| parse_error(format!("missing {}", field)))?;
+        decode_hex(text).map_err(|e| parse_error(e.to_string()))
+    };
+    let slot = document
+        .get("slot")
+        .and_then(Value::as_u64)
+        .ok_or_else(|| parse_error("missing slot".to_string()))?;
+
+    let mut out = Vec::new();
+    hashes("historical_summaries_proof")?
+        .iter()
+        .for_each(|h| out.extend_from_slice(h));
+    out.extend_from_slice(&hash("beacon_block_header_root")?);
+    hashes("beacon_block_body_proof")?
+        .iter()
+        .chain(hashes("beacon_block_header_proof")?.iter())
+        .for_each(|h| out.extend_from_slice(h));
+    out.extend_from_slice(&slot.to_le_bytes());
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vector::network_dir;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_generated_vectors_are_up_to_date() {
+        let root = network_dir("mainnet");
+        let vectors = generate(&Network::mainnet(), &root).unwrap();
+        let stale = stale_files(&root, &vectors);
+        assert!(
+            stale.is_empty(),
+            "run `portal-vector gen-invalid` to refresh {:?}",
+            stale
+        );
+
+        let on_disk = discover(&root)
+            .unwrap()
+            .into_iter()
+            .filter(|file| file.kind == Some(VectorKind::Invalid))
+            .count();
+        assert_eq!(on_disk, vectors.len());
+    }
+
+    #[test]
+    fn test_header_with_proof_mutations() {
+        let root = network_dir("mainnet");
+        let vectors = generate(&Network::mainnet(), &root).unwrap();
+        let mutations: HashSet<Mutation> = vectors
+            .iter()
+            .filter(|v| v.source == Path::new("history/headers_with_proof/15537393.yaml"))
+            .map(|v| v.mutation)
+            .collect();
+        assert!(mutations.contains(&Mutation::FlippedProofByte));
+        assert!(mutations.contains(&Mutation::WrongProofSelector));
+        assert!(mutations.contains(&Mutation::PreMergeHeaderInCapellaProof));
+
+        let capella = vectors
+            .iter()
+            .find(|v| v.mutation == Mutation::PreMergeHeaderInCapellaProof)
+            .unwrap();
+        let proof_start = read_offset(&capella.content_value, 4).unwrap();
+        // Selector, 13 + 1 + 11 hashes and the slot.
+        assert_eq!(capella.content_value.len() - proof_start, 1 + 25 * 32 + 8);
+        assert_eq!(capella.expected_error, ErrorClass::ForkMismatch);
+    }
+
+    #[test]
+    fn test_malformed_entries_are_skipped() {
+        let root = network_dir("mainnet");
+        let file =
+            VectorFile::load(&root, &root.join("state/validation/account_trie_node.yaml")).unwrap();
+        let mut entry = file.content_entries().unwrap().remove(0);
+        entry.offer_value = Some(vec![0x04, 0, 0, 0, 0, 0, 0, 0, 0x01]);
+        let mutations: Vec<_> = mutate(
+            &Network::mainnet(),
+            &file,
+            VectorKind::StateValidation,
+            &entry,
+            None,
+        )
+        .into_iter()
+        .map(|v| v.mutation)
+        .collect();
+        assert!(!mutations.contains(&Mutation::MismatchedBlockHash));
+        assert!(mutations.contains(&Mutation::EmptyValue));
+
+        // A header offset past the end of the value.
+        entry.offer_value = Some(vec![0, 0, 0, 0, 0xff, 0xff, 0xff, 0x7f, 0x01]);
+        let mutations: Vec<_> = mutate(
+            &Network::mainnet(),
+            &file,
+            VectorKind::HistoryHeaderWithProof,
+            &entry,
+            Some(&[0; ACCUMULATOR_PROOF_LEN]),
+        )
+        .into_iter()
+        .map(|v| v.mutation)
+        .collect();
+        assert!(!mutations.contains(&Mutation::PreMergeHeaderInCapellaProof));
+        assert!(mutations.contains(&Mutation::EmptyValue));
+
+        entry.content_key.clear();
+        assert!(mutate(
+            &Network::mainnet(),
+            &file,
+            VectorKind::StateValidation,
+            &entry,
+            None
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn test_sibling_fork_digest_follows_network() {
+        let sepolia = Network::sepolia();
+        let [(capella, _), (deneb, _), (electra, _)] = sepolia.fork_digests();
+        let value = |digest: [u8; 4]| [&digest[..], &[0; 8]].concat();
+        let kind = VectorKind::BeaconLightClient;
+        assert_eq!(
+            sibling_fork_digest(&sepolia, kind, &value(capella)),
+            Some(deneb)
+        );
+        assert_eq!(
+            sibling_fork_digest(&sepolia, kind, &value(electra)),
+            Some(deneb)
+        );
+        let mainnet_capella = Network::mainnet().fork_digest(crate::beacon::BeaconFork::Capella);
+        assert_eq!(
+            sibling_fork_digest(&sepolia, kind, &value(mainnet_capella)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_yaml_round_trip() {
+        let root = network_dir("mainnet");
+        let vector = generate(&Network::mainnet(), &root).unwrap().remove(0);
+        let path = root.join(vector.output_path());
+        let file = VectorFile::load(&root, &path).unwrap();
+        assert_eq!(expected_error(&file).unwrap(), Some(vector.expected_error));
+        let entries = file.content_entries().unwrap();
+        assert_eq!(entries[0].content_key, vector.content_key);
+    }
+}
@@ -1,4 +1,44 @@
+pub mod accumulator;
+pub mod beacon;
+pub mod bench;
+pub mod block_proof;
+pub mod bls;
+pub mod chain;
+pub mod conformance;
+pub mod content;
+pub mod content_key;
+pub mod convert;
+pub mod coverage;
+pub mod discv5;
+pub mod distance;
+pub mod e2store;
+pub mod enr;
+pub mod fuzz;
+pub mod gossip;
+pub mod header;
+pub mod invalid;
+pub mod lint;
+pub mod manifest;
+pub mod network;
+pub mod pcap;
+pub mod rlp;
+pub mod rpc;
+pub mod schema;
+pub mod secure_hash_verifier;
+pub mod show;
+pub mod simulator;
+pub mod ssz;
+pub mod store;
+pub mod transaction;
+pub mod trie;
+pub mod utp;
+pub mod validation;
+pub mod vector;
+pub mod wire;
+pub mod yaml;
+
 // Execution Layer hard forks https://ethereum.org/en/history/
+pub const PRAGUE_BLOCK_NUMBER: u64 = 22431084;
 pub const CANCUN_BLOCK_NUMBER: u64 = 19426587;
 pub const SHANGHAI_BLOCK_NUMBER: u64 = 17034870;
 pub const MERGE_BLOCK_NUMBER: u64 = 15537394;
@@ -9,26 +49,115 @@ pub const CONSTANTINOPLE_BLOCK_NUMBER: u64 = 7280000;
 pub const BYZANTIUM_BLOCK_NUMBER: u64 = 4370000;
 pub const HOMESTEAD_BLOCK_NUMBER: u64 = 1150000;
 
+// Total difficulty at which mainnet switched to proof of stake
+pub const TERMINAL_TOTAL_DIFFICULTY: u128 = 58750000000000000000000;
+
+/// Execution Layer forks that change header or transaction rules, in activation order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ExecutionFork {
+    Frontier,
+    Homestead,
+    Byzantium,
+    Constantinople,
+    Istanbul,
+    Berlin,
+    London,
+    Merge,
+    Shanghai,
+    Cancun,
+    Prague,
+}
+
+impl ExecutionFork {
+    pub const ALL: [ExecutionFork; 11] = [
+        ExecutionFork::Frontier,
+        ExecutionFork::Homestead,
+        ExecutionFork::Byzantium,
+        ExecutionFork::Constantinople,
+        ExecutionFork::Istanbul,
+        ExecutionFork::Berlin,
+        ExecutionFork::London,
+        ExecutionFork::Merge,
+        ExecutionFork::Shanghai,
+        ExecutionFork::Cancun,
+        ExecutionFork::Prague,
+    ];
+
+    /// The first block the fork's rules apply to.
+    pub fn activation_block(&self) -> u64 {
+        match self {
+            ExecutionFork::Frontier => 0,
+            ExecutionFork::Homestead => HOMESTEAD_BLOCK_NUMBER,
+            ExecutionFork::Byzantium => BYZANTIUM_BLOCK_NUMBER,
+            ExecutionFork::Constantinople => CONSTANTINOPLE_BLOCK_NUMBER,
+            ExecutionFork::Istanbul => ISTANBUL_BLOCK_NUMBER,
+            ExecutionFork::Berlin => BERLIN_BLOCK_NUMBER,
+            ExecutionFork::London => LONDON_BLOCK_NUMBER,
+            ExecutionFork::Merge => MERGE_BLOCK_NUMBER,
+            ExecutionFork::Shanghai => SHANGHAI_BLOCK_NUMBER,
+            ExecutionFork::Cancun => CANCUN_BLOCK_NUMBER,
+            ExecutionFork::Prague => PRAGUE_BLOCK_NUMBER,
+        }
+    }
+
+    /// The fork of a mainnet block, see `Network::execution_fork` for other networks.
+    pub fn at_block(block_number: u64) -> ExecutionFork {
+        ExecutionFork::active_at(block_number, |fork| fork.activation_block())
+    }
+
+    /// The latest fork whose activation block is at or before `block_number`, the
+    /// one rule behind both `at_block` and `Network::execution_fork`.
+    pub(crate) fn active_at(
+        block_number: u64,
+        activation_block: impl Fn(ExecutionFork) -> u64,
+    ) -> ExecutionFork {
+        ExecutionFork::ALL
+            .into_iter()
+            .rev()
+            .find(|fork| block_number >= activation_block(*fork))
+            .unwrap_or(ExecutionFork::Frontier)
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ExecutionFork::Frontier => "frontier",
+            ExecutionFork::Homestead => "homestead",
+            ExecutionFork::Byzantium => "byzantium",
+            ExecutionFork::Constantinople => "constantinople",
+            ExecutionFork::Istanbul => "istanbul",
+            ExecutionFork::Berlin => "berlin",
+            ExecutionFork::London => "london",
+            ExecutionFork::Merge => "merge",
+            ExecutionFork::Shanghai => "shanghai",
+            ExecutionFork::Cancun => "cancun",
+            ExecutionFork::Prague => "prague",
+        }
+    }
+}
+
+/// The provenance suffix of a block's comment, ` (post-<fork>)` from the block
+/// `ExecutionFork::at_block` puts in a fork onwards, empty before Homestead.
 pub fn get_flair(block_number: u64) -> String {
-    if block_number > CANCUN_BLOCK_NUMBER {
-        " (post-cancun)".to_string()
-    } else if block_number > SHANGHAI_BLOCK_NUMBER {
-        " (post-shanghai)".to_string()
-    } else if block_number > MERGE_BLOCK_NUMBER {
-        " (post-merge)".to_string()
-    } else if block_number > LONDON_BLOCK_NUMBER {
-        " (post-london)".to_string()
-    } else if block_number > BERLIN_BLOCK_NUMBER {
-        " (post-berlin)".to_string()
-    } else if block_number > ISTANBUL_BLOCK_NUMBER {
-        " (post-istanbul)".to_string()
-    } else if block_number > CONSTANTINOPLE_BLOCK_NUMBER {
-        " (post-constantinople)".to_string()
-    } else if block_number > BYZANTIUM_BLOCK_NUMBER {
-        " (post-byzantium)".to_string()
-    } else if block_number > HOMESTEAD_BLOCK_NUMBER {
-        " (post-homestead)".to_string()
-    } else {
-        "".to_string()
+    match ExecutionFork::at_block(block_number) {
+        ExecutionFork::Frontier => "".to_string(),
+        fork => format!(" (post-{})", fork.name()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flair_starts_at_activation_block() {
+        for fork in &ExecutionFork::ALL[1..] {
+            let first = fork.activation_block();
+            let suffix = format!(" (post-{})", fork.name());
+            assert_eq!(get_flair(first), suffix);
+            assert_ne!(get_flair(first - 1), suffix);
+        }
+        assert_eq!(get_flair(HOMESTEAD_BLOCK_NUMBER - 1), "");
+        assert_eq!(get_flair(PRAGUE_BLOCK_NUMBER), " (post-prague)");
+        assert_eq!(get_flair(PRAGUE_BLOCK_NUMBER - 1), " (post-cancun)");
     }
 }
@@ -0,0 +1,295 @@
+use crate::schema::validate_document;
+use crate::vector::{discover, VectorError, VectorFile, VectorFormat, VectorKind};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LintRule {
+    Parse,
+    UnknownLocation,
+    Schema,
+    UnquotedHex,
+    MixedQuotes,
+    MissingProvenance,
+    JsonVector,
+}
+
+impl LintRule {
+    pub fn name(&self) -> &'static str {
+        match self {
+            LintRule::Parse => "parse",
+            LintRule::UnknownLocation => "unknown-location",
+            LintRule::Schema => "schema",
+            LintRule::UnquotedHex => "unquoted-hex",
+            LintRule::MixedQuotes => "mixed-quotes",
+            LintRule::MissingProvenance => "missing-provenance",
+            LintRule::JsonVector => "json-vector",
+        }
+    }
+
+    pub fn severity(&self) -> Severity {
+        match self {
+            LintRule::JsonVector | LintRule::UnquotedHex => Severity::Warning,
+            _ => Severity::Error,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LintIssue {
+    pub path: PathBuf,
+    pub line: Option<usize>,
+    pub rule: LintRule,
+    pub message: String,
+}
+
+impl LintIssue {
+    fn new(file: &VectorFile, line: Option<usize>, rule: LintRule, message: String) -> Self {
+        LintIssue {
+            path: file.relative.clone(),
+            line,
+            rule,
+            message,
+        }
+    }
+
+    pub fn severity(&self) -> Severity {
+        self.rule.severity()
+    }
+}
+
+impl fmt::Display for LintIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let level = match self.severity() {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        match self.line {
+            Some(line) => write!(f, "{}:{}: ", self.path.display(), line)?,
+            None => write!(f, "{}: ", self.path.display())?,
+        }
+        write!(f, "{}[{}]: {}", level, self.rule.name(), self.message)
+    }
+}
+
+/// Lints every vector file below `root`.
+pub fn lint_dir(root: &Path) -> Result<Vec<LintIssue>, VectorError> {
+    Ok(discover(root)?.iter().flat_map(lint_file).collect())
+}
+
+pub fn lint_file(file: &VectorFile) -> Vec<LintIssue> {
+    let mut issues = Vec::new();
+
+    match file.format {
+        VectorFormat::Json => issues.push(LintIssue::new(
+            file,
+            None,
+            LintRule::JsonVector,
+            "JSON vectors cannot carry provenance comments, prefer YAML".to_string(),
+        )),
+        VectorFormat::Yaml => {
+            issues.extend(check_unquoted_hex(file));
+            issues.extend(check_mixed_quotes(file));
+            issues.extend(check_provenance(file));
+        }
+    }
+
+    let Some(kind) = file.kind else {
+        issues.push(LintIssue::new(
+            file,
+            None,
+            LintRule::UnknownLocation,
+            "no vector category is defined for this location".to_string(),
+        ));
+        return issues;
+    };
+
+    match file.document() {
+        Ok(document) => {
+            if let Err(errors) = validate_document(kind, &document) {
+                issues.extend(errors.into_iter().map(|e| {
+                    LintIssue::new(file, None, LintRule::Schema, format!("{}: {}", kind, e))
+                }));
+            }
+        }
+        Err(e) => issues.push(LintIssue::new(file, None, LintRule::Parse, e.to_string())),
+    }
+
+    issues
+}
+
+/// Returns the scalar on a YAML line, i.e. the text after `key:` or `- `, if any.
+fn yaml_scalar(line: &str) -> Option<&str> {
+    let trimmed = line.trim_start();
+    if trimmed.is_empty() || trimmed.starts_with('#') {
+        return None;
+    }
+    let value = match trimmed.strip_prefix("- ") {
+        Some(item) => match split_key(item) {
+            Some((_, value)) => value,
+            None => item,
+        },
+        None => split_key(trimmed)?.1,
+    };
+    let value = value.trim();
+    (!value.is_empty()).then_some(value)
+}
+
+fn split_key(text: &str) -> Option<(&str, &str)> {
+    if text.starts_with(['"', '\'']) {
+        return None;
+    }
+    let index = text
+        .find(": ")
+        .or_else(|| text.strip_suffix(':').map(|t| t.len()))?;
+    Some((&text[..index], &text[index + 1..]))
+}
+
+fn check_unquoted_hex(file: &VectorFile) -> Vec<LintIssue> {
+    file.text
+        .lines()
+        .enumerate()
+        .filter_map(|(index, line)| {
+            let value = yaml_scalar(line)?;
+            value.starts_with("0x").then(|| {
+                LintIssue::new(
+                    file,
+                    Some(index + 1),
+                    LintRule::UnquotedHex,
+                    "hex values should be quoted, other YAML readers may take them as integers"
+                        .to_string(),
+                )
+            })
+        })
+        .collect()
+}
+
+fn check_mixed_quotes(file: &VectorFile) -> Vec<LintIssue> {
+    let mut double = Vec::new();
+    let mut single = Vec::new();
+    for (index, line) in file.text.lines().enumerate() {
+        match yaml_scalar(line).and_then(|value| value.chars().next()) {
+            Some('"') => double.push(index + 1),
+            Some('\'') => single.push(index + 1),
+            _ => {}
+        }
+    }
+    if double.is_empty() || single.is_empty() {
+        return Vec::new();
+    }
+
+    let (minority, style) = if single.len() < double.len() {
+        (single, "single")
+    } else {
+        (double, "double")
+    };
+    minority
+        .into_iter()
+        .map(|line| {
+            LintIssue::new(
+                file,
+                Some(line),
+                LintRule::MixedQuotes,
+                format!(
+                    "{} quotes used in a file that mostly uses the other style",
+                    style
+                ),
+            )
+        })
+        .collect()
+}
+
+fn check_provenance(file: &VectorFile) -> Vec<LintIssue> {
+    let has_comment = file
+        .text
+        .lines()
+        .any(|line| line.trim_start().starts_with('#'));
+    // Block proofs are named after the block they prove.
+    let named_after_block = matches!(
+        file.kind,
+        Some(VectorKind::BlockProofBellatrix | VectorKind::BlockProofCapella)
+    );
+    if has_comment || named_after_block {
+        return Vec::new();
+    }
+    vec![LintIssue::new(
+        file,
+        None,
+        LintRule::MissingProvenance,
+        "add a comment describing where the vector comes from".to_string(),
+    )]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vector::network_dir;
+
+    fn yaml_file(relative: &str, text: &str) -> VectorFile {
+        VectorFile {
+            path: PathBuf::from(relative),
+            relative: PathBuf::from(relative),
+            kind: VectorKind::from_relative_path(Path::new(relative)),
+            format: VectorFormat::Yaml,
+            text: text.to_string(),
+        }
+    }
+
+    fn rules(issues: &[LintIssue]) -> Vec<LintRule> {
+        issues.iter().map(|issue| issue.rule).collect()
+    }
+
+    #[test]
+    fn test_mainnet_vectors_have_no_errors() {
+        let issues = lint_dir(&network_dir("mainnet")).unwrap();
+        let errors: Vec<String> = issues
+            .iter()
+            .filter(|issue| issue.severity() == Severity::Error)
+            .map(|issue| issue.to_string())
+            .collect();
+        assert!(errors.is_empty(), "{:#?}", errors);
+    }
+
+    #[test]
+    fn test_unquoted_hex_is_flagged() {
+        let file = yaml_file(
+            "history/headers_with_proof/block_proofs_capella/beacon_block_proof-1.yaml",
+            "# block number: 1\nexecution_block_header: 0x00\nbeacon_block_body_proof:\n  - 0x01\n",
+        );
+        let issues = lint_file(&file);
+        let unquoted: Vec<_> = issues
+            .iter()
+            .filter(|issue| issue.rule == LintRule::UnquotedHex)
+            .map(|issue| issue.line)
+            .collect();
+        assert_eq!(unquoted, vec![Some(2), Some(4)]);
+        assert_eq!(LintRule::UnquotedHex.severity(), Severity::Warning);
+
+        // The published Capella proofs are unquoted and named after their block only.
+        let file = yaml_file(
+            "history/headers_with_proof/block_proofs_capella/beacon_block_proof-1.yaml",
+            "execution_block_header: 0x00\n",
+        );
+        let rules = rules(&lint_file(&file));
+        assert!(rules.contains(&LintRule::UnquotedHex));
+        assert!(!rules.contains(&LintRule::MissingProvenance));
+    }
+
+    #[test]
+    fn test_mixed_quotes_and_provenance() {
+        let file = yaml_file(
+            "history/bodies/1.yaml",
+            "content_key: \"0x02\"\ncontent_value: '0x08'\n",
+        );
+        let issues = lint_file(&file);
+        assert!(rules(&issues).contains(&LintRule::MixedQuotes));
+        assert!(rules(&issues).contains(&LintRule::MissingProvenance));
+        assert!(rules(&issues).contains(&LintRule::Schema));
+    }
+}
@@ -0,0 +1,300 @@
+use crate::content_key::content_id;
+use crate::secure_hash_verifier::file_hash;
+use crate::vector::{encode_hex, VectorFile};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// The name of the manifest file, written at the root of the directory it covers.
+pub const MANIFEST_FILE: &str = "MANIFEST.sha256";
+
+#[derive(Error, Debug)]
+pub enum ManifestError {
+    #[error("failed to read {path}: {source}")]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("{MANIFEST_FILE} line {line}: {message}")]
+    Parse { line: usize, message: String },
+}
+
+/// The hash of one file and the ids of the content keys in it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ManifestEntry {
+    pub sha256: [u8; 32],
+    /// Content ids by entry label, for YAML and JSON vectors.
+    pub content_ids: Vec<(String, [u8; 32])>,
+}
+
+/// The files below a directory by relative path, written in the `sha256sum`
+/// format with the content ids of each vector on comment lines below it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Manifest {
+    pub files: BTreeMap<String, ManifestEntry>,
+}
+
+/// A difference between a manifest and the files on disk.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ManifestChange {
+    Changed(String),
+    Added(String),
+    Removed(String),
+    ContentIdChanged { path: String, label: String },
+}
+
+impl fmt::Display for ManifestChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ManifestChange::Changed(path) => write!(f, "changed: {}", path),
+            ManifestChange::Added(path) => write!(f, "not in manifest: {}", path),
+            ManifestChange::Removed(path) => write!(f, "missing: {}", path),
+            ManifestChange::ContentIdChanged { path, label } => {
+                write!(f, "content id changed: {}#{}", path, label)
+            }
+        }
+    }
+}
+
+pub fn file_sha256(path: &Path) -> Result<[u8; 32], ManifestError> {
+    file_hash(path).map_err(|source| ManifestError::Io {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<(), ManifestError> {
+    let io_error = |source| ManifestError::Io {
+        path: dir.to_path_buf(),
+        source,
+    };
+    for entry in fs::read_dir(dir).map_err(io_error)? {
+        let path = entry.map_err(io_error)?.path();
+        if path.is_dir() {
+            collect_files(&path, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Relative paths always use `/`, so a manifest written on one platform checks on another.
+fn relative_name(root: &Path, path: &Path) -> String {
+    let relative = path.strip_prefix(root).unwrap_or(path);
+    relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Hashes every file below `root` except the manifest itself. Vectors that do
+/// not parse are still hashed, just without content ids.
+pub fn build(root: &Path) -> Result<Manifest, ManifestError> {
+    let mut paths = Vec::new();
+    collect_files(root, &mut paths)?;
+    let mut manifest = Manifest::default();
+    for path in paths {
+        let name = relative_name(root, &path);
+        if name == MANIFEST_FILE {
+            continue;
+        }
+        let mut entry = ManifestEntry {
+            sha256: file_sha256(&path)?,
+            content_ids: Vec::new(),
+        };
+        if matches!(
+            path.extension().and_then(|e| e.to_str()),
+            Some("yaml" | "yml" | "json")
+        ) {
+            let entries = VectorFile::load(root, &path).and_then(|file| file.content_entries());
+            if let Ok(entries) = entries {
+                entry.content_ids = entries
+                    .iter()
+                    .map(|entry| (entry.label.clone(), content_id(&entry.content_key)))
+                    .collect();
+            }
+        }
+        manifest.files.insert(name, entry);
+    }
+    Ok(manifest)
+}
+
+fn parse_hash(text: &str, line: usize) -> Result<[u8; 32], ManifestError> {
+    let parse_error = |message: String| ManifestError::Parse { line, message };
+    let bytes = hex::decode(text.strip_prefix("0x").unwrap_or(text))
+        .map_err(|e| parse_error(e.to_string()))?;
+    bytes
+        .try_into()
+        .map_err(|_| parse_error(format!("expected 32 bytes, got {}", text)))
+}
+
+impl Manifest {
+    pub fn load(root: &Path) -> Result<Manifest, ManifestError> {
+        let path = root.join(MANIFEST_FILE);
+        let text =
+            fs::read_to_string(&path).map_err(|source| ManifestError::Io { path, source })?;
+        Manifest::parse(&text)
+    }
+
+    pub fn parse(text: &str) -> Result<Manifest, ManifestError> {
+        let mut manifest = Manifest::default();
+        let mut current: Option<&mut ManifestEntry> = None;
+        for (index, line) in text.lines().enumerate() {
+            let line_number = index + 1;
+            if line.trim().is_empty() {
+                continue;
+            }
+            if let Some(comment) = line.strip_prefix('#') {
+                // Content id lines are `#   <id>  <label>`, anything else is a plain comment.
+                let Some(rest) = comment.strip_prefix("   ") else {
+                    continue;
+                };
+                let (id, label) = rest.split_once("  ").unwrap_or((rest, ""));
+                let entry = current.as_deref_mut().ok_or(ManifestError::Parse {
+                    line: line_number,
+                    message: "content id before any file".to_string(),
+                })?;
+                entry
+                    .content_ids
+                    .push((label.to_string(), parse_hash(id, line_number)?));
+                continue;
+            }
+            let (hash, name) = line.split_once("  ").ok_or(ManifestError::Parse {
+                line: line_number,
+                message: "expected `<sha256>  <path>`".to_string(),
+            })?;
+            let entry = ManifestEntry {
+                sha256: parse_hash(hash, line_number)?,
+                content_ids: Vec::new(),
+            };
+            current = Some(manifest.files.entry(name.to_string()).or_insert(entry));
+        }
+        Ok(manifest)
+    }
+
+    pub fn render(&self) -> String {
+        let mut out =
+            String::from("# sha256 of every file; check with `sha256sum -c MANIFEST.sha256`\n");
+        for (name, entry) in &self.files {
+            out.push_str(&format!("{}  {}\n", hex::encode(entry.sha256), name));
+            for (label, id) in &entry.content_ids {
+                match label.as_str() {
+                    "" => out.push_str(&format!("#   {}\n", encode_hex(id))),
+                    label => out.push_str(&format!("#   {}  {}\n", encode_hex(id), label)),
+                }
+            }
+        }
+        out
+    }
+
+    pub fn write(&self, root: &Path) -> Result<PathBuf, ManifestError> {
+        let path = root.join(MANIFEST_FILE);
+        fs::write(&path, self.render()).map_err(|source| ManifestError::Io {
+            path: path.clone(),
+            source,
+        })?;
+        Ok(path)
+    }
+
+    /// Lists how `actual` differs from this manifest, by path.
+    pub fn compare(&self, actual: &Manifest) -> Vec<ManifestChange> {
+        let mut changes = Vec::new();
+        for (name, expected) in &self.files {
+            let Some(found) = actual.files.get(name) else {
+                changes.push(ManifestChange::Removed(name.clone()));
+                continue;
+            };
+            if found.sha256 == expected.sha256 {
+                continue;
+            }
+            changes.push(ManifestChange::Changed(name.clone()));
+            let expected_ids: BTreeMap<_, _> = expected.content_ids.iter().cloned().collect();
+            for (label, id) in &found.content_ids {
+                if expected_ids
+                    .get(label)
+                    .is_some_and(|expected| expected != id)
+                {
+                    changes.push(ManifestChange::ContentIdChanged {
+                        path: name.clone(),
+                        label: label.clone(),
+                    });
+                }
+            }
+        }
+        for name in actual.files.keys() {
+            if !self.files.contains_key(name) {
+                changes.push(ManifestChange::Added(name.clone()));
+            }
+        }
+        changes
+    }
+}
+
+/// Checks the files below `root` against its manifest.
+pub fn verify(root: &Path) -> Result<Vec<ManifestChange>, ManifestError> {
+    Ok(Manifest::load(root)?.compare(&build(root)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vector::spec_tests_dir;
+
+    #[test]
+    fn test_manifest_is_up_to_date() {
+        let root = spec_tests_dir();
+        let changes = verify(&root).unwrap();
+        assert!(
+            changes.is_empty(),
+            "run `portal-vector manifest` to refresh {:?}",
+            changes
+        );
+
+        let manifest = Manifest::load(&root).unwrap();
+        let fixture = &manifest.files["mainnet/history/accumulator/finished_accumulator.ssz"];
+        assert!(fixture.content_ids.is_empty());
+        let body = &manifest.files["mainnet/history/bodies/14764013.yaml"];
+        assert_eq!(body.content_ids.len(), 1);
+        let text = fs::read_to_string(root.join(MANIFEST_FILE)).unwrap();
+        assert_eq!(manifest.render(), text);
+    }
+
+    #[test]
+    fn test_verify_reports_edits() {
+        let scratch = tempfile::tempdir().unwrap();
+        let dir = scratch.path();
+        fs::create_dir_all(dir.join("history")).unwrap();
+        let key = "0x01720704f3aa11c53cf344ea069db95cecb81ad7453c8f276b2a1062979611f09c";
+        fs::write(
+            dir.join("history/body.yaml"),
+            format!("content_key: \"{}\"\n", key),
+        )
+        .unwrap();
+        fs::write(dir.join("history/fixture.ssz"), [1u8, 2, 3]).unwrap();
+        fs::write(dir.join("stale.txt"), "old").unwrap();
+        build(dir).unwrap().write(dir).unwrap();
+        assert_eq!(verify(dir).unwrap(), vec![]);
+
+        fs::write(dir.join("history/fixture.ssz"), [1u8, 2, 4]).unwrap();
+        fs::write(dir.join("history/body.yaml"), "content_key: \"0x00\"\n").unwrap();
+        fs::remove_file(dir.join("stale.txt")).unwrap();
+        fs::write(dir.join("new.txt"), "new").unwrap();
+        assert_eq!(
+            verify(dir).unwrap(),
+            vec![
+                ManifestChange::Changed("history/body.yaml".to_string()),
+                ManifestChange::ContentIdChanged {
+                    path: "history/body.yaml".to_string(),
+                    label: String::new(),
+                },
+                ManifestChange::Changed("history/fixture.ssz".to_string()),
+                ManifestChange::Removed("stale.txt".to_string()),
+                ManifestChange::Added("new.txt".to_string()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,576 @@
+use crate::beacon::BeaconFork;
+use crate::block_proof::BlockFork;
+use crate::ssz::hash_pair;
+use crate::vector::decode_hex;
+use crate::ExecutionFork;
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+pub const SLOTS_PER_EPOCH: u64 = 32;
+pub const DOMAIN_BEACON_PROPOSER: [u8; 4] = [0x00, 0x00, 0x00, 0x00];
+pub const DOMAIN_SYNC_COMMITTEE: [u8; 4] = [0x07, 0x00, 0x00, 0x00];
+
+#[derive(Error, Debug)]
+pub enum NetworkError {
+    #[error("failed to read {path}: {source}")]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("missing {0} in chain config")]
+    Missing(&'static str),
+    #[error("invalid {key} in chain config: {value}")]
+    Invalid { key: &'static str, value: String },
+    #[error("invalid chain config: {0}")]
+    Parse(String),
+}
+
+/// When a consensus fork activates and the version it signs with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ForkSchedule {
+    pub version: [u8; 4],
+    pub epoch: u64,
+}
+
+impl ForkSchedule {
+    pub fn slot(&self) -> u64 {
+        self.epoch.saturating_mul(SLOTS_PER_EPOCH)
+    }
+}
+
+/// `hash_tree_root` of the `ForkData` container.
+pub fn compute_fork_data_root(
+    fork_version: [u8; 4],
+    genesis_validators_root: &[u8; 32],
+) -> [u8; 32] {
+    let mut version = [0u8; 32];
+    version[..4].copy_from_slice(&fork_version);
+    hash_pair(&version, genesis_validators_root)
+}
+
+pub fn compute_fork_digest(fork_version: [u8; 4], genesis_validators_root: &[u8; 32]) -> [u8; 4] {
+    compute_fork_data_root(fork_version, genesis_validators_root)[..4]
+        .try_into()
+        .unwrap()
+}
+
+/// The domain type followed by the first 28 bytes of the fork data root.
+pub fn compute_domain(
+    domain_type: [u8; 4],
+    fork_version: [u8; 4],
+    genesis_validators_root: &[u8; 32],
+) -> [u8; 32] {
+    let mut domain = [0u8; 32];
+    domain[..4].copy_from_slice(&domain_type);
+    domain[4..]
+        .copy_from_slice(&compute_fork_data_root(fork_version, genesis_validators_root)[..28]);
+    domain
+}
+
+/// `hash_tree_root` of the `SigningData` container, the message a BLS signature covers.
+pub fn compute_signing_root(object_root: &[u8; 32], domain: &[u8; 32]) -> [u8; 32] {
+    hash_pair(object_root, domain)
+}
+
+/// The keys of a consensus `config.yaml` a `Network` is read from. Values are
+/// kept as written, since fork versions are unquoted hex and the terminal total
+/// difficulty does not fit in a u64.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+struct ChainConfig {
+    config_name: Option<String>,
+    genesis_validators_root: Option<String>,
+    genesis_fork_version: Option<String>,
+    altair_fork_version: Option<String>,
+    altair_fork_epoch: Option<String>,
+    bellatrix_fork_version: Option<String>,
+    bellatrix_fork_epoch: Option<String>,
+    capella_fork_version: Option<String>,
+    capella_fork_epoch: Option<String>,
+    deneb_fork_version: Option<String>,
+    deneb_fork_epoch: Option<String>,
+    electra_fork_version: Option<String>,
+    electra_fork_epoch: Option<String>,
+    terminal_total_difficulty: Option<String>,
+    merge_block_number: Option<String>,
+    shanghai_block_number: Option<String>,
+    cancun_block_number: Option<String>,
+    prague_block_number: Option<String>,
+    historical_accumulator_root: Option<String>,
+}
+
+/// The chain parameters vectors of a network are decoded and validated against.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Network {
+    pub name: String,
+    pub genesis_validators_root: [u8; 32],
+    pub genesis_fork_version: [u8; 4],
+    pub altair: ForkSchedule,
+    pub bellatrix: ForkSchedule,
+    pub capella: ForkSchedule,
+    pub deneb: ForkSchedule,
+    pub electra: ForkSchedule,
+    pub terminal_total_difficulty: u128,
+    /// First block of each execution fork, in `ExecutionFork::ALL` order.
+    pub execution_forks: [u64; 11],
+    /// Root of the pre-merge accumulator, for networks with Portal history.
+    pub accumulator_root: Option<[u8; 32]>,
+}
+
+fn root(text: &str) -> [u8; 32] {
+    decode_hex(text).unwrap().try_into().unwrap()
+}
+
+fn schedule(version: u32, epoch: u64) -> ForkSchedule {
+    ForkSchedule {
+        version: version.to_be_bytes(),
+        epoch,
+    }
+}
+
+/// Activation blocks of a network that merged at `merge` with every earlier fork at genesis.
+fn post_london(merge: u64, shanghai: u64, cancun: u64, prague: u64) -> [u64; 11] {
+    [0, 0, 0, 0, 0, 0, 0, merge, shanghai, cancun, prague]
+}
+
+impl Default for Network {
+    fn default() -> Self {
+        Network::mainnet()
+    }
+}
+
+impl Network {
+    pub fn mainnet() -> Network {
+        Network {
+            name: "mainnet".to_string(),
+            genesis_validators_root: root(
+                "0x4b363db94e286120d76eb905340fdd4e54bfe9f06bf33ff6cf5ad27f511bfe95",
+            ),
+            genesis_fork_version: [0, 0, 0, 0],
+            altair: schedule(0x01000000, 74240),
+            bellatrix: schedule(0x02000000, 144896),
+            capella: schedule(0x03000000, 194048),
+            deneb: schedule(0x04000000, 269568),
+            electra: schedule(0x05000000, 364032),
+            terminal_total_difficulty: crate::TERMINAL_TOTAL_DIFFICULTY,
+            execution_forks: ExecutionFork::ALL.map(|fork| fork.activation_block()),
+            accumulator_root: Some(root(
+                "0x8eac399e24480dce3cfe06f4bdecba51c6e5d0c46200e3e8611a0b44a3a69ff9",
+            )),
+        }
+    }
+
+    pub fn sepolia() -> Network {
+        Network {
+            name: "sepolia".to_string(),
+            genesis_validators_root: root(
+                "0xd8ea171f3c94aea21ebc42a1ed61052acf3f9209c00e4efbaaddac09ed9b8078",
+            ),
+            genesis_fork_version: [0x90, 0x00, 0x00, 0x69],
+            altair: schedule(0x90000070, 50),
+            bellatrix: schedule(0x90000071, 100),
+            capella: schedule(0x90000072, 56832),
+            deneb: schedule(0x90000073, 132608),
+            electra: schedule(0x90000074, 222464),
+            terminal_total_difficulty: 17_000_000_000_000_000,
+            execution_forks: post_london(1450409, 2990908, 5187023, 7836331),
+            accumulator_root: None,
+        }
+    }
+
+    pub fn holesky() -> Network {
+        Network {
+            name: "holesky".to_string(),
+            genesis_validators_root: root(
+                "0x9143aa7c615a7f7115e2b6aac319c03529df8242ae705fba9df39b79c59fa8b1",
+            ),
+            genesis_fork_version: [0x01, 0x01, 0x70, 0x00],
+            altair: schedule(0x02017000, 0),
+            bellatrix: schedule(0x03017000, 0),
+            capella: schedule(0x04017000, 256),
+            deneb: schedule(0x05017000, 29696),
+            electra: schedule(0x06017000, 115968),
+            terminal_total_difficulty: 0,
+            execution_forks: post_london(0, 6698, 894733, 3419703),
+            accumulator_root: None,
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Network> {
+        match name {
+            "mainnet" => Some(Network::mainnet()),
+            "sepolia" => Some(Network::sepolia()),
+            "holesky" => Some(Network::holesky()),
+            _ => None,
+        }
+    }
+
+    /// A known network by name, or a custom one from a chain config file.
+    pub fn resolve(name_or_path: &str) -> Result<Network, NetworkError> {
+        match Network::from_name(name_or_path) {
+            Some(network) => Ok(network),
+            None => Network::load(Path::new(name_or_path)),
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Network, NetworkError> {
+        let text = fs::read_to_string(path).map_err(|source| NetworkError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        Network::from_config(&text)
+    }
+
+    /// Parses a consensus `config.yaml`. Portal needs a few values it does not
+    /// carry, so `GENESIS_VALIDATORS_ROOT` is required as well, and
+    /// `MERGE_BLOCK_NUMBER`, `SHANGHAI_BLOCK_NUMBER`, `CANCUN_BLOCK_NUMBER`,
+    /// `PRAGUE_BLOCK_NUMBER` and `HISTORICAL_ACCUMULATOR_ROOT` are read when set.
+    pub fn from_config(text: &str) -> Result<Network, NetworkError> {
+        let config: ChainConfig =
+            serde_yaml::from_str(text).map_err(|e| NetworkError::Parse(e.to_string()))?;
+        let text = |key: &'static str, value: &Option<String>| {
+            value.clone().ok_or(NetworkError::Missing(key))
+        };
+        let invalid = |key: &'static str, value: &Option<String>| NetworkError::Invalid {
+            key,
+            value: value.clone().unwrap_or_default(),
+        };
+        let number = |key: &'static str, value: &Option<String>| {
+            value
+                .as_deref()
+                .map(|number| number.parse::<u64>().map_err(|_| invalid(key, value)))
+                .transpose()
+        };
+        let bytes = |key: &'static str, value: &Option<String>| {
+            decode_hex(&text(key, value)?).map_err(|_| invalid(key, value))
+        };
+        let version =
+            |key: &'static str, value: &Option<String>| -> Result<[u8; 4], NetworkError> {
+                bytes(key, value)?
+                    .try_into()
+                    .map_err(|_| invalid(key, value))
+            };
+        let root = |key: &'static str, value: &Option<String>| -> Result<[u8; 32], NetworkError> {
+            bytes(key, value)?
+                .try_into()
+                .map_err(|_| invalid(key, value))
+        };
+        let fork = |name: &'static str,
+                    version_value: &Option<String>,
+                    epoch: &'static str,
+                    epoch_value: &Option<String>|
+         -> Result<ForkSchedule, NetworkError> {
+            Ok(ForkSchedule {
+                version: version(name, version_value)?,
+                epoch: number(epoch, epoch_value)?.ok_or(NetworkError::Missing(epoch))?,
+            })
+        };
+        let never = u64::MAX;
+
+        Ok(Network {
+            name: text("CONFIG_NAME", &config.config_name)?,
+            genesis_validators_root: root(
+                "GENESIS_VALIDATORS_ROOT",
+                &config.genesis_validators_root,
+            )?,
+            genesis_fork_version: version("GENESIS_FORK_VERSION", &config.genesis_fork_version)?,
+            altair: fork(
+                "ALTAIR_FORK_VERSION",
+                &config.altair_fork_version,
+                "ALTAIR_FORK_EPOCH",
+                &config.altair_fork_epoch,
+            )?,
+            bellatrix: fork(
+                "BELLATRIX_FORK_VERSION",
+                &config.bellatrix_fork_version,
+                "BELLATRIX_FORK_EPOCH",
+                &config.bellatrix_fork_epoch,
+            )?,
+            capella: fork(
+                "CAPELLA_FORK_VERSION",
+                &config.capella_fork_version,
+                "CAPELLA_FORK_EPOCH",
+                &config.capella_fork_epoch,
+            )?,
+            deneb: fork(
+                "DENEB_FORK_VERSION",
+                &config.deneb_fork_version,
+                "DENEB_FORK_EPOCH",
+                &config.deneb_fork_epoch,
+            )?,
+            electra: fork(
+                "ELECTRA_FORK_VERSION",
+                &config.electra_fork_version,
+                "ELECTRA_FORK_EPOCH",
+                &config.electra_fork_epoch,
+            )?,
+            terminal_total_difficulty: config
+                .terminal_total_difficulty
+                .as_deref()
+                .map(|value| {
+                    value.parse().map_err(|_| {
+                        invalid(
+                            "TERMINAL_TOTAL_DIFFICULTY",
+                            &config.terminal_total_difficulty,
+                        )
+                    })
+                })
+                .transpose()?
+                .unwrap_or(0),
+            execution_forks: post_london(
+                number("MERGE_BLOCK_NUMBER", &config.merge_block_number)?.unwrap_or(0),
+                number("SHANGHAI_BLOCK_NUMBER", &config.shanghai_block_number)?.unwrap_or(never),
+                number("CANCUN_BLOCK_NUMBER", &config.cancun_block_number)?.unwrap_or(never),
+                number("PRAGUE_BLOCK_NUMBER", &config.prague_block_number)?.unwrap_or(never),
+            ),
+            accumulator_root: config
+                .historical_accumulator_root
+                .as_ref()
+                .map(|_| {
+                    root(
+                        "HISTORICAL_ACCUMULATOR_ROOT",
+                        &config.historical_accumulator_root,
+                    )
+                })
+                .transpose()?,
+        })
+    }
+
+    pub fn activation_block(&self, fork: ExecutionFork) -> u64 {
+        self.execution_forks[fork as usize]
+    }
+
+    pub fn execution_fork(&self, block_number: u64) -> ExecutionFork {
+        ExecutionFork::active_at(block_number, |fork| self.activation_block(fork))
+    }
+
+    pub fn merge_block(&self) -> u64 {
+        self.activation_block(ExecutionFork::Merge)
+    }
+
+    pub fn schedule(&self, fork: BeaconFork) -> &ForkSchedule {
+        match fork {
+            BeaconFork::Capella => &self.capella,
+            BeaconFork::Deneb => &self.deneb,
+            BeaconFork::Electra => &self.electra,
+        }
+    }
+
+    /// The forks from Altair on, in activation order.
+    pub fn forks(&self) -> [(&'static str, &ForkSchedule); 5] {
+        [
+            ("altair", &self.altair),
+            ("bellatrix", &self.bellatrix),
+            ("capella", &self.capella),
+            ("deneb", &self.deneb),
+            ("electra", &self.electra),
+        ]
+    }
+
+    /// `compute_fork_version` of the consensus specs.
+    pub fn fork_version(&self, epoch: u64) -> [u8; 4] {
+        self.forks()
+            .into_iter()
+            .rev()
+            .find(|(_, schedule)| epoch >= schedule.epoch)
+            .map_or(self.genesis_fork_version, |(_, schedule)| schedule.version)
+    }
+
+    pub fn fork_digest(&self, fork: BeaconFork) -> [u8; 4] {
+        compute_fork_digest(self.schedule(fork).version, &self.genesis_validators_root)
+    }
+
+    /// The digests prefixing beacon content values, one per light client fork.
+    pub fn fork_digests(&self) -> [([u8; 4], BeaconFork); 3] {
+        BeaconFork::ALL.map(|fork| (self.fork_digest(fork), fork))
+    }
+
+    /// The digest a value from `epoch` is prefixed with.
+    pub fn fork_digest_at(&self, epoch: u64) -> [u8; 4] {
+        compute_fork_digest(self.fork_version(epoch), &self.genesis_validators_root)
+    }
+
+    /// The domain signatures of `domain_type` made in `epoch` are checked against.
+    pub fn domain(&self, domain_type: [u8; 4], epoch: u64) -> [u8; 32] {
+        compute_domain(
+            domain_type,
+            self.fork_version(epoch),
+            &self.genesis_validators_root,
+        )
+    }
+
+    /// The light client fork a beacon content value's digest prefix names.
+    pub fn beacon_fork(&self, digest: [u8; 4]) -> Option<BeaconFork> {
+        BeaconFork::ALL
+            .into_iter()
+            .find(|fork| self.fork_digest(*fork) == digest)
+    }
+
+    /// The body layout of a beacon block, `None` before Bellatrix.
+    pub fn block_fork(&self, slot: u64) -> Option<BlockFork> {
+        [
+            (&self.electra, BlockFork::Electra),
+            (&self.deneb, BlockFork::Deneb),
+            (&self.capella, BlockFork::Capella),
+            (&self.bellatrix, BlockFork::Bellatrix),
+        ]
+        .into_iter()
+        .find(|(schedule, _)| slot >= schedule.slot())
+        .map(|(_, fork)| fork)
+    }
+
+    pub fn capella_slot(&self) -> u64 {
+        self.capella.slot()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DEVNET_CONFIG: &str = r#"
+# Extends the mainnet preset
+PRESET_BASE: 'mainnet'
+CONFIG_NAME: 'portal-devnet-0'
+
+TERMINAL_TOTAL_DIFFICULTY: 0
+GENESIS_VALIDATORS_ROOT: 0x83431ec7fcf92cfc44947fc0418e831c25e1d0806590231c439830db7ad54fda
+GENESIS_FORK_VERSION: 0x10000038
+ALTAIR_FORK_VERSION: 0x20000038
+ALTAIR_FORK_EPOCH: 0
+BELLATRIX_FORK_VERSION: 0x30000038
+BELLATRIX_FORK_EPOCH: 0
+CAPELLA_FORK_VERSION: 0x40000038
+CAPELLA_FORK_EPOCH: 0
+DENEB_FORK_VERSION: 0x50000038
+DENEB_FORK_EPOCH: 4
+ELECTRA_FORK_VERSION: 0x60000038
+ELECTRA_FORK_EPOCH: 18446744073709551615
+SHANGHAI_BLOCK_NUMBER: 0
+CANCUN_BLOCK_NUMBER: 120 # first block of epoch 4
+"#;
+
+    #[test]
+    fn test_mainnet_matches_constants() {
+        let mainnet = Network::mainnet();
+        assert_eq!(
+            mainnet.fork_digests(),
+            [
+                ([0xbb, 0xa4, 0xda, 0x96], BeaconFork::Capella),
+                ([0x6a, 0x95, 0xa1, 0xa9], BeaconFork::Deneb),
+                ([0xad, 0x53, 0x2c, 0xeb], BeaconFork::Electra),
+            ]
+        );
+        for (digest, fork) in mainnet.fork_digests() {
+            assert_eq!(mainnet.beacon_fork(digest), Some(fork));
+        }
+        for fork in ExecutionFork::ALL {
+            assert_eq!(mainnet.activation_block(fork), fork.activation_block());
+        }
+        assert_eq!(mainnet.execution_fork(15537393), ExecutionFork::London);
+        assert_eq!(mainnet.execution_fork(15537394), ExecutionFork::Merge);
+        assert_eq!(mainnet.capella_slot(), 6209536);
+        assert_eq!(mainnet.block_fork(11649024), Some(BlockFork::Electra));
+        assert_eq!(mainnet.block_fork(4636671), None);
+    }
+
+    #[test]
+    fn test_mainnet_fork_versions() {
+        let mainnet = Network::mainnet();
+        let expected = [
+            ("altair", [0x01, 0, 0, 0], [0xaf, 0xca, 0xab, 0xa0]),
+            ("bellatrix", [0x02, 0, 0, 0], [0x4a, 0x26, 0xc5, 0x8b]),
+            ("capella", [0x03, 0, 0, 0], [0xbb, 0xa4, 0xda, 0x96]),
+            ("deneb", [0x04, 0, 0, 0], [0x6a, 0x95, 0xa1, 0xa9]),
+            ("electra", [0x05, 0, 0, 0], [0xad, 0x53, 0x2c, 0xeb]),
+        ];
+        for ((name, version, digest), (fork, schedule)) in expected.into_iter().zip(mainnet.forks())
+        {
+            assert_eq!((name, version), (fork, schedule.version));
+            assert_eq!(
+                compute_fork_digest(version, &mainnet.genesis_validators_root),
+                digest
+            );
+            assert_eq!(mainnet.fork_digest_at(schedule.epoch), digest);
+        }
+        assert_eq!(mainnet.fork_version(74239), [0, 0, 0, 0]);
+        assert_eq!(mainnet.fork_digest_at(0), [0xb5, 0x30, 0x3f, 0x2a]);
+        assert_eq!(mainnet.fork_version(269567), [0x03, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_domain_and_signing_root() {
+        let mainnet = Network::mainnet();
+        let domain = mainnet.domain(DOMAIN_SYNC_COMMITTEE, 269568);
+        let fork_data_root =
+            compute_fork_data_root([0x04, 0x00, 0x00, 0x00], &mainnet.genesis_validators_root);
+        assert_eq!(domain[..4], DOMAIN_SYNC_COMMITTEE);
+        assert_eq!(domain[4..], fork_data_root[..28]);
+        assert_eq!(domain[4..8], [0x6a, 0x95, 0xa1, 0xa9]);
+        assert_ne!(domain, mainnet.domain(DOMAIN_BEACON_PROPOSER, 269568));
+        assert_ne!(
+            compute_signing_root(&[0; 32], &domain),
+            compute_signing_root(&[0; 32], &mainnet.domain(DOMAIN_SYNC_COMMITTEE, 269567))
+        );
+    }
+
+    #[test]
+    fn test_networks_differ() {
+        let sepolia = Network::sepolia();
+        let holesky = Network::holesky();
+        let digest = Network::mainnet().fork_digest(BeaconFork::Deneb);
+        assert_eq!(sepolia.beacon_fork(digest), None);
+        assert_ne!(
+            sepolia.fork_digest(BeaconFork::Deneb),
+            holesky.fork_digest(BeaconFork::Deneb)
+        );
+        assert_eq!(sepolia.execution_fork(15537394), ExecutionFork::Prague);
+        assert_eq!(holesky.execution_fork(0), ExecutionFork::Merge);
+        assert_eq!(Network::from_name("holesky"), Some(holesky));
+        assert_eq!(Network::from_name("goerli"), None);
+    }
+
+    #[test]
+    fn test_custom_config() {
+        let devnet = Network::from_config(DEVNET_CONFIG).unwrap();
+        assert_eq!(devnet.name, "portal-devnet-0");
+        assert_eq!(devnet.deneb.slot(), 128);
+        assert_eq!(devnet.electra.slot(), u64::MAX);
+        assert_eq!(devnet.block_fork(127), Some(BlockFork::Capella));
+        assert_eq!(devnet.block_fork(128), Some(BlockFork::Deneb));
+        assert_eq!(devnet.execution_fork(119), ExecutionFork::Shanghai);
+        assert_eq!(devnet.execution_fork(120), ExecutionFork::Cancun);
+        assert_eq!(devnet.accumulator_root, None);
+
+        // Quoted values keep a '#', and comments may follow them.
+        let quoted = DEVNET_CONFIG
+            .replace("'portal-devnet-0'", "'portal-devnet-#0' # the first one")
+            .replace(
+                "DENEB_FORK_VERSION: 0x50000038",
+                "DENEB_FORK_VERSION: \"0x50000038\" # quoted",
+            );
+        let quoted = Network::from_config(&quoted).unwrap();
+        assert_eq!(quoted.name, "portal-devnet-#0");
+        assert_eq!(quoted.deneb, devnet.deneb);
+        assert!(matches!(
+            Network::from_config("CONFIG_NAME: [unclosed"),
+            Err(NetworkError::Parse(_))
+        ));
+
+        let missing = DEVNET_CONFIG.replace("DENEB_FORK_EPOCH: 4\n", "");
+        assert!(matches!(
+            Network::from_config(&missing),
+            Err(NetworkError::Missing("DENEB_FORK_EPOCH"))
+        ));
+        let invalid = DEVNET_CONFIG.replace("0x50000038", "0x5000");
+        assert!(matches!(
+            Network::from_config(&invalid),
+            Err(NetworkError::Invalid {
+                key: "DENEB_FORK_VERSION",
+                ..
+            })
+        ));
+    }
+}
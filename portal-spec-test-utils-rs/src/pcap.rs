@@ -0,0 +1,547 @@
+use pnet_packet::ethernet::{EtherTypes, EthernetPacket};
+use pnet_packet::ip::IpNextHeaderProtocols;
+use pnet_packet::ipv4::Ipv4Packet;
+use pnet_packet::ipv6::Ipv6Packet;
+use pnet_packet::sll::SLLPacket;
+use pnet_packet::sll2::SLL2Packet;
+use pnet_packet::udp::UdpPacket;
+use pnet_packet::vlan::VlanPacket;
+use pnet_packet::Packet;
+use std::fmt;
+use std::fs;
+use std::net::{IpAddr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use thiserror::Error;
+
+const PCAP_MICROS: u32 = 0xa1b2_c3d4;
+const PCAP_NANOS: u32 = 0xa1b2_3c4d;
+const PCAPNG_SECTION: u32 = 0x0a0d_0d0a;
+const PCAPNG_BYTE_ORDER: u32 = 0x1a2b_3c4d;
+
+pub const LINKTYPE_NULL: u32 = 0;
+pub const LINKTYPE_ETHERNET: u32 = 1;
+pub const LINKTYPE_RAW: u32 = 101;
+pub const LINKTYPE_LINUX_SLL: u32 = 113;
+pub const LINKTYPE_IPV4: u32 = 228;
+pub const LINKTYPE_IPV6: u32 = 229;
+pub const LINKTYPE_LINUX_SLL2: u32 = 276;
+
+const UDP: u8 = 17;
+
+#[derive(Error, Debug)]
+pub enum CaptureError {
+    #[error("failed to read {path}: {source}")]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("not a pcap or pcapng file, magic 0x{0:08x}")]
+    UnknownFormat(u32),
+    #[error("capture truncated at byte {0}")]
+    Truncated(usize),
+    #[error("unsupported link type {0}")]
+    UnsupportedLinkType(u32),
+}
+
+/// A UDP datagram found in a capture.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Datagram {
+    /// Time since the Unix epoch.
+    pub timestamp: Duration,
+    pub source: SocketAddr,
+    pub destination: SocketAddr,
+    pub payload: Vec<u8>,
+}
+
+/// A link-layer frame of a capture.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Frame<'a> {
+    /// Time since the Unix epoch.
+    pub timestamp: Duration,
+    /// The `LINKTYPE_*` of the interface the frame was captured on.
+    pub link_type: u32,
+    pub data: &'a [u8],
+}
+
+/// Reads the bytes of a capture file in either byte order.
+struct Reader<'a> {
+    data: &'a [u8],
+    big_endian: bool,
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&self, at: usize, len: usize) -> Result<&'a [u8], CaptureError> {
+        self.data
+            .get(at..at + len)
+            .ok_or(CaptureError::Truncated(self.data.len().min(at)))
+    }
+
+    fn u16(&self, at: usize) -> Result<u16, CaptureError> {
+        let bytes = self.bytes(at, 2)?.try_into().unwrap();
+        Ok(if self.big_endian {
+            u16::from_be_bytes(bytes)
+        } else {
+            u16::from_le_bytes(bytes)
+        })
+    }
+
+    fn u32(&self, at: usize) -> Result<u32, CaptureError> {
+        let bytes = self.bytes(at, 4)?.try_into().unwrap();
+        Ok(if self.big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        })
+    }
+}
+
+/// What a capture held, reported after its datagrams are dissected.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Summary {
+    pub frames: usize,
+    pub datagrams: usize,
+    /// Time between the first and the last frame.
+    pub duration: Duration,
+}
+
+impl Summary {
+    pub fn new(frames: &[Frame], datagrams: &[Datagram]) -> Summary {
+        let duration = match (frames.first(), frames.last()) {
+            (Some(first), Some(last)) => last.timestamp.saturating_sub(first.timestamp),
+            _ => Duration::ZERO,
+        };
+        Summary {
+            frames: frames.len(),
+            datagrams: datagrams.len(),
+            duration,
+        }
+    }
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} frame(s), {} UDP datagram(s) over {:.6}s",
+            self.frames,
+            self.datagrams,
+            self.duration.as_secs_f64()
+        )
+    }
+}
+
+pub fn read(path: &Path) -> Result<Vec<u8>, CaptureError> {
+    fs::read(path).map_err(|source| CaptureError::Io {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+/// Extracts the UDP datagrams of a pcap or pcapng capture. Other frames,
+/// including IP fragments past the first, are skipped.
+pub fn parse(data: &[u8]) -> Result<Vec<Datagram>, CaptureError> {
+    datagrams(&frames(data)?)
+}
+
+/// The UDP datagrams carried by `frames`.
+pub fn datagrams(frames: &[Frame]) -> Result<Vec<Datagram>, CaptureError> {
+    let mut datagrams = Vec::new();
+    for frame in frames {
+        datagrams.extend(udp_in_frame(frame)?);
+    }
+    Ok(datagrams)
+}
+
+/// The frames of a pcap or pcapng capture, in capture order.
+pub fn frames(data: &[u8]) -> Result<Vec<Frame<'_>>, CaptureError> {
+    let magic = data
+        .get(..4)
+        .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()))
+        .ok_or(CaptureError::Truncated(data.len()))?;
+    match magic {
+        PCAPNG_SECTION => parse_pcapng(data),
+        _ if [PCAP_MICROS, PCAP_NANOS].contains(&magic) => parse_pcap(data, false),
+        _ if [PCAP_MICROS, PCAP_NANOS].contains(&magic.swap_bytes()) => parse_pcap(data, true),
+        _ => Err(CaptureError::UnknownFormat(magic)),
+    }
+}
+
+fn parse_pcap(data: &[u8], big_endian: bool) -> Result<Vec<Frame<'_>>, CaptureError> {
+    let reader = Reader { data, big_endian };
+    let nanos = reader.u32(0)? == PCAP_NANOS;
+    let link_type = reader.u32(20)? & 0x0fff_ffff;
+    let mut frames = Vec::new();
+    let mut at = 24;
+    while at < data.len() {
+        let seconds = reader.u32(at)? as u64;
+        let fraction = reader.u32(at + 4)?;
+        let captured = reader.u32(at + 8)? as usize;
+        let frame = reader.bytes(at + 16, captured)?;
+        let timestamp = Duration::from_secs(seconds)
+            + if nanos {
+                Duration::from_nanos(fraction as u64)
+            } else {
+                Duration::from_micros(fraction as u64)
+            };
+        frames.push(Frame {
+            timestamp,
+            link_type,
+            data: frame,
+        });
+        at += 16 + captured;
+    }
+    Ok(frames)
+}
+
+fn parse_pcapng(data: &[u8]) -> Result<Vec<Frame<'_>>, CaptureError> {
+    let mut reader = Reader {
+        data,
+        big_endian: false,
+    };
+    // Link type and timestamp units per second of each interface in the section.
+    let mut interfaces: Vec<(u32, u64)> = Vec::new();
+    let mut frames = Vec::new();
+    let mut at = 0;
+    while at < data.len() {
+        if reader.u32(at)? == PCAPNG_SECTION {
+            let order = reader.bytes(at + 8, 4)?;
+            reader.big_endian = order == PCAPNG_BYTE_ORDER.to_be_bytes();
+            interfaces.clear();
+        }
+        let block_type = reader.u32(at)?;
+        let length = reader.u32(at + 4)? as usize;
+        if length < 12 {
+            return Err(CaptureError::Truncated(at));
+        }
+        let body = at + 8;
+        reader.bytes(at, length)?;
+        match block_type {
+            // Interface description: link type, then options after the snap length.
+            1 => {
+                let link_type = reader.u16(body)? as u32;
+                let mut units = 1_000_000;
+                let mut option = body + 8;
+                while option + 4 <= at + length - 4 {
+                    let (code, len) = (reader.u16(option)?, reader.u16(option + 2)? as usize);
+                    if code == 0 {
+                        break;
+                    }
+                    if code == 9 && len == 1 {
+                        let resolution = reader.bytes(option + 4, 1)?[0];
+                        let exponent = (resolution & 0x7f) as u32;
+                        units = if resolution & 0x80 == 0 {
+                            10u64.saturating_pow(exponent)
+                        } else {
+                            1u64 << exponent.min(63)
+                        };
+                    }
+                    option += 4 + len.div_ceil(4) * 4;
+                }
+                interfaces.push((link_type, units));
+            }
+            // Enhanced packet: interface, timestamp in two halves, captured length.
+            6 => {
+                let interface = reader.u32(body)? as usize;
+                let &(link_type, units) = interfaces
+                    .get(interface)
+                    .ok_or(CaptureError::Truncated(at))?;
+                let ticks = ((reader.u32(body + 4)? as u64) << 32) | reader.u32(body + 8)? as u64;
+                let captured = reader.u32(body + 12)? as usize;
+                let frame = reader.bytes(body + 20, captured)?;
+                let timestamp = ticks_to_duration(ticks, units);
+                frames.push(Frame {
+                    timestamp,
+                    link_type,
+                    data: frame,
+                });
+            }
+            _ => {}
+        }
+        at += length;
+    }
+    Ok(frames)
+}
+
+/// Ticks of `units` per second since the epoch. Resolutions finer than
+/// nanoseconds overflow u64 when scaled, so the fraction is scaled in u128.
+fn ticks_to_duration(ticks: u64, units: u64) -> Duration {
+    let nanos = u128::from(ticks % units) * 1_000_000_000 / u128::from(units);
+    Duration::from_secs(ticks / units) + Duration::from_nanos(nanos as u64)
+}
+
+fn udp_in_frame(frame: &Frame) -> Result<Option<Datagram>, CaptureError> {
+    let data = frame.data;
+    let (ether_type, packet) = match frame.link_type {
+        LINKTYPE_ETHERNET => {
+            let Some(ethernet) = EthernetPacket::new(data) else {
+                return Ok(None);
+            };
+            let mut ether_type = ethernet.get_ethertype();
+            let mut packet = &data[EthernetPacket::minimum_packet_size()..];
+            while ether_type == EtherTypes::Vlan {
+                let Some(vlan) = VlanPacket::new(packet) else {
+                    return Ok(None);
+                };
+                ether_type = vlan.get_ethertype();
+                packet = &packet[VlanPacket::minimum_packet_size()..];
+            }
+            (ether_type, packet)
+        }
+        // A host-order address family, which pnet has no packet type for.
+        LINKTYPE_NULL => match data.get(..4) {
+            Some(family) => {
+                let family = u32::from_le_bytes(family.try_into().unwrap());
+                let family = if family > 0xffff {
+                    family.swap_bytes()
+                } else {
+                    family
+                };
+                let ether_type = if family == 2 {
+                    EtherTypes::Ipv4
+                } else {
+                    EtherTypes::Ipv6
+                };
+                (ether_type, &data[4..])
+            }
+            None => return Ok(None),
+        },
+        LINKTYPE_LINUX_SLL => match SLLPacket::new(data) {
+            Some(sll) => (
+                sll.get_protocol(),
+                &data[SLLPacket::minimum_packet_size()..],
+            ),
+            None => return Ok(None),
+        },
+        LINKTYPE_LINUX_SLL2 => match SLL2Packet::new(data) {
+            Some(sll) => (
+                sll.get_protocol_type(),
+                &data[SLL2Packet::minimum_packet_size()..],
+            ),
+            None => return Ok(None),
+        },
+        LINKTYPE_RAW | LINKTYPE_IPV4 | LINKTYPE_IPV6 => match data.first() {
+            Some(byte) if byte >> 4 == 6 => (EtherTypes::Ipv6, data),
+            Some(_) => (EtherTypes::Ipv4, data),
+            None => return Ok(None),
+        },
+        other => return Err(CaptureError::UnsupportedLinkType(other)),
+    };
+    Ok(match ether_type {
+        EtherTypes::Ipv4 => udp_in_ipv4(packet, frame.timestamp),
+        EtherTypes::Ipv6 => udp_in_ipv6(packet, frame.timestamp),
+        _ => None,
+    })
+}
+
+fn udp_in_ipv4(packet: &[u8], timestamp: Duration) -> Option<Datagram> {
+    let ipv4 = Ipv4Packet::new(packet)?;
+    if ipv4.get_next_level_protocol() != IpNextHeaderProtocols::Udp
+        || ipv4.get_fragment_offset() != 0
+        || ipv4.get_header_length() < 5
+    {
+        return None;
+    }
+    udp(
+        ipv4.payload(),
+        IpAddr::V4(ipv4.get_source()),
+        IpAddr::V4(ipv4.get_destination()),
+        timestamp,
+    )
+}
+
+fn udp_in_ipv6(packet: &[u8], timestamp: Duration) -> Option<Datagram> {
+    let ipv6 = Ipv6Packet::new(packet)?;
+    if ipv6.get_next_header() != IpNextHeaderProtocols::Udp {
+        return None;
+    }
+    udp(
+        ipv6.payload(),
+        IpAddr::V6(ipv6.get_source()),
+        IpAddr::V6(ipv6.get_destination()),
+        timestamp,
+    )
+}
+
+fn udp(
+    segment: &[u8],
+    source: IpAddr,
+    destination: IpAddr,
+    timestamp: Duration,
+) -> Option<Datagram> {
+    // `UdpPacket::new` rejects segments shorter than the 8-byte header.
+    let udp = UdpPacket::new(segment)?;
+    let length = (udp.get_length() as usize).clamp(8, segment.len());
+    Some(Datagram {
+        timestamp,
+        source: SocketAddr::new(source, udp.get_source()),
+        destination: SocketAddr::new(destination, udp.get_destination()),
+        payload: segment[8..length].to_vec(),
+    })
+}
+
+/// Writes IPv4 datagrams as a classic pcap capture of raw IP frames, for fixtures.
+pub fn encode(datagrams: &[Datagram]) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(&PCAP_MICROS.to_le_bytes());
+    out.extend_from_slice(&2u16.to_le_bytes());
+    out.extend_from_slice(&4u16.to_le_bytes());
+    out.extend_from_slice(&[0; 8]);
+    out.extend_from_slice(&65535u32.to_le_bytes());
+    out.extend_from_slice(&LINKTYPE_RAW.to_le_bytes());
+    for datagram in datagrams {
+        let (IpAddr::V4(source), IpAddr::V4(destination)) =
+            (datagram.source.ip(), datagram.destination.ip())
+        else {
+            continue;
+        };
+        let udp_len = 8 + datagram.payload.len();
+        let mut frame = vec![0x45, 0];
+        frame.extend_from_slice(&(20 + udp_len as u16).to_be_bytes());
+        frame.extend_from_slice(&[0, 0, 0, 0, 64, UDP, 0, 0]);
+        frame.extend_from_slice(&source.octets());
+        frame.extend_from_slice(&destination.octets());
+        frame.extend_from_slice(&datagram.source.port().to_be_bytes());
+        frame.extend_from_slice(&datagram.destination.port().to_be_bytes());
+        frame.extend_from_slice(&(udp_len as u16).to_be_bytes());
+        frame.extend_from_slice(&[0, 0]);
+        frame.extend_from_slice(&datagram.payload);
+
+        out.extend_from_slice(&(datagram.timestamp.as_secs() as u32).to_le_bytes());
+        out.extend_from_slice(&datagram.timestamp.subsec_micros().to_le_bytes());
+        out.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+        out.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+        out.extend_from_slice(&frame);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn datagram(payload: &[u8]) -> Datagram {
+        Datagram {
+            timestamp: Duration::from_micros(1_700_000_000_123_456),
+            source: "10.0.0.1:9000".parse().unwrap(),
+            destination: "10.0.0.2:9009".parse().unwrap(),
+            payload: payload.to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_pcap_round_trip() {
+        let datagrams = vec![datagram(b"first"), datagram(&[0xab; 1200])];
+        assert_eq!(parse(&encode(&datagrams)).unwrap(), datagrams);
+        assert!(matches!(
+            parse(&[0u8; 24]),
+            Err(CaptureError::UnknownFormat(0))
+        ));
+        let encoded = encode(&datagrams);
+        assert!(matches!(
+            parse(&encoded[..encoded.len() - 1]),
+            Err(CaptureError::Truncated(_))
+        ));
+    }
+
+    #[test]
+    fn test_summary() {
+        let mut later = datagram(b"second");
+        later.timestamp += Duration::from_millis(1500);
+        let mut frame = vec![0x60];
+        frame.resize(40, 0);
+        let mut capture = encode(&[datagram(b"first"), later]);
+        // An IPv6 frame without a UDP header.
+        capture.extend_from_slice(&1_700_000_002u32.to_le_bytes());
+        capture.extend_from_slice(&0u32.to_le_bytes());
+        capture.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+        capture.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+        capture.extend_from_slice(&frame);
+
+        let frames = frames(&capture).unwrap();
+        let summary = Summary::new(&frames, &datagrams(&frames).unwrap());
+        assert_eq!(
+            summary,
+            Summary {
+                frames: 3,
+                datagrams: 2,
+                duration: Duration::from_micros(1_876_544),
+            }
+        );
+        assert_eq!(
+            summary.to_string(),
+            "3 frame(s), 2 UDP datagram(s) over 1.876544s"
+        );
+        assert_eq!(Summary::new(&[], &[]).duration, Duration::ZERO);
+    }
+
+    #[test]
+    fn test_truncated_udp_header() {
+        let source = "10.0.0.1".parse().unwrap();
+        let destination = "10.0.0.2".parse().unwrap();
+        let timestamp = Duration::ZERO;
+        for len in 0..8 {
+            assert_eq!(udp(&[0x23; 7][..len], source, destination, timestamp), None);
+        }
+        let datagram = udp(
+            &[0x23, 0x28, 0x23, 0x31, 0, 9, 0, 0, 0xab],
+            source,
+            destination,
+            timestamp,
+        )
+        .unwrap();
+        assert_eq!(datagram.payload, [0xab]);
+    }
+
+    #[test]
+    fn test_fine_timestamp_resolution() {
+        // Picoseconds, whose fraction times 10^9 does not fit in u64.
+        let units = 1_000_000_000_000;
+        assert_eq!(
+            ticks_to_duration(1_700_000 * units + 999_999_999_999, units),
+            Duration::new(1_700_000, 999_999_999)
+        );
+    }
+
+    #[test]
+    fn test_pcapng_ethernet() {
+        let expected = datagram(b"payload");
+        // The raw IP frame from the pcap writer behind an Ethernet header.
+        let pcap = encode(std::slice::from_ref(&expected));
+        let ip = &pcap[24 + 16..];
+        let mut frame = vec![0u8; 12];
+        frame.extend_from_slice(&[0x08, 0x00]);
+        frame.extend_from_slice(ip);
+
+        let block = |block_type: u32, body: &[u8]| {
+            let length = (12 + body.len().div_ceil(4) * 4) as u32;
+            let mut out = block_type.to_le_bytes().to_vec();
+            out.extend_from_slice(&length.to_le_bytes());
+            out.extend_from_slice(body);
+            out.resize(length as usize - 4, 0);
+            out.extend_from_slice(&length.to_le_bytes());
+            out
+        };
+        let mut section = PCAPNG_BYTE_ORDER.to_le_bytes().to_vec();
+        section.extend_from_slice(&[1, 0, 0, 0]);
+        section.extend_from_slice(&u64::MAX.to_le_bytes());
+        // Ethernet, nanosecond timestamps.
+        let mut interface = vec![1, 0, 0, 0, 0, 0, 0, 0];
+        interface.extend_from_slice(&[9, 0, 1, 0, 9, 0, 0, 0, 0, 0, 0, 0]);
+        let ticks = expected.timestamp.as_nanos() as u64;
+        let mut packet = 0u32.to_le_bytes().to_vec();
+        packet.extend_from_slice(&((ticks >> 32) as u32).to_le_bytes());
+        packet.extend_from_slice(&(ticks as u32).to_le_bytes());
+        packet.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+        packet.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+        packet.extend_from_slice(&frame);
+
+        let capture = [
+            block(PCAPNG_SECTION, &section),
+            block(1, &interface),
+            block(6, &packet),
+        ]
+        .concat();
+        assert_eq!(parse(&capture).unwrap(), vec![expected]);
+    }
+}
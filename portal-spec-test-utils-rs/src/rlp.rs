@@ -0,0 +1,293 @@
+use ethnum::U256;
+use thiserror::Error;
+
+/// Deepest list nesting `decode` accepts. Ethereum structures nest a few levels,
+/// the limit keeps untrusted input from exhausting the stack.
+pub const MAX_DEPTH: usize = 64;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum RlpError {
+    #[error("input ended after {0} bytes")]
+    UnexpectedEnd(usize),
+    #[error("{0} trailing bytes after the item")]
+    TrailingBytes(usize),
+    #[error("non-canonical encoding at byte {0}")]
+    NonCanonical(usize),
+    #[error("expected a byte string")]
+    ExpectedBytes,
+    #[error("expected a list")]
+    ExpectedList,
+    #[error("integer of {0} bytes does not fit")]
+    IntegerOverflow(usize),
+    #[error("expected {expected} list items, found {found}")]
+    ItemCount { expected: usize, found: usize },
+    #[error("lists nested deeper than {MAX_DEPTH} levels")]
+    TooDeep,
+}
+
+/// A decoded RLP item borrowing from the input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RlpItem<'a> {
+    Bytes(&'a [u8]),
+    List(Vec<RlpItem<'a>>),
+}
+
+impl<'a> RlpItem<'a> {
+    pub fn as_bytes(&self) -> Result<&'a [u8], RlpError> {
+        match self {
+            RlpItem::Bytes(bytes) => Ok(bytes),
+            RlpItem::List(_) => Err(RlpError::ExpectedBytes),
+        }
+    }
+
+    pub fn as_list(&self) -> Result<&[RlpItem<'a>], RlpError> {
+        match self {
+            RlpItem::List(items) => Ok(items),
+            RlpItem::Bytes(_) => Err(RlpError::ExpectedList),
+        }
+    }
+
+    pub fn as_fixed<const N: usize>(&self) -> Result<[u8; N], RlpError> {
+        let bytes = self.as_bytes()?;
+        bytes.try_into().map_err(|_| RlpError::ItemCount {
+            expected: N,
+            found: bytes.len(),
+        })
+    }
+
+    pub fn as_u64(&self) -> Result<u64, RlpError> {
+        let bytes = self.as_integer_bytes(8)?;
+        Ok(bytes.iter().fold(0u64, |acc, b| (acc << 8) | *b as u64))
+    }
+
+    pub fn as_u256(&self) -> Result<U256, RlpError> {
+        let bytes = self.as_integer_bytes(32)?;
+        let mut padded = [0u8; 32];
+        padded[32 - bytes.len()..].copy_from_slice(bytes);
+        Ok(U256::from_be_bytes(padded))
+    }
+
+    fn as_integer_bytes(&self, max: usize) -> Result<&'a [u8], RlpError> {
+        let bytes = self.as_bytes()?;
+        if bytes.len() > max {
+            return Err(RlpError::IntegerOverflow(bytes.len()));
+        }
+        if bytes.first() == Some(&0) {
+            return Err(RlpError::NonCanonical(0));
+        }
+        Ok(bytes)
+    }
+}
+
+/// Decodes a single item that must span the whole input.
+pub fn decode(data: &[u8]) -> Result<RlpItem<'_>, RlpError> {
+    let (item, used) = decode_prefix(data)?;
+    if used != data.len() {
+        return Err(RlpError::TrailingBytes(data.len() - used));
+    }
+    Ok(item)
+}
+
+/// Decodes the item at the start of `data`, returning it and the number of bytes it used.
+pub fn decode_prefix(data: &[u8]) -> Result<(RlpItem<'_>, usize), RlpError> {
+    decode_prefix_at(data, 0)
+}
+
+fn decode_prefix_at(data: &[u8], depth: usize) -> Result<(RlpItem<'_>, usize), RlpError> {
+    let (is_list, header_len, payload_len) = decode_header(data)?;
+    let end = header_len
+        .checked_add(payload_len)
+        .filter(|end| *end <= data.len())
+        .ok_or(RlpError::UnexpectedEnd(data.len()))?;
+    let payload = &data[header_len..end];
+    if !is_list {
+        return Ok((RlpItem::Bytes(payload), end));
+    }
+    if depth >= MAX_DEPTH {
+        return Err(RlpError::TooDeep);
+    }
+
+    let mut items = Vec::new();
+    let mut rest = payload;
+    while !rest.is_empty() {
+        let (item, used) = decode_prefix_at(rest, depth + 1)?;
+        items.push(item);
+        rest = &rest[used..];
+    }
+    Ok((RlpItem::List(items), end))
+}
+
+/// Returns whether the item is a list, the header length and the payload length.
+pub fn decode_header(data: &[u8]) -> Result<(bool, usize, usize), RlpError> {
+    let first = *data.first().ok_or(RlpError::UnexpectedEnd(0))?;
+    match first {
+        0x00..=0x7f => Ok((false, 0, 1)),
+        0x80..=0xb7 => {
+            let len = (first - 0x80) as usize;
+            if len == 1 && data.get(1).is_some_and(|b| *b < 0x80) {
+                return Err(RlpError::NonCanonical(0));
+            }
+            Ok((false, 1, len))
+        }
+        0xb8..=0xbf => {
+            let len_of_len = (first - 0xb7) as usize;
+            let len = read_long_length(data, len_of_len)?;
+            Ok((false, 1 + len_of_len, len))
+        }
+        0xc0..=0xf7 => Ok((true, 1, (first - 0xc0) as usize)),
+        0xf8..=0xff => {
+            let len_of_len = (first - 0xf7) as usize;
+            let len = read_long_length(data, len_of_len)?;
+            Ok((true, 1 + len_of_len, len))
+        }
+    }
+}
+
+fn read_long_length(data: &[u8], len_of_len: usize) -> Result<usize, RlpError> {
+    let bytes = data
+        .get(1..1 + len_of_len)
+        .ok_or(RlpError::UnexpectedEnd(data.len()))?;
+    if bytes[0] == 0 || len_of_len > 8 {
+        return Err(RlpError::NonCanonical(0));
+    }
+    let len = bytes.iter().fold(0u64, |acc, b| (acc << 8) | *b as u64);
+    if len < 56 {
+        return Err(RlpError::NonCanonical(0));
+    }
+    usize::try_from(len).map_err(|_| RlpError::IntegerOverflow(len_of_len))
+}
+
+fn encode_length(out: &mut Vec<u8>, len: usize, offset: u8) {
+    if len < 56 {
+        out.push(offset + len as u8);
+    } else {
+        let bytes = (len as u64).to_be_bytes();
+        let skip = bytes.iter().take_while(|b| **b == 0).count();
+        out.push(offset + 55 + (8 - skip) as u8);
+        out.extend_from_slice(&bytes[skip..]);
+    }
+}
+
+pub fn encode_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    if bytes.len() == 1 && bytes[0] < 0x80 {
+        out.push(bytes[0]);
+    } else {
+        encode_length(out, bytes.len(), 0x80);
+        out.extend_from_slice(bytes);
+    }
+}
+
+pub fn encode_u64(out: &mut Vec<u8>, value: u64) {
+    let bytes = value.to_be_bytes();
+    let skip = bytes.iter().take_while(|b| **b == 0).count();
+    encode_bytes(out, &bytes[skip..]);
+}
+
+pub fn encode_u256(out: &mut Vec<u8>, value: U256) {
+    let bytes = value.to_be_bytes();
+    let skip = bytes.iter().take_while(|b| **b == 0).count();
+    encode_bytes(out, &bytes[skip..]);
+}
+
+/// Wraps already encoded items into a list.
+pub fn encode_list(out: &mut Vec<u8>, payload: &[u8]) {
+    encode_length(out, payload.len(), 0xc0);
+    out.extend_from_slice(payload);
+}
+
+/// Encodes a decoded item back to bytes.
+pub fn encode_item(out: &mut Vec<u8>, item: &RlpItem) {
+    match item {
+        RlpItem::Bytes(bytes) => encode_bytes(out, bytes),
+        RlpItem::List(items) => {
+            let mut payload = Vec::new();
+            for item in items {
+                encode_item(&mut payload, item);
+            }
+            encode_list(out, &payload);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encoded(item: &RlpItem) -> Vec<u8> {
+        let mut out = Vec::new();
+        encode_item(&mut out, item);
+        out
+    }
+
+    #[test]
+    fn test_known_encodings() {
+        assert_eq!(
+            encoded(&RlpItem::Bytes(b"dog")),
+            hex::decode("83646f67").unwrap()
+        );
+        assert_eq!(
+            encoded(&RlpItem::List(vec![
+                RlpItem::Bytes(b"cat"),
+                RlpItem::Bytes(b"dog")
+            ])),
+            hex::decode("c88363617483646f67").unwrap()
+        );
+        assert_eq!(encoded(&RlpItem::Bytes(&[])), vec![0x80]);
+        assert_eq!(encoded(&RlpItem::List(vec![])), vec![0xc0]);
+        let long = [0xaa; 60];
+        assert_eq!(encoded(&RlpItem::Bytes(&long))[..2], [0xb8, 60]);
+    }
+
+    #[test]
+    fn test_round_trip_and_integers() {
+        let mut out = Vec::new();
+        encode_u64(&mut out, 1024);
+        assert_eq!(out, vec![0x82, 0x04, 0x00]);
+        assert_eq!(decode(&out).unwrap().as_u64().unwrap(), 1024);
+
+        let mut out = Vec::new();
+        encode_u64(&mut out, 0);
+        assert_eq!(out, vec![0x80]);
+        assert_eq!(decode(&out).unwrap().as_u64().unwrap(), 0);
+
+        let nested = RlpItem::List(vec![
+            RlpItem::List(vec![]),
+            RlpItem::Bytes(&[0x7f]),
+            RlpItem::Bytes(&[0u8; 100]),
+        ]);
+        let bytes = encoded(&nested);
+        assert_eq!(decode(&bytes).unwrap(), nested);
+    }
+
+    #[test]
+    fn test_rejects_malformed_input() {
+        assert_eq!(decode(&[0x81, 0x05]), Err(RlpError::NonCanonical(0)));
+        assert_eq!(decode(&[0x83, 0x01]), Err(RlpError::UnexpectedEnd(2)));
+        assert_eq!(decode(&[0x01, 0x02]), Err(RlpError::TrailingBytes(1)));
+        assert_eq!(decode(&[0xb8, 0x05]), Err(RlpError::NonCanonical(0)));
+        assert!(decode(&[0x82, 0x00, 0x01]).unwrap().as_u64().is_err());
+    }
+
+    /// `depth` lists each holding the next, built back to front to stay linear.
+    fn nested_lists(depth: usize) -> Vec<u8> {
+        let mut reversed = vec![0xc0];
+        for _ in 1..depth {
+            let mut header = Vec::new();
+            encode_length(&mut header, reversed.len(), 0xc0);
+            reversed.extend(header.iter().rev());
+        }
+        reversed.reverse();
+        reversed
+    }
+
+    #[test]
+    fn test_rejects_deep_nesting() {
+        assert!(decode(&nested_lists(MAX_DEPTH)).is_ok());
+        assert_eq!(decode(&nested_lists(MAX_DEPTH + 1)), Err(RlpError::TooDeep));
+
+        // Far past the limit, where unbounded recursion used to overflow the stack.
+        let deep = nested_lists(100_000);
+        assert_eq!(decode(&deep), Err(RlpError::TooDeep));
+        assert_eq!(crate::trie::TrieNode::decode(&deep), Err(RlpError::TooDeep));
+    }
+}
@@ -0,0 +1,678 @@
+use crate::content::ContentValue;
+use crate::content_key::{ContentKey, Subnetwork};
+use crate::enr::{deterministic_key, Enr, EnrBuilder};
+use crate::invalid::ErrorClass;
+use crate::network::Network;
+use crate::validation::Validator;
+use crate::vector::{decode_hex, discover, encode_hex, VectorError, VectorKind};
+use crate::wire::MAX_CONTENT_SIZE;
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::net::Ipv4Addr;
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_tungstenite::tungstenite::handshake::derive_accept_key;
+use tokio_tungstenite::tungstenite::protocol::{Message, Role};
+use tokio_tungstenite::WebSocketStream;
+
+/// JSON-RPC 2.0 error codes, plus the Portal one for missing content.
+pub mod error_code {
+    pub const PARSE_ERROR: i64 = -32700;
+    pub const INVALID_REQUEST: i64 = -32600;
+    pub const METHOD_NOT_FOUND: i64 = -32601;
+    pub const INVALID_PARAMS: i64 = -32602;
+    pub const CONTENT_NOT_FOUND: i64 = -39001;
+    /// Content that fails validation on Store, from the server error range.
+    pub const INVALID_CONTENT: i64 = -32000;
+}
+
+const MAX_HEAD_SIZE: usize = 64 * 1024;
+const MAX_BODY_SIZE: usize = 16 * 1024 * 1024;
+
+/// Content values by content key.
+type ContentMap = HashMap<Vec<u8>, Vec<u8>>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+    pub data: Option<Value>,
+}
+
+impl RpcError {
+    pub fn new(code: i64, message: impl Into<String>) -> RpcError {
+        RpcError {
+            code,
+            message: message.into(),
+            data: None,
+        }
+    }
+
+    fn invalid_params(message: impl Into<String>) -> RpcError {
+        RpcError::new(error_code::INVALID_PARAMS, message)
+    }
+
+    /// Tags the error with the class of invalid content it reports.
+    fn with_class(mut self, class: ErrorClass) -> RpcError {
+        self.data = Some(json!({"class": class.name()}));
+        self
+    }
+
+    /// The error class from the `data` field, if the server reports one.
+    pub fn class(&self) -> Option<ErrorClass> {
+        self.data
+            .as_ref()?
+            .get("class")?
+            .as_str()
+            .and_then(ErrorClass::from_name)
+    }
+}
+
+impl fmt::Display for RpcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.message, self.code)
+    }
+}
+
+/// A stand-in Portal client that answers JSON-RPC calls from a fixed content set.
+#[derive(Debug)]
+pub struct MockNode {
+    pub enr: Enr,
+    content: RwLock<HashMap<Subnetwork, ContentMap>>,
+    validator: Option<RwLock<Validator>>,
+}
+
+fn mock_enr() -> Enr {
+    EnrBuilder::new()
+        .ip4(Ipv4Addr::LOCALHOST)
+        .udp(9009)
+        .client("portal-spec-tests mock")
+        .build(&deterministic_key(b"mock-node"))
+        .expect("record is far below the size limit")
+}
+
+impl MockNode {
+    pub fn new(enr: Enr) -> MockNode {
+        MockNode {
+            enr,
+            content: RwLock::new(HashMap::new()),
+            validator: None,
+        }
+    }
+
+    /// Validates content on `*Store`, rejecting what a Portal client would not accept.
+    pub fn with_validator(mut self, validator: Validator) -> MockNode {
+        self.validator = Some(RwLock::new(validator));
+        self
+    }
+
+    /// An empty validating node with the mock identity, trusting the anchors below `dir`.
+    pub fn validating(network: &Network, dir: &Path) -> Result<MockNode, VectorError> {
+        Ok(MockNode::new(mock_enr()).with_validator(Validator::from_vectors(network, dir)?))
+    }
+
+    /// A node with a fixed identity holding every content entry below `dir` that
+    /// decodes on `network`.
+    pub fn from_vectors(network: &Network, dir: &Path) -> Result<MockNode, VectorError> {
+        let node = MockNode::new(mock_enr());
+        for file in discover(dir)? {
+            if file.kind.is_none() || file.kind == Some(VectorKind::Invalid) {
+                continue;
+            }
+            for entry in file.content_entries()? {
+                let Some(value) = entry.content_value.or(entry.offer_value) else {
+                    continue;
+                };
+                let Ok(key) = ContentKey::decode(&entry.content_key) else {
+                    continue;
+                };
+                if ContentValue::decode(network, &key, &value).is_ok() {
+                    node.store(key.subnetwork(), entry.content_key, value);
+                }
+            }
+        }
+        Ok(node)
+    }
+
+    pub fn store(&self, subnetwork: Subnetwork, content_key: Vec<u8>, value: Vec<u8>) {
+        self.content
+            .write()
+            .unwrap()
+            .entry(subnetwork)
+            .or_default()
+            .insert(content_key, value);
+    }
+
+    pub fn local_content(&self, subnetwork: Subnetwork, content_key: &[u8]) -> Option<Vec<u8>> {
+        self.content
+            .read()
+            .unwrap()
+            .get(&subnetwork)
+            .and_then(|content| content.get(content_key))
+            .cloned()
+    }
+
+    pub fn content_count(&self, subnetwork: Subnetwork) -> usize {
+        self.content
+            .read()
+            .unwrap()
+            .get(&subnetwork)
+            .map_or(0, HashMap::len)
+    }
+
+    /// Runs one method with its positional parameters.
+    pub fn call(&self, method: &str, params: &[Value]) -> Result<Value, RpcError> {
+        let not_found = || RpcError::new(error_code::METHOD_NOT_FOUND, "Method not found");
+        if method == "discv5_nodeInfo" {
+            return Ok(json!({
+                "enr": self.enr.to_string(),
+                "nodeId": encode_hex(&self.enr.node_id().expect("record has a v4 key")),
+            }));
+        }
+        let (subnetwork, name) = method
+            .strip_prefix("portal_")
+            .and_then(split_subnetwork)
+            .ok_or_else(not_found)?;
+        match name {
+            "GetContent" | "LocalContent" => {
+                let key = content_key_param(params, subnetwork)?;
+                let value = self.local_content(subnetwork, &key).ok_or_else(|| {
+                    RpcError::new(error_code::CONTENT_NOT_FOUND, "Content not found")
+                })?;
+                if name == "LocalContent" {
+                    return Ok(json!(encode_hex(&value)));
+                }
+                Ok(json!({
+                    "content": encode_hex(&value),
+                    "utpTransfer": value.len() > MAX_CONTENT_SIZE,
+                }))
+            }
+            "Store" => {
+                let key = content_key_param(params, subnetwork)?;
+                let value = hex_param(params, 1, "contentValue")?;
+                let value = match &self.validator {
+                    Some(validator) => self.validate(validator, &key, value)?,
+                    None => value,
+                };
+                self.store(subnetwork, key, value);
+                Ok(json!(true))
+            }
+            _ => Err(not_found()),
+        }
+    }
+
+    /// Checks a stored value, learning headers and storing state offers in retrieval form.
+    fn validate(
+        &self,
+        validator: &RwLock<Validator>,
+        key: &[u8],
+        value: Vec<u8>,
+    ) -> Result<Vec<u8>, RpcError> {
+        let decoded = validator
+            .read()
+            .unwrap()
+            .validate(key, &value)
+            .map_err(|e| {
+                RpcError::new(error_code::INVALID_CONTENT, e.to_string()).with_class(e.class())
+            })?;
+        Ok(match decoded {
+            ContentValue::HeaderWithProof(header_with_proof) => {
+                validator
+                    .write()
+                    .unwrap()
+                    .add_header(header_with_proof.header);
+                value
+            }
+            ContentValue::State(state) => state.retrieval_value(),
+            _ => value,
+        })
+    }
+
+    /// Answers one request object, or `None` for a notification.
+    pub fn handle_request(&self, request: &Value) -> Option<Value> {
+        let id = request.get("id").cloned();
+        let method = request.get("method").and_then(Value::as_str);
+        let (Some(method), Some("2.0")) = (method, request.get("jsonrpc").and_then(Value::as_str))
+        else {
+            return Some(error_response(
+                Value::Null,
+                RpcError::new(error_code::INVALID_REQUEST, "Invalid request"),
+            ));
+        };
+        let result = match request.get("params") {
+            None => self.call(method, &[]),
+            Some(Value::Array(params)) => self.call(method, params),
+            Some(_) => Err(RpcError::invalid_params("params must be an array")),
+        };
+        let id = id?;
+        Some(match result {
+            Ok(result) => json!({"jsonrpc": "2.0", "id": id, "result": result}),
+            Err(error) => error_response(id, error),
+        })
+    }
+
+    /// Answers a request body, which may be a batch.
+    pub fn handle_text(&self, text: &str) -> Option<String> {
+        let request: Value = match serde_json::from_str(text) {
+            Ok(request) => request,
+            Err(e) => {
+                let error = RpcError::new(error_code::PARSE_ERROR, format!("Parse error: {}", e));
+                return Some(error_response(Value::Null, error).to_string());
+            }
+        };
+        match request {
+            Value::Array(batch) if batch.is_empty() => Some(
+                error_response(
+                    Value::Null,
+                    RpcError::new(error_code::INVALID_REQUEST, "Empty batch"),
+                )
+                .to_string(),
+            ),
+            Value::Array(batch) => {
+                let responses: Vec<Value> = batch
+                    .iter()
+                    .filter_map(|request| self.handle_request(request))
+                    .collect();
+                (!responses.is_empty()).then(|| Value::Array(responses).to_string())
+            }
+            request => self
+                .handle_request(&request)
+                .map(|response| response.to_string()),
+        }
+    }
+}
+
+fn split_subnetwork(name: &str) -> Option<(Subnetwork, &str)> {
+    [
+        ("history", Subnetwork::History),
+        ("state", Subnetwork::State),
+        ("beacon", Subnetwork::Beacon),
+    ]
+    .into_iter()
+    .find_map(|(prefix, subnetwork)| name.strip_prefix(prefix).map(|rest| (subnetwork, rest)))
+}
+
+fn hex_param(params: &[Value], index: usize, name: &str) -> Result<Vec<u8>, RpcError> {
+    let text = params
+        .get(index)
+        .and_then(Value::as_str)
+        .ok_or_else(|| RpcError::invalid_params(format!("missing {}", name)))?;
+    decode_hex(text).map_err(|e| RpcError::invalid_params(format!("{}: {}", name, e)))
+}
+
+fn content_key_param(params: &[Value], subnetwork: Subnetwork) -> Result<Vec<u8>, RpcError> {
+    let key = hex_param(params, 0, "contentKey")?;
+    let decoded = ContentKey::decode(&key).map_err(|e| {
+        RpcError::invalid_params(format!("contentKey: {}", e))
+            .with_class(ErrorClass::InvalidContentKey)
+    })?;
+    if decoded.subnetwork() != subnetwork {
+        return Err(RpcError::invalid_params(format!(
+            "contentKey is a {} key",
+            decoded.subnetwork()
+        )));
+    }
+    Ok(key)
+}
+
+fn error_response(id: Value, error: RpcError) -> Value {
+    let mut body = json!({"code": error.code, "message": error.message});
+    if let Some(data) = error.data {
+        body["data"] = data;
+    }
+    json!({"jsonrpc": "2.0", "id": id, "error": body})
+}
+
+/// Accepts HTTP and WebSocket clients on the same listener until it fails.
+pub async fn serve(node: Arc<MockNode>, listener: TcpListener) -> std::io::Result<()> {
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let node = Arc::clone(&node);
+        tokio::spawn(async move {
+            let _ = handle_connection(stream, node).await;
+        });
+    }
+}
+
+struct RequestHead {
+    method: String,
+    headers: Vec<(String, String)>,
+}
+
+impl RequestHead {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+async fn read_head(reader: &mut BufReader<TcpStream>) -> std::io::Result<Option<RequestHead>> {
+    let mut lines = Vec::new();
+    let mut size = 0;
+    loop {
+        let mut line = String::new();
+        let read = reader.read_line(&mut line).await?;
+        if read == 0 {
+            return Ok(None);
+        }
+        size += read;
+        if size > MAX_HEAD_SIZE {
+            return Err(std::io::Error::other("request head too large"));
+        }
+        let line = line.trim_end().to_string();
+        if line.is_empty() {
+            if lines.is_empty() {
+                continue;
+            }
+            break;
+        }
+        lines.push(line);
+    }
+    let method = lines[0].split(' ').next().unwrap_or_default().to_string();
+    let headers = lines[1..]
+        .iter()
+        .filter_map(|line| line.split_once(':'))
+        .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+        .collect();
+    Ok(Some(RequestHead { method, headers }))
+}
+
+async fn handle_connection(
+    stream: TcpStream,
+    node: Arc<MockNode>,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let mut reader = BufReader::new(stream);
+    while let Some(head) = read_head(&mut reader).await? {
+        let upgrade = head
+            .header("upgrade")
+            .is_some_and(|value| value.eq_ignore_ascii_case("websocket"));
+        if let (true, Some(key)) = (upgrade, head.header("sec-websocket-key")) {
+            let accept = derive_accept_key(key.as_bytes());
+            reader
+                .get_mut()
+                .write_all(
+                    format!(
+                        "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\n\
+                         Connection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n",
+                        accept
+                    )
+                    .as_bytes(),
+                )
+                .await?;
+            // Frames the client sent right after the upgrade may already be buffered.
+            return serve_websocket(reader, node).await;
+        }
+        let mut close = head
+            .header("connection")
+            .is_some_and(|value| value.eq_ignore_ascii_case("close"));
+        let length: usize = head
+            .header("content-length")
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(0);
+        let (status, reply) = if head.method != "POST" {
+            // The body is left unread, so the connection cannot carry another request.
+            close = true;
+            ("405 Method Not Allowed", None)
+        } else if length > MAX_BODY_SIZE {
+            close = true;
+            ("413 Payload Too Large", None)
+        } else {
+            let mut body = vec![0u8; length];
+            reader.read_exact(&mut body).await?;
+            match node.handle_text(&String::from_utf8_lossy(&body)) {
+                Some(reply) => ("200 OK", Some(reply)),
+                None => ("204 No Content", None),
+            }
+        };
+        let response = http_response(status, reply.as_deref().unwrap_or_default(), close);
+        reader.get_mut().write_all(response.as_bytes()).await?;
+        if close {
+            break;
+        }
+    }
+    Ok(())
+}
+
+fn http_response(status: &str, body: &str, close: bool) -> String {
+    format!(
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n{}\r\n{}",
+        status,
+        body.len(),
+        if close { "Connection: close\r\n" } else { "" },
+        body
+    )
+}
+
+async fn serve_websocket(
+    stream: BufReader<TcpStream>,
+    node: Arc<MockNode>,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let ws_stream = WebSocketStream::from_raw_socket(stream, Role::Server, None).await;
+    let (mut write, mut read) = ws_stream.split();
+    while let Some(message) = read.next().await {
+        match message? {
+            Message::Text(text) => {
+                if let Some(reply) = node.handle_text(&text) {
+                    write.send(Message::Text(reply)).await?;
+                }
+            }
+            Message::Close(_) => break,
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vector::network_dir;
+
+    fn node() -> MockNode {
+        MockNode::from_vectors(&Network::mainnet(), &network_dir("mainnet")).unwrap()
+    }
+
+    fn request(method: &str, params: Value) -> String {
+        json!({"jsonrpc": "2.0", "id": 1, "method": method, "params": params}).to_string()
+    }
+
+    fn reply(node: &MockNode, method: &str, params: Value) -> Value {
+        serde_json::from_str(&node.handle_text(&request(method, params)).unwrap()).unwrap()
+    }
+
+    // A header key for a hash no vector carries.
+    const HEADER_KEY: &str = "0x00d5a5ed58bfda0b7a6e8b5d3cd1b1b5f13a7a1bbd40f0a5f3bf9a7ba1b0f1d2a1";
+
+    #[test]
+    fn test_portal_methods() {
+        let node = node();
+        for subnetwork in [Subnetwork::History, Subnetwork::State, Subnetwork::Beacon] {
+            assert!(node.content_count(subnetwork) > 0, "{}", subnetwork);
+        }
+        // Mainnet fork digests do not decode on another network.
+        let sepolia = MockNode::from_vectors(&Network::sepolia(), &network_dir("mainnet")).unwrap();
+        assert_eq!(sepolia.content_count(Subnetwork::Beacon), 0);
+        assert_eq!(
+            sepolia.content_count(Subnetwork::History),
+            node.content_count(Subnetwork::History)
+        );
+        let (key, value) = {
+            let content = node.content.read().unwrap();
+            let (key, value) = content[&Subnetwork::History]
+                .iter()
+                .find(|(_, value)| value.len() > MAX_CONTENT_SIZE)
+                .unwrap();
+            (encode_hex(key), encode_hex(value))
+        };
+
+        let response = reply(&node, "portal_historyGetContent", json!([key]));
+        assert_eq!(response["result"]["content"], json!(value));
+        assert_eq!(response["result"]["utpTransfer"], json!(true));
+        let response = reply(&node, "portal_historyLocalContent", json!([key]));
+        assert_eq!(response["result"], json!(value));
+
+        let missing = reply(&node, "portal_historyLocalContent", json!([HEADER_KEY]));
+        assert_eq!(
+            missing["error"]["code"],
+            json!(error_code::CONTENT_NOT_FOUND)
+        );
+        let wrong = reply(&node, "portal_stateGetContent", json!([key]));
+        assert_eq!(wrong["error"]["code"], json!(error_code::INVALID_PARAMS));
+
+        let stored = reply(&node, "portal_historyStore", json!([HEADER_KEY, "0x1234"]));
+        assert_eq!(stored["result"], json!(true));
+        let response = reply(&node, "portal_historyGetContent", json!([HEADER_KEY]));
+        assert_eq!(response["result"]["content"], json!("0x1234"));
+        assert_eq!(response["result"]["utpTransfer"], json!(false));
+
+        let info = reply(&node, "discv5_nodeInfo", json!([]));
+        let enr: Enr = info["result"]["enr"].as_str().unwrap().parse().unwrap();
+        assert_eq!(
+            info["result"]["nodeId"],
+            json!(encode_hex(&enr.node_id().unwrap()))
+        );
+        let unknown = reply(&node, "portal_historyPing", json!([]));
+        assert_eq!(
+            unknown["error"]["code"],
+            json!(error_code::METHOD_NOT_FOUND)
+        );
+    }
+
+    #[test]
+    fn test_envelopes() {
+        let node = node();
+        let parse: Value = serde_json::from_str(&node.handle_text("{").unwrap()).unwrap();
+        assert_eq!(parse["error"]["code"], json!(error_code::PARSE_ERROR));
+        let invalid: Value = serde_json::from_str(&node.handle_text("{}").unwrap()).unwrap();
+        assert_eq!(invalid["error"]["code"], json!(error_code::INVALID_REQUEST));
+        let notification = json!({"jsonrpc": "2.0", "method": "discv5_nodeInfo"});
+        assert_eq!(node.handle_text(&notification.to_string()), None);
+
+        let batch = json!([
+            {"jsonrpc": "2.0", "id": 1, "method": "discv5_nodeInfo"},
+            notification,
+            {"jsonrpc": "2.0", "id": "two", "method": "nope"},
+        ]);
+        let responses: Value =
+            serde_json::from_str(&node.handle_text(&batch.to_string()).unwrap()).unwrap();
+        assert_eq!(responses.as_array().unwrap().len(), 2);
+        assert_eq!(responses[1]["id"], json!("two"));
+    }
+
+    #[tokio::test]
+    async fn test_serve_http_and_websocket() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(serve(Arc::new(node()), listener));
+        let body = request("discv5_nodeInfo", json!([]));
+
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        let http = format!(
+            "POST / HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\n\
+             Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+            addr,
+            body.len(),
+            body
+        );
+        stream.write_all(http.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{}", response);
+        assert!(response.contains("\"enr\":\"enr:"));
+
+        let (mut ws, _) = tokio_tungstenite::connect_async(format!("ws://{}", addr))
+            .await
+            .unwrap();
+        ws.send(Message::Text(body)).await.unwrap();
+        let Some(Ok(Message::Text(text))) = ws.next().await else {
+            panic!("expected a text reply");
+        };
+        let reply: Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(reply["id"], json!(1));
+        assert!(reply["result"]["nodeId"]
+            .as_str()
+            .unwrap()
+            .starts_with("0x"));
+    }
+
+    #[tokio::test]
+    async fn test_rejected_bodies_close_the_connection() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(serve(Arc::new(node()), listener));
+
+        // A body that would otherwise be read as the next request's head.
+        let smuggled = "POST / HTTP/1.1\r\nContent-Length: 2\r\n\r\n{}";
+        let oversized = format!(
+            "POST / HTTP/1.1\r\nContent-Length: {}\r\n\r\n{}",
+            MAX_BODY_SIZE + 1,
+            smuggled
+        );
+        let not_post = format!(
+            "GET / HTTP/1.1\r\nContent-Length: {}\r\n\r\n{}",
+            smuggled.len(),
+            smuggled
+        );
+        for (request, status) in [
+            (oversized, "413 Payload Too Large"),
+            (not_post, "405 Method Not Allowed"),
+        ] {
+            let mut stream = TcpStream::connect(addr).await.unwrap();
+            stream.write_all(request.as_bytes()).await.unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).await.unwrap();
+            assert!(response.starts_with(&format!("HTTP/1.1 {}\r\n", status)));
+            assert!(response.contains("Connection: close\r\n"));
+            assert_eq!(response.matches("HTTP/1.1").count(), 1, "{}", response);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_websocket_frame_sent_with_the_upgrade() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(serve(Arc::new(node()), listener));
+
+        let body = request("discv5_nodeInfo", json!([]));
+        // A masked text frame, as a client must send it.
+        let mask = [1u8, 2, 3, 4];
+        let mut frame = vec![0x81, 0x80 | 126];
+        frame.extend_from_slice(&(body.len() as u16).to_be_bytes());
+        frame.extend_from_slice(&mask);
+        frame.extend(body.bytes().enumerate().map(|(i, b)| b ^ mask[i % 4]));
+        let mut upgrade = format!(
+            "GET / HTTP/1.1\r\nHost: {}\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\
+             Sec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\nSec-WebSocket-Version: 13\r\n\r\n",
+            addr
+        )
+        .into_bytes();
+        upgrade.extend_from_slice(&frame);
+
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream.write_all(&upgrade).await.unwrap();
+        // A server that dropped the frame sees the end of the stream, not a hang.
+        stream.shutdown().await.unwrap();
+        let mut ws = BufReader::new(stream);
+        let mut line = String::new();
+        ws.read_line(&mut line).await.unwrap();
+        assert_eq!(line, "HTTP/1.1 101 Switching Protocols\r\n");
+        while line != "\r\n" {
+            line.clear();
+            ws.read_line(&mut line).await.unwrap();
+        }
+        let ws = WebSocketStream::from_raw_socket(ws, Role::Client, None).await;
+        let (_, mut read) = ws.split();
+        let Some(Ok(Message::Text(text))) = read.next().await else {
+            panic!("expected a text reply");
+        };
+        let reply: Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(reply["id"], json!(1));
+    }
+}
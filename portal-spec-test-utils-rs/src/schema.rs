@@ -0,0 +1,123 @@
+use crate::vector::VectorKind;
+use jsonschema::JSONSchema;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+/// Returns the JSON schema source that every vector file of `kind` must satisfy.
+pub fn schema_source(kind: VectorKind) -> &'static str {
+    match kind {
+        VectorKind::HistoryHeaderWithProof => {
+            include_str!("../schemas/history_header_with_proof.json")
+        }
+        VectorKind::HistoryBlockBody => include_str!("../schemas/history_block_body.json"),
+        VectorKind::HistoryReceipts => include_str!("../schemas/history_receipts.json"),
+        VectorKind::HistoryHive => include_str!("../schemas/history_hive.json"),
+        VectorKind::BlockProofBellatrix => include_str!("../schemas/block_proof_bellatrix.json"),
+        VectorKind::BlockProofCapella => include_str!("../schemas/block_proof_capella.json"),
+        VectorKind::StateTrieNodeKey => include_str!("../schemas/state_trie_node_key.json"),
+        VectorKind::StateBytecodeKey => include_str!("../schemas/state_bytecode_key.json"),
+        VectorKind::StateTrieNode => include_str!("../schemas/state_trie_node.json"),
+        VectorKind::StateBytecode => include_str!("../schemas/state_bytecode.json"),
+        VectorKind::StateValueWithProof => include_str!("../schemas/state_value_with_proof.json"),
+        VectorKind::StateValidation => include_str!("../schemas/state_validation.json"),
+        VectorKind::StateRecursiveGossip => {
+            include_str!("../schemas/state_recursive_gossip.json")
+        }
+        VectorKind::StateHive => include_str!("../schemas/state_hive.json"),
+        VectorKind::BeaconLightClient => include_str!("../schemas/beacon_light_client.json"),
+        VectorKind::BeaconHistoricalSummaries => {
+            include_str!("../schemas/beacon_historical_summaries.json")
+        }
+        VectorKind::BeaconHive => include_str!("../schemas/beacon_hive.json"),
+        VectorKind::WireMessages => include_str!("../schemas/wire_messages.json"),
+        VectorKind::Invalid => include_str!("../schemas/invalid.json"),
+    }
+}
+
+pub fn compile_schema(kind: VectorKind) -> Result<JSONSchema, String> {
+    let schema: Value = serde_json::from_str(schema_source(kind))
+        .map_err(|e| format!("Invalid schema for {}: {}", kind, e))?;
+    JSONSchema::compile(&schema).map_err(|e| format!("Schema compilation failed: {}", e))
+}
+
+/// The schema of `kind`, compiled once for every kind on first use.
+fn compiled_schema(kind: VectorKind) -> Result<&'static JSONSchema, String> {
+    static SCHEMAS: OnceLock<HashMap<VectorKind, Result<JSONSchema, String>>> = OnceLock::new();
+    SCHEMAS.get_or_init(|| {
+        VectorKind::ALL
+            .into_iter()
+            .map(|kind| (kind, compile_schema(kind)))
+            .collect()
+    })[&kind]
+        .as_ref()
+        .map_err(Clone::clone)
+}
+
+/// Validates a parsed vector document, returning one message per violation.
+pub fn validate_document(kind: VectorKind, document: &Value) -> Result<(), Vec<String>> {
+    let schema = compiled_schema(kind).map_err(|e| vec![e])?;
+    let result = schema.validate(document).map_err(|errors| {
+        errors
+            .map(|e| {
+                let path = e.instance_path.to_string();
+                if path.is_empty() {
+                    e.to_string()
+                } else {
+                    format!("{}: {}", path, e)
+                }
+            })
+            .collect::<Vec<String>>()
+    });
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_all_schemas_compile() {
+        for kind in VectorKind::ALL {
+            assert!(compile_schema(kind).is_ok(), "{}", kind);
+            assert!(std::ptr::eq(
+                compiled_schema(kind).unwrap(),
+                compiled_schema(kind).unwrap()
+            ));
+        }
+    }
+
+    #[test]
+    fn test_header_with_proof_schema() {
+        let valid = json!({
+            "content_key": "0x006251d65b8a8668efabe2f89c96a5b6332d83b3bbe585089ea6b2ab9b6754f5e9",
+            "content_value": "0x0800000023020000",
+        });
+        assert!(validate_document(VectorKind::HistoryHeaderWithProof, &valid).is_ok());
+
+        let odd_length = json!({
+            "content_key": "0x006251d65b8a8668efabe2f89c96a5b6332d83b3bbe585089ea6b2ab9b6754f5e9",
+            "content_value": "0x080",
+        });
+        assert!(validate_document(VectorKind::HistoryHeaderWithProof, &odd_length).is_err());
+
+        let wrong_selector = json!({
+            "content_key": "0x016251d65b8a8668efabe2f89c96a5b6332d83b3bbe585089ea6b2ab9b6754f5e9",
+            "content_value": "0x08000000",
+        });
+        assert!(validate_document(VectorKind::HistoryHeaderWithProof, &wrong_selector).is_err());
+    }
+
+    #[test]
+    fn test_state_key_schema_requires_fields() {
+        let missing_node_hash = json!({
+            "path": [8, 6],
+            "content_key": "0x2024000000",
+            "content_id": "0xe9d3cd4020b96d4c9222854f541eac0db76335c22bc3d1ea002f0a9ddcad7bf8",
+        });
+        let errors =
+            validate_document(VectorKind::StateTrieNodeKey, &missing_node_hash).unwrap_err();
+        assert!(errors.iter().any(|e| e.contains("node_hash")));
+    }
+}
@@ -3,7 +3,7 @@ use std::fs::File;
 use std::io::{Read, Error};
 use std::path::Path;
 
-pub fn calculate_file_hash(file_path: &Path) -> Result<String, Error> {
+pub fn file_hash(file_path: &Path) -> Result<[u8; 32], Error> {
     let mut file = File::open(file_path)?;
     let mut hasher = Sha256::new();
     let mut buffer = [0; 4096];
@@ -17,7 +17,11 @@ pub fn calculate_file_hash(file_path: &Path) -> Result<String, Error> {
     }
     
     let result = hasher.finalize();
-    Ok(format!("{:x}", result))
+    Ok(result.into())
+}
+
+pub fn calculate_file_hash(file_path: &Path) -> Result<String, Error> {
+    Ok(hex::encode(file_hash(file_path)?))
 }
 
 pub fn verify_file_integrity(file_path: &Path, expected_hash: &str) -> Result<bool, Error> {
@@ -0,0 +1,857 @@
+use crate::beacon::{
+    BeaconPayload, BeaconValue, ExecutionPayloadHeader, ForkedPayload, LightClientHeader,
+    SyncAggregate, SyncCommittee,
+};
+use crate::content::{
+    BlockBody, BlockHeaderProof, ContentValue, HeaderWithProof, Receipt, StateValue,
+};
+use crate::content_key::{
+    content_id, BeaconContentKey, ContentKey, HistoryContentKey, StateContentKey,
+};
+use crate::header::{keccak256, Header};
+use crate::network::Network;
+use crate::transaction::{Transaction, TxType};
+use crate::trie::{Account, NodeRef, TrieNode, TrieNodeKind};
+use crate::vector::{encode_hex, ContentEntry};
+use std::fmt;
+
+/// Values longer than this are shortened when rendered, but compared in full.
+const MAX_RENDERED_LEN: usize = 80;
+
+/// A decoded value laid out as a tree of named fields.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Node {
+    Value(String),
+    Struct(Vec<(String, Node)>),
+    List(Vec<Node>),
+}
+
+fn value(text: impl ToString) -> Node {
+    Node::Value(text.to_string())
+}
+
+fn bytes(data: &[u8]) -> Node {
+    Node::Value(encode_hex(data))
+}
+
+fn roots(roots: &[[u8; 32]]) -> Node {
+    Node::List(roots.iter().map(|root| bytes(root)).collect())
+}
+
+#[derive(Default)]
+struct Fields(Vec<(String, Node)>);
+
+impl Fields {
+    fn add(mut self, name: &str, node: Node) -> Self {
+        self.0.push((name.to_string(), node));
+        self
+    }
+
+    fn add_opt(self, name: &str, node: Option<Node>) -> Self {
+        match node {
+            Some(node) => self.add(name, node),
+            None => self,
+        }
+    }
+
+    fn build(self) -> Node {
+        Node::Struct(self.0)
+    }
+}
+
+fn shorten(text: &str) -> String {
+    if text.len() <= MAX_RENDERED_LEN || !text.starts_with("0x") {
+        return text.to_string();
+    }
+    format!(
+        "{}..{} ({} bytes)",
+        &text[..34],
+        &text[text.len() - 8..],
+        (text.len() - 2) / 2
+    )
+}
+
+impl Node {
+    /// Renders the tree as indented `name: value` lines.
+    pub fn render(&self, full: bool) -> String {
+        let mut out = String::new();
+        self.render_into(&mut out, 0, full);
+        out
+    }
+
+    fn render_into(&self, out: &mut String, depth: usize, full: bool) {
+        let children: Vec<(String, &Node)> = match self {
+            Node::Value(text) => {
+                let text = if full { text.clone() } else { shorten(text) };
+                out.push_str(&format!("{}{}\n", "  ".repeat(depth), text));
+                return;
+            }
+            Node::Struct(fields) => fields.iter().map(|(n, v)| (n.clone(), v)).collect(),
+            Node::List(items) => items
+                .iter()
+                .enumerate()
+                .map(|(i, v)| (format!("[{}]", i), v))
+                .collect(),
+        };
+        for (name, child) in children {
+            let indent = "  ".repeat(depth);
+            match child {
+                Node::Value(text) => {
+                    let text = if full { text.clone() } else { shorten(text) };
+                    out.push_str(&format!("{}{}: {}\n", indent, name, text));
+                }
+                Node::List(items) if items.is_empty() => {
+                    out.push_str(&format!("{}{}: []\n", indent, name));
+                }
+                Node::List(items) => {
+                    out.push_str(&format!("{}{}: ({} items)\n", indent, name, items.len()));
+                    child.render_into(out, depth + 1, full);
+                }
+                Node::Struct(_) => {
+                    out.push_str(&format!("{}{}:\n", indent, name));
+                    child.render_into(out, depth + 1, full);
+                }
+            }
+        }
+    }
+
+    fn leaves(&self, path: &str, out: &mut Vec<(String, String)>) {
+        match self {
+            Node::Value(text) => out.push((path.to_string(), text.clone())),
+            Node::Struct(fields) => {
+                for (name, child) in fields {
+                    child.leaves(&join(path, name), out);
+                }
+            }
+            Node::List(items) => {
+                for (i, child) in items.iter().enumerate() {
+                    child.leaves(&format!("{}[{}]", path, i), out);
+                }
+            }
+        }
+    }
+}
+
+fn join(path: &str, name: &str) -> String {
+    if path.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}.{}", path, name)
+    }
+}
+
+/// One field that differs between two trees.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Difference {
+    pub path: String,
+    pub left: Option<String>,
+    pub right: Option<String>,
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.left, &self.right) {
+            (Some(left), Some(right)) => {
+                write!(
+                    f,
+                    "~ {}: {} -> {}",
+                    self.path,
+                    shorten(left),
+                    shorten(right)
+                )
+            }
+            (Some(left), None) => write!(f, "- {}: {}", self.path, shorten(left)),
+            (None, Some(right)) => write!(f, "+ {}: {}", self.path, shorten(right)),
+            (None, None) => write!(f, "  {}", self.path),
+        }
+    }
+}
+
+/// Compares two trees field by field.
+pub fn diff(left: &Node, right: &Node) -> Vec<Difference> {
+    let mut out = Vec::new();
+    diff_into("", left, right, &mut out);
+    out
+}
+
+fn one_sided(path: &str, node: &Node, is_left: bool, out: &mut Vec<Difference>) {
+    let mut leaves = Vec::new();
+    node.leaves(path, &mut leaves);
+    out.extend(leaves.into_iter().map(|(path, text)| Difference {
+        path,
+        left: is_left.then(|| text.clone()),
+        right: (!is_left).then_some(text),
+    }));
+}
+
+fn diff_into(path: &str, left: &Node, right: &Node, out: &mut Vec<Difference>) {
+    match (left, right) {
+        (Node::Value(a), Node::Value(b)) => {
+            if a != b {
+                out.push(Difference {
+                    path: path.to_string(),
+                    left: Some(a.clone()),
+                    right: Some(b.clone()),
+                });
+            }
+        }
+        (Node::Struct(a), Node::Struct(b)) => {
+            for (name, child) in a {
+                let child_path = join(path, name);
+                match b.iter().find(|(other, _)| other == name) {
+                    Some((_, other)) => diff_into(&child_path, child, other, out),
+                    None => one_sided(&child_path, child, true, out),
+                }
+            }
+            for (name, child) in b {
+                if !a.iter().any(|(other, _)| other == name) {
+                    one_sided(&join(path, name), child, false, out);
+                }
+            }
+        }
+        (Node::List(a), Node::List(b)) => {
+            for i in 0..a.len().max(b.len()) {
+                let child_path = format!("{}[{}]", path, i);
+                match (a.get(i), b.get(i)) {
+                    (Some(x), Some(y)) => diff_into(&child_path, x, y, out),
+                    (Some(x), None) => one_sided(&child_path, x, true, out),
+                    (None, Some(y)) => one_sided(&child_path, y, false, out),
+                    (None, None) => {}
+                }
+            }
+        }
+        _ => {
+            one_sided(path, left, true, out);
+            one_sided(path, right, false, out);
+        }
+    }
+}
+
+pub fn describe_key(key: &ContentKey) -> Node {
+    let fields = Fields::default().add("type", value(key.name()));
+    let fields = match key {
+        ContentKey::History(key) => match key {
+            HistoryContentKey::BlockHeaderByHash(hash)
+            | HistoryContentKey::BlockBody(hash)
+            | HistoryContentKey::Receipts(hash) => fields.add("block_hash", bytes(hash)),
+            HistoryContentKey::BlockHeaderByNumber(number) => {
+                fields.add("block_number", value(number))
+            }
+        },
+        ContentKey::State(key) => match key {
+            StateContentKey::AccountTrieNode { path, node_hash } => fields
+                .add("path", value(path))
+                .add("node_hash", bytes(node_hash)),
+            StateContentKey::ContractStorageTrieNode {
+                address,
+                path,
+                node_hash,
+            } => fields
+                .add("address", bytes(address))
+                .add("path", value(path))
+                .add("node_hash", bytes(node_hash)),
+            StateContentKey::ContractBytecode { address, code_hash } => fields
+                .add("address", bytes(address))
+                .add("code_hash", bytes(code_hash)),
+        },
+        ContentKey::Beacon(key) => match key {
+            BeaconContentKey::LightClientBootstrap { block_root } => {
+                fields.add("block_root", bytes(block_root))
+            }
+            BeaconContentKey::LightClientUpdatesByRange {
+                start_period,
+                count,
+            } => fields
+                .add("start_period", value(start_period))
+                .add("count", value(count)),
+            BeaconContentKey::LightClientFinalityUpdate { finalized_slot } => {
+                fields.add("finalized_slot", value(finalized_slot))
+            }
+            BeaconContentKey::LightClientOptimisticUpdate { signature_slot } => {
+                fields.add("signature_slot", value(signature_slot))
+            }
+            BeaconContentKey::HistoricalSummariesWithProof { epoch } => {
+                fields.add("epoch", value(epoch))
+            }
+        },
+    };
+    fields
+        .add("content_id", bytes(&content_id(&key.encode())))
+        .build()
+}
+
+pub fn describe_header(header: &Header) -> Node {
+    Fields::default()
+        .add("hash", bytes(&header.hash()))
+        .add("parent_hash", bytes(&header.parent_hash))
+        .add("ommers_hash", bytes(&header.ommers_hash))
+        .add("beneficiary", bytes(&header.beneficiary))
+        .add("state_root", bytes(&header.state_root))
+        .add("transactions_root", bytes(&header.transactions_root))
+        .add("receipts_root", bytes(&header.receipts_root))
+        .add("logs_bloom", bytes(&header.logs_bloom))
+        .add("difficulty", value(header.difficulty))
+        .add("number", value(header.number))
+        .add("gas_limit", value(header.gas_limit))
+        .add("gas_used", value(header.gas_used))
+        .add("timestamp", value(header.timestamp))
+        .add("extra_data", bytes(&header.extra_data))
+        .add("mix_hash", bytes(&header.mix_hash))
+        .add("nonce", bytes(&header.nonce))
+        .add_opt("base_fee_per_gas", header.base_fee_per_gas.map(value))
+        .add_opt(
+            "withdrawals_root",
+            header.withdrawals_root.map(|r| bytes(&r)),
+        )
+        .add_opt("blob_gas_used", header.blob_gas_used.map(value))
+        .add_opt("excess_blob_gas", header.excess_blob_gas.map(value))
+        .add_opt(
+            "parent_beacon_block_root",
+            header.parent_beacon_block_root.map(|r| bytes(&r)),
+        )
+        .add_opt("requests_hash", header.requests_hash.map(|r| bytes(&r)))
+        .build()
+}
+
+fn describe_proof(proof: &BlockHeaderProof) -> Node {
+    match proof {
+        BlockHeaderProof::None => Fields::default().add("type", value("none")).build(),
+        BlockHeaderProof::HistoricalHashesAccumulator(proof) => Fields::default()
+            .add("type", value("historical_hashes_accumulator"))
+            .add("proof", roots(proof))
+            .build(),
+        BlockHeaderProof::HistoricalRoots(proof) => Fields::default()
+            .add("type", value("historical_roots"))
+            .add("beacon_block_proof", roots(&proof.beacon_block_proof))
+            .add("beacon_block_root", bytes(&proof.beacon_block_root))
+            .add("execution_block_proof", roots(&proof.execution_block_proof))
+            .add("slot", value(proof.slot))
+            .build(),
+        BlockHeaderProof::HistoricalSummaries(proof) => Fields::default()
+            .add("type", value("historical_summaries"))
+            .add("beacon_block_proof", roots(&proof.beacon_block_proof))
+            .add("beacon_block_root", bytes(&proof.beacon_block_root))
+            .add("execution_block_proof", roots(&proof.execution_block_proof))
+            .add("slot", value(proof.slot))
+            .build(),
+        BlockHeaderProof::HistoricalSummariesDeneb(proof) => Fields::default()
+            .add("type", value("historical_summaries_deneb"))
+            .add("beacon_block_proof", roots(&proof.beacon_block_proof))
+            .add("beacon_block_root", bytes(&proof.beacon_block_root))
+            .add("execution_block_proof", roots(&proof.execution_block_proof))
+            .add("slot", value(proof.slot))
+            .build(),
+    }
+}
+
+fn describe_header_with_proof(value: &HeaderWithProof) -> Node {
+    Fields::default()
+        .add("header", describe_header(&value.header))
+        .add("proof", describe_proof(&value.proof))
+        .build()
+}
+
+fn describe_transaction(raw: &[u8]) -> Node {
+    let tx = match Transaction::decode(raw) {
+        Ok(tx) => tx,
+        Err(e) => {
+            return Fields::default()
+                .add("hash", bytes(&keccak256(raw)))
+                .add("error", value(e))
+                .add("raw", bytes(raw))
+                .build()
+        }
+    };
+    let sender = match tx.recover_sender() {
+        Ok(sender) => bytes(&sender),
+        Err(e) => value(format!("unrecoverable: {}", e)),
+    };
+    Fields::default()
+        .add("hash", bytes(&tx.hash))
+        .add("type", value(tx.tx_type))
+        .add("sender", sender)
+        .add_opt("chain_id", tx.chain_id.map(value))
+        .add("nonce", value(tx.nonce))
+        .add_opt("gas_price", tx.gas_price.map(value))
+        .add_opt(
+            "max_priority_fee_per_gas",
+            tx.max_priority_fee_per_gas.map(value),
+        )
+        .add_opt("max_fee_per_gas", tx.max_fee_per_gas.map(value))
+        .add("gas_limit", value(tx.gas_limit))
+        .add(
+            "to",
+            tx.to
+                .map_or_else(|| value("contract creation"), |to| bytes(&to)),
+        )
+        .add("value", value(tx.value))
+        .add("input", bytes(&tx.input))
+        .add_opt(
+            "access_list",
+            (!tx.access_list.is_empty()).then(|| {
+                Node::List(
+                    tx.access_list
+                        .iter()
+                        .map(|item| {
+                            Fields::default()
+                                .add("address", bytes(&item.address))
+                                .add("storage_keys", roots(&item.storage_keys))
+                                .build()
+                        })
+                        .collect(),
+                )
+            }),
+        )
+        .add_opt("max_fee_per_blob_gas", tx.max_fee_per_blob_gas.map(value))
+        .add_opt(
+            "blob_versioned_hashes",
+            (tx.tx_type == TxType::Blob).then(|| roots(&tx.blob_versioned_hashes)),
+        )
+        .add_opt(
+            "authorization_list",
+            (tx.tx_type == TxType::SetCode).then(|| {
+                Node::List(
+                    tx.authorization_list
+                        .iter()
+                        .map(|auth| {
+                            Fields::default()
+                                .add("chain_id", value(auth.chain_id))
+                                .add("address", bytes(&auth.address))
+                                .add("nonce", value(auth.nonce))
+                                .add_opt("authority", auth.authority.map(|a| bytes(&a)))
+                                .build()
+                        })
+                        .collect(),
+                )
+            }),
+        )
+        .build()
+}
+
+fn describe_body(body: &BlockBody) -> Node {
+    Fields::default()
+        .add(
+            "transactions",
+            Node::List(
+                body.transactions
+                    .iter()
+                    .map(|tx| describe_transaction(tx))
+                    .collect(),
+            ),
+        )
+        .add(
+            "uncles",
+            Node::List(body.uncles.iter().map(describe_header).collect()),
+        )
+        .add_opt(
+            "withdrawals",
+            body.withdrawals.as_ref().map(|withdrawals| {
+                Node::List(
+                    withdrawals
+                        .iter()
+                        .map(|w| {
+                            Fields::default()
+                                .add("index", value(w.index))
+                                .add("validator_index", value(w.validator_index))
+                                .add("address", bytes(&w.address))
+                                .add("amount_gwei", value(w.amount))
+                                .build()
+                        })
+                        .collect(),
+                )
+            }),
+        )
+        .build()
+}
+
+fn describe_receipt(receipt: &Receipt) -> Node {
+    Fields::default()
+        .add("type", value(receipt.tx_type))
+        .add("status", bytes(&receipt.status))
+        .add("cumulative_gas_used", value(receipt.cumulative_gas_used))
+        .add("logs_bloom", bytes(&receipt.logs_bloom))
+        .add(
+            "logs",
+            Node::List(
+                receipt
+                    .logs
+                    .iter()
+                    .map(|log| {
+                        Fields::default()
+                            .add("address", bytes(&log.address))
+                            .add("topics", roots(&log.topics))
+                            .add("data", bytes(&log.data))
+                            .build()
+                    })
+                    .collect(),
+            ),
+        )
+        .build()
+}
+
+fn describe_node_ref(node: &Option<NodeRef>) -> Node {
+    match node {
+        None => value("empty"),
+        Some(NodeRef::Hash(hash)) => bytes(hash),
+        Some(NodeRef::Inline(encoded)) => value(format!("inline {}", encode_hex(encoded))),
+    }
+}
+
+fn describe_leaf_value(data: &[u8]) -> Node {
+    match Account::decode(data) {
+        Ok(account) => Fields::default()
+            .add("nonce", value(account.nonce))
+            .add("balance", value(account.balance))
+            .add("storage_root", bytes(&account.storage_root))
+            .add("code_hash", bytes(&account.code_hash))
+            .build(),
+        Err(_) => bytes(data),
+    }
+}
+
+pub fn describe_trie_node(node: &TrieNode) -> Node {
+    let fields = Fields::default().add("hash", bytes(&node.hash()));
+    match &node.kind {
+        TrieNodeKind::Branch { children, value: v } => fields
+            .add("type", value("branch"))
+            .add(
+                "children",
+                Node::List(children.iter().map(describe_node_ref).collect()),
+            )
+            .add("value", bytes(v)),
+        TrieNodeKind::Extension { path, child } => fields
+            .add("type", value("extension"))
+            .add("path", value(path))
+            .add("child", describe_node_ref(&Some(child.clone()))),
+        TrieNodeKind::Leaf { path, value: v } => fields
+            .add("type", value("leaf"))
+            .add("path", value(path))
+            .add("value", describe_leaf_value(v)),
+    }
+    .build()
+}
+
+fn describe_proof_nodes(nodes: &[TrieNode]) -> Node {
+    Node::List(nodes.iter().map(describe_trie_node).collect())
+}
+
+fn describe_state(state: &StateValue) -> Node {
+    match state {
+        StateValue::TrieNode(node) => Fields::default()
+            .add("node", describe_trie_node(node))
+            .build(),
+        StateValue::ContractBytecode(code) => Fields::default()
+            .add("code_hash", bytes(&keccak256(code)))
+            .add("code", bytes(code))
+            .build(),
+        StateValue::AccountTrieNodeWithProof { proof, block_hash } => Fields::default()
+            .add("proof", describe_proof_nodes(proof))
+            .add("block_hash", bytes(block_hash))
+            .build(),
+        StateValue::ContractStorageTrieNodeWithProof {
+            storage_proof,
+            account_proof,
+            block_hash,
+        } => Fields::default()
+            .add("storage_proof", describe_proof_nodes(storage_proof))
+            .add("account_proof", describe_proof_nodes(account_proof))
+            .add("block_hash", bytes(block_hash))
+            .build(),
+        StateValue::ContractBytecodeWithProof {
+            code,
+            account_proof,
+            block_hash,
+        } => Fields::default()
+            .add("code_hash", bytes(&keccak256(code)))
+            .add("code", bytes(code))
+            .add("account_proof", describe_proof_nodes(account_proof))
+            .add("block_hash", bytes(block_hash))
+            .build(),
+    }
+}
+
+fn describe_execution(header: &ExecutionPayloadHeader) -> Node {
+    Fields::default()
+        .add("parent_hash", bytes(&header.parent_hash))
+        .add("fee_recipient", bytes(&header.fee_recipient))
+        .add("state_root", bytes(&header.state_root))
+        .add("receipts_root", bytes(&header.receipts_root))
+        .add("logs_bloom", bytes(&header.logs_bloom))
+        .add("prev_randao", bytes(&header.prev_randao))
+        .add("block_number", value(header.block_number))
+        .add("gas_limit", value(header.gas_limit))
+        .add("gas_used", value(header.gas_used))
+        .add("timestamp", value(header.timestamp))
+        .add("extra_data", bytes(&header.extra_data))
+        .add("base_fee_per_gas", value(header.base_fee_per_gas))
+        .add("block_hash", bytes(&header.block_hash))
+        .add("transactions_root", bytes(&header.transactions_root))
+        .add("withdrawals_root", bytes(&header.withdrawals_root))
+        .add_opt("blob_gas_used", header.blob_gas_used.map(value))
+        .add_opt("excess_blob_gas", header.excess_blob_gas.map(value))
+        .build()
+}
+
+fn describe_lc_header(header: &LightClientHeader) -> Node {
+    let beacon = &header.beacon;
+    Fields::default()
+        .add(
+            "beacon",
+            Fields::default()
+                .add("slot", value(beacon.slot))
+                .add("proposer_index", value(beacon.proposer_index))
+                .add("parent_root", bytes(&beacon.parent_root))
+                .add("state_root", bytes(&beacon.state_root))
+                .add("body_root", bytes(&beacon.body_root))
+                .build(),
+        )
+        .add("execution", describe_execution(&header.execution))
+        .add("execution_branch", roots(&header.execution_branch))
+        .build()
+}
+
+fn describe_sync_committee(committee: &SyncCommittee) -> Node {
+    Fields::default()
+        .add(
+            "pubkeys",
+            Node::List(committee.pubkeys.iter().map(|key| bytes(key)).collect()),
+        )
+        .add("aggregate_pubkey", bytes(&committee.aggregate_pubkey))
+        .build()
+}
+
+fn describe_sync_aggregate(aggregate: &SyncAggregate) -> Node {
+    Fields::default()
+        .add("participants", value(aggregate.participants()))
+        .add("sync_committee_bits", bytes(&aggregate.sync_committee_bits))
+        .add(
+            "sync_committee_signature",
+            bytes(&aggregate.sync_committee_signature),
+        )
+        .build()
+}
+
+fn describe_forked(forked: &ForkedPayload) -> Node {
+    let fields = Fields::default()
+        .add("fork_digest", bytes(&forked.fork_digest))
+        .add("fork", value(forked.fork));
+    match &forked.payload {
+        BeaconPayload::Bootstrap(bootstrap) => fields
+            .add("header", describe_lc_header(&bootstrap.header))
+            .add(
+                "current_sync_committee",
+                describe_sync_committee(&bootstrap.current_sync_committee),
+            )
+            .add(
+                "current_sync_committee_branch",
+                roots(&bootstrap.current_sync_committee_branch),
+            ),
+        BeaconPayload::Update(update) => fields
+            .add(
+                "attested_header",
+                describe_lc_header(&update.attested_header),
+            )
+            .add(
+                "next_sync_committee",
+                describe_sync_committee(&update.next_sync_committee),
+            )
+            .add(
+                "next_sync_committee_branch",
+                roots(&update.next_sync_committee_branch),
+            )
+            .add(
+                "finalized_header",
+                describe_lc_header(&update.finalized_header),
+            )
+            .add("finality_branch", roots(&update.finality_branch))
+            .add(
+                "sync_aggregate",
+                describe_sync_aggregate(&update.sync_aggregate),
+            )
+            .add("signature_slot", value(update.signature_slot)),
+        BeaconPayload::FinalityUpdate(update) => fields
+            .add(
+                "attested_header",
+                describe_lc_header(&update.attested_header),
+            )
+            .add(
+                "finalized_header",
+                describe_lc_header(&update.finalized_header),
+            )
+            .add("finality_branch", roots(&update.finality_branch))
+            .add(
+                "sync_aggregate",
+                describe_sync_aggregate(&update.sync_aggregate),
+            )
+            .add("signature_slot", value(update.signature_slot)),
+        BeaconPayload::OptimisticUpdate(update) => fields
+            .add(
+                "attested_header",
+                describe_lc_header(&update.attested_header),
+            )
+            .add(
+                "sync_aggregate",
+                describe_sync_aggregate(&update.sync_aggregate),
+            )
+            .add("signature_slot", value(update.signature_slot)),
+        BeaconPayload::HistoricalSummaries(summaries) => fields
+            .add("epoch", value(summaries.epoch))
+            .add(
+                "historical_summaries",
+                Node::List(
+                    summaries
+                        .historical_summaries
+                        .iter()
+                        .map(|summary| {
+                            Fields::default()
+                                .add("block_summary_root", bytes(&summary.block_summary_root))
+                                .add("state_summary_root", bytes(&summary.state_summary_root))
+                                .build()
+                        })
+                        .collect(),
+                ),
+            )
+            .add("proof", roots(&summaries.proof)),
+    }
+    .build()
+}
+
+pub fn describe_value(value: &ContentValue) -> Node {
+    match value {
+        ContentValue::HeaderWithProof(value) => describe_header_with_proof(value),
+        ContentValue::BlockBody(body) => describe_body(body),
+        ContentValue::Receipts(receipts) => {
+            Node::List(receipts.iter().map(describe_receipt).collect())
+        }
+        ContentValue::State(state) => describe_state(state),
+        ContentValue::Beacon(beacon) => match beacon {
+            BeaconValue::Single(forked) => describe_forked(forked),
+            BeaconValue::Updates(updates) => {
+                Node::List(updates.iter().map(describe_forked).collect())
+            }
+        },
+    }
+}
+
+/// Describes a raw value, falling back to the error and hex when it does not decode.
+fn describe_raw_value(network: &Network, key: Option<&ContentKey>, data: &[u8]) -> Node {
+    let decoded = key.map(|key| ContentValue::decode(network, key, data));
+    match decoded {
+        Some(Ok(decoded)) => describe_value(&decoded),
+        Some(Err(e)) => Fields::default()
+            .add("error", value(e))
+            .add("raw", bytes(data))
+            .build(),
+        None => Fields::default().add("raw", bytes(data)).build(),
+    }
+}
+
+/// Describes a content key and optional value as found in a vector file.
+pub fn describe_content(
+    network: &Network,
+    content_key: &[u8],
+    content_value: Option<&[u8]>,
+) -> Node {
+    let key = ContentKey::decode(content_key);
+    let key_node = match &key {
+        Ok(key) => describe_key(key),
+        Err(e) => Fields::default()
+            .add("error", value(e))
+            .add("raw", bytes(content_key))
+            .build(),
+    };
+    Fields::default()
+        .add("content_key", key_node)
+        .add_opt(
+            "content_value",
+            content_value.map(|data| describe_raw_value(network, key.as_ref().ok(), data)),
+        )
+        .build()
+}
+
+pub fn describe_entry(network: &Network, entry: &ContentEntry) -> Node {
+    let Node::Struct(mut fields) =
+        describe_content(network, &entry.content_key, entry.content_value.as_deref())
+    else {
+        unreachable!("describe_content always builds a struct");
+    };
+    let key = ContentKey::decode(&entry.content_key).ok();
+    if let Some(offer) = &entry.offer_value {
+        fields.push((
+            "content_value_offer".to_string(),
+            describe_raw_value(network, key.as_ref(), offer),
+        ));
+    }
+    if let Some(state_root) = &entry.state_root {
+        fields.push(("state_root".to_string(), bytes(state_root)));
+    }
+    Node::Struct(fields)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vector::{network_dir, VectorFile};
+
+    fn entry(relative: &str) -> ContentEntry {
+        let root = network_dir("mainnet");
+        let file = VectorFile::load(&root, &root.join(relative)).unwrap();
+        file.content_entries().unwrap().remove(0)
+    }
+
+    #[test]
+    fn test_render_header_with_proof() {
+        let node = describe_entry(
+            &Network::mainnet(),
+            &entry("history/headers_with_proof/1000010.yaml"),
+        );
+        let text = node.render(false);
+        assert!(text.contains("    number: 1000010\n"), "{}", text);
+        assert!(text.contains("type: historical_hashes_accumulator"));
+        assert!(!text.contains("error"));
+    }
+
+    #[test]
+    fn test_diff_reports_changed_fields() {
+        let original = entry("history/headers_with_proof/1000010.yaml");
+        let mut changed = original.clone();
+        let value = changed.content_value.as_mut().unwrap();
+        let last = value.len() - 1;
+        value[last] ^= 0x01;
+
+        let differences = diff(
+            &describe_entry(&Network::mainnet(), &original),
+            &describe_entry(&Network::mainnet(), &changed),
+        );
+        assert_eq!(differences.len(), 1);
+        assert_eq!(differences[0].path, "content_value.proof.proof[14]");
+        assert!(differences[0]
+            .to_string()
+            .starts_with("~ content_value.proof.proof[14]: 0x"));
+        assert!(diff(
+            &describe_entry(&Network::mainnet(), &original),
+            &describe_entry(&Network::mainnet(), &original)
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn test_diff_added_and_removed() {
+        let left = Node::Struct(vec![("a".to_string(), value(1))]);
+        let right = Node::Struct(vec![(
+            "b".to_string(),
+            Node::List(vec![value(2), value(3)]),
+        )]);
+        let differences = diff(&left, &right);
+        let rendered: Vec<String> = differences.iter().map(|d| d.to_string()).collect();
+        assert_eq!(rendered, vec!["- a: 1", "+ b[0]: 2", "+ b[1]: 3"]);
+    }
+
+    #[test]
+    fn test_undecodable_value_keeps_raw_bytes() {
+        let node = describe_content(&Network::mainnet(), &[0x01; 33], Some(&[0xff]));
+        let text = node.render(true);
+        assert!(text.contains("error: invalid ssz"), "{}", text);
+        assert!(text.contains("raw: 0xff"));
+    }
+}
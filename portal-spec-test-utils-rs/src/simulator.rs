@@ -0,0 +1,461 @@
+use crate::content_key::content_id;
+use crate::distance::{distance, log2_distance, DataRadius};
+use crate::enr::{deterministic_key, node_id};
+use crate::vector::{discover, ContentEntry, VectorError};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+
+/// SplitMix64, so runs with the same seed are identical on every platform.
+#[derive(Debug, Clone)]
+pub struct SplitMix64(u64);
+
+impl SplitMix64 {
+    pub fn new(seed: u64) -> Self {
+        SplitMix64(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform index below `bound`.
+    pub fn below(&mut self, bound: usize) -> usize {
+        (self.next_u64() % bound as u64) as usize
+    }
+
+    /// Uniform float in `[0, 1)`.
+    pub fn unit(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.below(i + 1));
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SimulationConfig {
+    pub nodes: usize,
+    /// Capacity of each k-bucket and the number of nodes a response carries.
+    pub bucket_size: usize,
+    /// Concurrent requests per lookup round.
+    pub alpha: usize,
+    pub radius: DataRadius,
+    pub seed: u64,
+}
+
+impl Default for SimulationConfig {
+    fn default() -> Self {
+        SimulationConfig {
+            nodes: 256,
+            bucket_size: 16,
+            alpha: 3,
+            radius: DataRadius::from_log2(251),
+            seed: 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SimNode {
+    pub node_id: [u8; 32],
+    pub radius: DataRadius,
+    /// Known node ids by bucket index, the log2 distance minus one.
+    pub buckets: Vec<Vec<[u8; 32]>>,
+    pub store: HashMap<[u8; 32], Vec<u8>>,
+    pub online: bool,
+}
+
+impl SimNode {
+    fn new(node_id: [u8; 32], radius: DataRadius) -> Self {
+        SimNode {
+            node_id,
+            radius,
+            buckets: vec![Vec::new(); 256],
+            store: HashMap::new(),
+            online: true,
+        }
+    }
+
+    /// Adds a node unless its bucket is full, returning whether it was added.
+    fn insert(&mut self, other: [u8; 32], bucket_size: usize) -> bool {
+        let Some(log2) = log2_distance(&self.node_id, &other) else {
+            return false;
+        };
+        let bucket = &mut self.buckets[log2 as usize - 1];
+        if bucket.len() >= bucket_size || bucket.contains(&other) {
+            return false;
+        }
+        bucket.push(other);
+        true
+    }
+
+    fn known(&self) -> impl Iterator<Item = &[u8; 32]> {
+        self.buckets.iter().flatten()
+    }
+
+    fn closest_known(&self, target: &[u8; 32], count: usize) -> Vec<[u8; 32]> {
+        let mut known: Vec<[u8; 32]> = self.known().copied().collect();
+        known.sort_by_key(|id| distance(id, target));
+        known.truncate(count);
+        known
+    }
+
+    /// FINDNODES response: the nodes at the requested log2 distances.
+    fn nodes_at(&self, distances: &[u16], count: usize) -> Vec<[u8; 32]> {
+        distances
+            .iter()
+            .filter(|d| (1..=256).contains(*d))
+            .flat_map(|d| self.buckets[*d as usize - 1].iter().copied())
+            .take(count)
+            .collect()
+    }
+}
+
+/// Outcome of one recursive lookup.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LookupResult {
+    pub found: bool,
+    /// Request rounds until the target was found or the lookup gave up.
+    pub hops: usize,
+    pub requests: usize,
+    pub timeouts: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LookupStats {
+    pub lookups: usize,
+    pub successes: usize,
+    pub mean_hops: f64,
+    pub max_hops: usize,
+    pub timeouts: usize,
+    /// Successful lookups by hop count.
+    pub hop_histogram: BTreeMap<usize, usize>,
+}
+
+impl LookupStats {
+    pub fn success_rate(&self) -> f64 {
+        if self.lookups == 0 {
+            return 0.0;
+        }
+        self.successes as f64 / self.lookups as f64
+    }
+
+    fn from_results(results: &[LookupResult]) -> LookupStats {
+        let successful: Vec<&LookupResult> = results.iter().filter(|r| r.found).collect();
+        let mut hop_histogram = BTreeMap::new();
+        for result in &successful {
+            *hop_histogram.entry(result.hops).or_insert(0) += 1;
+        }
+        LookupStats {
+            lookups: results.len(),
+            successes: successful.len(),
+            mean_hops: if successful.is_empty() {
+                0.0
+            } else {
+                successful.iter().map(|r| r.hops).sum::<usize>() as f64 / successful.len() as f64
+            },
+            max_hops: successful.iter().map(|r| r.hops).max().unwrap_or(0),
+            timeouts: results.iter().map(|r| r.timeouts).sum(),
+            hop_histogram,
+        }
+    }
+}
+
+/// In-process Portal nodes with k-bucket routing tables and radius based storage.
+#[derive(Debug, Clone)]
+pub struct Simulator {
+    pub config: SimulationConfig,
+    pub nodes: Vec<SimNode>,
+    index: HashMap<[u8; 32], usize>,
+    /// Content keys of everything offered to the network.
+    pub content_keys: Vec<Vec<u8>>,
+    rng: SplitMix64,
+}
+
+enum FindContentResponse {
+    Content,
+    Enrs(Vec<[u8; 32]>),
+}
+
+impl Simulator {
+    /// Creates the nodes and fills every routing table from a shuffled view of the others.
+    pub fn new(config: SimulationConfig) -> Simulator {
+        let mut rng = SplitMix64::new(config.seed);
+        let mut nodes: Vec<SimNode> = (0..config.nodes)
+            .map(|i| {
+                let seed = format!("sim-{}-{}", config.seed, i);
+                SimNode::new(
+                    node_id(deterministic_key(seed.as_bytes()).verifying_key()),
+                    config.radius,
+                )
+            })
+            .collect();
+        let ids: Vec<[u8; 32]> = nodes.iter().map(|n| n.node_id).collect();
+        for node in &mut nodes {
+            let mut view = ids.clone();
+            rng.shuffle(&mut view);
+            for other in view {
+                node.insert(other, config.bucket_size);
+            }
+        }
+        let index = ids.iter().enumerate().map(|(i, id)| (*id, i)).collect();
+        Simulator {
+            config,
+            nodes,
+            index,
+            content_keys: Vec::new(),
+            rng,
+        }
+    }
+
+    /// Stores a value on every node whose radius covers its content id.
+    pub fn offer(&mut self, content_key: &[u8], value: &[u8]) -> usize {
+        let content_id = content_id(content_key);
+        let mut stored = 0;
+        for node in &mut self.nodes {
+            if node.radius.contains(&node.node_id, &content_id) {
+                node.store.insert(content_id, value.to_vec());
+                stored += 1;
+            }
+        }
+        self.content_keys.push(content_key.to_vec());
+        stored
+    }
+
+    /// Offers every content entry of the vector files below `dir`.
+    pub fn seed_from_vectors(&mut self, dir: &Path) -> Result<usize, VectorError> {
+        let mut entries: Vec<ContentEntry> = Vec::new();
+        for file in discover(dir)? {
+            entries.extend(file.content_entries()?);
+        }
+        for entry in &entries {
+            let value = entry
+                .content_value
+                .as_ref()
+                .or(entry.offer_value.as_ref())
+                .cloned()
+                .unwrap_or_default();
+            self.offer(&entry.content_key, &value);
+        }
+        Ok(entries.len())
+    }
+
+    /// Takes a random share of the nodes offline, bringing the rest back.
+    pub fn apply_churn(&mut self, offline_fraction: f64) {
+        for node in &mut self.nodes {
+            node.online = self.rng.unit() >= offline_fraction;
+        }
+    }
+
+    pub fn online_count(&self) -> usize {
+        self.nodes.iter().filter(|n| n.online).count()
+    }
+
+    fn node(&self, id: &[u8; 32]) -> Option<&SimNode> {
+        self.index.get(id).map(|i| &self.nodes[*i])
+    }
+
+    fn find_content(&self, peer: &SimNode, content_id: &[u8; 32]) -> FindContentResponse {
+        if peer.store.contains_key(content_id) {
+            return FindContentResponse::Content;
+        }
+        FindContentResponse::Enrs(peer.closest_known(content_id, self.config.bucket_size))
+    }
+
+    fn find_nodes(&self, peer: &SimNode, target: &[u8; 32]) -> Vec<[u8; 32]> {
+        match log2_distance(&peer.node_id, target) {
+            Some(d) => peer.nodes_at(&[d, d - 1, d + 1], self.config.bucket_size),
+            None => vec![peer.node_id],
+        }
+    }
+
+    /// Iterative lookup from `origin`: each round queries the `alpha` closest unqueried candidates.
+    fn lookup(
+        &self,
+        origin: usize,
+        target: &[u8; 32],
+        mut query: impl FnMut(&SimNode) -> Result<(), Vec<[u8; 32]>>,
+    ) -> LookupResult {
+        let mut result = LookupResult {
+            found: false,
+            hops: 0,
+            requests: 0,
+            timeouts: 0,
+        };
+        let mut candidates: BTreeMap<ethnum::U256, [u8; 32]> = self.nodes[origin]
+            .closest_known(target, self.config.bucket_size)
+            .into_iter()
+            .map(|id| (distance(&id, target), id))
+            .collect();
+        let mut queried = HashSet::new();
+        loop {
+            let round: Vec<[u8; 32]> = candidates
+                .values()
+                .take(self.config.bucket_size)
+                .filter(|id| !queried.contains(*id))
+                .take(self.config.alpha)
+                .copied()
+                .collect();
+            if round.is_empty() {
+                return result;
+            }
+            result.hops += 1;
+            for id in round {
+                queried.insert(id);
+                result.requests += 1;
+                let Some(peer) = self.node(&id).filter(|peer| peer.online) else {
+                    result.timeouts += 1;
+                    candidates.remove(&distance(&id, target));
+                    continue;
+                };
+                match query(peer) {
+                    Ok(()) => {
+                        result.found = true;
+                        return result;
+                    }
+                    Err(closer) => {
+                        for other in closer {
+                            if other != self.nodes[origin].node_id {
+                                candidates.insert(distance(&other, target), other);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Recursive FINDCONTENT lookup of a content key from node `origin`.
+    pub fn lookup_content(&self, origin: usize, content_key: &[u8]) -> LookupResult {
+        let content_id = content_id(content_key);
+        if self.nodes[origin].store.contains_key(&content_id) {
+            return LookupResult {
+                found: true,
+                hops: 0,
+                requests: 0,
+                timeouts: 0,
+            };
+        }
+        self.lookup(origin, &content_id, |peer| {
+            match self.find_content(peer, &content_id) {
+                FindContentResponse::Content => Ok(()),
+                FindContentResponse::Enrs(enrs) => Err(enrs),
+            }
+        })
+    }
+
+    /// Recursive FINDNODES lookup of a node id from node `origin`.
+    pub fn lookup_node(&self, origin: usize, target: &[u8; 32]) -> LookupResult {
+        self.lookup(origin, target, |peer| {
+            let nodes = self.find_nodes(peer, target);
+            if nodes.contains(target) {
+                Ok(())
+            } else {
+                Err(nodes)
+            }
+        })
+    }
+
+    /// Runs content lookups of random seeded keys from random online nodes.
+    pub fn run_content_lookups(&mut self, lookups: usize) -> LookupStats {
+        let online: Vec<usize> = (0..self.nodes.len())
+            .filter(|i| self.nodes[*i].online)
+            .collect();
+        if online.is_empty() || self.content_keys.is_empty() {
+            return LookupStats::from_results(&[]);
+        }
+        let results: Vec<LookupResult> = (0..lookups)
+            .map(|_| {
+                let origin = online[self.rng.below(online.len())];
+                let key = self.content_keys[self.rng.below(self.content_keys.len())].clone();
+                self.lookup_content(origin, &key)
+            })
+            .collect();
+        LookupStats::from_results(&results)
+    }
+
+    /// Runs node lookups of random online targets from random online nodes.
+    pub fn run_node_lookups(&mut self, lookups: usize) -> LookupStats {
+        let online: Vec<usize> = (0..self.nodes.len())
+            .filter(|i| self.nodes[*i].online)
+            .collect();
+        if online.len() < 2 {
+            return LookupStats::from_results(&[]);
+        }
+        let results: Vec<LookupResult> = (0..lookups)
+            .map(|_| {
+                let origin = online[self.rng.below(online.len())];
+                let target = self.nodes[online[self.rng.below(online.len())]].node_id;
+                self.lookup_node(origin, &target)
+            })
+            .collect();
+        LookupStats::from_results(&results)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vector::network_dir;
+
+    fn seeded(config: SimulationConfig) -> Simulator {
+        let mut simulator = Simulator::new(config);
+        let seeded = simulator
+            .seed_from_vectors(&network_dir("mainnet").join("history/hive"))
+            .unwrap();
+        assert!(seeded > 10);
+        simulator
+    }
+
+    #[test]
+    fn test_split_mix_is_stable() {
+        let mut rng = SplitMix64::new(1234567);
+        assert_eq!(rng.next_u64(), 6457827717110365317);
+        assert_eq!(rng.next_u64(), 3203168211198807973);
+    }
+
+    #[test]
+    fn test_lookups_without_churn() {
+        let config = SimulationConfig {
+            nodes: 64,
+            ..SimulationConfig::default()
+        };
+        let mut simulator = seeded(SimulationConfig {
+            radius: DataRadius::from_log2(253),
+            ..config
+        });
+        let stats = simulator.run_content_lookups(100);
+        assert_eq!(stats.successes, 100);
+        assert!(stats.max_hops <= 4, "{:?}", stats);
+
+        let stats = simulator.run_node_lookups(100);
+        assert_eq!(stats.successes, 100);
+        assert_eq!(stats.timeouts, 0);
+    }
+
+    #[test]
+    fn test_churn_is_deterministic_and_costs_lookups() {
+        let config = SimulationConfig {
+            nodes: 128,
+            ..SimulationConfig::default()
+        };
+        let run = |churn| {
+            let mut simulator = seeded(config);
+            simulator.apply_churn(churn);
+            simulator.run_content_lookups(200)
+        };
+        let calm = run(0.0);
+        let churned = run(0.5);
+        assert_eq!(churned, run(0.5));
+        assert!(churned.timeouts > calm.timeouts);
+        assert!(churned.success_rate() <= calm.success_rate());
+        assert!(calm.success_rate() > 0.95);
+    }
+}
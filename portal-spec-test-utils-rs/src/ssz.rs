@@ -0,0 +1,1180 @@
+use ethnum::U256;
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+pub const BYTES_PER_CHUNK: usize = 32;
+pub const BYTES_PER_LENGTH_OFFSET: usize = 4;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum SszError {
+    #[error("expected {len} bytes at {pos}")]
+    UnexpectedEnd { len: usize, pos: usize },
+    #[error("{0} trailing bytes")]
+    TrailingBytes(usize),
+    #[error("first offset {offset} does not match fixed size {fixed}")]
+    FirstOffset { offset: usize, fixed: usize },
+    #[error("offset {0} out of bounds")]
+    InvalidOffset(usize),
+    #[error("length {len} is not a multiple of {size}")]
+    InvalidLength { len: usize, size: usize },
+    #[error("{len} elements exceed the limit of {limit}")]
+    TooLong { len: usize, limit: usize },
+    #[error("invalid boolean byte 0x{0:02x}")]
+    InvalidBool(u8),
+    #[error("invalid bits: {0}")]
+    InvalidBits(&'static str),
+    #[error("unknown union selector {0}")]
+    InvalidSelector(u8),
+    #[error("value does not match type {0}")]
+    TypeMismatch(String),
+    #[error("invalid path: {0}")]
+    InvalidPath(String),
+}
+
+/// Sequential reader over the fixed part of an SSZ container.
+pub struct SszReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    offsets: Vec<usize>,
+}
+
+impl<'a> SszReader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        SszReader {
+            data,
+            pos: 0,
+            offsets: Vec::new(),
+        }
+    }
+
+    pub fn bytes(&mut self, len: usize) -> Result<&'a [u8], SszError> {
+        let slice = self
+            .data
+            .get(self.pos..self.pos + len)
+            .ok_or(SszError::UnexpectedEnd { len, pos: self.pos })?;
+        self.pos += len;
+        Ok(slice)
+    }
+
+    pub fn array<const N: usize>(&mut self) -> Result<[u8; N], SszError> {
+        Ok(self.bytes(N)?.try_into().unwrap())
+    }
+
+    pub fn u64(&mut self) -> Result<u64, SszError> {
+        Ok(u64::from_le_bytes(self.array()?))
+    }
+
+    pub fn u256(&mut self) -> Result<U256, SszError> {
+        Ok(U256::from_le_bytes(self.array()?))
+    }
+
+    pub fn roots(&mut self, count: usize) -> Result<Vec<[u8; 32]>, SszError> {
+        (0..count).map(|_| self.array()).collect()
+    }
+
+    /// Records the offset of a variable size field.
+    pub fn offset(&mut self) -> Result<(), SszError> {
+        let offset = u32::from_le_bytes(self.array()?) as usize;
+        self.offsets.push(offset);
+        Ok(())
+    }
+
+    /// Checks the offsets and returns the variable size fields in order.
+    pub fn finish(self) -> Result<Vec<&'a [u8]>, SszError> {
+        if self.offsets.is_empty() {
+            if self.pos != self.data.len() {
+                return Err(SszError::TrailingBytes(self.data.len() - self.pos));
+            }
+            return Ok(Vec::new());
+        }
+        if self.offsets[0] != self.pos {
+            return Err(SszError::FirstOffset {
+                offset: self.offsets[0],
+                fixed: self.pos,
+            });
+        }
+        let mut ends = self.offsets[1..].to_vec();
+        ends.push(self.data.len());
+        self.offsets
+            .iter()
+            .zip(ends)
+            .map(|(&start, end)| {
+                if start > end || end > self.data.len() {
+                    return Err(SszError::InvalidOffset(start));
+                }
+                Ok(&self.data[start..end])
+            })
+            .collect()
+    }
+}
+
+/// Splits a list of variable size elements, such as `List[ByteList]`.
+pub fn decode_byte_lists(data: &[u8]) -> Result<Vec<&[u8]>, SszError> {
+    if data.is_empty() {
+        return Ok(Vec::new());
+    }
+    let first = u32::from_le_bytes(
+        data.get(..BYTES_PER_LENGTH_OFFSET)
+            .ok_or(SszError::UnexpectedEnd { len: 4, pos: 0 })?
+            .try_into()
+            .unwrap(),
+    ) as usize;
+    if !first.is_multiple_of(BYTES_PER_LENGTH_OFFSET) || first == 0 {
+        return Err(SszError::InvalidOffset(first));
+    }
+    let mut reader = SszReader::new(data);
+    for _ in 0..first / BYTES_PER_LENGTH_OFFSET {
+        reader.offset()?;
+    }
+    reader.finish()
+}
+
+/// Describes an SSZ type, so values can be decoded and merkleized without derives.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SszType {
+    /// Unsigned integer of the given byte width.
+    Uint(usize),
+    Boolean,
+    ByteVector(usize),
+    ByteList(usize),
+    Bitvector(usize),
+    Bitlist(usize),
+    Vector(Box<SszType>, usize),
+    List(Box<SszType>, usize),
+    Container(Vec<(&'static str, SszType)>),
+    /// Union options, `None` being the empty option.
+    Union(Vec<Option<SszType>>),
+}
+
+/// A decoded SSZ value, shaped like its `SszType`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SszValue {
+    Uint(U256),
+    Bool(bool),
+    Bytes(Vec<u8>),
+    Bits(Vec<bool>),
+    /// Elements of a vector or list.
+    Sequence(Vec<SszValue>),
+    /// Fields of a container in declaration order.
+    Container(Vec<SszValue>),
+    Union(u8, Option<Box<SszValue>>),
+}
+
+/// One step of a path into a type, as in `get_generalized_index`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathElement<'a> {
+    Field(&'a str),
+    Index(usize),
+    Length,
+}
+
+impl SszValue {
+    pub fn uint(value: u64) -> SszValue {
+        SszValue::Uint(U256::from(value))
+    }
+
+    pub fn as_uint(&self) -> Option<U256> {
+        match self {
+            SszValue::Uint(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_u64(&self) -> Option<u64> {
+        self.as_uint().and_then(|value| u64::try_from(value).ok())
+    }
+
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            SszValue::Bytes(bytes) => Some(bytes),
+            _ => None,
+        }
+    }
+
+    /// Elements of a sequence or fields of a container.
+    pub fn as_items(&self) -> Option<&[SszValue]> {
+        match self {
+            SszValue::Sequence(items) | SszValue::Container(items) => Some(items),
+            _ => None,
+        }
+    }
+}
+
+impl SszType {
+    pub fn uint8() -> SszType {
+        SszType::Uint(1)
+    }
+
+    pub fn uint64() -> SszType {
+        SszType::Uint(8)
+    }
+
+    pub fn uint256() -> SszType {
+        SszType::Uint(32)
+    }
+
+    pub fn bytes32() -> SszType {
+        SszType::ByteVector(32)
+    }
+
+    pub fn vector(element: SszType, length: usize) -> SszType {
+        SszType::Vector(Box::new(element), length)
+    }
+
+    pub fn list(element: SszType, limit: usize) -> SszType {
+        SszType::List(Box::new(element), limit)
+    }
+
+    pub fn container(fields: impl IntoIterator<Item = (&'static str, SszType)>) -> SszType {
+        SszType::Container(fields.into_iter().collect())
+    }
+
+    pub fn union(options: impl IntoIterator<Item = Option<SszType>>) -> SszType {
+        SszType::Union(options.into_iter().collect())
+    }
+
+    fn is_basic(&self) -> bool {
+        matches!(self, SszType::Uint(_) | SszType::Boolean)
+    }
+
+    /// The serialized size, or `None` for variable size types.
+    pub fn fixed_size(&self) -> Option<usize> {
+        match self {
+            SszType::Uint(width) => Some(*width),
+            SszType::Boolean => Some(1),
+            SszType::ByteVector(length) => Some(*length),
+            SszType::Bitvector(length) => Some(length.div_ceil(8)),
+            SszType::Vector(element, length) => element.fixed_size().map(|size| size * length),
+            SszType::Container(fields) => fields.iter().map(|(_, field)| field.fixed_size()).sum(),
+            SszType::ByteList(_) | SszType::Bitlist(_) | SszType::List(..) | SszType::Union(_) => {
+                None
+            }
+        }
+    }
+
+    /// The size a field takes in the fixed part of its parent.
+    fn fixed_part_size(&self) -> usize {
+        self.fixed_size().unwrap_or(BYTES_PER_LENGTH_OFFSET)
+    }
+
+    pub fn field_index(&self, name: &str) -> Option<usize> {
+        match self {
+            SszType::Container(fields) => fields.iter().position(|(field, _)| *field == name),
+            _ => None,
+        }
+    }
+
+    pub fn decode(&self, data: &[u8]) -> Result<SszValue, SszError> {
+        match self {
+            SszType::Uint(width) => {
+                let bytes = exact(data, *width)?;
+                let mut padded = [0u8; 32];
+                padded[..bytes.len()].copy_from_slice(bytes);
+                Ok(SszValue::Uint(U256::from_le_bytes(padded)))
+            }
+            SszType::Boolean => match exact(data, 1)?[0] {
+                0 => Ok(SszValue::Bool(false)),
+                1 => Ok(SszValue::Bool(true)),
+                other => Err(SszError::InvalidBool(other)),
+            },
+            SszType::ByteVector(length) => Ok(SszValue::Bytes(exact(data, *length)?.to_vec())),
+            SszType::ByteList(limit) => {
+                check_limit(data.len(), *limit)?;
+                Ok(SszValue::Bytes(data.to_vec()))
+            }
+            SszType::Bitvector(length) => {
+                let bytes = exact(data, length.div_ceil(8))?;
+                let bits = unpack_bits(bytes, bytes.len() * 8);
+                if bits[*length..].iter().any(|bit| *bit) {
+                    return Err(SszError::InvalidBits("bits set past the length"));
+                }
+                Ok(SszValue::Bits(bits[..*length].to_vec()))
+            }
+            SszType::Bitlist(limit) => {
+                let last = *data
+                    .last()
+                    .ok_or(SszError::InvalidBits("missing delimiter"))?;
+                if last == 0 {
+                    return Err(SszError::InvalidBits("missing delimiter"));
+                }
+                let length = (data.len() - 1) * 8 + 7 - last.leading_zeros() as usize;
+                check_limit(length, *limit)?;
+                Ok(SszValue::Bits(unpack_bits(data, length)))
+            }
+            SszType::Vector(element, length) => {
+                let items = decode_elements(element, data)?;
+                if items.len() != *length {
+                    return Err(SszError::TypeMismatch(format!(
+                        "vector of {} elements, found {}",
+                        length,
+                        items.len()
+                    )));
+                }
+                Ok(SszValue::Sequence(items))
+            }
+            SszType::List(element, limit) => {
+                let items = decode_elements(element, data)?;
+                check_limit(items.len(), *limit)?;
+                Ok(SszValue::Sequence(items))
+            }
+            SszType::Container(fields) => {
+                let mut reader = SszReader::new(data);
+                let mut fixed = Vec::new();
+                for (_, field) in fields {
+                    match field.fixed_size() {
+                        Some(size) => fixed.push(Some(reader.bytes(size)?)),
+                        None => {
+                            reader.offset()?;
+                            fixed.push(None);
+                        }
+                    }
+                }
+                let mut variable = reader.finish()?.into_iter();
+                fields
+                    .iter()
+                    .zip(fixed)
+                    .map(|((_, field), bytes)| {
+                        field.decode(bytes.unwrap_or_else(|| variable.next().unwrap()))
+                    })
+                    .collect::<Result<_, _>>()
+                    .map(SszValue::Container)
+            }
+            SszType::Union(options) => {
+                let (&selector, rest) = data
+                    .split_first()
+                    .ok_or(SszError::UnexpectedEnd { len: 1, pos: 0 })?;
+                match options.get(selector as usize) {
+                    Some(Some(option)) => Ok(SszValue::Union(
+                        selector,
+                        Some(Box::new(option.decode(rest)?)),
+                    )),
+                    Some(None) if rest.is_empty() => Ok(SszValue::Union(selector, None)),
+                    Some(None) => Err(SszError::TrailingBytes(rest.len())),
+                    None => Err(SszError::InvalidSelector(selector)),
+                }
+            }
+        }
+    }
+
+    pub fn encode(&self, value: &SszValue) -> Result<Vec<u8>, SszError> {
+        let mut out = Vec::new();
+        self.encode_into(value, &mut out)?;
+        Ok(out)
+    }
+
+    fn encode_into(&self, value: &SszValue, out: &mut Vec<u8>) -> Result<(), SszError> {
+        match (self, value) {
+            (SszType::Uint(width), SszValue::Uint(value)) => {
+                let bytes = value.to_le_bytes();
+                if bytes[*width..].iter().any(|b| *b != 0) {
+                    return Err(self.mismatch());
+                }
+                out.extend_from_slice(&bytes[..*width]);
+            }
+            (SszType::Boolean, SszValue::Bool(value)) => out.push(*value as u8),
+            (SszType::ByteVector(length), SszValue::Bytes(bytes)) if bytes.len() == *length => {
+                out.extend_from_slice(bytes)
+            }
+            (SszType::ByteList(limit), SszValue::Bytes(bytes)) => {
+                check_limit(bytes.len(), *limit)?;
+                out.extend_from_slice(bytes);
+            }
+            (SszType::Bitvector(length), SszValue::Bits(bits)) if bits.len() == *length => {
+                out.extend(pack_bits(bits));
+            }
+            (SszType::Bitlist(limit), SszValue::Bits(bits)) => {
+                check_limit(bits.len(), *limit)?;
+                let mut with_delimiter = bits.clone();
+                with_delimiter.push(true);
+                out.extend(pack_bits(&with_delimiter));
+            }
+            (SszType::Vector(element, length), SszValue::Sequence(items))
+                if items.len() == *length =>
+            {
+                encode_fields(items.iter().map(|item| (&**element, item)), out)?;
+            }
+            (SszType::List(element, limit), SszValue::Sequence(items)) => {
+                check_limit(items.len(), *limit)?;
+                encode_fields(items.iter().map(|item| (&**element, item)), out)?;
+            }
+            (SszType::Container(fields), SszValue::Container(values))
+                if fields.len() == values.len() =>
+            {
+                encode_fields(fields.iter().map(|(_, field)| field).zip(values), out)?;
+            }
+            (SszType::Union(options), SszValue::Union(selector, inner)) => {
+                out.push(*selector);
+                match (options.get(*selector as usize), inner) {
+                    (Some(Some(option)), Some(inner)) => option.encode_into(inner, out)?,
+                    (Some(None), None) => {}
+                    _ => return Err(SszError::InvalidSelector(*selector)),
+                }
+            }
+            _ => return Err(self.mismatch()),
+        }
+        Ok(())
+    }
+
+    fn mismatch(&self) -> SszError {
+        SszError::TypeMismatch(format!("{:?}", self))
+    }
+
+    /// Number of leaf chunks before padding, bounded by the limit for lists.
+    fn chunk_count(&self) -> usize {
+        match self {
+            SszType::Uint(_) | SszType::Boolean => 1,
+            SszType::ByteVector(length) | SszType::ByteList(length) => {
+                length.div_ceil(BYTES_PER_CHUNK)
+            }
+            SszType::Bitvector(length) | SszType::Bitlist(length) => length.div_ceil(256),
+            SszType::Vector(element, length) | SszType::List(element, length) => {
+                match element.fixed_size().filter(|_| element.is_basic()) {
+                    Some(size) => (length * size).div_ceil(BYTES_PER_CHUNK),
+                    None => *length,
+                }
+            }
+            SszType::Container(fields) => fields.len(),
+            SszType::Union(_) => 1,
+        }
+    }
+
+    fn has_length(&self) -> bool {
+        matches!(
+            self,
+            SszType::ByteList(_) | SszType::Bitlist(_) | SszType::List(..)
+        )
+    }
+
+    pub fn hash_tree_root(&self, value: &SszValue) -> Result<[u8; 32], SszError> {
+        Ok(self.merkle_tree(value)?.root())
+    }
+
+    /// Builds the full Merkle tree of a value, for roots and proofs.
+    pub fn merkle_tree(&self, value: &SszValue) -> Result<MerkleTree, SszError> {
+        let depth = tree_depth(self.chunk_count());
+        let tree = match (self, value) {
+            (SszType::Uint(_) | SszType::Boolean, _) => {
+                MerkleTree::Leaf(pack(&self.encode(value)?)[0])
+            }
+            (SszType::ByteVector(_) | SszType::ByteList(_), SszValue::Bytes(bytes)) => {
+                self.encode(value)?;
+                MerkleTree::from_chunks(pack(bytes), depth)
+            }
+            (SszType::Bitvector(_) | SszType::Bitlist(_), SszValue::Bits(bits)) => {
+                self.encode(value)?;
+                MerkleTree::from_chunks(pack(&pack_bits(bits)), depth)
+            }
+            (
+                SszType::Vector(element, _) | SszType::List(element, _),
+                SszValue::Sequence(items),
+            ) => {
+                if element.is_basic() {
+                    MerkleTree::from_chunks(pack(&self.encode(value)?), depth)
+                } else {
+                    match self {
+                        SszType::List(_, limit) => check_limit(items.len(), *limit)?,
+                        SszType::Vector(_, length) if items.len() != *length => {
+                            return Err(self.mismatch())
+                        }
+                        _ => {}
+                    }
+                    let leaves = items
+                        .iter()
+                        .map(|item| element.merkle_tree(item))
+                        .collect::<Result<_, _>>()?;
+                    MerkleTree::build(leaves, depth)
+                }
+            }
+            (SszType::Container(fields), SszValue::Container(values))
+                if fields.len() == values.len() =>
+            {
+                let leaves = fields
+                    .iter()
+                    .zip(values)
+                    .map(|((_, field), value)| field.merkle_tree(value))
+                    .collect::<Result<_, _>>()?;
+                MerkleTree::build(leaves, depth)
+            }
+            (SszType::Union(options), SszValue::Union(selector, inner)) => {
+                let value_tree = match (options.get(*selector as usize), inner) {
+                    (Some(Some(option)), Some(inner)) => option.merkle_tree(inner)?,
+                    (Some(None), None) => MerkleTree::Zero(0),
+                    _ => return Err(SszError::InvalidSelector(*selector)),
+                };
+                return Ok(MerkleTree::node(
+                    value_tree,
+                    MerkleTree::Leaf(length_chunk(*selector as usize)),
+                ));
+            }
+            _ => return Err(self.mismatch()),
+        };
+        if self.has_length() {
+            let length = match value {
+                SszValue::Bytes(bytes) => bytes.len(),
+                SszValue::Bits(bits) => bits.len(),
+                SszValue::Sequence(items) => items.len(),
+                _ => unreachable!(),
+            };
+            return Ok(MerkleTree::node(
+                tree,
+                MerkleTree::Leaf(length_chunk(length)),
+            ));
+        }
+        Ok(tree)
+    }
+
+    /// Resolves a path to its generalized index, as in the consensus specs.
+    pub fn generalized_index(&self, path: &[PathElement]) -> Result<u64, SszError> {
+        let mut root = 1u64;
+        let mut current = self.clone();
+        for element in path {
+            if current.is_basic() {
+                return Err(SszError::InvalidPath(format!(
+                    "cannot descend into {:?}",
+                    current
+                )));
+            }
+            if *element == PathElement::Length {
+                if !current.has_length() {
+                    return Err(SszError::InvalidPath(format!(
+                        "{:?} has no length",
+                        current
+                    )));
+                }
+                root = root
+                    .checked_mul(2)
+                    .and_then(|root| root.checked_add(1))
+                    .ok_or_else(overflow)?;
+                current = SszType::uint64();
+                continue;
+            }
+            let (position, next) = current.item_position(element)?;
+            let base = if current.has_length() { 2 } else { 1 };
+            let width = current.chunk_count().max(1).next_power_of_two() as u64;
+            root = root
+                .checked_mul(base * width)
+                .and_then(|root| root.checked_add(position as u64))
+                .ok_or_else(overflow)?;
+            current = next;
+        }
+        Ok(root)
+    }
+
+    /// The chunk position of a path element and the type found there.
+    fn item_position(&self, element: &PathElement) -> Result<(usize, SszType), SszError> {
+        let out_of_range = || SszError::InvalidPath(format!("{:?} not in {:?}", element, self));
+        match (self, element) {
+            (SszType::Container(fields), PathElement::Field(name)) => {
+                let index = self.field_index(name).ok_or_else(out_of_range)?;
+                Ok((index, fields[index].1.clone()))
+            }
+            (SszType::ByteVector(length) | SszType::ByteList(length), PathElement::Index(i))
+                if i < length =>
+            {
+                Ok((i / BYTES_PER_CHUNK, SszType::uint8()))
+            }
+            (SszType::Bitvector(length) | SszType::Bitlist(length), PathElement::Index(i))
+                if i < length =>
+            {
+                Ok((i / 256, SszType::Boolean))
+            }
+            (
+                SszType::Vector(element, length) | SszType::List(element, length),
+                PathElement::Index(i),
+            ) if i < length => {
+                let position = match element.fixed_size().filter(|_| element.is_basic()) {
+                    Some(size) => i * size / BYTES_PER_CHUNK,
+                    None => *i,
+                };
+                Ok((position, (**element).clone()))
+            }
+            _ => Err(out_of_range()),
+        }
+    }
+}
+
+fn overflow() -> SszError {
+    SszError::InvalidPath("generalized index overflows".to_string())
+}
+
+fn exact(data: &[u8], len: usize) -> Result<&[u8], SszError> {
+    if data.len() < len {
+        return Err(SszError::UnexpectedEnd {
+            len,
+            pos: data.len(),
+        });
+    }
+    if data.len() > len {
+        return Err(SszError::TrailingBytes(data.len() - len));
+    }
+    Ok(data)
+}
+
+fn check_limit(len: usize, limit: usize) -> Result<(), SszError> {
+    if len > limit {
+        return Err(SszError::TooLong { len, limit });
+    }
+    Ok(())
+}
+
+fn decode_elements(element: &SszType, data: &[u8]) -> Result<Vec<SszValue>, SszError> {
+    match element.fixed_size() {
+        Some(0) => Err(SszError::TypeMismatch("zero sized element".to_string())),
+        Some(size) => {
+            if !data.len().is_multiple_of(size) {
+                return Err(SszError::InvalidLength {
+                    len: data.len(),
+                    size,
+                });
+            }
+            data.chunks(size).map(|item| element.decode(item)).collect()
+        }
+        None => decode_byte_lists(data)?
+            .into_iter()
+            .map(|item| element.decode(item))
+            .collect(),
+    }
+}
+
+fn encode_fields<'v>(
+    fields: impl Iterator<Item = (&'v SszType, &'v SszValue)> + Clone,
+    out: &mut Vec<u8>,
+) -> Result<(), SszError> {
+    let fixed_len: usize = fields.clone().map(|(ty, _)| ty.fixed_part_size()).sum();
+    let mut variable = Vec::new();
+    for (ty, value) in fields {
+        if ty.fixed_size().is_some() {
+            ty.encode_into(value, out)?;
+        } else {
+            let offset = u32::try_from(fixed_len + variable.len())
+                .map_err(|_| SszError::InvalidOffset(fixed_len + variable.len()))?;
+            out.extend_from_slice(&offset.to_le_bytes());
+            ty.encode_into(value, &mut variable)?;
+        }
+    }
+    out.extend(variable);
+    Ok(())
+}
+
+fn unpack_bits(bytes: &[u8], length: usize) -> Vec<bool> {
+    (0..length)
+        .map(|i| bytes[i / 8] & (1 << (i % 8)) != 0)
+        .collect()
+}
+
+fn pack_bits(bits: &[bool]) -> Vec<u8> {
+    let mut bytes = vec![0u8; bits.len().div_ceil(8)];
+    for (i, bit) in bits.iter().enumerate() {
+        if *bit {
+            bytes[i / 8] |= 1 << (i % 8);
+        }
+    }
+    bytes
+}
+
+/// Splits serialized basic values into zero padded chunks.
+pub fn pack(bytes: &[u8]) -> Vec<[u8; 32]> {
+    bytes
+        .chunks(BYTES_PER_CHUNK)
+        .map(|chunk| {
+            let mut padded = [0u8; 32];
+            padded[..chunk.len()].copy_from_slice(chunk);
+            padded
+        })
+        .collect()
+}
+
+fn length_chunk(length: usize) -> [u8; 32] {
+    u64_chunk(length as u64)
+}
+
+/// The leaf chunk of a `uint64`.
+pub fn u64_chunk(value: u64) -> [u8; 32] {
+    let mut chunk = [0u8; 32];
+    chunk[..8].copy_from_slice(&value.to_le_bytes());
+    chunk
+}
+
+fn tree_depth(chunk_count: usize) -> usize {
+    chunk_count.max(1).next_power_of_two().trailing_zeros() as usize
+}
+
+pub fn hash_pair(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+/// Root of a subtree of the given depth whose leaves are all zero.
+pub fn zero_hash(depth: usize) -> [u8; 32] {
+    (0..depth).fold([0u8; 32], |hash, _| hash_pair(&hash, &hash))
+}
+
+/// Merkleizes chunks padded up to the chunk count of `limit`.
+pub fn merkleize(chunks: &[[u8; 32]], limit: usize) -> [u8; 32] {
+    let leaves = chunks.iter().copied().map(MerkleTree::Leaf).collect();
+    MerkleTree::build(leaves, tree_depth(limit.max(chunks.len()))).root()
+}
+
+pub fn mix_in_length(root: &[u8; 32], length: usize) -> [u8; 32] {
+    hash_pair(root, &length_chunk(length))
+}
+
+/// A binary Merkle tree that keeps zero padding implicit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MerkleTree {
+    Leaf([u8; 32]),
+    /// A subtree of the given depth with only zero leaves.
+    Zero(usize),
+    Node(Box<MerkleTree>, Box<MerkleTree>, [u8; 32]),
+}
+
+impl MerkleTree {
+    pub fn node(left: MerkleTree, right: MerkleTree) -> MerkleTree {
+        let hash = hash_pair(&left.root(), &right.root());
+        MerkleTree::Node(Box::new(left), Box::new(right), hash)
+    }
+
+    fn from_chunks(chunks: Vec<[u8; 32]>, depth: usize) -> MerkleTree {
+        MerkleTree::build(chunks.into_iter().map(MerkleTree::Leaf).collect(), depth)
+    }
+
+    /// Places the subtrees as the leftmost leaves of a tree of the given depth.
+    fn build(mut leaves: Vec<MerkleTree>, depth: usize) -> MerkleTree {
+        if leaves.is_empty() {
+            return MerkleTree::Zero(depth);
+        }
+        if depth == 0 {
+            return leaves.pop().unwrap();
+        }
+        let half = 1usize << (depth - 1);
+        let right = if leaves.len() > half {
+            leaves.split_off(half)
+        } else {
+            Vec::new()
+        };
+        MerkleTree::node(
+            MerkleTree::build(leaves, depth - 1),
+            MerkleTree::build(right, depth - 1),
+        )
+    }
+
+    pub fn root(&self) -> [u8; 32] {
+        match self {
+            MerkleTree::Leaf(chunk) => *chunk,
+            MerkleTree::Zero(depth) => zero_hash(*depth),
+            MerkleTree::Node(_, _, hash) => *hash,
+        }
+    }
+
+    /// Returns the node at a generalized index and its siblings from the root down.
+    fn walk(&self, gindex: u64) -> Option<([u8; 32], Vec<[u8; 32]>)> {
+        if gindex == 0 {
+            return None;
+        }
+        let depth = 63 - gindex.leading_zeros() as usize;
+        let mut node = self;
+        let mut siblings = Vec::with_capacity(depth);
+        for level in (0..depth).rev() {
+            let right = gindex >> level & 1 == 1;
+            match node {
+                MerkleTree::Node(left_tree, right_tree, _) => {
+                    let (next, sibling) = if right {
+                        (right_tree, left_tree)
+                    } else {
+                        (left_tree, right_tree)
+                    };
+                    siblings.push(sibling.root());
+                    node = next;
+                }
+                MerkleTree::Zero(zero_depth) if level < *zero_depth => {
+                    siblings.extend((0..=level).map(|step| zero_hash(zero_depth - 1 - step)));
+                    return Some((zero_hash(zero_depth - level - 1), siblings));
+                }
+                _ => return None,
+            }
+        }
+        Some((node.root(), siblings))
+    }
+
+    pub fn node_at(&self, gindex: u64) -> Option<[u8; 32]> {
+        self.walk(gindex).map(|(node, _)| node)
+    }
+
+    /// The Merkle branch of a generalized index, deepest sibling first.
+    pub fn prove(&self, gindex: u64) -> Option<Vec<[u8; 32]>> {
+        self.walk(gindex).map(|(_, mut siblings)| {
+            siblings.reverse();
+            siblings
+        })
+    }
+
+    /// The nodes needed to prove all the generalized indices at once.
+    pub fn multiproof(&self, gindices: &[u64]) -> Option<Vec<[u8; 32]>> {
+        helper_indices(gindices)
+            .into_iter()
+            .map(|gindex| self.node_at(gindex))
+            .collect()
+    }
+}
+
+fn sibling(gindex: u64) -> u64 {
+    gindex ^ 1
+}
+
+fn parent(gindex: u64) -> u64 {
+    gindex / 2
+}
+
+/// Joins generalized indices of nested subtrees into one index from the outer root.
+pub fn concat_generalized_indices(indices: &[u64]) -> u64 {
+    indices.iter().fold(1, |outer, &inner| {
+        let depth = 63 - inner.leading_zeros();
+        (outer << depth) | (inner ^ (1 << depth))
+    })
+}
+
+/// Helper indices of a multiproof in descending order, as in the consensus specs.
+pub fn helper_indices(gindices: &[u64]) -> Vec<u64> {
+    let mut helpers = std::collections::BTreeSet::new();
+    let mut paths = std::collections::BTreeSet::new();
+    for &gindex in gindices {
+        let mut index = gindex;
+        while index > 1 {
+            helpers.insert(sibling(index));
+            paths.insert(index);
+            index = parent(index);
+        }
+        paths.insert(1);
+    }
+    let mut helpers: Vec<u64> = helpers.difference(&paths).copied().collect();
+    helpers.reverse();
+    helpers
+}
+
+pub fn verify_merkle_proof(
+    leaf: &[u8; 32],
+    branch: &[[u8; 32]],
+    gindex: u64,
+    root: &[u8; 32],
+) -> bool {
+    if gindex == 0 || branch.len() != 63 - gindex.leading_zeros() as usize {
+        return false;
+    }
+    let computed = branch.iter().enumerate().fold(*leaf, |node, (i, sibling)| {
+        if gindex >> i & 1 == 1 {
+            hash_pair(sibling, &node)
+        } else {
+            hash_pair(&node, sibling)
+        }
+    });
+    computed == *root
+}
+
+pub fn verify_multiproof(
+    leaves: &[[u8; 32]],
+    proof: &[[u8; 32]],
+    gindices: &[u64],
+    root: &[u8; 32],
+) -> bool {
+    let helpers = helper_indices(gindices);
+    if leaves.len() != gindices.len() || proof.len() != helpers.len() {
+        return false;
+    }
+    let mut nodes: std::collections::BTreeMap<u64, [u8; 32]> = gindices
+        .iter()
+        .copied()
+        .zip(leaves.iter().copied())
+        .chain(helpers.into_iter().zip(proof.iter().copied()))
+        .collect();
+    let mut pending: Vec<u64> = nodes.keys().copied().collect();
+    while let Some(gindex) = pending.pop() {
+        if gindex <= 1 || nodes.contains_key(&parent(gindex)) {
+            continue;
+        }
+        let Some(other) = nodes.get(&sibling(gindex)).copied() else {
+            continue;
+        };
+        let node = nodes[&gindex];
+        let hash = if gindex & 1 == 1 {
+            hash_pair(&other, &node)
+        } else {
+            hash_pair(&node, &other)
+        };
+        nodes.insert(parent(gindex), hash);
+        pending.push(parent(gindex));
+        pending.sort_unstable();
+    }
+    nodes.get(&1) == Some(root)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::content::{BlockHeaderProof, HeaderWithProof};
+    use crate::vector::{network_dir, VectorFile};
+
+    const CAPELLA_SLOT: u64 = 6209536;
+
+    fn root(hex_str: &str) -> [u8; 32] {
+        hex::decode(hex_str.trim_start_matches("0x"))
+            .unwrap()
+            .try_into()
+            .unwrap()
+    }
+
+    fn header_record() -> SszType {
+        SszType::container([
+            ("block_hash", SszType::bytes32()),
+            ("total_difficulty", SszType::uint256()),
+        ])
+    }
+
+    fn epoch_record() -> (SszType, SszValue) {
+        let ty = SszType::list(header_record(), 8192);
+        let path = network_dir("mainnet").join("history/accumulator/epoch-accumulator-00122.ssz");
+        let value = ty.decode(&std::fs::read(path).unwrap()).unwrap();
+        (ty, value)
+    }
+
+    fn historical_epochs() -> Vec<SszValue> {
+        let ty = SszType::container([(
+            "historical_epochs",
+            SszType::list(SszType::bytes32(), 1 << 17),
+        )]);
+        let path = network_dir("mainnet").join("history/accumulator/finished_accumulator.ssz");
+        let data = std::fs::read(path).unwrap();
+        let value = ty.decode(&data).unwrap();
+        assert_eq!(ty.encode(&value).unwrap(), data);
+        assert_eq!(
+            ty.hash_tree_root(&value).unwrap(),
+            root("0x8eac399e24480dce3cfe06f4bdecba51c6e5d0c46200e3e8611a0b44a3a69ff9")
+        );
+        let SszValue::Container(mut fields) = value else {
+            panic!("expected a container");
+        };
+        let SszValue::Sequence(epochs) = fields.remove(0) else {
+            panic!("expected a list");
+        };
+        epochs
+    }
+
+    #[test]
+    fn test_round_trip_and_known_encodings() {
+        let ty = SszType::container([
+            ("number", SszType::uint64()),
+            ("flag", SszType::Boolean),
+            ("bits", SszType::Bitlist(8)),
+            ("items", SszType::list(SszType::Uint(2), 4)),
+            ("option", SszType::union([None, Some(SszType::uint8())])),
+        ]);
+        let value = SszValue::Container(vec![
+            SszValue::Uint(U256::new(5)),
+            SszValue::Bool(true),
+            SszValue::Bits(vec![true, false, true]),
+            SszValue::Sequence(vec![SszValue::Uint(U256::new(0x0102))]),
+            SszValue::Union(1, Some(Box::new(SszValue::Uint(U256::new(7))))),
+        ]);
+        let encoded = ty.encode(&value).unwrap();
+        assert_eq!(
+            hex::encode(&encoded),
+            "0500000000000000011500000016000000180000000d02010107"
+        );
+        assert_eq!(ty.decode(&encoded).unwrap(), value);
+
+        let bits = SszType::Bitlist(8);
+        let empty = SszValue::Bits(Vec::new());
+        assert_eq!(
+            bits.hash_tree_root(&empty).unwrap(),
+            mix_in_length(&[0u8; 32], 0)
+        );
+        assert_eq!(
+            SszType::uint64()
+                .hash_tree_root(&SszValue::Uint(U256::new(9)))
+                .unwrap(),
+            u64_chunk(9)
+        );
+    }
+
+    #[test]
+    fn test_rejects_malformed_input() {
+        assert_eq!(SszType::Boolean.decode(&[2]), Err(SszError::InvalidBool(2)));
+        assert!(SszType::Bitlist(8).decode(&[0x00]).is_err());
+        assert_eq!(
+            SszType::Bitlist(2).decode(&[0x0f]),
+            Err(SszError::TooLong { len: 3, limit: 2 })
+        );
+        assert!(SszType::Bitvector(4).decode(&[0x10]).is_err());
+        assert_eq!(
+            SszType::list(SszType::uint64(), 1).decode(&[0u8; 16]),
+            Err(SszError::TooLong { len: 2, limit: 1 })
+        );
+        assert_eq!(
+            SszType::union([None]).decode(&[1]),
+            Err(SszError::InvalidSelector(1))
+        );
+        assert!(SszType::uint64()
+            .encode(&SszValue::Uint(U256::MAX))
+            .is_err());
+    }
+
+    #[test]
+    fn test_epoch_accumulator_proves_header() {
+        let epochs = historical_epochs();
+        assert_eq!(epochs.len(), 1897);
+        let (ty, value) = epoch_record();
+        let tree = ty.merkle_tree(&value).unwrap();
+        assert_eq!(epochs[122], SszValue::Bytes(tree.root().to_vec()));
+
+        let root_dir = network_dir("mainnet");
+        let file = VectorFile::load(
+            &root_dir,
+            &root_dir.join("history/headers_with_proof/1000010.yaml"),
+        )
+        .unwrap();
+        let entry = file.content_entries().unwrap().remove(0);
+        let header_with_proof =
+            HeaderWithProof::decode(entry.content_value.as_ref().unwrap()).unwrap();
+        let BlockHeaderProof::HistoricalHashesAccumulator(proof) = header_with_proof.proof else {
+            panic!("expected an accumulator proof");
+        };
+
+        let gindex = ty
+            .generalized_index(&[
+                PathElement::Index(1000010 % 8192),
+                PathElement::Field("block_hash"),
+            ])
+            .unwrap();
+        assert_eq!(tree.prove(gindex).unwrap(), proof);
+        assert!(verify_merkle_proof(
+            &header_with_proof.header.hash(),
+            &proof,
+            gindex,
+            &tree.root()
+        ));
+        assert!(!verify_merkle_proof(
+            &[0u8; 32],
+            &proof,
+            gindex,
+            &tree.root()
+        ));
+    }
+
+    #[test]
+    fn test_multiproof_round_trip() {
+        let (ty, value) = epoch_record();
+        let tree = ty.merkle_tree(&value).unwrap();
+        let gindices: Vec<u64> = [0, 1, 4000, 8191]
+            .into_iter()
+            .map(|i| {
+                ty.generalized_index(&[PathElement::Index(i), PathElement::Field("block_hash")])
+                    .unwrap()
+            })
+            .chain([ty.generalized_index(&[PathElement::Length]).unwrap()])
+            .collect();
+        let leaves: Vec<_> = gindices
+            .iter()
+            .map(|gindex| tree.node_at(*gindex).unwrap())
+            .collect();
+        let proof = tree.multiproof(&gindices).unwrap();
+        assert!(proof.len() < gindices.len() * 15);
+        assert!(verify_multiproof(&leaves, &proof, &gindices, &tree.root()));
+
+        let mut tampered = leaves.clone();
+        tampered[2][0] ^= 1;
+        assert!(!verify_multiproof(
+            &tampered,
+            &proof,
+            &gindices,
+            &tree.root()
+        ));
+        assert_eq!(leaves[4], u64_chunk(8192));
+    }
+
+    #[test]
+    fn test_zero_padding_proofs() {
+        let ty = SszType::list(SszType::bytes32(), 1 << 20);
+        let value = SszValue::Sequence(vec![SszValue::Bytes(vec![0xaa; 32])]);
+        let tree = ty.merkle_tree(&value).unwrap();
+        let gindex = ty.generalized_index(&[PathElement::Index(70000)]).unwrap();
+        let branch = tree.prove(gindex).unwrap();
+        assert_eq!(branch.len(), 21);
+        assert!(verify_merkle_proof(
+            &[0u8; 32],
+            &branch,
+            gindex,
+            &tree.root()
+        ));
+    }
+
+    #[test]
+    fn test_capella_block_proofs() {
+        let dir = network_dir("mainnet").join("history/headers_with_proof/block_proofs_capella");
+        let summaries_type = SszType::list(
+            SszType::container([
+                ("block_summary_root", SszType::bytes32()),
+                ("state_summary_root", SszType::bytes32()),
+            ]),
+            1 << 24,
+        );
+        let summaries = summaries_type
+            .decode(&std::fs::read(dir.join("historical_summaries_at_slot_8953856.ssz")).unwrap())
+            .unwrap();
+        let SszValue::Sequence(summaries) = summaries else {
+            panic!("expected a list");
+        };
+        let body_gindex = concat_generalized_indices(&[25, 28]);
+        assert_eq!(body_gindex, 412);
+
+        let mut verified = 0;
+        for entry in std::fs::read_dir(&dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().and_then(|e| e.to_str()) != Some("yaml") {
+                continue;
+            }
+            let yaml = crate::yaml::Document::parse(&std::fs::read_to_string(&path).unwrap())
+                .unwrap()
+                .to_value()
+                .unwrap();
+            let field = |name: &str| root(yaml[name].as_str().unwrap());
+            let branch = |name: &str| -> Vec<[u8; 32]> {
+                yaml[name]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|node| root(node.as_str().unwrap()))
+                    .collect()
+            };
+            let slot = yaml["slot"].as_u64().unwrap();
+            let SszValue::Container(summary) = &summaries[((slot - CAPELLA_SLOT) / 8192) as usize]
+            else {
+                panic!("expected a container");
+            };
+            let SszValue::Bytes(block_summary_root) = &summary[0] else {
+                panic!("expected bytes");
+            };
+
+            assert!(verify_merkle_proof(
+                &field("execution_block_header"),
+                &branch("beacon_block_body_proof"),
+                body_gindex,
+                &field("beacon_block_body_root"),
+            ));
+            assert!(verify_merkle_proof(
+                &field("beacon_block_body_root"),
+                &branch("beacon_block_header_proof"),
+                12,
+                &field("beacon_block_header_root"),
+            ));
+            assert!(verify_merkle_proof(
+                &field("beacon_block_header_root"),
+                &branch("historical_summaries_proof"),
+                8192 + slot % 8192,
+                &block_summary_root[..].try_into().unwrap(),
+            ));
+            verified += 1;
+        }
+        assert_eq!(verified, 3);
+    }
+}
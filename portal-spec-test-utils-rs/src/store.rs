@@ -0,0 +1,696 @@
+use crate::content_key::content_id;
+use crate::distance::{distance, DataRadius};
+use crate::vector::{discover, VectorError, VectorKind};
+use ethnum::U256;
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::ops::RangeBounds;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+const LOG_FILE: &str = "content.log";
+const INDEX_FILE: &str = "content.idx";
+
+const PUT: u8 = 0x01;
+const DELETE: u8 = 0x00;
+/// Tag, content id and the two lengths in front of every put record.
+const PUT_HEADER: u64 = 1 + 32 + 4 + 4;
+
+#[derive(Error, Debug)]
+pub enum StoreError {
+    #[error("failed to access {path}: {source}")]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("corrupt index {path}")]
+    CorruptIndex { path: PathBuf },
+    #[error(transparent)]
+    Vector(#[from] VectorError),
+}
+
+/// A content key and the value stored for it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoredContent {
+    pub content_key: Vec<u8>,
+    pub value: Vec<u8>,
+}
+
+impl StoredContent {
+    pub fn content_id(&self) -> [u8; 32] {
+        content_id(&self.content_key)
+    }
+
+    /// Bytes counted against the capacity of a store.
+    pub fn size(&self) -> u64 {
+        (self.content_key.len() + self.value.len()) as u64
+    }
+}
+
+/// The content ids of a store ordered by their distance from its node. Each
+/// distance belongs to exactly one content id, so the distance is the key.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DistanceIndex {
+    node_id: [u8; 32],
+    entries: BTreeMap<U256, ([u8; 32], u64)>,
+    used: u64,
+}
+
+impl DistanceIndex {
+    pub fn new(node_id: [u8; 32]) -> DistanceIndex {
+        DistanceIndex {
+            node_id,
+            ..DistanceIndex::default()
+        }
+    }
+
+    pub fn node_id(&self) -> [u8; 32] {
+        self.node_id
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Bytes of content held, keys included.
+    pub fn used(&self) -> u64 {
+        self.used
+    }
+
+    pub fn contains(&self, content_id: &[u8; 32]) -> bool {
+        self.entries
+            .contains_key(&distance(&self.node_id, content_id))
+    }
+
+    fn insert(&mut self, content_id: [u8; 32], size: u64) {
+        let previous = self
+            .entries
+            .insert(distance(&self.node_id, &content_id), (content_id, size));
+        self.used = self.used + size - previous.map_or(0, |(_, size)| size);
+    }
+
+    fn remove(&mut self, content_id: &[u8; 32]) -> bool {
+        let removed = self.entries.remove(&distance(&self.node_id, content_id));
+        self.used -= removed.map_or(0, |(_, size)| size);
+        removed.is_some()
+    }
+
+    pub fn farthest(&self) -> Option<(U256, [u8; 32])> {
+        self.entries
+            .last_key_value()
+            .map(|(distance, (id, _))| (*distance, *id))
+    }
+
+    /// Content ids whose distance from the node falls in `range`, closest first.
+    pub fn range(
+        &self,
+        range: impl RangeBounds<U256>,
+    ) -> impl Iterator<Item = (U256, [u8; 32])> + '_ {
+        self.entries
+            .range(range)
+            .map(|(distance, (id, _))| (*distance, *id))
+    }
+}
+
+/// Storage of a Portal node: content keyed by content id, kept within a radius
+/// of the node and within a capacity in bytes.
+pub trait ContentStore {
+    fn index(&self) -> &DistanceIndex;
+    fn capacity(&self) -> u64;
+    fn radius(&self) -> DataRadius;
+    fn set_radius(&mut self, radius: DataRadius);
+    fn get(&self, content_id: &[u8; 32]) -> Result<Option<StoredContent>, StoreError>;
+    /// Stores content whatever its distance and the space left.
+    fn insert(&mut self, content: StoredContent) -> Result<(), StoreError>;
+    fn remove(&mut self, content_id: &[u8; 32]) -> Result<bool, StoreError>;
+
+    fn node_id(&self) -> [u8; 32] {
+        self.index().node_id()
+    }
+
+    fn contains(&self, content_id: &[u8; 32]) -> bool {
+        self.index().contains(content_id)
+    }
+
+    /// Stores content inside the radius and prunes back to capacity. Returns
+    /// whether the content is still held afterwards.
+    fn put(&mut self, content_key: Vec<u8>, value: Vec<u8>) -> Result<bool, StoreError> {
+        let content = StoredContent { content_key, value };
+        let id = content.content_id();
+        if !self.radius().contains(&self.node_id(), &id) {
+            return Ok(false);
+        }
+        self.insert(content)?;
+        self.prune()?;
+        Ok(self.contains(&id))
+    }
+
+    /// Evicts the farthest content until the store fits its capacity, then
+    /// shrinks the radius to the farthest content kept.
+    fn prune(&mut self) -> Result<Vec<[u8; 32]>, StoreError> {
+        let mut evicted = Vec::new();
+        while self.index().used() > self.capacity() {
+            let Some((_, id)) = self.index().farthest() else {
+                break;
+            };
+            self.remove(&id)?;
+            evicted.push(id);
+        }
+        if !evicted.is_empty() {
+            let radius = self
+                .index()
+                .farthest()
+                .map_or(DataRadius::ZERO, |(distance, _)| DataRadius(distance));
+            self.set_radius(radius);
+        }
+        Ok(evicted)
+    }
+}
+
+/// Puts the valid vectors below `dir` into a store and returns how many it accepted.
+pub fn populate(store: &mut dyn ContentStore, dir: &Path) -> Result<usize, StoreError> {
+    let mut accepted = 0;
+    for file in discover(dir)? {
+        if file.kind.is_none() || file.kind == Some(VectorKind::Invalid) {
+            continue;
+        }
+        for entry in file.content_entries()? {
+            let Some(value) = entry.content_value.or(entry.offer_value) else {
+                continue;
+            };
+            if store.put(entry.content_key, value)? {
+                accepted += 1;
+            }
+        }
+    }
+    Ok(accepted)
+}
+
+#[derive(Debug, Clone)]
+pub struct MemoryStore {
+    index: DistanceIndex,
+    capacity: u64,
+    radius: DataRadius,
+    content: HashMap<[u8; 32], StoredContent>,
+}
+
+impl MemoryStore {
+    pub fn new(node_id: [u8; 32], capacity: u64) -> MemoryStore {
+        MemoryStore {
+            index: DistanceIndex::new(node_id),
+            capacity,
+            radius: DataRadius::MAX,
+            content: HashMap::new(),
+        }
+    }
+}
+
+impl ContentStore for MemoryStore {
+    fn index(&self) -> &DistanceIndex {
+        &self.index
+    }
+
+    fn capacity(&self) -> u64 {
+        self.capacity
+    }
+
+    fn radius(&self) -> DataRadius {
+        self.radius
+    }
+
+    fn set_radius(&mut self, radius: DataRadius) {
+        self.radius = radius;
+    }
+
+    fn get(&self, content_id: &[u8; 32]) -> Result<Option<StoredContent>, StoreError> {
+        Ok(self.content.get(content_id).cloned())
+    }
+
+    fn insert(&mut self, content: StoredContent) -> Result<(), StoreError> {
+        let id = content.content_id();
+        self.index.insert(id, content.size());
+        self.content.insert(id, content);
+        Ok(())
+    }
+
+    fn remove(&mut self, content_id: &[u8; 32]) -> Result<bool, StoreError> {
+        self.content.remove(content_id);
+        Ok(self.index.remove(content_id))
+    }
+}
+
+/// Where a live put record sits in the log.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct LogEntry {
+    offset: u64,
+    key_len: u32,
+    value_len: u32,
+}
+
+impl LogEntry {
+    /// Bytes of key and value, summed in u64 so two u32 lengths cannot overflow.
+    fn len(&self) -> u64 {
+        u64::from(self.key_len) + u64::from(self.value_len)
+    }
+
+    fn put_record(&self, id: &[u8; 32], content: &StoredContent) -> Vec<u8> {
+        let mut record = Vec::with_capacity((PUT_HEADER + self.len()) as usize);
+        record.push(PUT);
+        record.extend_from_slice(id);
+        record.extend_from_slice(&self.key_len.to_le_bytes());
+        record.extend_from_slice(&self.value_len.to_le_bytes());
+        record.extend_from_slice(&content.content_key);
+        record.extend_from_slice(&content.value);
+        record
+    }
+}
+
+/// A store in a directory: an append-only `content.log` of put and delete
+/// records, and a `content.idx` snapshot of the live records written on
+/// `flush` and drop. When the index is missing or older than the log, the log
+/// is replayed, dropping a torn record at its end, and the radius, which only
+/// the index records, starts again from the maximum.
+#[derive(Debug)]
+pub struct FileStore {
+    dir: PathBuf,
+    log: File,
+    log_len: u64,
+    entries: HashMap<[u8; 32], LogEntry>,
+    index: DistanceIndex,
+    capacity: u64,
+    radius: DataRadius,
+}
+
+impl FileStore {
+    pub fn open(dir: &Path, node_id: [u8; 32], capacity: u64) -> Result<FileStore, StoreError> {
+        fs::create_dir_all(dir).map_err(|source| StoreError::Io {
+            path: dir.to_path_buf(),
+            source,
+        })?;
+        let log_path = dir.join(LOG_FILE);
+        let io_error = |source| StoreError::Io {
+            path: log_path.clone(),
+            source,
+        };
+        let log = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(&log_path)
+            .map_err(io_error)?;
+        let log_len = log.metadata().map_err(io_error)?.len();
+        let mut store = FileStore {
+            dir: dir.to_path_buf(),
+            log,
+            log_len,
+            entries: HashMap::new(),
+            index: DistanceIndex::new(node_id),
+            capacity,
+            radius: DataRadius::MAX,
+        };
+        if !store.load_index()? {
+            store.replay()?;
+        }
+        store.prune()?;
+        Ok(store)
+    }
+
+    fn io_error(&self, file: &str) -> impl Fn(std::io::Error) -> StoreError {
+        let path = self.dir.join(file);
+        move |source| StoreError::Io {
+            path: path.clone(),
+            source,
+        }
+    }
+
+    /// Loads the index if it was written for the current log.
+    fn load_index(&mut self) -> Result<bool, StoreError> {
+        let path = self.dir.join(INDEX_FILE);
+        let data = match fs::read(&path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+            Err(source) => return Err(StoreError::Io { path, source }),
+        };
+        let corrupt = || StoreError::CorruptIndex { path: path.clone() };
+        let (header, records) = data.split_at_checked(40).ok_or_else(corrupt)?;
+        if records.len() % 48 != 0 {
+            return Err(corrupt());
+        }
+        let log_len = u64::from_le_bytes(header[..8].try_into().unwrap());
+        if log_len != self.log_len {
+            return Ok(false);
+        }
+        self.radius = DataRadius(U256::from_be_bytes(header[8..].try_into().unwrap()));
+        for record in records.chunks(48) {
+            let id: [u8; 32] = record[..32].try_into().unwrap();
+            let entry = LogEntry {
+                offset: u64::from_le_bytes(record[32..40].try_into().unwrap()),
+                key_len: u32::from_le_bytes(record[40..44].try_into().unwrap()),
+                value_len: u32::from_le_bytes(record[44..48].try_into().unwrap()),
+            };
+            let end = entry
+                .offset
+                .checked_add(PUT_HEADER + entry.len())
+                .ok_or_else(corrupt)?;
+            if end > log_len {
+                return Err(corrupt());
+            }
+            self.index.insert(id, entry.len());
+            self.entries.insert(id, entry);
+        }
+        Ok(true)
+    }
+
+    fn replay(&mut self) -> Result<(), StoreError> {
+        let mut data = Vec::new();
+        (&self.log)
+            .seek(SeekFrom::Start(0))
+            .and_then(|_| (&self.log).read_to_end(&mut data))
+            .map_err(self.io_error(LOG_FILE))?;
+        let mut offset = 0usize;
+        while offset < data.len() {
+            let record = &data[offset..];
+            let Some(id) = record.get(1..33) else {
+                break;
+            };
+            let id: [u8; 32] = id.try_into().unwrap();
+            match record[0] {
+                DELETE => {
+                    self.entries.remove(&id);
+                    self.index.remove(&id);
+                    offset += 33;
+                }
+                PUT => {
+                    let Some(lengths) = record.get(33..41) else {
+                        break;
+                    };
+                    let key_len = u32::from_le_bytes(lengths[..4].try_into().unwrap());
+                    let value_len = u32::from_le_bytes(lengths[4..].try_into().unwrap());
+                    let entry = LogEntry {
+                        offset: offset as u64,
+                        key_len,
+                        value_len,
+                    };
+                    let len = PUT_HEADER + entry.len();
+                    if (record.len() as u64) < len {
+                        break;
+                    }
+                    self.entries.insert(id, entry);
+                    self.index.insert(id, entry.len());
+                    offset += len as usize;
+                }
+                _ => break,
+            }
+        }
+        if offset < data.len() {
+            self.log
+                .set_len(offset as u64)
+                .map_err(self.io_error(LOG_FILE))?;
+            self.log_len = offset as u64;
+        }
+        Ok(())
+    }
+
+    fn append(&mut self, record: &[u8]) -> Result<u64, StoreError> {
+        let offset = self.log_len;
+        self.log
+            .write_all(record)
+            .map_err(self.io_error(LOG_FILE))?;
+        self.log_len += record.len() as u64;
+        Ok(offset)
+    }
+
+    /// Writes the index for the current log, replacing the previous one at once.
+    pub fn flush(&self) -> Result<(), StoreError> {
+        let mut out = Vec::with_capacity(40 + self.entries.len() * 48);
+        out.extend_from_slice(&self.log_len.to_le_bytes());
+        out.extend_from_slice(&self.radius.0.to_be_bytes());
+        for (_, id) in self.index.range(..) {
+            let entry = self.entries[&id];
+            out.extend_from_slice(&id);
+            out.extend_from_slice(&entry.offset.to_le_bytes());
+            out.extend_from_slice(&entry.key_len.to_le_bytes());
+            out.extend_from_slice(&entry.value_len.to_le_bytes());
+        }
+        let temporary = self.dir.join(format!("{}.tmp", INDEX_FILE));
+        fs::write(&temporary, out)
+            .and_then(|_| fs::rename(&temporary, self.dir.join(INDEX_FILE)))
+            .map_err(self.io_error(INDEX_FILE))
+    }
+
+    /// Rewrites the log with only the live records. The new log is written and
+    /// synced beside the old one and renamed over it, so a crash leaves one of
+    /// the two whole.
+    pub fn compact(&mut self) -> Result<(), StoreError> {
+        let temporary_name = format!("{}.tmp", LOG_FILE);
+        let temporary = self.dir.join(&temporary_name);
+        let mut log = Vec::new();
+        let mut entries = HashMap::with_capacity(self.entries.len());
+        for (_, id) in self.index.range(..) {
+            let content = self.get(&id)?.expect("indexed content is in the log");
+            let entry = LogEntry {
+                offset: log.len() as u64,
+                ..self.entries[&id]
+            };
+            log.extend_from_slice(&entry.put_record(&id, &content));
+            entries.insert(id, entry);
+        }
+        File::create(&temporary)
+            .and_then(|mut file| {
+                file.write_all(&log)?;
+                file.sync_all()
+            })
+            .map_err(self.io_error(&temporary_name))?;
+        let path = self.dir.join(LOG_FILE);
+        fs::rename(&temporary, &path).map_err(self.io_error(LOG_FILE))?;
+        self.log = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .open(&path)
+            .map_err(self.io_error(LOG_FILE))?;
+        self.log_len = log.len() as u64;
+        self.entries = entries;
+        self.flush()
+    }
+}
+
+impl ContentStore for FileStore {
+    fn index(&self) -> &DistanceIndex {
+        &self.index
+    }
+
+    fn capacity(&self) -> u64 {
+        self.capacity
+    }
+
+    fn radius(&self) -> DataRadius {
+        self.radius
+    }
+
+    fn set_radius(&mut self, radius: DataRadius) {
+        self.radius = radius;
+    }
+
+    fn get(&self, content_id: &[u8; 32]) -> Result<Option<StoredContent>, StoreError> {
+        let Some(entry) = self.entries.get(content_id) else {
+            return Ok(None);
+        };
+        let mut data = vec![0u8; entry.len() as usize];
+        (&self.log)
+            .seek(SeekFrom::Start(entry.offset + PUT_HEADER))
+            .and_then(|_| (&self.log).read_exact(&mut data))
+            .map_err(self.io_error(LOG_FILE))?;
+        let value = data.split_off(entry.key_len as usize);
+        Ok(Some(StoredContent {
+            content_key: data,
+            value,
+        }))
+    }
+
+    fn insert(&mut self, content: StoredContent) -> Result<(), StoreError> {
+        let id = content.content_id();
+        let entry = LogEntry {
+            offset: self.log_len,
+            key_len: content.content_key.len() as u32,
+            value_len: content.value.len() as u32,
+        };
+        self.append(&entry.put_record(&id, &content))?;
+        self.entries.insert(id, entry);
+        self.index.insert(id, content.size());
+        Ok(())
+    }
+
+    fn remove(&mut self, content_id: &[u8; 32]) -> Result<bool, StoreError> {
+        if !self.entries.contains_key(content_id) {
+            return Ok(false);
+        }
+        self.append(&[&[DELETE][..], content_id].concat())?;
+        self.entries.remove(content_id);
+        Ok(self.index.remove(content_id))
+    }
+}
+
+impl Drop for FileStore {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vector::network_dir;
+
+    fn content(index: u8, size: usize) -> StoredContent {
+        StoredContent {
+            content_key: vec![0x00, index],
+            value: vec![index; size],
+        }
+    }
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("store-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    /// Fills a store past capacity and checks what an in-memory store would keep.
+    fn check_eviction(store: &mut dyn ContentStore) {
+        for index in 0..20 {
+            let content = content(index, 98);
+            store.put(content.content_key, content.value).unwrap();
+        }
+        assert_eq!(store.index().len(), 10);
+        assert_eq!(store.index().used(), 1000);
+        let (farthest, _) = store.index().farthest().unwrap();
+        assert_eq!(store.radius(), DataRadius(farthest));
+
+        // Everything kept is closer than everything evicted.
+        let node_id = store.node_id();
+        for index in 0..20 {
+            let id = content(index, 0).content_id();
+            assert_eq!(store.contains(&id), distance(&node_id, &id) <= farthest);
+        }
+        let outside = (0..20)
+            .map(|index| content(index, 98))
+            .find(|content| !store.contains(&content.content_id()))
+            .unwrap();
+        assert!(!store.put(outside.content_key, outside.value).unwrap());
+
+        let half = farthest / 2;
+        let near: Vec<_> = store.index().range(..=half).collect();
+        assert!(near.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        assert!(near.iter().all(|(distance, _)| *distance <= half));
+    }
+
+    #[test]
+    fn test_memory_store_evicts_farthest() {
+        let mut store = MemoryStore::new([0x42; 32], 1000);
+        check_eviction(&mut store);
+        let (_, id) = store.index().range(..).next().unwrap();
+        assert_eq!(store.get(&id).unwrap().unwrap().content_id(), id);
+        assert!(store.remove(&id).unwrap());
+        assert_eq!(store.get(&id).unwrap(), None);
+        assert_eq!(store.index().used(), 900);
+    }
+
+    #[test]
+    fn test_file_store_survives_reopen() {
+        let dir = scratch_dir("reopen");
+        let node_id = [0x42; 32];
+        let kept: Vec<StoredContent> = {
+            let mut store = FileStore::open(&dir, node_id, 1000).unwrap();
+            check_eviction(&mut store);
+            let mut memory = MemoryStore::new(node_id, 1000);
+            check_eviction(&mut memory);
+            assert_eq!(store.index(), memory.index());
+            store
+                .index()
+                .range(..)
+                .map(|(_, id)| store.get(&id).unwrap().unwrap())
+                .collect()
+        };
+
+        // From the index written on drop.
+        let store = FileStore::open(&dir, node_id, 1000).unwrap();
+        assert_eq!(store.index().len(), kept.len());
+        let radius = store.radius();
+        for content in &kept {
+            assert_eq!(
+                store.get(&content.content_id()).unwrap().as_ref(),
+                Some(content)
+            );
+        }
+        drop(store);
+
+        // From the log, after losing the index and tearing the last record.
+        fs::remove_file(dir.join(INDEX_FILE)).unwrap();
+        let mut log = OpenOptions::new()
+            .append(true)
+            .open(dir.join(LOG_FILE))
+            .unwrap();
+        log.write_all(&[PUT, 0x01, 0x02]).unwrap();
+        let mut store = FileStore::open(&dir, node_id, 1000).unwrap();
+        assert_eq!(store.index().len(), kept.len());
+        assert_eq!(store.radius(), DataRadius::MAX);
+        store.set_radius(radius);
+        let before = fs::metadata(dir.join(LOG_FILE)).unwrap().len();
+        store.compact().unwrap();
+        assert!(fs::metadata(dir.join(LOG_FILE)).unwrap().len() < before);
+        assert!(!dir.join(format!("{}.tmp", LOG_FILE)).exists());
+        for content in &kept {
+            assert_eq!(
+                store.get(&content.content_id()).unwrap().as_ref(),
+                Some(content)
+            );
+        }
+        drop(store);
+
+        // The compacted log replays to the same content.
+        fs::remove_file(dir.join(INDEX_FILE)).unwrap();
+        let store = FileStore::open(&dir, node_id, 1000).unwrap();
+        assert_eq!(store.index().len(), kept.len());
+        drop(store);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_index_lengths_past_log_are_corrupt() {
+        let dir = scratch_dir("overflow");
+        let mut store = FileStore::open(&dir, [0x42; 32], 1000).unwrap();
+        store.insert(content(0, 10)).unwrap();
+        drop(store);
+
+        // Lengths whose u32 sum wraps around to the 12 bytes actually logged.
+        let mut index = fs::read(dir.join(INDEX_FILE)).unwrap();
+        index[80..84].copy_from_slice(&u32::MAX.to_le_bytes());
+        index[84..88].copy_from_slice(&13u32.to_le_bytes());
+        fs::write(dir.join(INDEX_FILE), &index).unwrap();
+        assert!(matches!(
+            FileStore::open(&dir, [0x42; 32], 1000),
+            Err(StoreError::CorruptIndex { .. })
+        ));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_populate_from_vectors() {
+        let mut everything = MemoryStore::new([0u8; 32], u64::MAX);
+        let accepted = populate(&mut everything, &network_dir("mainnet")).unwrap();
+        assert!(accepted > 50);
+
+        let mut half = MemoryStore::new([0u8; 32], u64::MAX);
+        half.set_radius(DataRadius::from_log2(255));
+        populate(&mut half, &network_dir("mainnet")).unwrap();
+        assert!(half.index().len() < everything.index().len());
+        assert!(half
+            .index()
+            .range(..)
+            .all(|(_, id)| id[0] & 0x80 == 0 && everything.contains(&id)));
+    }
+}
@@ -0,0 +1,600 @@
+use crate::header::keccak256;
+use crate::network::Network;
+use crate::rlp::{self, RlpError, RlpItem};
+use crate::ExecutionFork;
+use ethnum::U256;
+use k256::ecdsa::{RecoveryId, Signature, VerifyingKey};
+use thiserror::Error;
+
+/// Magic prefix of the message an EIP-7702 authorization signs.
+pub const SET_CODE_AUTHORIZATION_MAGIC: u8 = 0x05;
+pub const VERSIONED_HASH_VERSION_KZG: u8 = 0x01;
+
+/// Half the secp256k1 group order, the largest `s` allowed since Homestead (EIP-2).
+pub const SECP256K1_HALF_ORDER: U256 = U256::from_words(
+    0x7fffffffffffffffffffffffffffffff,
+    0x5d576e7357a4501ddfe92f46681b20a0,
+);
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum TransactionError {
+    #[error("invalid rlp: {0}")]
+    Rlp(#[from] RlpError),
+    #[error("empty transaction")]
+    Empty,
+    #[error("unknown transaction type 0x{0:02x}")]
+    UnknownType(u8),
+    #[error("{tx_type} transaction has {found} fields, expected {expected}")]
+    FieldCount {
+        tx_type: TxType,
+        expected: usize,
+        found: usize,
+    },
+    #[error("{0} transaction without a recipient")]
+    MissingRecipient(TxType),
+    #[error("invalid signature: {0}")]
+    InvalidSignature(String),
+    #[error("{tx_type} transaction before {fork}")]
+    NotActive { tx_type: TxType, fork: &'static str },
+    #[error("signature s value is in the upper half of the curve order")]
+    HighS,
+    #[error("blob transaction without blob hashes")]
+    NoBlobs,
+    #[error("blob hash with version 0x{0:02x}")]
+    InvalidBlobHashVersion(u8),
+    #[error("set code transaction with an empty authorization list")]
+    EmptyAuthorizationList,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TxType {
+    Legacy,
+    AccessList,
+    DynamicFee,
+    Blob,
+    SetCode,
+}
+
+impl TxType {
+    pub fn from_byte(byte: u8) -> Result<TxType, TransactionError> {
+        match byte {
+            0x01 => Ok(TxType::AccessList),
+            0x02 => Ok(TxType::DynamicFee),
+            0x03 => Ok(TxType::Blob),
+            0x04 => Ok(TxType::SetCode),
+            other => Err(TransactionError::UnknownType(other)),
+        }
+    }
+
+    pub fn byte(&self) -> u8 {
+        match self {
+            TxType::Legacy => 0x00,
+            TxType::AccessList => 0x01,
+            TxType::DynamicFee => 0x02,
+            TxType::Blob => 0x03,
+            TxType::SetCode => 0x04,
+        }
+    }
+
+    /// The fork that introduced the transaction type.
+    pub fn activation_fork(&self) -> ExecutionFork {
+        match self {
+            TxType::Legacy => ExecutionFork::Frontier,
+            TxType::AccessList => ExecutionFork::Berlin,
+            TxType::DynamicFee => ExecutionFork::London,
+            TxType::Blob => ExecutionFork::Cancun,
+            TxType::SetCode => ExecutionFork::Prague,
+        }
+    }
+
+    fn field_count(&self) -> usize {
+        match self {
+            TxType::Legacy => 9,
+            TxType::AccessList => 11,
+            TxType::DynamicFee => 12,
+            TxType::Blob => 14,
+            TxType::SetCode => 13,
+        }
+    }
+}
+
+impl std::fmt::Display for TxType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            TxType::Legacy => "legacy",
+            TxType::AccessList => "EIP-2930",
+            TxType::DynamicFee => "EIP-1559",
+            TxType::Blob => "EIP-4844",
+            TxType::SetCode => "EIP-7702",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccessListItem {
+    pub address: [u8; 20],
+    pub storage_keys: Vec<[u8; 32]>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Authorization {
+    pub chain_id: U256,
+    pub address: [u8; 20],
+    pub nonce: u64,
+    pub y_parity: bool,
+    pub r: U256,
+    pub s: U256,
+    /// The signer, `None` when the signature does not recover.
+    pub authority: Option<[u8; 20]>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transaction {
+    pub tx_type: TxType,
+    /// `None` for legacy transactions signed without EIP-155 replay protection.
+    pub chain_id: Option<u64>,
+    pub nonce: u64,
+    pub gas_price: Option<U256>,
+    pub max_priority_fee_per_gas: Option<U256>,
+    pub max_fee_per_gas: Option<U256>,
+    pub gas_limit: u64,
+    pub to: Option<[u8; 20]>,
+    pub value: U256,
+    pub input: Vec<u8>,
+    pub access_list: Vec<AccessListItem>,
+    pub max_fee_per_blob_gas: Option<U256>,
+    pub blob_versioned_hashes: Vec<[u8; 32]>,
+    pub authorization_list: Vec<Authorization>,
+    pub y_parity: bool,
+    pub r: U256,
+    pub s: U256,
+    pub hash: [u8; 32],
+    /// The hash the sender signed.
+    pub signing_hash: [u8; 32],
+}
+
+/// Recovers the address that produced a signature over `prehash`.
+pub fn recover_address(
+    prehash: &[u8; 32],
+    y_parity: bool,
+    r: U256,
+    s: U256,
+) -> Result<[u8; 20], TransactionError> {
+    let mut bytes = [0u8; 64];
+    bytes[..32].copy_from_slice(&r.to_be_bytes());
+    bytes[32..].copy_from_slice(&s.to_be_bytes());
+    let mut signature = Signature::from_slice(&bytes)
+        .map_err(|e| TransactionError::InvalidSignature(e.to_string()))?;
+    let mut y_parity = y_parity;
+    // k256 only accepts low-s signatures; frontier transactions may carry high ones.
+    if let Some(normalized) = signature.normalize_s() {
+        signature = normalized;
+        y_parity = !y_parity;
+    }
+    let key =
+        VerifyingKey::recover_from_prehash(prehash, &signature, RecoveryId::new(y_parity, false))
+            .map_err(|e| TransactionError::InvalidSignature(e.to_string()))?;
+    let point = key.to_encoded_point(false);
+    Ok(keccak256(&point.as_bytes()[1..])[12..].try_into().unwrap())
+}
+
+fn optional_address(item: &RlpItem) -> Result<Option<[u8; 20]>, RlpError> {
+    match item.as_bytes()? {
+        [] => Ok(None),
+        _ => Ok(Some(item.as_fixed()?)),
+    }
+}
+
+fn decode_access_list(item: &RlpItem) -> Result<Vec<AccessListItem>, RlpError> {
+    item.as_list()?
+        .iter()
+        .map(|entry| {
+            let fields = entry.as_list()?;
+            if fields.len() != 2 {
+                return Err(RlpError::ItemCount {
+                    expected: 2,
+                    found: fields.len(),
+                });
+            }
+            Ok(AccessListItem {
+                address: fields[0].as_fixed()?,
+                storage_keys: fields[1]
+                    .as_list()?
+                    .iter()
+                    .map(RlpItem::as_fixed)
+                    .collect::<Result<_, _>>()?,
+            })
+        })
+        .collect()
+}
+
+fn decode_y_parity(item: &RlpItem) -> Result<bool, TransactionError> {
+    match item.as_u64()? {
+        0 => Ok(false),
+        1 => Ok(true),
+        other => Err(TransactionError::InvalidSignature(format!(
+            "y parity {}",
+            other
+        ))),
+    }
+}
+
+fn decode_authorization(item: &RlpItem) -> Result<Authorization, TransactionError> {
+    let fields = item.as_list()?;
+    if fields.len() != 6 {
+        return Err(RlpError::ItemCount {
+            expected: 6,
+            found: fields.len(),
+        }
+        .into());
+    }
+    let mut message = vec![SET_CODE_AUTHORIZATION_MAGIC];
+    encode_items(&mut message, &fields[..3]);
+    let y_parity = decode_y_parity(&fields[3])?;
+    let r = fields[4].as_u256()?;
+    let s = fields[5].as_u256()?;
+    Ok(Authorization {
+        chain_id: fields[0].as_u256()?,
+        address: fields[1].as_fixed()?,
+        nonce: fields[2].as_u64()?,
+        y_parity,
+        r,
+        s,
+        authority: recover_address(&keccak256(&message), y_parity, r, s).ok(),
+    })
+}
+
+fn encode_items(out: &mut Vec<u8>, items: &[RlpItem]) {
+    let mut payload = Vec::new();
+    for item in items {
+        rlp::encode_item(&mut payload, item);
+    }
+    rlp::encode_list(out, &payload);
+}
+
+impl Transaction {
+    /// Decodes a transaction as it appears in a block body: the RLP list for legacy
+    /// transactions, the type byte followed by the RLP payload for typed ones.
+    pub fn decode(data: &[u8]) -> Result<Transaction, TransactionError> {
+        let first = *data.first().ok_or(TransactionError::Empty)?;
+        let (tx_type, payload) = if first >= 0xc0 {
+            (TxType::Legacy, data)
+        } else {
+            (TxType::from_byte(first)?, &data[1..])
+        };
+        let item = rlp::decode(payload)?;
+        let fields = item.as_list()?;
+        if fields.len() != tx_type.field_count() {
+            return Err(TransactionError::FieldCount {
+                tx_type,
+                expected: tx_type.field_count(),
+                found: fields.len(),
+            });
+        }
+
+        let mut tx = Transaction {
+            tx_type,
+            chain_id: None,
+            nonce: 0,
+            gas_price: None,
+            max_priority_fee_per_gas: None,
+            max_fee_per_gas: None,
+            gas_limit: 0,
+            to: None,
+            value: U256::ZERO,
+            input: Vec::new(),
+            access_list: Vec::new(),
+            max_fee_per_blob_gas: None,
+            blob_versioned_hashes: Vec::new(),
+            authorization_list: Vec::new(),
+            y_parity: false,
+            r: U256::ZERO,
+            s: U256::ZERO,
+            hash: keccak256(data),
+            signing_hash: [0; 32],
+        };
+        let signature = &fields[fields.len() - 3..];
+        tx.r = signature[1].as_u256()?;
+        tx.s = signature[2].as_u256()?;
+
+        if tx_type == TxType::Legacy {
+            tx.nonce = fields[0].as_u64()?;
+            tx.gas_price = Some(fields[1].as_u256()?);
+            tx.gas_limit = fields[2].as_u64()?;
+            tx.to = optional_address(&fields[3])?;
+            tx.value = fields[4].as_u256()?;
+            tx.input = fields[5].as_bytes()?.to_vec();
+            let v = signature[0].as_u64()?;
+            let mut unsigned = Vec::new();
+            match v {
+                27 | 28 => {
+                    tx.y_parity = v == 28;
+                    encode_items(&mut unsigned, &fields[..6]);
+                }
+                v if v >= 35 => {
+                    let chain_id = (v - 35) / 2;
+                    tx.chain_id = Some(chain_id);
+                    tx.y_parity = (v - 35) % 2 == 1;
+                    let mut payload = Vec::new();
+                    for item in &fields[..6] {
+                        rlp::encode_item(&mut payload, item);
+                    }
+                    rlp::encode_u64(&mut payload, chain_id);
+                    rlp::encode_u64(&mut payload, 0);
+                    rlp::encode_u64(&mut payload, 0);
+                    rlp::encode_list(&mut unsigned, &payload);
+                }
+                other => {
+                    return Err(TransactionError::InvalidSignature(format!("v {}", other)));
+                }
+            }
+            tx.signing_hash = keccak256(&unsigned);
+            return Ok(tx);
+        }
+
+        tx.chain_id = Some(fields[0].as_u64()?);
+        tx.nonce = fields[1].as_u64()?;
+        let rest = if tx_type == TxType::AccessList {
+            tx.gas_price = Some(fields[2].as_u256()?);
+            &fields[3..]
+        } else {
+            tx.max_priority_fee_per_gas = Some(fields[2].as_u256()?);
+            tx.max_fee_per_gas = Some(fields[3].as_u256()?);
+            &fields[4..]
+        };
+        tx.gas_limit = rest[0].as_u64()?;
+        tx.to = optional_address(&rest[1])?;
+        tx.value = rest[2].as_u256()?;
+        tx.input = rest[3].as_bytes()?.to_vec();
+        tx.access_list = decode_access_list(&rest[4])?;
+        match tx_type {
+            TxType::Blob => {
+                tx.max_fee_per_blob_gas = Some(rest[5].as_u256()?);
+                tx.blob_versioned_hashes = rest[6]
+                    .as_list()?
+                    .iter()
+                    .map(RlpItem::as_fixed)
+                    .collect::<Result<_, _>>()?;
+            }
+            TxType::SetCode => {
+                tx.authorization_list = rest[5]
+                    .as_list()?
+                    .iter()
+                    .map(decode_authorization)
+                    .collect::<Result<_, _>>()?;
+            }
+            _ => {}
+        }
+        if matches!(tx_type, TxType::Blob | TxType::SetCode) && tx.to.is_none() {
+            return Err(TransactionError::MissingRecipient(tx_type));
+        }
+        tx.y_parity = decode_y_parity(&signature[0])?;
+
+        let mut unsigned = vec![tx_type.byte()];
+        encode_items(&mut unsigned, &fields[..fields.len() - 3]);
+        tx.signing_hash = keccak256(&unsigned);
+        Ok(tx)
+    }
+
+    pub fn recover_sender(&self) -> Result<[u8; 20], TransactionError> {
+        recover_address(&self.signing_hash, self.y_parity, self.r, self.s)
+    }
+
+    /// Checks the rules that depend on the fork of the including block.
+    pub fn check_fork(&self, network: &Network, block_number: u64) -> Result<(), TransactionError> {
+        let fork = network.execution_fork(block_number);
+        let since = self.tx_type.activation_fork();
+        if fork < since {
+            return Err(TransactionError::NotActive {
+                tx_type: self.tx_type,
+                fork: since.name(),
+            });
+        }
+        if fork >= ExecutionFork::Homestead && self.s > SECP256K1_HALF_ORDER {
+            return Err(TransactionError::HighS);
+        }
+        if self.tx_type == TxType::Blob {
+            if self.blob_versioned_hashes.is_empty() {
+                return Err(TransactionError::NoBlobs);
+            }
+            if let Some(hash) = self
+                .blob_versioned_hashes
+                .iter()
+                .find(|hash| hash[0] != VERSIONED_HASH_VERSION_KZG)
+            {
+                return Err(TransactionError::InvalidBlobHashVersion(hash[0]));
+            }
+        }
+        if self.tx_type == TxType::SetCode && self.authorization_list.is_empty() {
+            return Err(TransactionError::EmptyAuthorizationList);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::content::{BlockBody, HeaderWithProof};
+    use crate::content_key::{ContentKey, HistoryContentKey};
+    use crate::vector::{decode_hex, discover, network_dir, VectorKind};
+    use k256::ecdsa::SigningKey;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_eip155_example() {
+        // The example transaction from EIP-155, signed with key 0x4646..46.
+        let raw = decode_hex("0xf86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83").unwrap();
+        let tx = Transaction::decode(&raw).unwrap();
+        assert_eq!(tx.tx_type, TxType::Legacy);
+        assert_eq!(tx.chain_id, Some(1));
+        assert_eq!(tx.nonce, 9);
+        assert_eq!(tx.value, U256::new(1_000_000_000_000_000_000));
+        assert_eq!(
+            hex::encode(tx.signing_hash),
+            "daf5a779ae972f972197303d7b574746c7ef83eadac0f2791ad23db92e4c8e53"
+        );
+        assert_eq!(
+            hex::encode(tx.recover_sender().unwrap()),
+            "9d8a62f656a8d1615c1294fd71e9cfb3e4855a4f"
+        );
+        assert_eq!(
+            tx.check_fork(&Network::mainnet(), crate::BERLIN_BLOCK_NUMBER),
+            Ok(())
+        );
+    }
+
+    fn sign_typed(tx_type: TxType, fields: &[Vec<u8>], key: &SigningKey) -> Vec<u8> {
+        let mut payload: Vec<u8> = fields.concat();
+        let mut unsigned = vec![tx_type.byte()];
+        rlp::encode_list(&mut unsigned, &payload);
+        let (signature, recovery_id) = key.sign_prehash_recoverable(&keccak256(&unsigned)).unwrap();
+        let bytes = signature.to_bytes();
+        rlp::encode_u64(&mut payload, recovery_id.is_y_odd() as u64);
+        rlp::encode_u256(
+            &mut payload,
+            U256::from_be_bytes(bytes[..32].try_into().unwrap()),
+        );
+        rlp::encode_u256(
+            &mut payload,
+            U256::from_be_bytes(bytes[32..].try_into().unwrap()),
+        );
+        let mut out = vec![tx_type.byte()];
+        rlp::encode_list(&mut out, &payload);
+        out
+    }
+
+    fn item(encode: impl Fn(&mut Vec<u8>)) -> Vec<u8> {
+        let mut out = Vec::new();
+        encode(&mut out);
+        out
+    }
+
+    fn address_of(key: &SigningKey) -> [u8; 20] {
+        let point = key.verifying_key().to_encoded_point(false);
+        keccak256(&point.as_bytes()[1..])[12..].try_into().unwrap()
+    }
+
+    #[test]
+    fn test_blob_and_set_code_transactions() {
+        let sender = SigningKey::from_slice(&[0x11; 32]).unwrap();
+        let authority = SigningKey::from_slice(&[0x22; 32]).unwrap();
+        let common = |fields: &mut Vec<Vec<u8>>| {
+            fields.push(item(|o| rlp::encode_u64(o, 1)));
+            fields.push(item(|o| rlp::encode_u64(o, 7)));
+            fields.push(item(|o| rlp::encode_u64(o, 2_000_000_000)));
+            fields.push(item(|o| rlp::encode_u64(o, 30_000_000_000)));
+            fields.push(item(|o| rlp::encode_u64(o, 100_000)));
+            fields.push(item(|o| rlp::encode_bytes(o, &[0xaa; 20])));
+            fields.push(item(|o| rlp::encode_u64(o, 0)));
+            fields.push(item(|o| rlp::encode_bytes(o, &[])));
+            fields.push(item(|o| rlp::encode_list(o, &[])));
+        };
+
+        let mut blob_fields = Vec::new();
+        common(&mut blob_fields);
+        blob_fields.push(item(|o| rlp::encode_u64(o, 1)));
+        let mut hashes = Vec::new();
+        rlp::encode_bytes(&mut hashes, &[0x01; 32]);
+        blob_fields.push(item(|o| rlp::encode_list(o, &hashes)));
+        let blob = Transaction::decode(&sign_typed(TxType::Blob, &blob_fields, &sender)).unwrap();
+        assert_eq!(blob.blob_versioned_hashes, vec![[0x01; 32]]);
+        assert_eq!(blob.recover_sender().unwrap(), address_of(&sender));
+        assert_eq!(
+            blob.check_fork(&Network::mainnet(), crate::CANCUN_BLOCK_NUMBER),
+            Ok(())
+        );
+        assert_eq!(
+            blob.check_fork(&Network::mainnet(), crate::CANCUN_BLOCK_NUMBER - 1),
+            Err(TransactionError::NotActive {
+                tx_type: TxType::Blob,
+                fork: "cancun"
+            })
+        );
+
+        let mut message = Vec::new();
+        rlp::encode_u64(&mut message, 1);
+        rlp::encode_bytes(&mut message, &[0xbb; 20]);
+        rlp::encode_u64(&mut message, 3);
+        let mut signed = vec![SET_CODE_AUTHORIZATION_MAGIC];
+        rlp::encode_list(&mut signed, &message);
+        let (signature, recovery_id) = authority
+            .sign_prehash_recoverable(&keccak256(&signed))
+            .unwrap();
+        let bytes = signature.to_bytes();
+        rlp::encode_u64(&mut message, recovery_id.is_y_odd() as u64);
+        rlp::encode_bytes(&mut message, &bytes[..32]);
+        rlp::encode_bytes(&mut message, &bytes[32..]);
+        let mut authorizations = Vec::new();
+        rlp::encode_list(&mut authorizations, &message);
+
+        let mut set_code_fields = Vec::new();
+        common(&mut set_code_fields);
+        set_code_fields.push(item(|o| rlp::encode_list(o, &authorizations)));
+        let set_code =
+            Transaction::decode(&sign_typed(TxType::SetCode, &set_code_fields, &sender)).unwrap();
+        assert_eq!(set_code.recover_sender().unwrap(), address_of(&sender));
+        let authorization = &set_code.authorization_list[0];
+        assert_eq!(authorization.address, [0xbb; 20]);
+        assert_eq!(authorization.nonce, 3);
+        assert_eq!(authorization.authority, Some(address_of(&authority)));
+        assert_eq!(
+            set_code.check_fork(&Network::mainnet(), crate::PRAGUE_BLOCK_NUMBER),
+            Ok(())
+        );
+        assert!(set_code
+            .check_fork(&Network::mainnet(), crate::CANCUN_BLOCK_NUMBER)
+            .is_err());
+    }
+
+    #[test]
+    fn test_body_vector_transactions() {
+        let mut block_numbers = HashMap::new();
+        let mut bodies = Vec::new();
+        for file in discover(&network_dir("mainnet")).unwrap() {
+            if file.kind == Some(VectorKind::Invalid) {
+                continue;
+            }
+            for entry in file.content_entries().unwrap() {
+                let (Ok(ContentKey::History(key)), Some(value)) = (
+                    ContentKey::decode(&entry.content_key),
+                    entry.content_value.as_ref(),
+                ) else {
+                    continue;
+                };
+                match key {
+                    HistoryContentKey::BlockHeaderByHash(hash) => {
+                        let header = HeaderWithProof::decode(value).unwrap().header;
+                        block_numbers.insert(hash, header.number);
+                    }
+                    HistoryContentKey::BlockBody(hash) => {
+                        bodies.push((hash, BlockBody::decode(value).unwrap()))
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let mut types = Vec::new();
+        let mut checked = 0;
+        for (hash, body) in bodies {
+            let number = block_numbers.get(&hash).copied();
+            for raw in &body.transactions {
+                let tx = Transaction::decode(raw).unwrap();
+                tx.recover_sender().unwrap();
+                if let Some(number) = number {
+                    tx.check_fork(&Network::mainnet(), number)
+                        .unwrap_or_else(|e| panic!("block {}: {}", number, e));
+                    checked += 1;
+                }
+                if !types.contains(&tx.tx_type) {
+                    types.push(tx.tx_type);
+                }
+            }
+        }
+        assert!(checked > 100);
+        assert!(types.contains(&TxType::Legacy));
+        assert!(types.contains(&TxType::DynamicFee));
+    }
+}
@@ -0,0 +1,295 @@
+use crate::content_key::Nibbles;
+use crate::header::keccak256;
+use crate::rlp::{self, RlpError, RlpItem};
+use ethnum::U256;
+
+/// RLP of the empty byte string, which stands for an empty trie or a missing child.
+const EMPTY_STRING: u8 = 0x80;
+
+/// A child reference: a hash, or a node under 32 bytes embedded in its parent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NodeRef {
+    Hash([u8; 32]),
+    Inline(Vec<u8>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TrieNodeKind {
+    Branch {
+        children: Vec<Option<NodeRef>>,
+        value: Vec<u8>,
+    },
+    Extension {
+        path: Nibbles,
+        child: NodeRef,
+    },
+    Leaf {
+        path: Nibbles,
+        value: Vec<u8>,
+    },
+}
+
+/// A Merkle Patricia Trie node together with its encoding.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrieNode {
+    pub encoded: Vec<u8>,
+    pub kind: TrieNodeKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Account {
+    pub nonce: u64,
+    pub balance: U256,
+    pub storage_root: [u8; 32],
+    pub code_hash: [u8; 32],
+}
+
+impl Account {
+    pub fn decode(data: &[u8]) -> Result<Account, RlpError> {
+        let item = rlp::decode(data)?;
+        let fields = item.as_list()?;
+        if fields.len() != 4 {
+            return Err(RlpError::ItemCount {
+                expected: 4,
+                found: fields.len(),
+            });
+        }
+        Ok(Account {
+            nonce: fields[0].as_u64()?,
+            balance: fields[1].as_u256()?,
+            storage_root: fields[2].as_fixed()?,
+            code_hash: fields[3].as_fixed()?,
+        })
+    }
+}
+
+fn node_ref(item: &RlpItem) -> Result<Option<NodeRef>, RlpError> {
+    match item {
+        RlpItem::Bytes([]) => Ok(None),
+        RlpItem::Bytes(_) => Ok(Some(NodeRef::Hash(item.as_fixed()?))),
+        RlpItem::List(_) => {
+            let mut encoded = Vec::new();
+            rlp::encode_item(&mut encoded, item);
+            Ok(Some(NodeRef::Inline(encoded)))
+        }
+    }
+}
+
+/// Decodes a hex-prefix encoded path, returning it and whether it ends in a leaf.
+pub fn decode_compact_path(data: &[u8]) -> Result<(Nibbles, bool), RlpError> {
+    let first = *data.first().ok_or(RlpError::UnexpectedEnd(0))?;
+    let flag = first >> 4;
+    if flag > 3 || (flag & 1 == 0 && first & 0x0f != 0) {
+        return Err(RlpError::NonCanonical(0));
+    }
+    let mut nibbles = Vec::with_capacity(data.len() * 2);
+    if flag & 1 == 1 {
+        nibbles.push(first & 0x0f);
+    }
+    for byte in &data[1..] {
+        nibbles.push(byte >> 4);
+        nibbles.push(byte & 0x0f);
+    }
+    Ok((Nibbles(nibbles), flag >= 2))
+}
+
+/// Hex-prefix encodes a path, the inverse of `decode_compact_path`.
+pub fn encode_compact_path(nibbles: &[u8], leaf: bool) -> Vec<u8> {
+    let flag = if leaf { 2 } else { 0 } + (nibbles.len() % 2) as u8;
+    let mut out = Vec::with_capacity(nibbles.len() / 2 + 1);
+    let rest = if nibbles.len() % 2 == 1 {
+        out.push(flag << 4 | nibbles[0]);
+        &nibbles[1..]
+    } else {
+        out.push(flag << 4);
+        nibbles
+    };
+    for pair in rest.chunks(2) {
+        out.push(pair[0] << 4 | pair[1]);
+    }
+    out
+}
+
+/// The root of the trie an execution block commits a list to: the RLP of each
+/// index keys its item, as in the transactions, receipts and withdrawals roots.
+pub fn ordered_trie_root<T: AsRef<[u8]>>(items: &[T]) -> [u8; 32] {
+    let mut entries: Vec<(Vec<u8>, &[u8])> = items
+        .iter()
+        .enumerate()
+        .map(|(index, item)| {
+            let mut key = Vec::new();
+            rlp::encode_u64(&mut key, index as u64);
+            let nibbles = key
+                .iter()
+                .flat_map(|byte| [byte >> 4, byte & 0x0f])
+                .collect();
+            (nibbles, item.as_ref())
+        })
+        .collect();
+    entries.sort();
+    keccak256(&encode_trie(&entries, 0))
+}
+
+fn encode_trie(entries: &[(Vec<u8>, &[u8])], depth: usize) -> Vec<u8> {
+    let mut payload = Vec::new();
+    match entries {
+        [] => return vec![EMPTY_STRING],
+        [(path, value)] => {
+            rlp::encode_bytes(&mut payload, &encode_compact_path(&path[depth..], true));
+            rlp::encode_bytes(&mut payload, value);
+        }
+        [(first, _), .., (last, _)] => {
+            let shared = first[depth..]
+                .iter()
+                .zip(&last[depth..])
+                .take_while(|(a, b)| a == b)
+                .count();
+            if shared > 0 {
+                let path = &first[depth..depth + shared];
+                rlp::encode_bytes(&mut payload, &encode_compact_path(path, false));
+                encode_child(&mut payload, encode_trie(entries, depth + shared));
+            } else {
+                let mut value: &[u8] = &[];
+                let mut rest = entries;
+                if let Some(((path, ended), tail)) = rest.split_first() {
+                    if path.len() == depth {
+                        value = ended;
+                        rest = tail;
+                    }
+                }
+                for nibble in 0..16 {
+                    let end = rest.partition_point(|(path, _)| path[depth] <= nibble);
+                    let (child, tail) = rest.split_at(end);
+                    if child.is_empty() {
+                        payload.push(EMPTY_STRING);
+                    } else {
+                        encode_child(&mut payload, encode_trie(child, depth + 1));
+                    }
+                    rest = tail;
+                }
+                rlp::encode_bytes(&mut payload, value);
+            }
+        }
+    }
+    let mut out = Vec::new();
+    rlp::encode_list(&mut out, &payload);
+    out
+}
+
+/// References a child by hash, or inline when its encoding is under 32 bytes.
+fn encode_child(out: &mut Vec<u8>, encoded: Vec<u8>) {
+    if encoded.len() < 32 {
+        out.extend_from_slice(&encoded);
+    } else {
+        rlp::encode_bytes(out, &keccak256(&encoded));
+    }
+}
+
+impl TrieNode {
+    pub fn decode(data: &[u8]) -> Result<TrieNode, RlpError> {
+        let item = rlp::decode(data)?;
+        let fields = item.as_list()?;
+        let kind = match fields.len() {
+            17 => TrieNodeKind::Branch {
+                children: fields[..16]
+                    .iter()
+                    .map(node_ref)
+                    .collect::<Result<_, _>>()?,
+                value: fields[16].as_bytes()?.to_vec(),
+            },
+            2 => {
+                let (path, is_leaf) = decode_compact_path(fields[0].as_bytes()?)?;
+                if is_leaf {
+                    TrieNodeKind::Leaf {
+                        path,
+                        value: fields[1].as_bytes()?.to_vec(),
+                    }
+                } else {
+                    TrieNodeKind::Extension {
+                        path,
+                        child: node_ref(&fields[1])?.ok_or(RlpError::ExpectedBytes)?,
+                    }
+                }
+            }
+            found => {
+                return Err(RlpError::ItemCount {
+                    expected: 17,
+                    found,
+                })
+            }
+        };
+        Ok(TrieNode {
+            encoded: data.to_vec(),
+            kind,
+        })
+    }
+
+    pub fn hash(&self) -> [u8; 32] {
+        keccak256(&self.encoded)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vector::{decode_hex, network_dir};
+
+    #[test]
+    fn test_decode_trie_node_vector() {
+        let text = std::fs::read_to_string(
+            network_dir("mainnet").join("state/serialization/trie_node.yaml"),
+        )
+        .unwrap();
+        let doc: serde_yaml::Value = serde_yaml::from_str(&text).unwrap();
+        let node = decode_hex(doc["trie_node"].as_str().unwrap()).unwrap();
+        let node = TrieNode::decode(&node).unwrap();
+        let TrieNodeKind::Leaf { path, value } = node.kind else {
+            panic!("expected a leaf node");
+        };
+        assert_eq!(path.0.len(), 56);
+        assert_eq!(Account::decode(&value).unwrap().nonce, 1);
+    }
+
+    #[test]
+    fn test_ordered_trie_root() {
+        let empty: [&[u8]; 0] = [];
+        assert_eq!(
+            hex::encode(ordered_trie_root(&empty)),
+            "56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421"
+        );
+        // Keys 0x80 for the first transaction and single bytes for the rest.
+        let root = network_dir("mainnet");
+        let value = |relative: &str| {
+            let file = crate::vector::VectorFile::load(&root, &root.join(relative)).unwrap();
+            file.content_entries().unwrap()[0]
+                .content_value
+                .clone()
+                .unwrap()
+        };
+        let header = crate::content::HeaderWithProof::decode(&value(
+            "history/headers_with_proof/14764013.yaml",
+        ))
+        .unwrap()
+        .header;
+        let body =
+            crate::content::BlockBody::decode(&value("history/bodies/14764013.yaml")).unwrap();
+        assert_eq!(body.transactions.len(), 19);
+        assert_eq!(
+            ordered_trie_root(&body.transactions),
+            header.transactions_root
+        );
+    }
+
+    #[test]
+    fn test_compact_path() {
+        assert_eq!(
+            decode_compact_path(&[0x20, 0x0f]).unwrap(),
+            (Nibbles(vec![0, 15]), true)
+        );
+        assert_eq!(
+            decode_compact_path(&[0x11, 0x23]).unwrap(),
+            (Nibbles(vec![1, 2, 3]), false)
+        );
+        assert!(decode_compact_path(&[0x05]).is_err());
+    }
+}
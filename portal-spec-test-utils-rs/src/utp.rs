@@ -0,0 +1,903 @@
+use crate::simulator::SplitMix64;
+use std::collections::{HashMap, VecDeque};
+use std::io;
+use std::net::{SocketAddr, UdpSocket};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+pub const HEADER_SIZE: usize = 20;
+pub const VERSION: u8 = 1;
+/// Payload bytes per DATA packet.
+pub const MAX_PAYLOAD: usize = 1024;
+/// LEDBAT target queuing delay.
+pub const TARGET_DELAY_MICROS: f64 = 100_000.0;
+/// LEDBAT gain, the most the window may grow per round trip.
+pub const MAX_CWND_INCREASE_BYTES_PER_RTT: f64 = 3000.0;
+pub const RECEIVE_WINDOW: u32 = 1 << 20;
+const SELECTIVE_ACK_EXTENSION: u8 = 1;
+/// Packets past `ack_nr + 1` a selective ack can describe.
+const MAX_SELECTIVE_ACK_BITS: usize = 256;
+
+#[derive(Debug, Error)]
+pub enum UtpError {
+    #[error("packet of {0} bytes is shorter than the header")]
+    TooShort(usize),
+    #[error("unsupported version {0}")]
+    UnsupportedVersion(u8),
+    #[error("unknown packet type {0}")]
+    UnknownPacketType(u8),
+    #[error("extension runs past the end of the packet")]
+    TruncatedExtension,
+    #[error("connection reset by peer")]
+    Reset,
+    #[error("timed out waiting for {0}")]
+    TimedOut(&'static str),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PacketType {
+    Data = 0,
+    Fin = 1,
+    State = 2,
+    Reset = 3,
+    Syn = 4,
+}
+
+impl PacketType {
+    fn from_u8(value: u8) -> Result<PacketType, UtpError> {
+        Ok(match value {
+            0 => PacketType::Data,
+            1 => PacketType::Fin,
+            2 => PacketType::State,
+            3 => PacketType::Reset,
+            4 => PacketType::Syn,
+            _ => return Err(UtpError::UnknownPacketType(value)),
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Packet {
+    pub packet_type: PacketType,
+    pub connection_id: u16,
+    pub timestamp: u32,
+    pub timestamp_diff: u32,
+    pub wnd_size: u32,
+    pub seq_nr: u16,
+    pub ack_nr: u16,
+    /// Bitmask of packets received past `ack_nr + 1`, least significant bit first.
+    pub selective_ack: Option<Vec<u8>>,
+    pub payload: Vec<u8>,
+}
+
+impl Packet {
+    pub fn decode(data: &[u8]) -> Result<Packet, UtpError> {
+        if data.len() < HEADER_SIZE {
+            return Err(UtpError::TooShort(data.len()));
+        }
+        if data[0] & 0x0f != VERSION {
+            return Err(UtpError::UnsupportedVersion(data[0] & 0x0f));
+        }
+        let u16_at = |pos: usize| u16::from_be_bytes([data[pos], data[pos + 1]]);
+        let u32_at = |pos: usize| u32::from_be_bytes(data[pos..pos + 4].try_into().unwrap());
+        let mut selective_ack = None;
+        let mut extension = data[1];
+        let mut pos = HEADER_SIZE;
+        while extension != 0 {
+            if pos + 2 > data.len() || pos + 2 + data[pos + 1] as usize > data.len() {
+                return Err(UtpError::TruncatedExtension);
+            }
+            let (next, len) = (data[pos], data[pos + 1] as usize);
+            if extension == SELECTIVE_ACK_EXTENSION {
+                selective_ack = Some(data[pos + 2..pos + 2 + len].to_vec());
+            }
+            extension = next;
+            pos += 2 + len;
+        }
+        Ok(Packet {
+            packet_type: PacketType::from_u8(data[0] >> 4)?,
+            connection_id: u16_at(2),
+            timestamp: u32_at(4),
+            timestamp_diff: u32_at(8),
+            wnd_size: u32_at(12),
+            seq_nr: u16_at(16),
+            ack_nr: u16_at(18),
+            selective_ack,
+            payload: data[pos..].to_vec(),
+        })
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(HEADER_SIZE + self.payload.len());
+        out.push((self.packet_type as u8) << 4 | VERSION);
+        out.push(if self.selective_ack.is_some() {
+            SELECTIVE_ACK_EXTENSION
+        } else {
+            0
+        });
+        out.extend_from_slice(&self.connection_id.to_be_bytes());
+        out.extend_from_slice(&self.timestamp.to_be_bytes());
+        out.extend_from_slice(&self.timestamp_diff.to_be_bytes());
+        out.extend_from_slice(&self.wnd_size.to_be_bytes());
+        out.extend_from_slice(&self.seq_nr.to_be_bytes());
+        out.extend_from_slice(&self.ack_nr.to_be_bytes());
+        if let Some(mask) = &self.selective_ack {
+            out.push(0);
+            out.push(mask.len() as u8);
+            out.extend_from_slice(mask);
+        }
+        out.extend_from_slice(&self.payload);
+        out
+    }
+
+    /// Sequence numbers the selective ack marks as received.
+    pub fn selectively_acked(&self) -> Vec<u16> {
+        let Some(mask) = &self.selective_ack else {
+            return Vec::new();
+        };
+        (0..mask.len() * 8)
+            .filter(|bit| mask[bit / 8] & (1 << (bit % 8)) != 0)
+            .map(|bit| self.ack_nr.wrapping_add(2 + bit as u16))
+            .collect()
+    }
+}
+
+/// Whether `a` comes before `b` in wrapping sequence space.
+fn seq_lt(a: u16, b: u16) -> bool {
+    a != b && b.wrapping_sub(a) < 0x8000
+}
+
+/// Which end of a Portal exchange opens the connection.
+///
+/// For FINDCONTENT the requester initiates and reads what the responder writes; for OFFER
+/// the offering node initiates and writes. Either way the id comes from the node that answered,
+/// in its CONTENT or ACCEPT message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    Initiator,
+    Acceptor,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConnectionIds {
+    /// Carried by packets sent to this end, and by the SYN.
+    pub recv: u16,
+    /// Carried by every other packet this end sends.
+    pub send: u16,
+}
+
+impl ConnectionIds {
+    pub fn new(connection_id: [u8; 2], role: Role) -> ConnectionIds {
+        let id = u16::from_be_bytes(connection_id);
+        match role {
+            Role::Initiator => ConnectionIds {
+                recv: id,
+                send: id.wrapping_add(1),
+            },
+            Role::Acceptor => ConnectionIds {
+                recv: id.wrapping_add(1),
+                send: id,
+            },
+        }
+    }
+}
+
+/// LEDBAT congestion window, in bytes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ledbat {
+    pub cwnd: f64,
+    /// Lowest one way delay seen, standing in for the propagation delay.
+    pub base_delay: Option<u32>,
+}
+
+impl Default for Ledbat {
+    fn default() -> Self {
+        Ledbat {
+            cwnd: 2.0 * MAX_PAYLOAD as f64,
+            base_delay: None,
+        }
+    }
+}
+
+impl Ledbat {
+    pub fn window(&self) -> usize {
+        self.cwnd as usize
+    }
+
+    /// Grows or shrinks the window by how far the queuing delay is from the target.
+    pub fn on_ack(&mut self, bytes_acked: usize, delay_micros: u32) {
+        let base = self
+            .base_delay
+            .map_or(delay_micros, |b| b.min(delay_micros));
+        self.base_delay = Some(base);
+        let queuing = (delay_micros - base) as f64;
+        let off_target = (TARGET_DELAY_MICROS - queuing) / TARGET_DELAY_MICROS;
+        self.cwnd += MAX_CWND_INCREASE_BYTES_PER_RTT * off_target * bytes_acked as f64 / self.cwnd;
+        self.cwnd = self.cwnd.clamp(MAX_PAYLOAD as f64, RECEIVE_WINDOW as f64);
+    }
+
+    pub fn on_loss(&mut self) {
+        self.cwnd = (self.cwnd / 2.0).max(MAX_PAYLOAD as f64);
+    }
+
+    pub fn on_timeout(&mut self) {
+        self.cwnd = MAX_PAYLOAD as f64;
+    }
+}
+
+/// Loss and reordering applied to every packet a stream sends.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct LinkConditions {
+    /// Share of packets dropped.
+    pub loss: f64,
+    /// Share of packets held back and sent after the next one.
+    pub reorder: f64,
+    pub seed: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UtpConfig {
+    pub initial_timeout: Duration,
+    pub min_timeout: Duration,
+    /// Transmissions of one packet before the connection is given up.
+    pub max_transmissions: u32,
+    pub idle_timeout: Duration,
+    pub link: LinkConditions,
+}
+
+impl Default for UtpConfig {
+    fn default() -> Self {
+        UtpConfig {
+            initial_timeout: Duration::from_secs(1),
+            min_timeout: Duration::from_millis(500),
+            max_transmissions: 8,
+            idle_timeout: Duration::from_secs(20),
+            link: LinkConditions::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TransferStats {
+    pub packets_sent: usize,
+    pub retransmissions: usize,
+    pub dropped: usize,
+    pub reordered: usize,
+    pub duplicates_received: usize,
+}
+
+struct Link {
+    conditions: LinkConditions,
+    rng: SplitMix64,
+    held: Option<Vec<u8>>,
+}
+
+impl Link {
+    fn send(
+        &mut self,
+        socket: &UdpSocket,
+        peer: SocketAddr,
+        bytes: Vec<u8>,
+        stats: &mut TransferStats,
+    ) -> io::Result<()> {
+        stats.packets_sent += 1;
+        if self.rng.unit() < self.conditions.loss {
+            stats.dropped += 1;
+            return Ok(());
+        }
+        if self.held.is_none() && self.rng.unit() < self.conditions.reorder {
+            stats.reordered += 1;
+            self.held = Some(bytes);
+            return Ok(());
+        }
+        socket.send_to(&bytes, peer)?;
+        self.flush(socket, peer)
+    }
+
+    fn flush(&mut self, socket: &UdpSocket, peer: SocketAddr) -> io::Result<()> {
+        if let Some(held) = self.held.take() {
+            socket.send_to(&held, peer)?;
+        }
+        Ok(())
+    }
+}
+
+fn timestamp() -> u32 {
+    static EPOCH: OnceLock<Instant> = OnceLock::new();
+    EPOCH.get_or_init(Instant::now).elapsed().as_micros() as u32
+}
+
+struct Sent {
+    packet: Packet,
+    sent_at: Instant,
+    transmissions: u32,
+    fast_retransmitted: bool,
+}
+
+/// One side of a uTP connection, driven by blocking calls on the current thread.
+///
+/// A stream carries data one way, as Portal uses it: one end calls `write_all` and `close`,
+/// the other `read_to_end`.
+pub struct UtpStream {
+    socket: UdpSocket,
+    peer: SocketAddr,
+    ids: ConnectionIds,
+    config: UtpConfig,
+    link: Link,
+    established: bool,
+    /// Sequence number of the next DATA or FIN.
+    seq_nr: u16,
+    /// Last sequence number received in order.
+    ack_nr: u16,
+    syn_seq_nr: u16,
+    reply_diff: u32,
+    peer_window: u32,
+    pub ledbat: Ledbat,
+    srtt: Option<(f64, f64)>,
+    in_flight: VecDeque<Sent>,
+    last_ack: u16,
+    dup_acks: u32,
+    last_loss: Option<Instant>,
+    received: HashMap<u16, Vec<u8>>,
+    fin_seq_nr: Option<u16>,
+    eof: bool,
+    inbox: Vec<u8>,
+    last_heard: Instant,
+    pub stats: TransferStats,
+}
+
+impl UtpStream {
+    fn new(
+        socket: UdpSocket,
+        peer: SocketAddr,
+        connection_id: [u8; 2],
+        role: Role,
+        config: UtpConfig,
+    ) -> UtpStream {
+        let ids = ConnectionIds::new(connection_id, role);
+        let mut rng = SplitMix64::new(config.link.seed ^ ((ids.recv as u64) << 16));
+        let seq_nr = rng.next_u64() as u16;
+        UtpStream {
+            socket,
+            peer,
+            ids,
+            config,
+            link: Link {
+                conditions: config.link,
+                rng,
+                held: None,
+            },
+            established: false,
+            seq_nr,
+            ack_nr: 0,
+            syn_seq_nr: 0,
+            reply_diff: 0,
+            peer_window: RECEIVE_WINDOW,
+            ledbat: Ledbat::default(),
+            srtt: None,
+            in_flight: VecDeque::new(),
+            last_ack: 0,
+            dup_acks: 0,
+            last_loss: None,
+            received: HashMap::new(),
+            fin_seq_nr: None,
+            eof: false,
+            inbox: Vec::new(),
+            last_heard: Instant::now(),
+            stats: TransferStats::default(),
+        }
+    }
+
+    /// Opens a connection to `peer` with the id from a CONTENT or ACCEPT message.
+    pub fn connect(
+        socket: UdpSocket,
+        peer: SocketAddr,
+        connection_id: [u8; 2],
+        config: UtpConfig,
+    ) -> Result<UtpStream, UtpError> {
+        let mut stream = UtpStream::new(socket, peer, connection_id, Role::Initiator, config);
+        let mut syn = stream.packet(PacketType::Syn, Vec::new());
+        syn.connection_id = stream.ids.recv;
+        syn.seq_nr = stream.seq_nr;
+        stream.seq_nr = stream.seq_nr.wrapping_add(1);
+        stream.transmit(syn)?;
+        while !stream.established {
+            stream.step()?;
+        }
+        Ok(stream)
+    }
+
+    /// Waits for the SYN of a connection this node announced in a CONTENT or ACCEPT message.
+    pub fn accept(
+        socket: UdpSocket,
+        connection_id: [u8; 2],
+        config: UtpConfig,
+    ) -> Result<UtpStream, UtpError> {
+        let ids = ConnectionIds::new(connection_id, Role::Acceptor);
+        socket.set_read_timeout(Some(config.idle_timeout))?;
+        let mut buf = [0u8; 2048];
+        let (syn, peer) = loop {
+            let (len, from) = match socket.recv_from(&mut buf) {
+                Ok(received) => received,
+                Err(e) if is_timeout(&e) => return Err(UtpError::TimedOut("SYN")),
+                Err(e) => return Err(e.into()),
+            };
+            if let Ok(packet) = Packet::decode(&buf[..len]) {
+                if packet.packet_type == PacketType::Syn && packet.connection_id == ids.send {
+                    break (packet, from);
+                }
+            }
+        };
+        let mut stream = UtpStream::new(socket, peer, connection_id, Role::Acceptor, config);
+        stream.established = true;
+        stream.syn_seq_nr = syn.seq_nr;
+        stream.ack_nr = syn.seq_nr;
+        stream.reply_diff = timestamp().wrapping_sub(syn.timestamp);
+        stream.seq_nr = stream.seq_nr.wrapping_add(1);
+        stream.send_state()?;
+        Ok(stream)
+    }
+
+    pub fn peer(&self) -> SocketAddr {
+        self.peer
+    }
+
+    pub fn write_all(&mut self, data: &[u8]) -> Result<(), UtpError> {
+        let mut chunks = data.chunks(MAX_PAYLOAD).peekable();
+        while let Some(chunk) = chunks.peek() {
+            let window = self.ledbat.window().min(self.peer_window as usize);
+            if self.in_flight.is_empty() || self.bytes_in_flight() + chunk.len() <= window {
+                let packet = self.packet(PacketType::Data, chunk.to_vec());
+                self.seq_nr = self.seq_nr.wrapping_add(1);
+                self.transmit(packet)?;
+                chunks.next();
+            } else {
+                self.step()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Sends FIN and waits until everything written has been acknowledged.
+    pub fn close(&mut self) -> Result<(), UtpError> {
+        let fin = self.packet(PacketType::Fin, Vec::new());
+        self.seq_nr = self.seq_nr.wrapping_add(1);
+        self.transmit(fin)?;
+        while !self.in_flight.is_empty() {
+            self.step()?;
+        }
+        Ok(())
+    }
+
+    /// Reads until the peer's FIN, then stays around to acknowledge retransmissions.
+    pub fn read_to_end(&mut self) -> Result<Vec<u8>, UtpError> {
+        while !self.eof {
+            self.step()?;
+        }
+        let linger = Instant::now() + self.config.min_timeout * 4;
+        while Instant::now() < linger {
+            self.step()?;
+        }
+        Ok(std::mem::take(&mut self.inbox))
+    }
+
+    fn bytes_in_flight(&self) -> usize {
+        self.in_flight.iter().map(|s| s.packet.payload.len()).sum()
+    }
+
+    fn packet(&self, packet_type: PacketType, payload: Vec<u8>) -> Packet {
+        Packet {
+            packet_type,
+            connection_id: self.ids.send,
+            timestamp: 0,
+            timestamp_diff: 0,
+            wnd_size: 0,
+            seq_nr: self.seq_nr,
+            ack_nr: self.ack_nr,
+            selective_ack: None,
+            payload,
+        }
+    }
+
+    fn send(&mut self, mut packet: Packet) -> Result<(), UtpError> {
+        packet.timestamp = timestamp();
+        packet.timestamp_diff = self.reply_diff;
+        let buffered: usize = self.received.values().map(Vec::len).sum();
+        packet.wnd_size = RECEIVE_WINDOW.saturating_sub(buffered as u32);
+        if packet.packet_type != PacketType::Syn {
+            packet.ack_nr = self.ack_nr;
+        }
+        self.link
+            .send(&self.socket, self.peer, packet.encode(), &mut self.stats)?;
+        Ok(())
+    }
+
+    /// Sends a packet that must be acknowledged and keeps it for retransmission.
+    fn transmit(&mut self, packet: Packet) -> Result<(), UtpError> {
+        self.send(packet.clone())?;
+        self.in_flight.push_back(Sent {
+            packet,
+            sent_at: Instant::now(),
+            transmissions: 1,
+            fast_retransmitted: false,
+        });
+        Ok(())
+    }
+
+    fn retransmit(&mut self, index: usize) -> Result<(), UtpError> {
+        let packet = self.in_flight[index].packet.clone();
+        self.send(packet)?;
+        let sent = &mut self.in_flight[index];
+        sent.sent_at = Instant::now();
+        sent.transmissions += 1;
+        self.stats.retransmissions += 1;
+        if sent.transmissions > self.config.max_transmissions {
+            return Err(UtpError::TimedOut("an acknowledgement"));
+        }
+        Ok(())
+    }
+
+    fn send_state(&mut self) -> Result<(), UtpError> {
+        let mut state = self.packet(PacketType::State, Vec::new());
+        state.seq_nr = self.seq_nr.wrapping_sub(1);
+        state.selective_ack = self.selective_ack();
+        self.send(state)
+    }
+
+    fn selective_ack(&self) -> Option<Vec<u8>> {
+        let offsets: Vec<usize> = self
+            .received
+            .keys()
+            .map(|seq| seq.wrapping_sub(self.ack_nr).wrapping_sub(2) as usize)
+            .filter(|offset| *offset < MAX_SELECTIVE_ACK_BITS)
+            .collect();
+        let last = *offsets.iter().max()?;
+        let mut mask = vec![0u8; (last / 32 + 1) * 4];
+        for offset in offsets {
+            mask[offset / 8] |= 1 << (offset % 8);
+        }
+        Some(mask)
+    }
+
+    fn timeout(&self) -> Duration {
+        match self.srtt {
+            Some((srtt, rttvar)) => {
+                Duration::from_secs_f64(srtt + 4.0 * rttvar).max(self.config.min_timeout)
+            }
+            None => self.config.initial_timeout,
+        }
+    }
+
+    fn deadline(&self, sent: &Sent) -> Instant {
+        sent.sent_at + self.timeout() * 2u32.pow(sent.transmissions.min(6) - 1)
+    }
+
+    /// Waits for one packet or the next retransmission timer, whichever comes first.
+    fn step(&mut self) -> Result<(), UtpError> {
+        let now = Instant::now();
+        let mut wait = Duration::from_millis(50);
+        if self.link.held.is_some() {
+            wait = Duration::from_millis(2);
+        }
+        if let Some(next) = self.in_flight.iter().map(|s| self.deadline(s)).min() {
+            wait = wait.min(next.saturating_duration_since(now));
+        }
+        self.socket
+            .set_read_timeout(Some(wait.max(Duration::from_millis(1))))?;
+        let mut buf = [0u8; 2048];
+        match self.socket.recv_from(&mut buf) {
+            Ok((len, from)) if from == self.peer => {
+                if let Ok(packet) = Packet::decode(&buf[..len]) {
+                    self.handle(packet)?;
+                }
+            }
+            Ok(_) => {}
+            Err(e) if is_timeout(&e) => {}
+            Err(e) => return Err(e.into()),
+        }
+        self.link.flush(&self.socket, self.peer)?;
+        if self.last_heard.elapsed() > self.config.idle_timeout {
+            return Err(UtpError::TimedOut("the peer"));
+        }
+        let now = Instant::now();
+        let expired: Vec<usize> = (0..self.in_flight.len())
+            .filter(|i| self.deadline(&self.in_flight[*i]) <= now)
+            .collect();
+        if !expired.is_empty() {
+            self.ledbat.on_timeout();
+        }
+        for index in expired {
+            self.retransmit(index)?;
+        }
+        Ok(())
+    }
+
+    fn handle(&mut self, packet: Packet) -> Result<(), UtpError> {
+        if packet.connection_id != self.ids.recv {
+            if packet.packet_type == PacketType::Syn
+                && packet.connection_id == self.ids.send
+                && packet.seq_nr == self.syn_seq_nr
+            {
+                self.last_heard = Instant::now();
+                self.send_state()?;
+            }
+            return Ok(());
+        }
+        self.last_heard = Instant::now();
+        self.reply_diff = timestamp().wrapping_sub(packet.timestamp);
+        self.peer_window = packet.wnd_size;
+        match packet.packet_type {
+            PacketType::Reset => return Err(UtpError::Reset),
+            PacketType::Syn => return Ok(()),
+            PacketType::State if !self.established => {
+                self.established = true;
+                self.ack_nr = packet.seq_nr;
+                self.process_ack(&packet);
+                self.deliver();
+            }
+            _ if !self.established => {
+                // Data overtook the STATE answering our SYN; keep it until we know where it goes.
+                self.received.insert(packet.seq_nr, packet.payload);
+            }
+            PacketType::State => self.process_ack(&packet),
+            PacketType::Data | PacketType::Fin => {
+                self.process_ack(&packet);
+                if !seq_lt(self.ack_nr, packet.seq_nr) || self.received.contains_key(&packet.seq_nr)
+                {
+                    self.stats.duplicates_received += 1;
+                } else if packet.packet_type == PacketType::Fin {
+                    self.fin_seq_nr = Some(packet.seq_nr);
+                } else {
+                    self.received.insert(packet.seq_nr, packet.payload);
+                }
+                self.deliver();
+                self.send_state()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Moves data that is now in order to the inbox.
+    fn deliver(&mut self) {
+        loop {
+            let next = self.ack_nr.wrapping_add(1);
+            if let Some(payload) = self.received.remove(&next) {
+                self.inbox.extend_from_slice(&payload);
+                self.ack_nr = next;
+            } else if self.fin_seq_nr == Some(next) {
+                self.ack_nr = next;
+                self.eof = true;
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn process_ack(&mut self, packet: &Packet) {
+        let mut bytes_acked = 0;
+        let mut progressed = false;
+        while let Some(sent) = self.in_flight.front() {
+            if seq_lt(packet.ack_nr, sent.packet.seq_nr) {
+                break;
+            }
+            let sent = self.in_flight.pop_front().unwrap();
+            if sent.transmissions == 1 {
+                self.sample_rtt(sent.sent_at.elapsed());
+            }
+            bytes_acked += sent.packet.payload.len();
+            progressed = true;
+        }
+        let sacked = packet.selectively_acked();
+        if !sacked.is_empty() {
+            let before = self.in_flight.len();
+            let mut kept = VecDeque::with_capacity(before);
+            for sent in self.in_flight.drain(..) {
+                if sacked.contains(&sent.packet.seq_nr) {
+                    bytes_acked += sent.packet.payload.len();
+                } else {
+                    kept.push_back(sent);
+                }
+            }
+            self.in_flight = kept;
+        }
+        let mut lost: Vec<usize> = Vec::new();
+        for (index, sent) in self.in_flight.iter().enumerate() {
+            let acked_after = sacked
+                .iter()
+                .filter(|seq| seq_lt(sent.packet.seq_nr, **seq))
+                .count();
+            if acked_after >= 3 && !sent.fast_retransmitted {
+                lost.push(index);
+            }
+        }
+        if packet.ack_nr == self.last_ack && !progressed && !self.in_flight.is_empty() {
+            self.dup_acks += 1;
+            if self.dup_acks == 3 && !self.in_flight[0].fast_retransmitted && !lost.contains(&0) {
+                lost.insert(0, 0);
+            }
+        } else {
+            self.dup_acks = 0;
+        }
+        self.last_ack = packet.ack_nr;
+        if bytes_acked > 0 && packet.timestamp_diff != 0 {
+            self.ledbat.on_ack(bytes_acked, packet.timestamp_diff);
+        }
+        if !lost.is_empty() {
+            let rtt = self.timeout();
+            if self.last_loss.is_none_or(|at| at.elapsed() > rtt) {
+                self.ledbat.on_loss();
+                self.last_loss = Some(Instant::now());
+            }
+            for index in lost {
+                self.in_flight[index].fast_retransmitted = true;
+                // A failed send shows up again through the retransmission timer.
+                let _ = self.retransmit(index);
+            }
+        }
+    }
+
+    fn sample_rtt(&mut self, rtt: Duration) {
+        let rtt = rtt.as_secs_f64();
+        self.srtt = Some(match self.srtt {
+            None => (rtt, rtt / 2.0),
+            Some((srtt, rttvar)) => (
+                0.875 * srtt + 0.125 * rtt,
+                0.75 * rttvar + 0.25 * (srtt - rtt).abs(),
+            ),
+        });
+    }
+}
+
+fn is_timeout(e: &io::Error) -> bool {
+    matches!(
+        e.kind(),
+        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::content_key::{ContentKey, HistoryContentKey};
+    use crate::vector::{discover, network_dir, VectorKind};
+    use crate::wire::{ContentResponse, Message};
+    use std::thread;
+
+    fn largest_values() -> (Vec<u8>, Vec<u8>) {
+        let (mut body, mut receipts) = (Vec::new(), Vec::new());
+        for file in discover(&network_dir("mainnet").join("history")).unwrap() {
+            if file.kind == Some(VectorKind::Invalid) {
+                continue;
+            }
+            for entry in file.content_entries().unwrap() {
+                let (Ok(key), Some(value)) =
+                    (ContentKey::decode(&entry.content_key), entry.content_value)
+                else {
+                    continue;
+                };
+                let largest = match key {
+                    ContentKey::History(HistoryContentKey::BlockBody(_)) => &mut body,
+                    ContentKey::History(HistoryContentKey::Receipts(_)) => &mut receipts,
+                    _ => continue,
+                };
+                if value.len() > largest.len() {
+                    *largest = value;
+                }
+            }
+        }
+        (body, receipts)
+    }
+
+    fn lossy(seed: u64) -> UtpConfig {
+        UtpConfig {
+            initial_timeout: Duration::from_millis(100),
+            min_timeout: Duration::from_millis(20),
+            max_transmissions: 20,
+            idle_timeout: Duration::from_secs(10),
+            link: LinkConditions {
+                loss: 0.1,
+                reorder: 0.1,
+                seed,
+            },
+        }
+    }
+
+    fn bind() -> UdpSocket {
+        UdpSocket::bind("127.0.0.1:0").unwrap()
+    }
+
+    #[test]
+    fn test_packet_encoding() {
+        let packet = Packet {
+            packet_type: PacketType::State,
+            connection_id: 0x1234,
+            timestamp: 1,
+            timestamp_diff: 2,
+            wnd_size: RECEIVE_WINDOW,
+            seq_nr: 5,
+            ack_nr: 7,
+            selective_ack: Some(vec![0x05, 0, 0, 0]),
+            payload: Vec::new(),
+        };
+        let encoded = packet.encode();
+        assert_eq!(
+            hex::encode(&encoded),
+            "2101123400000001000000020010000000050007000405000000"
+        );
+        assert_eq!(Packet::decode(&encoded).unwrap(), packet);
+        assert_eq!(packet.selectively_acked(), vec![9, 11]);
+        assert!(matches!(
+            Packet::decode(&encoded[..19]),
+            Err(UtpError::TooShort(19))
+        ));
+
+        let ids = ConnectionIds::new([0xff, 0xff], Role::Initiator);
+        assert_eq!((ids.recv, ids.send), (0xffff, 0));
+        let ids = ConnectionIds::new([0xff, 0xff], Role::Acceptor);
+        assert_eq!((ids.recv, ids.send), (0, 0xffff));
+    }
+
+    #[test]
+    fn test_ledbat_window() {
+        let mut ledbat = Ledbat::default();
+        ledbat.on_ack(MAX_PAYLOAD, 1_000);
+        let grown = ledbat.cwnd;
+        assert!(grown > 2.0 * MAX_PAYLOAD as f64);
+        ledbat.on_ack(MAX_PAYLOAD, 301_000);
+        assert!(ledbat.cwnd < grown);
+        ledbat.on_loss();
+        assert!(ledbat.cwnd < grown / 2.0 + 1.0);
+        ledbat.on_timeout();
+        assert_eq!(ledbat.window(), MAX_PAYLOAD);
+    }
+
+    #[test]
+    fn test_transfer_with_loss_and_reordering() {
+        let (body, receipts) = largest_values();
+        assert!(body.len() > 50_000 && receipts.len() > 10_000);
+
+        // FINDCONTENT: the responder announces a connection id and writes, the requester reads.
+        let response = Message::Content(ContentResponse::ConnectionId([0x12, 0x34]))
+            .encode()
+            .unwrap();
+        let Message::Content(ContentResponse::ConnectionId(connection_id)) =
+            Message::decode(&response).unwrap()
+        else {
+            panic!("expected a connection id");
+        };
+        let (responder, requester) = (bind(), bind());
+        let responder_addr = responder.local_addr().unwrap();
+        let value = body.clone();
+        let writer = thread::spawn(move || {
+            let mut stream = UtpStream::accept(responder, connection_id, lossy(1)).unwrap();
+            stream.write_all(&value).unwrap();
+            stream.close().unwrap();
+            stream.stats
+        });
+        let mut stream =
+            UtpStream::connect(requester, responder_addr, connection_id, lossy(2)).unwrap();
+        assert_eq!(stream.read_to_end().unwrap(), body);
+        let stats = writer.join().unwrap();
+        assert!(
+            stats.dropped > 0 && stats.retransmissions > 0,
+            "{:?}",
+            stats
+        );
+
+        // OFFER: the accepting node announces the id and reads, the offering node writes.
+        let (acceptor, offerer) = (bind(), bind());
+        let acceptor_addr = acceptor.local_addr().unwrap();
+        let reader = thread::spawn(move || {
+            let mut stream = UtpStream::accept(acceptor, [0xff, 0xff], lossy(3)).unwrap();
+            stream.read_to_end().unwrap()
+        });
+        let mut stream =
+            UtpStream::connect(offerer, acceptor_addr, [0xff, 0xff], lossy(4)).unwrap();
+        stream.write_all(&receipts).unwrap();
+        stream.close().unwrap();
+        assert_eq!(reader.join().unwrap(), receipts);
+    }
+}
@@ -0,0 +1,924 @@
+use crate::accumulator::{block_hash_gindex, epoch_index, epoch_offset};
+use crate::beacon::{BeaconPayload, BeaconValue, LightClientHeader, EXECUTION_PAYLOAD_GINDEX};
+use crate::block_proof::{self, BlockFork};
+use crate::content::{
+    BlockHeaderProof, BlockProofHistoricalSummaries, ContentError, ContentValue, HeaderWithProof,
+    StateValue, Withdrawal,
+};
+use crate::content_key::{
+    BeaconContentKey, ContentKey, ContentKeyError, HistoryContentKey, Nibbles, StateContentKey,
+};
+use crate::header::{keccak256, Header};
+use crate::invalid::ErrorClass;
+use crate::network::Network;
+use crate::rlp;
+use crate::ssz::{
+    concat_generalized_indices, decode_byte_lists, merkleize, mix_in_length, verify_merkle_proof,
+    SszReader,
+};
+use crate::trie::{ordered_trie_root, Account, NodeRef, TrieNode, TrieNodeKind};
+use crate::vector::{discover, VectorError, VectorKind};
+use crate::ExecutionFork;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use thiserror::Error;
+
+const SLOTS_PER_HISTORICAL_ROOT: u64 = 8192;
+/// Generalized index of the body in a beacon block.
+const BEACON_BLOCK_BODY_GINDEX: u64 = 12;
+
+const HISTORICAL_EPOCHS_LIMIT: usize = 1 << 17;
+
+const ACCUMULATOR_PATH: &str = "history/accumulator/finished_accumulator.ssz";
+const HISTORICAL_SUMMARIES_PATH: &str =
+    "history/headers_with_proof/block_proofs_capella/historical_summaries_at_slot_8953856.ssz";
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ValidationError {
+    #[error("invalid content key: {0}")]
+    Key(#[from] ContentKeyError),
+    #[error("content value is empty")]
+    EmptyValue,
+    #[error("invalid content value: {0}")]
+    Value(#[from] ContentError),
+    #[error("value is {found} content, not {expected}")]
+    WrongContentType {
+        expected: &'static str,
+        found: &'static str,
+    },
+    #[error("{0} does not match the content key")]
+    KeyMismatch(&'static str),
+    #[error("no header known for block 0x{}", hex::encode(.0))]
+    UnknownHeader([u8; 32]),
+    #[error("no state root known for block 0x{}", hex::encode(.0))]
+    UnknownStateRoot([u8; 32]),
+    #[error("{0} does not match the block header")]
+    HeaderMismatch(&'static str),
+    #[error("invalid {0} proof")]
+    InvalidProof(&'static str),
+    #[error("no proof for block {0}, which is past the merge")]
+    MissingProof(u64),
+    #[error("{0} proof is past the loaded anchors and cannot be verified")]
+    Unverifiable(&'static str),
+    #[error("{proof} proof for block {number} of the {fork} fork")]
+    ForkMismatch {
+        number: u64,
+        proof: &'static str,
+        fork: &'static str,
+    },
+}
+
+impl ValidationError {
+    /// The class of error the invalid vectors expect for this failure.
+    pub fn class(&self) -> ErrorClass {
+        match self {
+            ValidationError::Key(_) => ErrorClass::InvalidContentKey,
+            ValidationError::Value(
+                ContentError::UnknownForkDigest(_) | ContentError::WrongForkDigest { .. },
+            )
+            | ValidationError::ForkMismatch { .. } => ErrorClass::ForkMismatch,
+            ValidationError::EmptyValue | ValidationError::Value(_) => ErrorClass::InvalidSsz,
+            ValidationError::WrongContentType { .. }
+            | ValidationError::KeyMismatch(_)
+            | ValidationError::UnknownHeader(_)
+            | ValidationError::UnknownStateRoot(_)
+            | ValidationError::HeaderMismatch(_) => ErrorClass::ContentMismatch,
+            ValidationError::InvalidProof(_)
+            | ValidationError::MissingProof(_)
+            | ValidationError::Unverifiable(_) => ErrorClass::InvalidProof,
+        }
+    }
+}
+
+/// Checks content against the anchors a Portal client trusts: the pre-merge
+/// accumulator, the Capella historical summaries, and the headers and state
+/// roots it has already accepted.
+#[derive(Debug, Clone, Default)]
+pub struct Validator {
+    network: Network,
+    historical_epochs: Vec<[u8; 32]>,
+    historical_summaries: Vec<[u8; 32]>,
+    headers: HashMap<[u8; 32], Header>,
+    state_roots: HashMap<[u8; 32], [u8; 32]>,
+    allow_unproven_headers: bool,
+}
+
+fn read_anchor(dir: &Path, relative: &str) -> Result<Vec<u8>, VectorError> {
+    let path = dir.join(relative);
+    fs::read(&path).map_err(|source| VectorError::Io { path, source })
+}
+
+impl Validator {
+    pub fn new(
+        network: Network,
+        historical_epochs: Vec<[u8; 32]>,
+        historical_summaries: Vec<[u8; 32]>,
+    ) -> Self {
+        Validator {
+            network,
+            historical_epochs,
+            historical_summaries,
+            ..Validator::default()
+        }
+    }
+
+    /// Loads the anchors shipped with a network directory. No headers ship for
+    /// the state vectors' blocks, so their `state_root` fields are trusted instead.
+    ///
+    /// The accumulator is only required, and checked against its root, on networks
+    /// with Portal history. Historical summaries are optional.
+    pub fn from_vectors(network: &Network, dir: &Path) -> Result<Validator, VectorError> {
+        let parse_error = |relative: &str, message: String| VectorError::Parse {
+            path: dir.join(relative),
+            message,
+        };
+        let mut historical_epochs = Vec::new();
+        if let Some(expected) = network.accumulator_root {
+            let accumulator = read_anchor(dir, ACCUMULATOR_PATH)?;
+            let mut reader = SszReader::new(&accumulator);
+            let epochs = reader
+                .offset()
+                .and_then(|_| reader.finish())
+                .map_err(|e| parse_error(ACCUMULATOR_PATH, e.to_string()))?[0];
+            if epochs.len() % 32 != 0 {
+                return Err(parse_error(
+                    ACCUMULATOR_PATH,
+                    "truncated list of roots".to_string(),
+                ));
+            }
+            historical_epochs = epochs
+                .chunks(32)
+                .map(|root| root.try_into().unwrap())
+                .collect();
+            let root = mix_in_length(
+                &merkleize(&historical_epochs, HISTORICAL_EPOCHS_LIMIT),
+                historical_epochs.len(),
+            );
+            if root != expected {
+                return Err(parse_error(
+                    ACCUMULATOR_PATH,
+                    format!(
+                        "root 0x{} is not the {} accumulator",
+                        hex::encode(root),
+                        network.name
+                    ),
+                ));
+            }
+        }
+        let summaries = if dir.join(HISTORICAL_SUMMARIES_PATH).exists() {
+            read_anchor(dir, HISTORICAL_SUMMARIES_PATH)?
+        } else {
+            Vec::new()
+        };
+        if summaries.len() % 64 != 0 {
+            return Err(parse_error(
+                HISTORICAL_SUMMARIES_PATH,
+                "truncated list of roots".to_string(),
+            ));
+        }
+        let mut validator = Validator::new(
+            network.clone(),
+            historical_epochs,
+            summaries
+                .chunks(64)
+                .map(|summary| summary[..32].try_into().unwrap())
+                .collect(),
+        );
+
+        for file in discover(dir)? {
+            if file.kind.is_none() || file.kind == Some(VectorKind::Invalid) {
+                continue;
+            }
+            for entry in file.content_entries()? {
+                let (Some(state_root), Some(offer)) = (&entry.state_root, &entry.offer_value)
+                else {
+                    continue;
+                };
+                let Ok(ContentKey::State(key)) = ContentKey::decode(&entry.content_key) else {
+                    continue;
+                };
+                let block_hash = match StateValue::decode(&key, offer) {
+                    Ok(
+                        StateValue::AccountTrieNodeWithProof { block_hash, .. }
+                        | StateValue::ContractStorageTrieNodeWithProof { block_hash, .. }
+                        | StateValue::ContractBytecodeWithProof { block_hash, .. },
+                    ) => block_hash,
+                    _ => continue,
+                };
+                if let Ok(state_root) = state_root.as_slice().try_into() {
+                    validator.add_state_root(block_hash, state_root);
+                }
+            }
+        }
+        Ok(validator)
+    }
+
+    /// Accepts post-merge headers that come without a proof, as early post-merge
+    /// headers were gossiped. Off by default.
+    pub fn allow_unproven_headers(mut self, allow: bool) -> Self {
+        self.allow_unproven_headers = allow;
+        self
+    }
+
+    /// Trusts a header, for the bodies, receipts and state proofs of its block.
+    pub fn add_header(&mut self, header: Header) {
+        let hash = header.hash();
+        self.state_roots.insert(hash, header.state_root);
+        self.headers.insert(hash, header);
+    }
+
+    pub fn add_state_root(&mut self, block_hash: [u8; 32], state_root: [u8; 32]) {
+        self.state_roots.insert(block_hash, state_root);
+    }
+
+    pub fn header(&self, block_hash: &[u8; 32]) -> Option<&Header> {
+        self.headers.get(block_hash)
+    }
+
+    /// Decodes and checks a key and value pair, as a client does before storing it.
+    pub fn validate(
+        &self,
+        content_key: &[u8],
+        value: &[u8],
+    ) -> Result<ContentValue, ValidationError> {
+        let key = ContentKey::decode(content_key)?;
+        // An empty receipt list encodes to no bytes, no other value can be empty.
+        if value.is_empty() && !matches!(key, ContentKey::History(HistoryContentKey::Receipts(_))) {
+            return Err(ValidationError::EmptyValue);
+        }
+        let decoded = ContentValue::decode(&self.network, &key, value)
+            .map_err(|e| decode_error(&self.network, &key, value, e))?;
+        match (&key, &decoded) {
+            (ContentKey::History(key), ContentValue::HeaderWithProof(header_with_proof)) => {
+                self.validate_header(key, header_with_proof)?
+            }
+            (ContentKey::History(key), ContentValue::BlockBody(body)) => {
+                let header = self.key_header(key)?;
+                let mut uncles = Vec::new();
+                for uncle in &body.uncles {
+                    uncles.extend_from_slice(&uncle.encode());
+                }
+                let mut encoded = Vec::new();
+                rlp::encode_list(&mut encoded, &uncles);
+                if keccak256(&encoded) != header.ommers_hash {
+                    return Err(ValidationError::HeaderMismatch("uncles"));
+                }
+                if ordered_trie_root(&body.transactions) != header.transactions_root {
+                    return Err(ValidationError::HeaderMismatch("transactions"));
+                }
+                let withdrawals_root = body.withdrawals.as_ref().map(|withdrawals| {
+                    let encoded: Vec<Vec<u8>> =
+                        withdrawals.iter().map(Withdrawal::encode).collect();
+                    ordered_trie_root(&encoded)
+                });
+                if withdrawals_root != header.withdrawals_root {
+                    return Err(ValidationError::HeaderMismatch("withdrawals"));
+                }
+            }
+            (ContentKey::History(key), ContentValue::Receipts(receipts)) => {
+                let header = self.key_header(key)?;
+                let gas_used = receipts.last().map_or(0, |r| r.cumulative_gas_used);
+                if gas_used != header.gas_used {
+                    return Err(ValidationError::HeaderMismatch("gas used"));
+                }
+                // Receipts are committed to in the encoding they arrived in.
+                let encoded = decode_byte_lists(value).map_err(ContentError::from)?;
+                if ordered_trie_root(&encoded) != header.receipts_root {
+                    return Err(ValidationError::HeaderMismatch("receipts"));
+                }
+            }
+            (ContentKey::State(key), ContentValue::State(value)) => {
+                self.validate_state(key, value)?
+            }
+            (ContentKey::Beacon(key), ContentValue::Beacon(value)) => validate_beacon(key, value)?,
+            _ => unreachable!("values decode according to their key"),
+        }
+        Ok(decoded)
+    }
+
+    fn key_header(&self, key: &HistoryContentKey) -> Result<&Header, ValidationError> {
+        let (HistoryContentKey::BlockBody(hash) | HistoryContentKey::Receipts(hash)) = key else {
+            unreachable!("only bodies and receipts are looked up by header");
+        };
+        self.headers
+            .get(hash)
+            .ok_or(ValidationError::UnknownHeader(*hash))
+    }
+
+    fn validate_header(
+        &self,
+        key: &HistoryContentKey,
+        header_with_proof: &HeaderWithProof,
+    ) -> Result<(), ValidationError> {
+        let header = &header_with_proof.header;
+        let hash = header.hash();
+        match key {
+            HistoryContentKey::BlockHeaderByHash(expected) if *expected != hash => {
+                return Err(ValidationError::KeyMismatch("block hash"))
+            }
+            HistoryContentKey::BlockHeaderByNumber(number) if *number != header.number => {
+                return Err(ValidationError::KeyMismatch("block number"))
+            }
+            _ => {}
+        }
+
+        let fork = self.network.execution_fork(header.number);
+        match &header_with_proof.proof {
+            BlockHeaderProof::HistoricalHashesAccumulator(branch)
+                if fork < ExecutionFork::Merge =>
+            {
+                let gindex = block_hash_gindex(epoch_offset(header.number));
+                let root = self
+                    .historical_epochs
+                    .get(epoch_index(header.number) as usize)
+                    .ok_or(ValidationError::InvalidProof("accumulator"))?;
+                if !verify_merkle_proof(&hash, branch, gindex, root) {
+                    return Err(ValidationError::InvalidProof("accumulator"));
+                }
+            }
+            BlockHeaderProof::None if fork < ExecutionFork::Merge => {
+                return Err(ValidationError::InvalidProof("accumulator"))
+            }
+            // Early post-merge headers were gossiped without proofs, which a
+            // validator only takes when told to.
+            BlockHeaderProof::None if self.allow_unproven_headers => {}
+            BlockHeaderProof::None => return Err(ValidationError::MissingProof(header.number)),
+            BlockHeaderProof::HistoricalRoots(proof)
+                if (ExecutionFork::Merge..ExecutionFork::Shanghai).contains(&fork) =>
+            {
+                // The historical roots list is not among the vectors, so only the
+                // execution part of the proof is checked.
+                verify_execution_proof(
+                    &hash,
+                    &proof.execution_block_proof,
+                    &proof.beacon_block_root,
+                    BlockFork::Bellatrix,
+                )?;
+            }
+            BlockHeaderProof::HistoricalSummaries(proof) if fork == ExecutionFork::Shanghai => {
+                self.verify_summaries_proof(&hash, proof, BlockFork::Capella)?;
+            }
+            BlockHeaderProof::HistoricalSummariesDeneb(proof) if fork >= ExecutionFork::Cancun => {
+                self.verify_summaries_proof(&hash, proof, BlockFork::Deneb)?;
+            }
+            proof => {
+                return Err(ValidationError::ForkMismatch {
+                    number: header.number,
+                    proof: proof_name(proof),
+                    fork: fork.name(),
+                })
+            }
+        }
+        Ok(())
+    }
+
+    fn verify_summaries_proof(
+        &self,
+        hash: &[u8; 32],
+        proof: &BlockProofHistoricalSummaries,
+        fork: BlockFork,
+    ) -> Result<(), ValidationError> {
+        verify_execution_proof(
+            hash,
+            &proof.execution_block_proof,
+            &proof.beacon_block_root,
+            fork,
+        )?;
+        let period = proof
+            .slot
+            .checked_sub(self.network.capella_slot())
+            .ok_or(ValidationError::InvalidProof("historical summaries"))?
+            / SLOTS_PER_HISTORICAL_ROOT;
+        // Summaries past the loaded ones cannot be checked offline.
+        let root = self
+            .historical_summaries
+            .get(period as usize)
+            .ok_or(ValidationError::Unverifiable("historical summaries"))?;
+        let gindex = SLOTS_PER_HISTORICAL_ROOT + proof.slot % SLOTS_PER_HISTORICAL_ROOT;
+        if !verify_merkle_proof(
+            &proof.beacon_block_root,
+            &proof.beacon_block_proof,
+            gindex,
+            root,
+        ) {
+            return Err(ValidationError::InvalidProof("historical summaries"));
+        }
+        Ok(())
+    }
+
+    fn validate_state(
+        &self,
+        key: &StateContentKey,
+        value: &StateValue,
+    ) -> Result<(), ValidationError> {
+        let state_root = |block_hash: &[u8; 32]| {
+            self.state_roots
+                .get(block_hash)
+                .ok_or(ValidationError::UnknownStateRoot(*block_hash))
+        };
+        let node_hash = |node: Option<&TrieNode>| node.map(TrieNode::hash);
+        match (key, value) {
+            (
+                StateContentKey::AccountTrieNode {
+                    node_hash: expected,
+                    ..
+                },
+                StateValue::TrieNode(node),
+            )
+            | (
+                StateContentKey::ContractStorageTrieNode {
+                    node_hash: expected,
+                    ..
+                },
+                StateValue::TrieNode(node),
+            ) => {
+                if node.hash() != *expected {
+                    return Err(ValidationError::KeyMismatch("node hash"));
+                }
+            }
+            (
+                StateContentKey::ContractBytecode { code_hash, .. },
+                StateValue::ContractBytecode(code),
+            ) => {
+                if keccak256(code) != *code_hash {
+                    return Err(ValidationError::KeyMismatch("code hash"));
+                }
+            }
+            (
+                StateContentKey::AccountTrieNode {
+                    node_hash: expected,
+                    ..
+                },
+                StateValue::AccountTrieNodeWithProof { proof, block_hash },
+            ) => {
+                verify_trie_proof(state_root(block_hash)?, proof, "account")?;
+                if node_hash(proof.last()) != Some(*expected) {
+                    return Err(ValidationError::KeyMismatch("node hash"));
+                }
+            }
+            (
+                StateContentKey::ContractStorageTrieNode {
+                    node_hash: expected,
+                    ..
+                },
+                StateValue::ContractStorageTrieNodeWithProof {
+                    storage_proof,
+                    account_proof,
+                    block_hash,
+                },
+            ) => {
+                verify_trie_proof(state_root(block_hash)?, account_proof, "account")?;
+                let account = proven_account(account_proof)?;
+                verify_trie_proof(&account.storage_root, storage_proof, "storage")?;
+                if node_hash(storage_proof.last()) != Some(*expected) {
+                    return Err(ValidationError::KeyMismatch("node hash"));
+                }
+            }
+            (
+                StateContentKey::ContractBytecode { code_hash, .. },
+                StateValue::ContractBytecodeWithProof {
+                    code,
+                    account_proof,
+                    block_hash,
+                },
+            ) => {
+                verify_trie_proof(state_root(block_hash)?, account_proof, "account")?;
+                if keccak256(code) != *code_hash {
+                    return Err(ValidationError::KeyMismatch("code hash"));
+                }
+                if proven_account(account_proof)?.code_hash != *code_hash {
+                    return Err(ValidationError::InvalidProof("account"));
+                }
+            }
+            _ => unreachable!("state values decode according to their key"),
+        }
+        Ok(())
+    }
+}
+
+/// Sorts a value that does not decode: content of another type under this key
+/// is a mismatch, anything else is malformed.
+fn decode_error(
+    network: &Network,
+    key: &ContentKey,
+    value: &[u8],
+    error: ContentError,
+) -> ValidationError {
+    if let Some(other) = other_content_types(key)
+        .into_iter()
+        .find(|other| ContentValue::decode(network, other, value).is_ok())
+    {
+        return ValidationError::WrongContentType {
+            expected: key.name(),
+            found: other.name(),
+        };
+    }
+    match (key, error) {
+        // Trie nodes are RLP inside the SSZ, a broken one is a broken proof.
+        (ContentKey::State(_), ContentError::Rlp(_)) => ValidationError::InvalidProof("trie node"),
+        (_, error) => ValidationError::Value(error),
+    }
+}
+
+/// One key of every other content type of the key's subnetwork.
+fn other_content_types(key: &ContentKey) -> Vec<ContentKey> {
+    let hash = [0u8; 32];
+    let all = match key {
+        ContentKey::History(_) => vec![
+            ContentKey::History(HistoryContentKey::BlockHeaderByHash(hash)),
+            ContentKey::History(HistoryContentKey::BlockBody(hash)),
+            ContentKey::History(HistoryContentKey::Receipts(hash)),
+        ],
+        ContentKey::State(_) => vec![
+            ContentKey::State(StateContentKey::AccountTrieNode {
+                path: Nibbles::default(),
+                node_hash: hash,
+            }),
+            ContentKey::State(StateContentKey::ContractStorageTrieNode {
+                address: [0u8; 20],
+                path: Nibbles::default(),
+                node_hash: hash,
+            }),
+            ContentKey::State(StateContentKey::ContractBytecode {
+                address: [0u8; 20],
+                code_hash: hash,
+            }),
+        ],
+        ContentKey::Beacon(_) => vec![
+            ContentKey::Beacon(BeaconContentKey::LightClientBootstrap { block_root: hash }),
+            ContentKey::Beacon(BeaconContentKey::LightClientUpdatesByRange {
+                start_period: 0,
+                count: 0,
+            }),
+            ContentKey::Beacon(BeaconContentKey::LightClientFinalityUpdate { finalized_slot: 0 }),
+            ContentKey::Beacon(BeaconContentKey::LightClientOptimisticUpdate { signature_slot: 0 }),
+            ContentKey::Beacon(BeaconContentKey::HistoricalSummariesWithProof { epoch: 0 }),
+        ],
+    };
+    let is_header = |key: &ContentKey| {
+        matches!(
+            key,
+            ContentKey::History(
+                HistoryContentKey::BlockHeaderByHash(_) | HistoryContentKey::BlockHeaderByNumber(_)
+            )
+        )
+    };
+    all.into_iter()
+        .filter(|other| other.selector() != key.selector() && !(is_header(other) && is_header(key)))
+        .collect()
+}
+
+fn proof_name(proof: &BlockHeaderProof) -> &'static str {
+    match proof {
+        BlockHeaderProof::None => "no",
+        BlockHeaderProof::HistoricalHashesAccumulator(_) => "accumulator",
+        BlockHeaderProof::HistoricalRoots(_) => "historical roots",
+        BlockHeaderProof::HistoricalSummaries(_) => "historical summaries",
+        BlockHeaderProof::HistoricalSummariesDeneb(_) => "Deneb historical summaries",
+    }
+}
+
+/// Checks the proof of a block hash against the beacon block of `fork` it is in.
+fn verify_execution_proof(
+    block_hash: &[u8; 32],
+    branch: &[[u8; 32]],
+    beacon_block_root: &[u8; 32],
+    fork: BlockFork,
+) -> Result<(), ValidationError> {
+    let gindex = concat_generalized_indices(&[
+        BEACON_BLOCK_BODY_GINDEX,
+        block_proof::block_hash_gindex(fork),
+    ]);
+    if !verify_merkle_proof(block_hash, branch, gindex, beacon_block_root) {
+        return Err(ValidationError::InvalidProof("execution block"));
+    }
+    Ok(())
+}
+
+/// Checks that the proof starts at `root` and every node references the next one.
+fn verify_trie_proof(
+    root: &[u8; 32],
+    proof: &[TrieNode],
+    name: &'static str,
+) -> Result<(), ValidationError> {
+    if proof.first().map(TrieNode::hash) != Some(*root) {
+        return Err(ValidationError::InvalidProof(name));
+    }
+    for pair in proof.windows(2) {
+        let child = NodeRef::Hash(pair[1].hash());
+        let linked = match &pair[0].kind {
+            TrieNodeKind::Branch { children, .. } => children.iter().flatten().any(|c| *c == child),
+            TrieNodeKind::Extension { child: next, .. } => *next == child,
+            TrieNodeKind::Leaf { .. } => false,
+        };
+        if !linked {
+            return Err(ValidationError::InvalidProof(name));
+        }
+    }
+    Ok(())
+}
+
+/// The account in the leaf an account proof ends in.
+fn proven_account(proof: &[TrieNode]) -> Result<Account, ValidationError> {
+    match proof.last().map(|node| &node.kind) {
+        Some(TrieNodeKind::Leaf { value, .. }) => {
+            Account::decode(value).map_err(|_| ValidationError::InvalidProof("account"))
+        }
+        _ => Err(ValidationError::InvalidProof("account")),
+    }
+}
+
+fn verify_light_client_header(header: &LightClientHeader) -> Result<(), ValidationError> {
+    if !verify_merkle_proof(
+        &header.execution.hash_tree_root(),
+        &header.execution_branch,
+        EXECUTION_PAYLOAD_GINDEX,
+        &header.beacon.body_root,
+    ) {
+        return Err(ValidationError::InvalidProof("execution payload"));
+    }
+    Ok(())
+}
+
+fn validate_beacon(key: &BeaconContentKey, value: &BeaconValue) -> Result<(), ValidationError> {
+    let payloads = match value {
+        BeaconValue::Single(forked) => vec![forked.as_ref()],
+        BeaconValue::Updates(updates) => {
+            let BeaconContentKey::LightClientUpdatesByRange { count, .. } = key else {
+                unreachable!("only update ranges hold several values");
+            };
+            if updates.len() as u64 != *count {
+                return Err(ValidationError::KeyMismatch("update count"));
+            }
+            updates.iter().collect()
+        }
+    };
+    for forked in payloads {
+        let fork = forked.fork;
+        match (&forked.payload, key) {
+            (
+                BeaconPayload::Bootstrap(bootstrap),
+                BeaconContentKey::LightClientBootstrap { block_root },
+            ) => {
+                if bootstrap.header.beacon.hash_tree_root() != *block_root {
+                    return Err(ValidationError::KeyMismatch("block root"));
+                }
+                verify_light_client_header(&bootstrap.header)?;
+                if !verify_merkle_proof(
+                    &bootstrap.current_sync_committee.hash_tree_root(),
+                    &bootstrap.current_sync_committee_branch,
+                    fork.current_sync_committee_gindex(),
+                    &bootstrap.header.beacon.state_root,
+                ) {
+                    return Err(ValidationError::InvalidProof("current sync committee"));
+                }
+            }
+            (BeaconPayload::Update(update), _) => {
+                verify_light_client_header(&update.attested_header)?;
+                if !verify_merkle_proof(
+                    &update.next_sync_committee.hash_tree_root(),
+                    &update.next_sync_committee_branch,
+                    fork.next_sync_committee_gindex(),
+                    &update.attested_header.beacon.state_root,
+                ) {
+                    return Err(ValidationError::InvalidProof("next sync committee"));
+                }
+            }
+            (BeaconPayload::FinalityUpdate(update), _) => {
+                verify_light_client_header(&update.attested_header)?;
+                verify_light_client_header(&update.finalized_header)?;
+                if !verify_merkle_proof(
+                    &update.finalized_header.beacon.hash_tree_root(),
+                    &update.finality_branch,
+                    fork.finalized_root_gindex(),
+                    &update.attested_header.beacon.state_root,
+                ) {
+                    return Err(ValidationError::InvalidProof("finality"));
+                }
+            }
+            (BeaconPayload::OptimisticUpdate(update), _) => {
+                verify_light_client_header(&update.attested_header)?;
+            }
+            (
+                BeaconPayload::HistoricalSummaries(summaries),
+                BeaconContentKey::HistoricalSummariesWithProof { epoch },
+            ) => {
+                if summaries.epoch != *epoch {
+                    return Err(ValidationError::KeyMismatch("epoch"));
+                }
+            }
+            _ => unreachable!("beacon values decode according to their key"),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::invalid::expected_error;
+    use crate::vector::{network_dir, VectorFile};
+
+    fn entry(relative: &str) -> crate::vector::ContentEntry {
+        let root = network_dir("mainnet");
+        let file = VectorFile::load(&root, &root.join(relative)).unwrap();
+        file.content_entries().unwrap().remove(0)
+    }
+
+    #[test]
+    fn test_vectors_validate_as_expected() {
+        let root = network_dir("mainnet");
+        let mut validator = Validator::from_vectors(&Network::mainnet(), &root).unwrap();
+        let files = discover(&root).unwrap();
+        let mut valid = Vec::new();
+        for file in &files {
+            if file.kind.is_some() && file.kind != Some(VectorKind::Invalid) {
+                valid.extend(file.content_entries().unwrap());
+            }
+        }
+        valid.sort_by_key(|entry| entry.content_key[0] != 0x00);
+        let mut unproven = Vec::new();
+        let mut unknown = std::collections::HashSet::new();
+        for entry in &valid {
+            let Some(value) = entry.offer_value.as_ref().or(entry.content_value.as_ref()) else {
+                continue;
+            };
+            match validator.validate(&entry.content_key, value) {
+                Ok(ContentValue::HeaderWithProof(header)) => validator.add_header(header.header),
+                Ok(_) => {}
+                // The post-merge hive headers, which ship without proofs.
+                Err(ValidationError::MissingProof(number)) => unproven.push(number),
+                // Their bodies and receipts, and the only body without a header
+                // among the vectors, block 17139055.
+                Err(ValidationError::UnknownHeader(hash)) => {
+                    unknown.insert(hex::encode(hash));
+                }
+                Err(e) => panic!("{}: {}", entry.label, e),
+            }
+        }
+        assert_eq!(unproven, [15600000, 17510000, 19463337]);
+        assert_eq!(unknown.len(), 4);
+        assert!(unknown
+            .contains("a468e1fc13aebc6b5e1be1db0d4e0de9ddf96b42accc69bcb726e98d4503e817"));
+
+        for file in files.iter().filter(|f| f.kind == Some(VectorKind::Invalid)) {
+            let expected = expected_error(file).unwrap().unwrap();
+            let entry = file.content_entries().unwrap().remove(0);
+            let value = entry.offer_value.or(entry.content_value).unwrap();
+            let error = validator
+                .validate(&entry.content_key, &value)
+                .expect_err(&file.relative.display().to_string());
+            assert_eq!(
+                error.class(),
+                expected,
+                "{}: {}",
+                file.relative.display(),
+                error
+            );
+        }
+    }
+
+    #[test]
+    fn test_header_proofs() {
+        let validator =
+            Validator::from_vectors(&Network::mainnet(), &network_dir("mainnet")).unwrap();
+        let entry = entry("history/headers_with_proof/1000010.yaml");
+        let value = entry.content_value.unwrap();
+        assert!(validator.validate(&entry.content_key, &value).is_ok());
+
+        let proof_start = u32::from_le_bytes(value[4..8].try_into().unwrap()) as usize;
+        let mut without_proof = value[..proof_start].to_vec();
+        without_proof.push(0x00);
+        assert_eq!(
+            validator.validate(&entry.content_key, &without_proof),
+            Err(ValidationError::InvalidProof("accumulator"))
+        );
+        let mut flipped = value.clone();
+        *flipped.last_mut().unwrap() ^= 0x01;
+        assert_eq!(
+            validator
+                .validate(&entry.content_key, &flipped)
+                .unwrap_err()
+                .class(),
+            ErrorClass::InvalidProof
+        );
+        let body_key = [&[0x01][..], &entry.content_key[1..]].concat();
+        assert_eq!(
+            validator.validate(&body_key, &value),
+            Err(ValidationError::WrongContentType {
+                expected: "block_body",
+                found: "block_header_by_hash",
+            })
+        );
+    }
+
+    #[test]
+    fn test_post_merge_proofs_are_required() {
+        let root = network_dir("mainnet");
+        let file = VectorFile::load(
+            &root,
+            &root.join("history/hive/test_data_collection_of_forks_blocks.yaml"),
+        )
+        .unwrap();
+        let header = file
+            .content_entries()
+            .unwrap()
+            .into_iter()
+            .find(|entry| entry.label == "9")
+            .unwrap();
+        let value = header.content_value.unwrap();
+        let validator = Validator::from_vectors(&Network::mainnet(), &root).unwrap();
+        assert_eq!(
+            validator.validate(&header.content_key, &value),
+            Err(ValidationError::MissingProof(15600000))
+        );
+        let validator = validator.allow_unproven_headers(true);
+        assert!(validator.validate(&header.content_key, &value).is_ok());
+
+        let file = VectorFile::load(
+            &root,
+            &root.join(
+                "history/headers_with_proof/block_proofs_capella/beacon_block_proof-17034870.yaml",
+            ),
+        )
+        .unwrap();
+        let encoded = crate::invalid::encode_capella_proof(&file).unwrap();
+        let Ok(BlockHeaderProof::HistoricalSummaries(proof)) =
+            BlockHeaderProof::decode(&[&[0x03][..], &encoded].concat())
+        else {
+            panic!("expected a Capella proof");
+        };
+        let hash = file.document().unwrap()["execution_block_header"]
+            .as_str()
+            .map(|text| crate::vector::decode_hex(text).unwrap())
+            .unwrap()
+            .try_into()
+            .unwrap();
+        assert!(validator
+            .verify_summaries_proof(&hash, &proof, BlockFork::Capella)
+            .is_ok());
+        let unloaded = Validator::new(Network::mainnet(), Vec::new(), Vec::new());
+        assert_eq!(
+            unloaded.verify_summaries_proof(&hash, &proof, BlockFork::Capella),
+            Err(ValidationError::Unverifiable("historical summaries"))
+        );
+    }
+
+    #[test]
+    fn test_state_offer_and_retrieval() {
+        let entry = entry("state/validation/account_trie_node.yaml");
+        let offer = entry.offer_value.unwrap();
+        let mut validator = Validator::default();
+        let Err(ValidationError::UnknownStateRoot(block_hash)) =
+            validator.validate(&entry.content_key, &offer)
+        else {
+            panic!("expected an unknown state root");
+        };
+        let state_root = entry.state_root.unwrap().try_into().unwrap();
+        validator.add_state_root(block_hash, state_root);
+        let Ok(ContentValue::State(value)) = validator.validate(&entry.content_key, &offer) else {
+            panic!("expected a valid state offer");
+        };
+        let retrieval = value.retrieval_value();
+        assert_eq!(Some(&retrieval), entry.content_value.as_ref());
+        assert!(validator.validate(&entry.content_key, &retrieval).is_ok());
+
+        validator.add_state_root(block_hash, [0u8; 32]);
+        assert_eq!(
+            validator.validate(&entry.content_key, &offer),
+            Err(ValidationError::InvalidProof("account"))
+        );
+    }
+
+    #[test]
+    fn test_network_parameters() {
+        let root = network_dir("mainnet");
+        let mut wrong_root = Network::mainnet();
+        wrong_root.accumulator_root = Some([0u8; 32]);
+        assert!(matches!(
+            Validator::from_vectors(&wrong_root, &root),
+            Err(VectorError::Parse { .. })
+        ));
+
+        // Holesky launched as proof of stake and has no accumulator to load.
+        let holesky = Validator::from_vectors(&Network::holesky(), &root).unwrap();
+        let header = entry("history/headers_with_proof/1000010.yaml");
+        assert!(matches!(
+            holesky.validate(&header.content_key, header.content_value.as_ref().unwrap()),
+            Err(ValidationError::ForkMismatch { .. })
+        ));
+        let bootstrap = entry("beacon_chain/light_client/bootstrap.yaml");
+        let error = holesky
+            .validate(
+                &bootstrap.content_key,
+                bootstrap.content_value.as_ref().unwrap(),
+            )
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            ValidationError::Value(ContentError::UnknownForkDigest(_))
+        ));
+        assert_eq!(error.class(), ErrorClass::ForkMismatch);
+    }
+}
@@ -0,0 +1,404 @@
+use crate::yaml::{has_plain_hex, Document};
+use serde_json::Value;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum VectorError {
+    #[error("failed to read {path}: {source}")]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("failed to parse {path}: {message}")]
+    Parse { path: PathBuf, message: String },
+}
+
+/// The category of a test vector file, derived from where it lives in the tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum VectorKind {
+    HistoryHeaderWithProof,
+    HistoryBlockBody,
+    HistoryReceipts,
+    HistoryHive,
+    BlockProofBellatrix,
+    BlockProofCapella,
+    StateTrieNodeKey,
+    StateBytecodeKey,
+    StateTrieNode,
+    StateBytecode,
+    StateValueWithProof,
+    StateValidation,
+    StateRecursiveGossip,
+    StateHive,
+    BeaconLightClient,
+    BeaconHistoricalSummaries,
+    BeaconHive,
+    WireMessages,
+    Invalid,
+}
+
+impl VectorKind {
+    pub const ALL: [VectorKind; 19] = [
+        VectorKind::HistoryHeaderWithProof,
+        VectorKind::HistoryBlockBody,
+        VectorKind::HistoryReceipts,
+        VectorKind::HistoryHive,
+        VectorKind::BlockProofBellatrix,
+        VectorKind::BlockProofCapella,
+        VectorKind::StateTrieNodeKey,
+        VectorKind::StateBytecodeKey,
+        VectorKind::StateTrieNode,
+        VectorKind::StateBytecode,
+        VectorKind::StateValueWithProof,
+        VectorKind::StateValidation,
+        VectorKind::StateRecursiveGossip,
+        VectorKind::StateHive,
+        VectorKind::BeaconLightClient,
+        VectorKind::BeaconHistoricalSummaries,
+        VectorKind::BeaconHive,
+        VectorKind::WireMessages,
+        VectorKind::Invalid,
+    ];
+
+    /// Classifies a file by its path below a network directory such as `tests/mainnet`.
+    /// Leading components before the sub-network directory are ignored.
+    pub fn from_relative_path(relative: &Path) -> Option<VectorKind> {
+        let components: Vec<&str> = relative
+            .components()
+            .filter_map(|c| c.as_os_str().to_str())
+            .collect();
+        let start = components
+            .iter()
+            .position(|c| matches!(*c, "history" | "state" | "beacon_chain" | "wire"))?;
+        let (file_name, dirs) = components[start..].split_last()?;
+        let stem = Path::new(file_name).file_stem()?.to_str()?;
+
+        let kind = match dirs {
+            [.., "invalid"] => VectorKind::Invalid,
+            ["history", "headers_with_proof"] => VectorKind::HistoryHeaderWithProof,
+            ["history", "headers_with_proof", "block_proofs_bellatrix"] => {
+                VectorKind::BlockProofBellatrix
+            }
+            ["history", "headers_with_proof", "block_proofs_capella"] => {
+                VectorKind::BlockProofCapella
+            }
+            ["history", "bodies"] => VectorKind::HistoryBlockBody,
+            ["history", "receipts"] => VectorKind::HistoryReceipts,
+            ["history", "hive"] => VectorKind::HistoryHive,
+            ["state", "serialization"] => match stem {
+                "contract_bytecode_key" => VectorKind::StateBytecodeKey,
+                "trie_node" => VectorKind::StateTrieNode,
+                "contract_bytecode" => VectorKind::StateBytecode,
+                s if s.ends_with("_key") => VectorKind::StateTrieNodeKey,
+                s if s.ends_with("_with_proof") => VectorKind::StateValueWithProof,
+                _ => return None,
+            },
+            ["state", "validation"] if stem == "recursive_gossip" => {
+                VectorKind::StateRecursiveGossip
+            }
+            ["state", "validation"] => VectorKind::StateValidation,
+            ["state", "hive"] => VectorKind::StateHive,
+            ["beacon_chain", "light_client"] => VectorKind::BeaconLightClient,
+            ["beacon_chain", "historical_summaries_with_proof", _] => {
+                VectorKind::BeaconHistoricalSummaries
+            }
+            ["beacon_chain", "hive"] => VectorKind::BeaconHive,
+            ["wire"] => VectorKind::WireMessages,
+            _ => return None,
+        };
+        Some(kind)
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            VectorKind::HistoryHeaderWithProof => "history_header_with_proof",
+            VectorKind::HistoryBlockBody => "history_block_body",
+            VectorKind::HistoryReceipts => "history_receipts",
+            VectorKind::HistoryHive => "history_hive",
+            VectorKind::BlockProofBellatrix => "block_proof_bellatrix",
+            VectorKind::BlockProofCapella => "block_proof_capella",
+            VectorKind::StateTrieNodeKey => "state_trie_node_key",
+            VectorKind::StateBytecodeKey => "state_bytecode_key",
+            VectorKind::StateTrieNode => "state_trie_node",
+            VectorKind::StateBytecode => "state_bytecode",
+            VectorKind::StateValueWithProof => "state_value_with_proof",
+            VectorKind::StateValidation => "state_validation",
+            VectorKind::StateRecursiveGossip => "state_recursive_gossip",
+            VectorKind::StateHive => "state_hive",
+            VectorKind::BeaconLightClient => "beacon_light_client",
+            VectorKind::BeaconHistoricalSummaries => "beacon_historical_summaries",
+            VectorKind::BeaconHive => "beacon_hive",
+            VectorKind::WireMessages => "wire_messages",
+            VectorKind::Invalid => "invalid",
+        }
+    }
+}
+
+impl fmt::Display for VectorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VectorFormat {
+    Yaml,
+    Json,
+}
+
+/// A YAML or JSON test vector file together with its raw text.
+#[derive(Debug, Clone)]
+pub struct VectorFile {
+    pub path: PathBuf,
+    pub relative: PathBuf,
+    pub kind: Option<VectorKind>,
+    pub format: VectorFormat,
+    pub text: String,
+}
+
+impl VectorFile {
+    pub fn load(root: &Path, path: &Path) -> Result<Self, VectorError> {
+        let format = match path.extension().and_then(|e| e.to_str()) {
+            Some("json") => VectorFormat::Json,
+            _ => VectorFormat::Yaml,
+        };
+        let text = fs::read_to_string(path).map_err(|source| VectorError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        let relative = path.strip_prefix(root).unwrap_or(path).to_path_buf();
+        Ok(VectorFile {
+            path: path.to_path_buf(),
+            kind: VectorKind::from_relative_path(&relative),
+            relative,
+            format,
+            text,
+        })
+    }
+
+    /// Parses the file into a JSON value, whichever format it is written in.
+    pub fn document(&self) -> Result<Value, VectorError> {
+        let parsed = match self.format {
+            // serde_yaml reads plain hex such as a zero root as an integer.
+            VectorFormat::Yaml if has_plain_hex(&self.text) => Document::parse(&self.text)
+                .and_then(|document| document.to_value())
+                .map_err(|e| e.to_string()),
+            VectorFormat::Yaml => serde_yaml::from_str(&self.text).map_err(|e| e.to_string()),
+            VectorFormat::Json => serde_json::from_str(&self.text).map_err(|e| e.to_string()),
+        };
+        parsed.map_err(|message| VectorError::Parse {
+            path: self.path.clone(),
+            message,
+        })
+    }
+}
+
+/// A content key and its values as found in a vector file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContentEntry {
+    /// Where the entry sits in the file, e.g. a block number or a list index.
+    pub label: String,
+    pub content_key: Vec<u8>,
+    /// The value returned on retrieval, absent in files that only carry offers.
+    pub content_value: Option<Vec<u8>>,
+    /// The value gossiped in OFFER messages, only present for state vectors.
+    pub offer_value: Option<Vec<u8>>,
+    pub state_root: Option<Vec<u8>>,
+}
+
+impl VectorFile {
+    /// Collects every object in the file that carries a `content_key`.
+    pub fn content_entries(&self) -> Result<Vec<ContentEntry>, VectorError> {
+        let document = self.document()?;
+        let mut entries = Vec::new();
+        self.collect_entries(&document, String::new(), None, &mut entries)?;
+        Ok(entries)
+    }
+
+    fn collect_entries(
+        &self,
+        value: &Value,
+        label: String,
+        state_root: Option<&Value>,
+        entries: &mut Vec<ContentEntry>,
+    ) -> Result<(), VectorError> {
+        let join = |child: String| {
+            if label.is_empty() {
+                child
+            } else {
+                format!("{}/{}", label, child)
+            }
+        };
+        match value {
+            Value::Array(items) => {
+                for (index, item) in items.iter().enumerate() {
+                    self.collect_entries(item, join(index.to_string()), state_root, entries)?;
+                }
+            }
+            Value::Object(map) => {
+                let state_root = map.get("state_root").or(state_root);
+                if let Some(key) = map.get("content_key") {
+                    let offer = map.get("content_value_offer").or_else(|| {
+                        (self.kind == Some(VectorKind::StateRecursiveGossip))
+                            .then(|| map.get("content_value"))
+                            .flatten()
+                    });
+                    let retrieval = match self.kind {
+                        Some(VectorKind::StateRecursiveGossip) => None,
+                        _ => map
+                            .get("content_value_retrieval")
+                            .or_else(|| map.get("content_value")),
+                    };
+                    entries.push(ContentEntry {
+                        label: label.clone(),
+                        content_key: self.hex_field(key)?,
+                        content_value: retrieval.map(|v| self.hex_field(v)).transpose()?,
+                        offer_value: offer.map(|v| self.hex_field(v)).transpose()?,
+                        state_root: state_root.map(|v| self.hex_field(v)).transpose()?,
+                    });
+                }
+                for (name, child) in map {
+                    if child.is_object() || child.is_array() {
+                        self.collect_entries(child, join(name.clone()), state_root, entries)?;
+                    }
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn hex_field(&self, value: &Value) -> Result<Vec<u8>, VectorError> {
+        value
+            .as_str()
+            .ok_or_else(|| "expected a hex string".to_string())
+            .and_then(|s| decode_hex(s).map_err(|e| e.to_string()))
+            .map_err(|message| VectorError::Parse {
+                path: self.path.clone(),
+                message,
+            })
+    }
+}
+
+pub fn decode_hex(text: &str) -> Result<Vec<u8>, hex::FromHexError> {
+    hex::decode(text.strip_prefix("0x").unwrap_or(text))
+}
+
+pub fn encode_hex(bytes: &[u8]) -> String {
+    format!("0x{}", hex::encode(bytes))
+}
+
+/// Returns the `tests` directory of this repository.
+pub fn spec_tests_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("..")
+        .join("tests")
+}
+
+/// Returns the directory holding the vectors for `network`, e.g. `tests/mainnet`.
+pub fn network_dir(network: &str) -> PathBuf {
+    spec_tests_dir().join(network)
+}
+
+/// Recursively collects every YAML and JSON file below `root`, sorted by path.
+pub fn discover(root: &Path) -> Result<Vec<VectorFile>, VectorError> {
+    let mut paths = Vec::new();
+    collect_paths(root, &mut paths)?;
+    paths.sort();
+    paths
+        .iter()
+        .map(|path| VectorFile::load(root, path))
+        .collect()
+}
+
+fn collect_paths(dir: &Path, paths: &mut Vec<PathBuf>) -> Result<(), VectorError> {
+    let io_error = |source| VectorError::Io {
+        path: dir.to_path_buf(),
+        source,
+    };
+    for entry in fs::read_dir(dir).map_err(io_error)? {
+        let path = entry.map_err(io_error)?.path();
+        if path.is_dir() {
+            collect_paths(&path, paths)?;
+        } else if matches!(
+            path.extension().and_then(|e| e.to_str()),
+            Some("yaml" | "yml" | "json")
+        ) {
+            paths.push(path);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_mainnet_vector_is_classified() {
+        let files = discover(&network_dir("mainnet")).unwrap();
+        assert!(files.len() > 30);
+        for file in &files {
+            assert!(
+                file.kind.is_some(),
+                "{} is unclassified",
+                file.relative.display()
+            );
+        }
+    }
+
+    #[test]
+    fn test_classification() {
+        let kind = |p: &str| VectorKind::from_relative_path(Path::new(p));
+        assert_eq!(
+            kind("history/headers_with_proof/1000010.yaml"),
+            Some(VectorKind::HistoryHeaderWithProof)
+        );
+        assert_eq!(
+            kind("state/serialization/contract_bytecode_key.yaml"),
+            Some(VectorKind::StateBytecodeKey)
+        );
+        assert_eq!(
+            kind("state/serialization/account_trie_node_key.yaml"),
+            Some(VectorKind::StateTrieNodeKey)
+        );
+        assert_eq!(
+            kind("mainnet/history/receipts/14764013.yaml"),
+            Some(VectorKind::HistoryReceipts)
+        );
+        assert_eq!(
+            kind("history/bodies/invalid/14764013-empty_value.yaml"),
+            Some(VectorKind::Invalid)
+        );
+        assert_eq!(kind("history/unknown/1.yaml"), None);
+    }
+
+    #[test]
+    fn test_content_entries() {
+        let root = network_dir("mainnet");
+        let load = |p: &str| VectorFile::load(&root, &root.join(p)).unwrap();
+
+        let headers = load("history/headers_with_proof/1000001-1000010.json")
+            .content_entries()
+            .unwrap();
+        assert_eq!(headers.len(), 10);
+        assert_eq!(headers[0].label, "1000001");
+        assert_eq!(headers[0].content_key[0], 0x00);
+
+        let state = load("state/validation/account_trie_node.yaml")
+            .content_entries()
+            .unwrap();
+        assert_eq!(state.len(), 5);
+        assert_eq!(state[1].label, "0/recursive_gossip");
+        assert!(state.iter().all(|e| e.offer_value.is_some()));
+        assert_eq!(state[1].state_root, state[0].state_root);
+
+        let gossip = load("state/validation/recursive_gossip.yaml")
+            .content_entries()
+            .unwrap();
+        assert!(gossip.iter().all(|e| e.content_value.is_none()));
+    }
+}
@@ -0,0 +1,581 @@
+use crate::ssz::{SszError, SszType, SszValue};
+use ethnum::U256;
+use thiserror::Error;
+
+// SSZ limits from the message definitions of the Portal wire protocol spec.
+pub const MAX_CUSTOM_PAYLOAD_SIZE: usize = 1100;
+pub const MAX_DISTANCES: usize = 256;
+pub const MAX_ENRS: usize = 32;
+/// The `ByteList[2048]` an ENR takes in NODES and CONTENT. Records themselves
+/// are capped at `enr::MAX_ENR_SIZE`, this is only the list's element limit.
+pub const MAX_ENR_LIST_ITEM_SIZE: usize = 2048;
+pub const MAX_CONTENT_KEY_SIZE: usize = 2048;
+/// The `ByteList[2048]` of the `content` arm of the CONTENT union. The spec
+/// sends larger values over uTP, under the `connection_id` arm instead.
+pub const MAX_CONTENT_SIZE: usize = 2048;
+pub const MAX_CONTENT_KEYS_PER_OFFER: usize = 64;
+pub const MAX_CLIENT_INFO_SIZE: usize = 200;
+pub const MAX_CAPABILITIES: usize = 400;
+pub const MAX_ERROR_MESSAGE_SIZE: usize = 300;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum WireError {
+    #[error("empty message")]
+    Empty,
+    #[error("unknown message id 0x{0:02x}")]
+    UnknownMessageId(u8),
+    #[error("invalid ssz: {0}")]
+    Ssz(#[from] SszError),
+    #[error("distance {0} is above 256")]
+    InvalidDistance(u16),
+    #[error("unknown accept code {0}")]
+    UnknownAcceptCode(u8),
+}
+
+/// Payload types of the PING and PONG extensions.
+pub mod payload_type {
+    pub const CLIENT_INFO_RADIUS_AND_CAPABILITIES: u16 = 0;
+    pub const BASIC_RADIUS: u16 = 1;
+    pub const HISTORY_RADIUS: u16 = 2;
+    pub const ERROR: u16 = 0xffff;
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PingPayload {
+    ClientInfoRadiusAndCapabilities {
+        client_info: Vec<u8>,
+        data_radius: U256,
+        capabilities: Vec<u16>,
+    },
+    BasicRadius {
+        data_radius: U256,
+    },
+    HistoryRadius {
+        data_radius: U256,
+        ephemeral_header_count: u16,
+    },
+    Error {
+        error_code: u16,
+        message: Vec<u8>,
+    },
+    /// A payload type this codec does not know, kept as raw bytes.
+    Unknown {
+        payload_type: u16,
+        payload: Vec<u8>,
+    },
+}
+
+impl PingPayload {
+    pub fn payload_type(&self) -> u16 {
+        match self {
+            PingPayload::ClientInfoRadiusAndCapabilities { .. } => {
+                payload_type::CLIENT_INFO_RADIUS_AND_CAPABILITIES
+            }
+            PingPayload::BasicRadius { .. } => payload_type::BASIC_RADIUS,
+            PingPayload::HistoryRadius { .. } => payload_type::HISTORY_RADIUS,
+            PingPayload::Error { .. } => payload_type::ERROR,
+            PingPayload::Unknown { payload_type, .. } => *payload_type,
+        }
+    }
+
+    fn ssz_type(payload_type: u16) -> Option<SszType> {
+        Some(match payload_type {
+            payload_type::CLIENT_INFO_RADIUS_AND_CAPABILITIES => SszType::container([
+                ("client_info", SszType::ByteList(MAX_CLIENT_INFO_SIZE)),
+                ("data_radius", SszType::uint256()),
+                (
+                    "capabilities",
+                    SszType::list(SszType::Uint(2), MAX_CAPABILITIES),
+                ),
+            ]),
+            payload_type::BASIC_RADIUS => SszType::container([("data_radius", SszType::uint256())]),
+            payload_type::HISTORY_RADIUS => SszType::container([
+                ("data_radius", SszType::uint256()),
+                ("ephemeral_header_count", SszType::Uint(2)),
+            ]),
+            payload_type::ERROR => SszType::container([
+                ("error_code", SszType::Uint(2)),
+                ("message", SszType::ByteList(MAX_ERROR_MESSAGE_SIZE)),
+            ]),
+            _ => return None,
+        })
+    }
+
+    pub fn decode(payload_type: u16, data: &[u8]) -> Result<PingPayload, WireError> {
+        let Some(ty) = PingPayload::ssz_type(payload_type) else {
+            return Ok(PingPayload::Unknown {
+                payload_type,
+                payload: data.to_vec(),
+            });
+        };
+        let value = ty.decode(data)?;
+        let fields = items(&value)?;
+        Ok(match payload_type {
+            payload_type::CLIENT_INFO_RADIUS_AND_CAPABILITIES => {
+                PingPayload::ClientInfoRadiusAndCapabilities {
+                    client_info: bytes(&fields[0])?.to_vec(),
+                    data_radius: uint(&fields[1])?,
+                    capabilities: items(&fields[2])?
+                        .iter()
+                        .map(u16_value)
+                        .collect::<Result<_, _>>()?,
+                }
+            }
+            payload_type::BASIC_RADIUS => PingPayload::BasicRadius {
+                data_radius: uint(&fields[0])?,
+            },
+            payload_type::HISTORY_RADIUS => PingPayload::HistoryRadius {
+                data_radius: uint(&fields[0])?,
+                ephemeral_header_count: u16_value(&fields[1])?,
+            },
+            _ => PingPayload::Error {
+                error_code: u16_value(&fields[0])?,
+                message: bytes(&fields[1])?.to_vec(),
+            },
+        })
+    }
+
+    pub fn encode(&self) -> Result<Vec<u8>, WireError> {
+        let value = match self {
+            PingPayload::ClientInfoRadiusAndCapabilities {
+                client_info,
+                data_radius,
+                capabilities,
+            } => vec![
+                SszValue::Bytes(client_info.clone()),
+                SszValue::Uint(*data_radius),
+                SszValue::Sequence(
+                    capabilities
+                        .iter()
+                        .map(|c| SszValue::uint(*c as u64))
+                        .collect(),
+                ),
+            ],
+            PingPayload::BasicRadius { data_radius } => vec![SszValue::Uint(*data_radius)],
+            PingPayload::HistoryRadius {
+                data_radius,
+                ephemeral_header_count,
+            } => vec![
+                SszValue::Uint(*data_radius),
+                SszValue::uint(*ephemeral_header_count as u64),
+            ],
+            PingPayload::Error {
+                error_code,
+                message,
+            } => vec![
+                SszValue::uint(*error_code as u64),
+                SszValue::Bytes(message.clone()),
+            ],
+            PingPayload::Unknown { payload, .. } => return Ok(payload.clone()),
+        };
+        let ty = PingPayload::ssz_type(self.payload_type()).unwrap();
+        Ok(ty.encode(&SszValue::Container(value))?)
+    }
+}
+
+/// The body shared by PING and PONG.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PingPong {
+    pub enr_seq: u64,
+    pub payload: PingPayload,
+}
+
+/// Response to FINDCONTENT, a union of the three ways content is returned.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ContentResponse {
+    ConnectionId([u8; 2]),
+    Content(Vec<u8>),
+    Enrs(Vec<Vec<u8>>),
+}
+
+/// Per content key answer in an ACCEPT.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AcceptCode {
+    Accepted,
+    Declined,
+    AlreadyStored,
+    NotWithinRadius,
+    RateLimited,
+    InboundTransferInProgress,
+}
+
+impl AcceptCode {
+    pub fn from_byte(byte: u8) -> Option<AcceptCode> {
+        Some(match byte {
+            0 => AcceptCode::Accepted,
+            1 => AcceptCode::Declined,
+            2 => AcceptCode::AlreadyStored,
+            3 => AcceptCode::NotWithinRadius,
+            4 => AcceptCode::RateLimited,
+            5 => AcceptCode::InboundTransferInProgress,
+            _ => return None,
+        })
+    }
+
+    pub fn byte(&self) -> u8 {
+        match self {
+            AcceptCode::Accepted => 0,
+            AcceptCode::Declined => 1,
+            AcceptCode::AlreadyStored => 2,
+            AcceptCode::NotWithinRadius => 3,
+            AcceptCode::RateLimited => 4,
+            AcceptCode::InboundTransferInProgress => 5,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            AcceptCode::Accepted => "accepted",
+            AcceptCode::Declined => "declined",
+            AcceptCode::AlreadyStored => "already_stored",
+            AcceptCode::NotWithinRadius => "not_within_radius",
+            AcceptCode::RateLimited => "rate_limited",
+            AcceptCode::InboundTransferInProgress => "inbound_transfer_in_progress",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Message {
+    Ping(PingPong),
+    Pong(PingPong),
+    FindNodes {
+        distances: Vec<u16>,
+    },
+    Nodes {
+        total: u8,
+        enrs: Vec<Vec<u8>>,
+    },
+    FindContent {
+        content_key: Vec<u8>,
+    },
+    Content(ContentResponse),
+    Offer {
+        content_keys: Vec<Vec<u8>>,
+    },
+    Accept {
+        connection_id: [u8; 2],
+        accept_codes: Vec<AcceptCode>,
+    },
+}
+
+fn enrs_type() -> SszType {
+    SszType::list(SszType::ByteList(MAX_ENR_LIST_ITEM_SIZE), MAX_ENRS)
+}
+
+fn byte_lists(lists: &[Vec<u8>]) -> SszValue {
+    SszValue::Sequence(lists.iter().cloned().map(SszValue::Bytes).collect())
+}
+
+fn mismatch() -> SszError {
+    SszError::TypeMismatch("wire message".to_string())
+}
+
+fn items(value: &SszValue) -> Result<&[SszValue], SszError> {
+    value.as_items().ok_or_else(mismatch)
+}
+
+fn bytes(value: &SszValue) -> Result<&[u8], SszError> {
+    value.as_bytes().ok_or_else(mismatch)
+}
+
+fn uint(value: &SszValue) -> Result<U256, SszError> {
+    value.as_uint().ok_or_else(mismatch)
+}
+
+fn u16_value(value: &SszValue) -> Result<u16, SszError> {
+    value
+        .as_u64()
+        .and_then(|v| u16::try_from(v).ok())
+        .ok_or_else(mismatch)
+}
+
+fn to_byte_lists(value: &SszValue) -> Result<Vec<Vec<u8>>, SszError> {
+    items(value)?
+        .iter()
+        .map(|item| bytes(item).map(<[u8]>::to_vec))
+        .collect()
+}
+
+impl Message {
+    pub fn id(&self) -> u8 {
+        match self {
+            Message::Ping(_) => 0x00,
+            Message::Pong(_) => 0x01,
+            Message::FindNodes { .. } => 0x02,
+            Message::Nodes { .. } => 0x03,
+            Message::FindContent { .. } => 0x04,
+            Message::Content(_) => 0x05,
+            Message::Offer { .. } => 0x06,
+            Message::Accept { .. } => 0x07,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Message::Ping(_) => "PING",
+            Message::Pong(_) => "PONG",
+            Message::FindNodes { .. } => "FINDNODES",
+            Message::Nodes { .. } => "NODES",
+            Message::FindContent { .. } => "FINDCONTENT",
+            Message::Content(_) => "CONTENT",
+            Message::Offer { .. } => "OFFER",
+            Message::Accept { .. } => "ACCEPT",
+        }
+    }
+
+    /// The SSZ type of the message body following the id byte.
+    pub fn ssz_type(id: u8) -> Option<SszType> {
+        Some(match id {
+            0x00 | 0x01 => SszType::container([
+                ("enr_seq", SszType::uint64()),
+                ("payload_type", SszType::Uint(2)),
+                ("payload", SszType::ByteList(MAX_CUSTOM_PAYLOAD_SIZE)),
+            ]),
+            0x02 => {
+                SszType::container([("distances", SszType::list(SszType::Uint(2), MAX_DISTANCES))])
+            }
+            0x03 => SszType::container([("total", SszType::uint8()), ("enrs", enrs_type())]),
+            0x04 => SszType::container([("content_key", SszType::ByteList(MAX_CONTENT_KEY_SIZE))]),
+            0x05 => SszType::union([
+                Some(SszType::ByteVector(2)),
+                Some(SszType::ByteList(MAX_CONTENT_SIZE)),
+                Some(enrs_type()),
+            ]),
+            0x06 => SszType::container([(
+                "content_keys",
+                SszType::list(
+                    SszType::ByteList(MAX_CONTENT_KEY_SIZE),
+                    MAX_CONTENT_KEYS_PER_OFFER,
+                ),
+            )]),
+            0x07 => SszType::container([
+                ("connection_id", SszType::ByteVector(2)),
+                (
+                    "content_keys",
+                    SszType::ByteList(MAX_CONTENT_KEYS_PER_OFFER),
+                ),
+            ]),
+            _ => return None,
+        })
+    }
+
+    pub fn decode(data: &[u8]) -> Result<Message, WireError> {
+        let (&id, body) = data.split_first().ok_or(WireError::Empty)?;
+        let ty = Message::ssz_type(id).ok_or(WireError::UnknownMessageId(id))?;
+        let value = ty.decode(body)?;
+        if let SszValue::Union(selector, Some(inner)) = &value {
+            return Ok(Message::Content(match selector {
+                0 => ContentResponse::ConnectionId(bytes(inner)?.try_into().unwrap()),
+                1 => ContentResponse::Content(bytes(inner)?.to_vec()),
+                _ => ContentResponse::Enrs(to_byte_lists(inner)?),
+            }));
+        }
+        let fields = items(&value)?;
+        Ok(match id {
+            0x00 | 0x01 => {
+                let ping = PingPong {
+                    enr_seq: fields[0].as_u64().ok_or_else(mismatch)?,
+                    payload: PingPayload::decode(u16_value(&fields[1])?, bytes(&fields[2])?)?,
+                };
+                if id == 0x00 {
+                    Message::Ping(ping)
+                } else {
+                    Message::Pong(ping)
+                }
+            }
+            0x02 => {
+                let distances = items(&fields[0])?
+                    .iter()
+                    .map(u16_value)
+                    .collect::<Result<Vec<_>, _>>()?;
+                if let Some(distance) = distances.iter().find(|d| **d > 256) {
+                    return Err(WireError::InvalidDistance(*distance));
+                }
+                Message::FindNodes { distances }
+            }
+            0x03 => Message::Nodes {
+                total: fields[0].as_u64().ok_or_else(mismatch)? as u8,
+                enrs: to_byte_lists(&fields[1])?,
+            },
+            0x04 => Message::FindContent {
+                content_key: bytes(&fields[0])?.to_vec(),
+            },
+            0x06 => Message::Offer {
+                content_keys: to_byte_lists(&fields[0])?,
+            },
+            _ => Message::Accept {
+                connection_id: bytes(&fields[0])?.try_into().unwrap(),
+                accept_codes: bytes(&fields[1])?
+                    .iter()
+                    .map(|b| AcceptCode::from_byte(*b).ok_or(WireError::UnknownAcceptCode(*b)))
+                    .collect::<Result<_, _>>()?,
+            },
+        })
+    }
+
+    pub fn encode(&self) -> Result<Vec<u8>, WireError> {
+        let value = match self {
+            Message::Ping(ping) | Message::Pong(ping) => SszValue::Container(vec![
+                SszValue::uint(ping.enr_seq),
+                SszValue::uint(ping.payload.payload_type() as u64),
+                SszValue::Bytes(ping.payload.encode()?),
+            ]),
+            Message::FindNodes { distances } => {
+                if let Some(distance) = distances.iter().find(|d| **d > 256) {
+                    return Err(WireError::InvalidDistance(*distance));
+                }
+                SszValue::Container(vec![SszValue::Sequence(
+                    distances
+                        .iter()
+                        .map(|d| SszValue::uint(*d as u64))
+                        .collect(),
+                )])
+            }
+            Message::Nodes { total, enrs } => {
+                SszValue::Container(vec![SszValue::uint(*total as u64), byte_lists(enrs)])
+            }
+            Message::FindContent { content_key } => {
+                SszValue::Container(vec![SszValue::Bytes(content_key.clone())])
+            }
+            Message::Content(response) => match response {
+                ContentResponse::ConnectionId(id) => {
+                    SszValue::Union(0, Some(Box::new(SszValue::Bytes(id.to_vec()))))
+                }
+                ContentResponse::Content(content) => {
+                    SszValue::Union(1, Some(Box::new(SszValue::Bytes(content.clone()))))
+                }
+                ContentResponse::Enrs(enrs) => SszValue::Union(2, Some(Box::new(byte_lists(enrs)))),
+            },
+            Message::Offer { content_keys } => SszValue::Container(vec![byte_lists(content_keys)]),
+            Message::Accept {
+                connection_id,
+                accept_codes,
+            } => SszValue::Container(vec![
+                SszValue::Bytes(connection_id.to_vec()),
+                SszValue::Bytes(accept_codes.iter().map(AcceptCode::byte).collect()),
+            ]),
+        };
+        let mut out = vec![self.id()];
+        out.extend(Message::ssz_type(self.id()).unwrap().encode(&value)?);
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vector::{network_dir, VectorFile};
+
+    const EXAMPLE_ENR: &str = "f884b8407098ad865b00a582051940cb9cf36836572411a47278783077011599ed5cd16b76f2635f4e234738f30813a89eb9137e3e3df5266e3a1f11df72ecf1145ccb9c01826964827634826970847f00000189736563703235366b31a103ca634cae0d49acb401d8a4c6b6fe8c55b70d115bf400769cc1400f3258cd31388375647082765f";
+
+    fn expected(name: &str) -> Message {
+        let radius = U256::MAX - 1;
+        let ping = |payload| PingPong {
+            enr_seq: 1,
+            payload,
+        };
+        let enr = hex::decode(EXAMPLE_ENR).unwrap();
+        match name {
+            "ping_basic_radius" => Message::Ping(ping(PingPayload::BasicRadius {
+                data_radius: radius,
+            })),
+            "pong_basic_radius" => Message::Pong(ping(PingPayload::BasicRadius {
+                data_radius: radius,
+            })),
+            "ping_client_info_radius_and_capabilities" => {
+                Message::Ping(ping(PingPayload::ClientInfoRadiusAndCapabilities {
+                    client_info: b"trin/v0.1.1-b61fdc5c/linux-x86_64/rustc1.81.0".to_vec(),
+                    data_radius: radius,
+                    capabilities: vec![0, 1, 65535],
+                }))
+            }
+            "pong_history_radius" => Message::Pong(ping(PingPayload::HistoryRadius {
+                data_radius: radius,
+                ephemeral_header_count: 4242,
+            })),
+            "pong_error" => Message::Pong(ping(PingPayload::Error {
+                error_code: 2,
+                message: b"hello world".to_vec(),
+            })),
+            "find_nodes" => Message::FindNodes {
+                distances: vec![256, 255],
+            },
+            "nodes_empty" => Message::Nodes {
+                total: 1,
+                enrs: Vec::new(),
+            },
+            "nodes_with_enr" => Message::Nodes {
+                total: 1,
+                enrs: vec![enr],
+            },
+            "find_content" => Message::FindContent {
+                content_key: b"portal".to_vec(),
+            },
+            "content_connection_id" => Message::Content(ContentResponse::ConnectionId([1, 2])),
+            "content_raw" => Message::Content(ContentResponse::Content(b"the content".to_vec())),
+            "content_enrs" => Message::Content(ContentResponse::Enrs(vec![enr])),
+            "offer" => Message::Offer {
+                content_keys: vec![vec![1, 2, 3]],
+            },
+            "accept" => Message::Accept {
+                connection_id: [1, 2],
+                accept_codes: (0..6).map(|b| AcceptCode::from_byte(b).unwrap()).collect(),
+            },
+            other => panic!("no expected message for {}", other),
+        }
+    }
+
+    #[test]
+    fn test_golden_messages() {
+        let root = network_dir("mainnet");
+        let file = VectorFile::load(&root, &root.join("wire/messages.yaml")).unwrap();
+        let document: serde_yaml::Value = serde_yaml::from_str(&file.text).unwrap();
+        let entries = document.as_sequence().unwrap();
+        for entry in entries {
+            let name = entry["name"].as_str().unwrap();
+            let encoded =
+                hex::decode(entry["message"].as_str().unwrap().trim_start_matches("0x")).unwrap();
+            let message = expected(name);
+            assert_eq!(message.encode().unwrap(), encoded, "{}", name);
+            assert_eq!(Message::decode(&encoded).unwrap(), message, "{}", name);
+        }
+        assert_eq!(entries.len(), 14);
+    }
+
+    #[test]
+    fn test_rejects_invalid_messages() {
+        assert_eq!(Message::decode(&[]), Err(WireError::Empty));
+        assert_eq!(
+            Message::decode(&[0x08]),
+            Err(WireError::UnknownMessageId(0x08))
+        );
+        assert_eq!(
+            Message::decode(&hex::decode("02040000000101").unwrap()),
+            Err(WireError::InvalidDistance(257))
+        );
+        assert_eq!(
+            Message::decode(&hex::decode("0701020600000006").unwrap()),
+            Err(WireError::UnknownAcceptCode(6))
+        );
+        assert!(Message::decode(&hex::decode("0503").unwrap()).is_err());
+        assert!(Message::Offer {
+            content_keys: vec![vec![0]; MAX_CONTENT_KEYS_PER_OFFER + 1],
+        }
+        .encode()
+        .is_err());
+    }
+
+    #[test]
+    fn test_unknown_payload_type_is_kept() {
+        let message = Message::Ping(PingPong {
+            enr_seq: 7,
+            payload: PingPayload::Unknown {
+                payload_type: 9,
+                payload: vec![0xaa, 0xbb],
+            },
+        });
+        let encoded = message.encode().unwrap();
+        assert_eq!(Message::decode(&encoded).unwrap(), message);
+    }
+}
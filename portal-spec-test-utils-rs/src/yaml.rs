@@ -0,0 +1,700 @@
+use serde_json::Value;
+use std::fmt::Write;
+use thiserror::Error;
+
+/// Spaces added per nesting level when writing.
+const INDENT: usize = 2;
+
+#[derive(Error, Debug)]
+pub enum YamlError {
+    #[error("line {line}: {message}")]
+    Syntax { line: usize, message: String },
+    #[error(transparent)]
+    Value(#[from] serde_yaml::Error),
+}
+
+fn syntax(line: usize, message: &str) -> YamlError {
+    YamlError::Syntax {
+        line,
+        message: message.to_string(),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScalarStyle {
+    Plain,
+    SingleQuoted,
+    DoubleQuoted,
+}
+
+/// A scalar with the quoting it was written in. Plain scalars keep their raw
+/// text, including flow collections such as `[1, 2]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Scalar {
+    pub value: String,
+    pub style: ScalarStyle,
+}
+
+impl Scalar {
+    pub fn plain(value: impl Into<String>) -> Scalar {
+        Scalar {
+            value: value.into(),
+            style: ScalarStyle::Plain,
+        }
+    }
+
+    pub fn double_quoted(value: impl Into<String>) -> Scalar {
+        Scalar {
+            value: value.into(),
+            style: ScalarStyle::DoubleQuoted,
+        }
+    }
+
+    fn parse(text: &str, line: usize) -> Result<Scalar, YamlError> {
+        let (style, quote) = match text.chars().next() {
+            Some('"') => (ScalarStyle::DoubleQuoted, '"'),
+            Some('\'') => (ScalarStyle::SingleQuoted, '\''),
+            Some('|' | '>') => return Err(syntax(line, "block scalars are not supported")),
+            Some('&' | '*' | '!') => {
+                return Err(syntax(line, "anchors, aliases and tags are not supported"))
+            }
+            _ => return Ok(Scalar::plain(text)),
+        };
+        let end = closing_quote(text, quote).ok_or_else(|| syntax(line, "unterminated string"))?;
+        if end + 1 != text.len() {
+            return Err(syntax(line, "unexpected text after a quoted string"));
+        }
+        let inner = &text[1..end];
+        let value = match style {
+            ScalarStyle::DoubleQuoted => {
+                unescape(inner).ok_or_else(|| syntax(line, "invalid escape"))?
+            }
+            _ => inner.replace("''", "'"),
+        };
+        Ok(Scalar { value, style })
+    }
+
+    fn render(&self) -> String {
+        match self.style {
+            ScalarStyle::Plain => self.value.clone(),
+            ScalarStyle::SingleQuoted => format!("'{}'", self.value.replace('\'', "''")),
+            ScalarStyle::DoubleQuoted => {
+                let mut out = String::from("\"");
+                for c in self.value.chars() {
+                    match c {
+                        '"' => out.push_str("\\\""),
+                        '\\' => out.push_str("\\\\"),
+                        '\n' => out.push_str("\\n"),
+                        '\t' => out.push_str("\\t"),
+                        '\r' => out.push_str("\\r"),
+                        c if c.is_control() => {
+                            let _ = write!(out, "\\u{:04x}", c as u32);
+                        }
+                        c => out.push(c),
+                    }
+                }
+                out.push('"');
+                out
+            }
+        }
+    }
+}
+
+/// The byte index of the quote closing the string that opens `text`.
+fn closing_quote(text: &str, quote: char) -> Option<usize> {
+    let mut chars = text.char_indices().skip(1).peekable();
+    while let Some((i, c)) = chars.next() {
+        if quote == '"' && c == '\\' {
+            chars.next();
+        } else if c == quote {
+            if quote == '\'' && chars.peek().map(|&(_, next)| next) == Some('\'') {
+                chars.next();
+            } else {
+                return Some(i);
+            }
+        }
+    }
+    None
+}
+
+fn unescape(text: &str) -> Option<String> {
+    let mut out = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        let hex_char = |chars: &mut std::str::Chars, digits: usize| {
+            let code: String = chars.take(digits).collect();
+            u32::from_str_radix(&code, 16).ok().and_then(char::from_u32)
+        };
+        out.push(match chars.next()? {
+            'n' => '\n',
+            't' => '\t',
+            'r' => '\r',
+            '0' => '\0',
+            ' ' => ' ',
+            '/' => '/',
+            '"' => '"',
+            '\\' => '\\',
+            'x' => hex_char(&mut chars, 2)?,
+            'u' => hex_char(&mut chars, 4)?,
+            'U' => hex_char(&mut chars, 8)?,
+            _ => return None,
+        });
+    }
+    Some(out)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Node {
+    Scalar(Scalar),
+    Mapping(Vec<(Scalar, Entry)>),
+    Sequence(Vec<Entry>),
+}
+
+impl Node {
+    /// The value of `key` when this is a mapping.
+    pub fn get(&self, key: &str) -> Option<&Node> {
+        match self {
+            Node::Mapping(entries) => entries
+                .iter()
+                .find(|(k, _)| k.value == key)
+                .map(|(_, entry)| &entry.value),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Node::Scalar(scalar) => Some(&scalar.value),
+            _ => None,
+        }
+    }
+}
+
+/// A mapping value or sequence item with the comments written around it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    /// Whole comment lines above the entry, with an empty string per blank line.
+    pub comments: Vec<String>,
+    pub value: Node,
+    /// A comment at the end of the entry's first line.
+    pub comment: Option<String>,
+}
+
+impl Entry {
+    pub fn new(value: Node) -> Entry {
+        Entry {
+            comments: Vec::new(),
+            value,
+            comment: None,
+        }
+    }
+}
+
+/// A YAML file that keeps its comments, blank lines and quoting when it is
+/// written back. Covers the block style subset the vectors are written in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Document {
+    pub root: Node,
+    /// Comment lines after the last entry.
+    pub trailing: Vec<String>,
+}
+
+impl Default for Document {
+    fn default() -> Self {
+        Document {
+            root: Node::Mapping(Vec::new()),
+            trailing: Vec::new(),
+        }
+    }
+}
+
+impl Document {
+    pub fn parse(text: &str) -> Result<Document, YamlError> {
+        let mut parser = Parser {
+            items: lex(text)?,
+            pos: 0,
+        };
+        let root = match parser.peek() {
+            Some(line) => {
+                let indent = line.indent;
+                parser.node(indent)?
+            }
+            None => Node::Mapping(Vec::new()),
+        };
+        let trailing = parser.comments();
+        if let Some(line) = parser.peek() {
+            return Err(syntax(line.number, "unexpected indentation"));
+        }
+        Ok(Document { root, trailing })
+    }
+
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        match &self.root {
+            Node::Scalar(scalar) => {
+                out.push_str(&scalar.render());
+                out.push('\n');
+            }
+            Node::Mapping(entries) if entries.is_empty() => {}
+            Node::Mapping(entries) => write_mapping(&mut out, entries, 0),
+            Node::Sequence(items) => write_sequence(&mut out, items, 0),
+        }
+        write_comments(&mut out, &self.trailing, 0);
+        out
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Node> {
+        self.root.get(key)
+    }
+
+    /// The entry of `key` in the top level mapping, appended when missing.
+    pub fn entry(&mut self, key: &str) -> &mut Entry {
+        if !matches!(self.root, Node::Mapping(_)) {
+            self.root = Node::Mapping(Vec::new());
+        }
+        let Node::Mapping(entries) = &mut self.root else {
+            unreachable!()
+        };
+        let index = match entries.iter().position(|(k, _)| k.value == key) {
+            Some(index) => index,
+            None => {
+                entries.push((
+                    Scalar::plain(key),
+                    Entry::new(Node::Scalar(Scalar::plain(""))),
+                ));
+                entries.len() - 1
+            }
+        };
+        &mut entries[index].1
+    }
+
+    /// Replaces the value of `key`, keeping the comments around it.
+    pub fn set(&mut self, key: &str, value: Node) {
+        self.entry(key).value = value;
+    }
+
+    /// The parsed value, as `VectorFile::document` returns it. Plain `0x`
+    /// scalars are read as the hex strings they are written as, not as integers.
+    pub fn to_value(&self) -> Result<Value, YamlError> {
+        let mut root = self.root.clone();
+        quote_hex(&mut root);
+        let document = Document {
+            root,
+            trailing: Vec::new(),
+        };
+        Ok(serde_yaml::from_str(&document.render())?)
+    }
+}
+
+/// Whether a mapping value or sequence item in `text` is unquoted hex.
+pub fn has_plain_hex(text: &str) -> bool {
+    text.lines().any(|line| {
+        let line = line.trim_start();
+        line.strip_prefix("- ").unwrap_or(line).starts_with("0x") || line.contains(": 0x")
+    })
+}
+
+fn quote_hex(node: &mut Node) {
+    match node {
+        Node::Scalar(scalar) => {
+            if scalar.style == ScalarStyle::Plain && scalar.value.starts_with("0x") {
+                scalar.style = ScalarStyle::DoubleQuoted;
+            }
+        }
+        Node::Mapping(entries) => {
+            for (_, entry) in entries {
+                quote_hex(&mut entry.value);
+            }
+        }
+        Node::Sequence(items) => {
+            for entry in items {
+                quote_hex(&mut entry.value);
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Line {
+    number: usize,
+    indent: usize,
+    text: String,
+    comment: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+enum Item {
+    Comment(String),
+    Content(Line),
+}
+
+fn lex(text: &str) -> Result<Vec<Item>, YamlError> {
+    let mut items = Vec::new();
+    for (index, raw) in text.lines().enumerate() {
+        let number = index + 1;
+        let trimmed = raw.trim();
+        if trimmed.is_empty() {
+            items.push(Item::Comment(String::new()));
+            continue;
+        }
+        if trimmed.starts_with('#') {
+            items.push(Item::Comment(trimmed.to_string()));
+            continue;
+        }
+        let indent = raw.len() - raw.trim_start_matches(' ').len();
+        if raw[indent..].starts_with('\t') {
+            return Err(syntax(number, "tabs are not allowed in indentation"));
+        }
+        if indent == 0 && (trimmed.starts_with("---") || trimmed.starts_with('%')) {
+            return Err(syntax(
+                number,
+                "directives and multiple documents are not supported",
+            ));
+        }
+        let (text, comment) = split_comment(trimmed);
+        items.push(Item::Content(Line {
+            number,
+            indent,
+            text: text.to_string(),
+            comment: comment.map(str::to_string),
+        }));
+    }
+    Ok(items)
+}
+
+/// Splits a `#` comment that starts outside quotes off the end of a line.
+fn split_comment(line: &str) -> (&str, Option<&str>) {
+    let mut quote = None;
+    let mut previous = ' ';
+    let mut chars = line.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match (quote, c) {
+            (None, '#') if previous.is_whitespace() => {
+                return (line[..i].trim_end(), Some(line[i..].trim_end()));
+            }
+            (None, '"' | '\'')
+                if previous.is_whitespace() || previous == ':' || previous == '-' =>
+            {
+                quote = Some(c)
+            }
+            (Some('"'), '\\') => {
+                chars.next();
+            }
+            (Some(q), c) if c == q => quote = None,
+            _ => {}
+        }
+        previous = c;
+    }
+    (line, None)
+}
+
+/// Splits `key: rest` at the first colon outside quotes that ends the line or
+/// is followed by a space.
+fn split_key(text: &str) -> Option<(&str, &str)> {
+    if text.starts_with(['[', '{']) {
+        return None;
+    }
+    let start = match text.chars().next()? {
+        quote @ ('"' | '\'') => closing_quote(text, quote)? + 1,
+        _ => 0,
+    };
+    let colon = text[start..].find(':').map(|i| start + i)?;
+    if start > 0 && colon != start {
+        return None;
+    }
+    let rest = &text[colon + 1..];
+    if !(rest.is_empty() || rest.starts_with(' ')) {
+        return None;
+    }
+    Some((&text[..colon], rest.trim_start()))
+}
+
+fn is_sequence_item(text: &str) -> bool {
+    text == "-" || text.starts_with("- ")
+}
+
+struct Parser {
+    items: Vec<Item>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Line> {
+        self.items[self.pos..].iter().find_map(|item| match item {
+            Item::Content(line) => Some(line),
+            Item::Comment(_) => None,
+        })
+    }
+
+    fn comments(&mut self) -> Vec<String> {
+        let mut comments = Vec::new();
+        while let Some(Item::Comment(comment)) = self.items.get(self.pos) {
+            comments.push(comment.clone());
+            self.pos += 1;
+        }
+        comments
+    }
+
+    fn next(&mut self) -> Line {
+        match &self.items[self.pos] {
+            Item::Content(line) => {
+                self.pos += 1;
+                line.clone()
+            }
+            Item::Comment(_) => unreachable!("comments are taken first"),
+        }
+    }
+
+    /// Puts the rest of a `- key: value` line back as the first line of the item's mapping.
+    fn unread(&mut self, line: Line) {
+        self.pos -= 1;
+        self.items[self.pos] = Item::Content(line);
+    }
+
+    fn node(&mut self, indent: usize) -> Result<Node, YamlError> {
+        let line = self.peek().expect("caller checked there is a line");
+        if is_sequence_item(&line.text) {
+            self.sequence(indent)
+        } else if split_key(&line.text).is_some() {
+            self.mapping(indent)
+        } else {
+            let line = self.next();
+            Ok(Node::Scalar(Scalar::parse(&line.text, line.number)?))
+        }
+    }
+
+    /// The value of a key or item whose line ends after the colon or dash.
+    fn nested(&mut self, indent: usize, sequence_allowed: bool) -> Result<Node, YamlError> {
+        match self.peek() {
+            Some(next)
+                if next.indent > indent
+                    || (sequence_allowed
+                        && next.indent == indent
+                        && is_sequence_item(&next.text)) =>
+            {
+                let indent = next.indent;
+                self.node(indent)
+            }
+            _ => Ok(Node::Scalar(Scalar::plain(""))),
+        }
+    }
+
+    fn mapping(&mut self, indent: usize) -> Result<Node, YamlError> {
+        let mut entries = Vec::new();
+        while let Some(line) = self.peek() {
+            if line.indent < indent || (line.indent == indent && is_sequence_item(&line.text)) {
+                break;
+            }
+            if line.indent > indent {
+                return Err(syntax(line.number, "unexpected indentation"));
+            }
+            let comments = self.comments();
+            let line = self.next();
+            let (key, rest) =
+                split_key(&line.text).ok_or_else(|| syntax(line.number, "expected a key"))?;
+            let key = Scalar::parse(key, line.number)?;
+            let value = if rest.is_empty() {
+                self.nested(indent, true)?
+            } else {
+                Node::Scalar(Scalar::parse(rest, line.number)?)
+            };
+            entries.push((
+                key,
+                Entry {
+                    comments,
+                    value,
+                    comment: line.comment,
+                },
+            ));
+        }
+        Ok(Node::Mapping(entries))
+    }
+
+    fn sequence(&mut self, indent: usize) -> Result<Node, YamlError> {
+        let mut items = Vec::new();
+        while let Some(line) = self.peek() {
+            if line.indent < indent || (line.indent == indent && !is_sequence_item(&line.text)) {
+                break;
+            }
+            if line.indent > indent {
+                return Err(syntax(line.number, "unexpected indentation"));
+            }
+            let comments = self.comments();
+            let line = self.next();
+            let rest = line.text[1..].trim_start();
+            let (value, comment) = if rest.is_empty() {
+                (self.nested(indent, false)?, line.comment)
+            } else if is_sequence_item(rest) || split_key(rest).is_some() {
+                let item_indent = indent + line.text.len() - rest.len();
+                self.unread(Line {
+                    indent: item_indent,
+                    text: rest.to_string(),
+                    ..line
+                });
+                (self.node(item_indent)?, None)
+            } else {
+                (
+                    Node::Scalar(Scalar::parse(rest, line.number)?),
+                    line.comment,
+                )
+            };
+            items.push(Entry {
+                comments,
+                value,
+                comment,
+            });
+        }
+        Ok(Node::Sequence(items))
+    }
+}
+
+fn write_comments(out: &mut String, comments: &[String], indent: usize) {
+    for comment in comments {
+        if !comment.is_empty() {
+            out.push_str(&" ".repeat(indent));
+            out.push_str(comment);
+        }
+        out.push('\n');
+    }
+}
+
+/// Writes what follows the `key:` or `-` that introduces `entry`.
+fn write_value(out: &mut String, entry: &Entry, indent: usize) {
+    let inline = match &entry.value {
+        Node::Scalar(scalar) if scalar.value.is_empty() && scalar.style == ScalarStyle::Plain => {
+            None
+        }
+        Node::Scalar(scalar) => Some(scalar.render()),
+        Node::Mapping(entries) if entries.is_empty() => Some("{}".to_string()),
+        Node::Sequence(items) if items.is_empty() => Some("[]".to_string()),
+        _ => None,
+    };
+    for part in inline.iter().chain(entry.comment.iter()) {
+        out.push(' ');
+        out.push_str(part);
+    }
+    out.push('\n');
+    match &entry.value {
+        Node::Mapping(entries) => write_mapping(out, entries, indent + INDENT),
+        Node::Sequence(items) => write_sequence(out, items, indent + INDENT),
+        Node::Scalar(_) => {}
+    }
+}
+
+fn write_mapping(out: &mut String, entries: &[(Scalar, Entry)], indent: usize) {
+    for (key, entry) in entries {
+        write_comments(out, &entry.comments, indent);
+        let _ = write!(out, "{}{}:", " ".repeat(indent), key.render());
+        write_value(out, entry, indent);
+    }
+}
+
+fn write_sequence(out: &mut String, items: &[Entry], indent: usize) {
+    for item in items {
+        write_comments(out, &item.comments, indent);
+        match &item.value {
+            Node::Mapping(entries) if !entries.is_empty() => {
+                let (key, first) = &entries[0];
+                write_comments(out, &first.comments, indent);
+                let _ = write!(out, "{}- {}:", " ".repeat(indent), key.render());
+                write_value(out, first, indent + INDENT);
+                write_mapping(out, &entries[1..], indent + INDENT);
+            }
+            _ => {
+                let _ = write!(out, "{}-", " ".repeat(indent));
+                write_value(out, item, indent);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vector::{discover, spec_tests_dir, VectorFormat};
+
+    #[test]
+    fn test_vectors_round_trip() {
+        let mut exact = 0;
+        for file in discover(&spec_tests_dir()).unwrap() {
+            if file.format != VectorFormat::Yaml {
+                continue;
+            }
+            let document = Document::parse(&file.text)
+                .unwrap_or_else(|e| panic!("{}: {}", file.relative.display(), e));
+            let rendered = document.render();
+            assert_eq!(
+                document.to_value().unwrap(),
+                file.document().unwrap(),
+                "{}",
+                file.relative.display()
+            );
+            let comments = |text: &str| -> Vec<String> {
+                text.lines()
+                    .filter(|line| line.trim_start().starts_with('#'))
+                    .map(|line| line.trim().to_string())
+                    .collect()
+            };
+            assert_eq!(comments(&rendered), comments(&file.text));
+            assert_eq!(Document::parse(&rendered).unwrap().render(), rendered);
+            if rendered == file.text {
+                exact += 1;
+            }
+        }
+        assert!(exact > 100);
+    }
+
+    #[test]
+    fn test_plain_hex_is_read_as_text() {
+        let text = "root: 0x0000\nproof:\n  - 0x01\n";
+        assert!(has_plain_hex(text));
+        assert_eq!(
+            Document::parse(text).unwrap().to_value().unwrap(),
+            serde_json::json!({"root": "0x0000", "proof": ["0x01"]})
+        );
+    }
+
+    #[test]
+    fn test_set_keeps_comments() {
+        let text = "# Provenance\n\n# Block #1\ncontent_key: '0x00'  # key\nupdates:\n  # first\n  - slot: 1\n    root: \"0x01\"\n  - [1, 2]\n# end\n";
+        let mut document = Document::parse(text).unwrap();
+        assert_eq!(
+            document.entry("content_key").comment.as_deref(),
+            Some("# key")
+        );
+        document.set("content_key", Node::Scalar(Scalar::double_quoted("0x01")));
+        document.set("content_value", Node::Scalar(Scalar::double_quoted("0x02")));
+        assert_eq!(
+            document.render(),
+            "# Provenance\n\n# Block #1\ncontent_key: \"0x01\" # key\nupdates:\n  # first\n  - slot: 1\n    root: \"0x01\"\n  - [1, 2]\ncontent_value: \"0x02\"\n# end\n"
+        );
+        let value = document.to_value().unwrap();
+        assert_eq!(value["updates"][0]["root"], "0x01");
+        assert_eq!(value["updates"][1][1], 2);
+    }
+
+    #[test]
+    fn test_scalars_and_errors() {
+        let document =
+            Document::parse("a: \"x # y \\\"q\\\"\"\nb: 'it''s'\nc: plain # note\nd:\n").unwrap();
+        assert_eq!(document.get("a").unwrap().as_str(), Some("x # y \"q\""));
+        assert_eq!(document.get("b").unwrap().as_str(), Some("it's"));
+        assert_eq!(document.get("c").unwrap().as_str(), Some("plain"));
+        assert_eq!(
+            document.render(),
+            "a: \"x # y \\\"q\\\"\"\nb: 'it''s'\nc: plain # note\nd:\n"
+        );
+        assert!(matches!(
+            Document::parse("a: |\n  text\n"),
+            Err(YamlError::Syntax { line: 1, .. })
+        ));
+        assert!(matches!(
+            Document::parse("a: 1\n    b: 2\n"),
+            Err(YamlError::Syntax { line: 2, .. })
+        ));
+    }
+}
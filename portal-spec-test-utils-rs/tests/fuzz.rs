@@ -0,0 +1,95 @@
+//! The proptest corpus for the fuzz targets. It runs in its own test binary so
+//! the counting allocator only replaces the global one here.
+
+use portal_spec_test_utils_rs::e2store;
+use portal_spec_test_utils_rs::fuzz::{
+    allocation_limit, peak_allocation, seeds, CountingAllocator, Target,
+};
+use portal_spec_test_utils_rs::vector::network_dir;
+use proptest::collection::vec;
+use proptest::prelude::*;
+use proptest::sample::{select, Index};
+use proptest::test_runner::{RngAlgorithm, TestRng, TestRunner};
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+#[derive(Debug, Clone)]
+enum Edit {
+    Set(Index, u8),
+    Insert(Index, u8),
+    Remove(Index),
+    Truncate(Index),
+}
+
+fn edit() -> impl Strategy<Value = Edit> {
+    prop_oneof![
+        4 => (any::<Index>(), any::<u8>()).prop_map(|(at, byte)| Edit::Set(at, byte)),
+        2 => (any::<Index>(), any::<u8>()).prop_map(|(at, byte)| Edit::Insert(at, byte)),
+        2 => any::<Index>().prop_map(Edit::Remove),
+        1 => any::<Index>().prop_map(Edit::Truncate),
+    ]
+}
+
+/// Vector seeds with a few byte edits, or plain random bytes.
+fn mutations(seeds: Vec<Vec<u8>>) -> impl Strategy<Value = Vec<u8>> {
+    let mutated = (select(seeds), vec(edit(), 0..6)).prop_map(|(mut data, edits)| {
+        for edit in edits {
+            match edit {
+                Edit::Set(at, byte) if !data.is_empty() => {
+                    let at = at.index(data.len());
+                    data[at] = byte;
+                }
+                Edit::Insert(at, byte) => data.insert(at.index(data.len() + 1), byte),
+                Edit::Remove(at) if !data.is_empty() => {
+                    data.remove(at.index(data.len()));
+                }
+                Edit::Truncate(at) => data.truncate(at.index(data.len() + 1)),
+                _ => {}
+            }
+        }
+        data
+    });
+    prop_oneof![8 => mutated, 1 => vec(any::<u8>(), 0..256)]
+}
+
+#[test]
+fn test_mutated_vectors() {
+    let root = network_dir("mainnet");
+    for target in Target::ALL {
+        let seeds = seeds(target, &root).unwrap();
+        assert!(!seeds.is_empty(), "{} has no seeds", target);
+        let config = ProptestConfig {
+            cases: 256,
+            failure_persistence: None,
+            rng_algorithm: RngAlgorithm::ChaCha,
+            ..ProptestConfig::default()
+        };
+        let mut runner =
+            TestRunner::new_with_rng(config, TestRng::deterministic_rng(RngAlgorithm::ChaCha));
+        runner
+            .run(&mutations(seeds), |data| {
+                target.check(&data);
+                Ok(())
+            })
+            .unwrap_or_else(|e| panic!("{}: {}", target, e));
+    }
+}
+
+#[test]
+fn test_seeds_round_trip() {
+    let root = network_dir("mainnet");
+    for target in Target::ALL {
+        for seed in seeds(target, &root).unwrap() {
+            target.check(&seed);
+        }
+    }
+}
+
+#[test]
+fn test_allocation_is_counted() {
+    let (buffer, peak) = peak_allocation(|| vec![0u8; 1 << 20]);
+    assert!(peak >= buffer.len());
+    let (_, peak) = peak_allocation(|| e2store::decode(&[0xff, 0, 0xff, 0xff, 0xff, 0xff, 0, 0]));
+    assert!(peak <= allocation_limit(8));
+}